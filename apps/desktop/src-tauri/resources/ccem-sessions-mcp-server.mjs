@@ -0,0 +1,385 @@
+#!/usr/bin/env node
+
+// Stdio MCP server that exposes CCEM native sessions as tools, so an agent in
+// one session can spawn, drive and read sibling sessions. Every call goes
+// through the desktop's external control server with a per-session token; the
+// server enforces the calling session's permission mode on anything it spawns.
+
+const endpoint = process.env.CCEM_SESSIONS_MCP_ENDPOINT;
+const token = process.env.CCEM_SESSIONS_MCP_TOKEN;
+const WAIT_POLL_MS = 1000;
+const DEFAULT_WAIT_TIMEOUT_SECS = 600;
+const DEFAULT_TRANSCRIPT_CHARS = 20000;
+const SETTLED_STATUSES = new Set(['idle', 'ready', 'interrupted', 'closed_idle']);
+const FINISHED_STATUSES = new Set(['completed', 'error', 'stopped', 'handoff']);
+
+if (!endpoint || !token) {
+  console.error('Missing CCEM_SESSIONS_MCP_ENDPOINT or CCEM_SESSIONS_MCP_TOKEN');
+  process.exit(1);
+}
+
+let buffer = Buffer.alloc(0);
+let rpcCounter = 0;
+
+function send(message) {
+  process.stdout.write(`${JSON.stringify(message)}\n`);
+}
+
+function sendError(id, code, message) {
+  send({ jsonrpc: '2.0', id, error: { code, message } });
+}
+
+async function callControl(method, params) {
+  rpcCounter += 1;
+  const response = await fetch(endpoint, {
+    method: 'POST',
+    headers: {
+      authorization: `Bearer ${token}`,
+      'content-type': 'application/json',
+    },
+    body: JSON.stringify({ jsonrpc: '2.0', id: rpcCounter, method, params }),
+  });
+  const body = await response.json().catch(() => null);
+  if (!body) {
+    throw new Error(`${method} failed with HTTP ${response.status}`);
+  }
+  if (body.error) {
+    throw new Error(body.error.message || `${method} failed`);
+  }
+  return body.result;
+}
+
+const TOOLS = [
+  {
+    name: 'list_sessions',
+    description:
+      'List the sessions started by this session, optionally filtered by project directory, '
+      + 'provider or status.',
+    inputSchema: {
+      type: 'object',
+      properties: {
+        cwd: { type: 'string' },
+        provider: { type: 'string', enum: ['claude', 'codex'] },
+        status: { type: 'string' },
+      },
+    },
+  },
+  {
+    name: 'create_session',
+    description:
+      'Start a new Claude or Codex worker session, either from a provider or a named launch preset. '
+      + "cwd must be relative and resolves against this session's project directory. "
+      + "The permission mode defaults to this session's and cannot exceed it.",
+    inputSchema: {
+      type: 'object',
+      properties: {
+        provider: { type: 'string', enum: ['claude', 'codex'] },
+        preset: { type: 'string' },
+        prompt: { type: 'string' },
+        cwd: { type: 'string' },
+        env_name: { type: 'string' },
+        permission_mode: { type: 'string' },
+      },
+      required: ['prompt'],
+    },
+  },
+  {
+    name: 'send_input',
+    description: 'Send a follow-up prompt to a session started by this session.',
+    inputSchema: {
+      type: 'object',
+      properties: {
+        runtime_id: { type: 'string' },
+        text: { type: 'string' },
+      },
+      required: ['runtime_id', 'text'],
+    },
+  },
+  {
+    name: 'wait_for_idle',
+    description:
+      'Block until a session started by this session finishes its current turn, stops, or the '
+      + 'timeout elapses.',
+    inputSchema: {
+      type: 'object',
+      properties: {
+        runtime_id: { type: 'string' },
+        timeout_secs: { type: 'number' },
+      },
+      required: ['runtime_id'],
+    },
+  },
+  {
+    name: 'get_transcript',
+    description:
+      'Read the transcript of a session started by this session: prompts, assistant replies and '
+      + 'tool activity. '
+      + 'Pass since_seq from a previous call to read only newer events.',
+    inputSchema: {
+      type: 'object',
+      properties: {
+        runtime_id: { type: 'string' },
+        since_seq: { type: 'number' },
+        max_chars: { type: 'number' },
+      },
+      required: ['runtime_id'],
+    },
+  },
+  {
+    name: 'stop_session',
+    description: 'Stop a session started by this session.',
+    inputSchema: {
+      type: 'object',
+      properties: {
+        runtime_id: { type: 'string' },
+      },
+      required: ['runtime_id'],
+    },
+  },
+];
+
+function requireString(args, key) {
+  const value = args[key];
+  if (typeof value !== 'string' || value.trim().length === 0) {
+    throw new Error(`${key} is required`);
+  }
+  return value;
+}
+
+function sessionView(session) {
+  return {
+    runtime_id: session.runtimeId,
+    provider: session.provider,
+    project_dir: session.projectDir,
+    env_name: session.envName,
+    perm_mode: session.runtimePermMode || session.permMode,
+    status: session.status,
+    is_active: session.isActive,
+    last_event_seq: session.lastEventSeq ?? null,
+    last_error: session.lastError ?? null,
+  };
+}
+
+async function waitForIdle(runtimeId, timeoutSecs) {
+  const deadline = Date.now() + timeoutSecs * 1000;
+  while (true) {
+    const session = await callControl('ccem.workspace.getSession', { runtimeId });
+    const settled = SETTLED_STATUSES.has(session.status);
+    const finished = FINISHED_STATUSES.has(session.status) || !session.isActive;
+    if (settled || finished || Date.now() >= deadline) {
+      return { ...sessionView(session), timed_out: !settled && !finished };
+    }
+    await new Promise((resolve) => setTimeout(resolve, WAIT_POLL_MS));
+  }
+}
+
+function transcriptLine(payload) {
+  switch (payload.type) {
+    case 'user_prompt':
+      return `User: ${payload.text}`;
+    case 'tool_use_started':
+      return `[tool] ${payload.raw_name} ${payload.input_summary}`;
+    case 'tool_use_completed':
+      return `[tool ${payload.success ? 'ok' : 'failed'}] ${payload.raw_name} ${payload.result_summary}`;
+    case 'permission_required':
+      return `[permission pending] ${payload.tool_name} ${payload.input_summary ?? ''}`;
+    case 'permission_responded':
+      return `[permission ${payload.approved ? 'approved' : 'denied'}]`;
+    case 'system_message':
+      return `[system] ${payload.message}`;
+    case 'session_completed':
+      return `[completed] ${payload.reason}`;
+    default:
+      return null;
+  }
+}
+
+async function getTranscript(runtimeId, sinceSeq, maxChars) {
+  const lines = [];
+  let assistant = '';
+  let lastSeq = sinceSeq ?? null;
+  const flushAssistant = () => {
+    if (assistant.trim().length > 0) {
+      lines.push(`Assistant: ${assistant.trim()}`);
+    }
+    assistant = '';
+  };
+
+  while (true) {
+    const batch = await callControl('ccem.workspace.getEvents', {
+      runtimeId,
+      sinceSeq: lastSeq,
+    });
+    for (const event of batch.events) {
+      lastSeq = event.seq;
+      if (event.payload.type === 'assistant_chunk') {
+        assistant += event.payload.text;
+        continue;
+      }
+      const line = transcriptLine(event.payload);
+      if (line) {
+        flushAssistant();
+        lines.push(line);
+      }
+    }
+    if (!batch.truncated || batch.events.length === 0) {
+      break;
+    }
+  }
+  flushAssistant();
+
+  let transcript = lines.join('\n');
+  const truncated = transcript.length > maxChars;
+  if (truncated) {
+    transcript = transcript.slice(transcript.length - maxChars);
+  }
+  return { runtime_id: runtimeId, last_seq: lastSeq, truncated, transcript };
+}
+
+async function runTool(name, args) {
+  switch (name) {
+    case 'list_sessions': {
+      const sessions = await callControl('ccem.workspace.listSessions', {
+        cwd: args.cwd,
+        provider: args.provider,
+        status: args.status,
+      });
+      return sessions.map(sessionView);
+    }
+    case 'create_session': {
+      if (!args.provider && !args.preset) {
+        throw new Error('provider or preset is required');
+      }
+      const created = await callControl('ccem.workspace.createSession', {
+        provider: args.provider,
+        preset: args.preset,
+        prompt: requireString(args, 'prompt'),
+        cwd: args.cwd,
+        envName: args.env_name,
+        permissionMode: args.permission_mode,
+      });
+      return {
+        runtime_id: created.runtimeId,
+        provider: created.provider,
+        cwd: created.cwd,
+        status: created.status,
+      };
+    }
+    case 'send_input':
+      return callControl('ccem.workspace.sendInput', {
+        runtimeId: requireString(args, 'runtime_id'),
+        text: requireString(args, 'text'),
+      });
+    case 'wait_for_idle': {
+      const timeoutSecs = Number(args.timeout_secs) > 0
+        ? Number(args.timeout_secs)
+        : DEFAULT_WAIT_TIMEOUT_SECS;
+      return waitForIdle(requireString(args, 'runtime_id'), timeoutSecs);
+    }
+    case 'get_transcript': {
+      const maxChars = Number(args.max_chars) > 0
+        ? Number(args.max_chars)
+        : DEFAULT_TRANSCRIPT_CHARS;
+      const sinceSeq = Number.isInteger(args.since_seq) ? args.since_seq : null;
+      return getTranscript(requireString(args, 'runtime_id'), sinceSeq, maxChars);
+    }
+    case 'stop_session':
+      return callControl('ccem.workspace.stopSession', {
+        runtimeId: requireString(args, 'runtime_id'),
+      });
+    default:
+      throw new Error(`Unknown tool: ${name}`);
+  }
+}
+
+async function handleToolCall(id, params) {
+  const name = params?.name;
+  if (!TOOLS.some((tool) => tool.name === name)) {
+    sendError(id, -32602, `Unknown tool: ${name}`);
+    return;
+  }
+
+  try {
+    const result = await runTool(name, params?.arguments ?? {});
+    send({
+      jsonrpc: '2.0',
+      id,
+      result: { content: [{ type: 'text', text: JSON.stringify(result, null, 2) }] },
+    });
+  } catch (error) {
+    send({
+      jsonrpc: '2.0',
+      id,
+      result: {
+        isError: true,
+        content: [{ type: 'text', text: error instanceof Error ? error.message : String(error) }],
+      },
+    });
+  }
+}
+
+async function handleMessage(message) {
+  if (message.method === 'initialize') {
+    const protocolVersion =
+      typeof message?.params?.protocolVersion === 'string'
+      && message.params.protocolVersion.length > 0
+        ? message.params.protocolVersion
+        : '2025-11-25';
+    send({
+      jsonrpc: '2.0',
+      id: message.id,
+      result: {
+        protocolVersion,
+        capabilities: { tools: {} },
+        serverInfo: { name: 'ccem-sessions-mcp', version: '0.1.0' },
+      },
+    });
+    return;
+  }
+
+  if (message.method === 'notifications/initialized') {
+    return;
+  }
+
+  if (message.method === 'tools/list') {
+    send({ jsonrpc: '2.0', id: message.id, result: { tools: TOOLS } });
+    return;
+  }
+
+  if (message.method === 'tools/call') {
+    await handleToolCall(message.id, message.params);
+    return;
+  }
+
+  if (typeof message.id !== 'undefined') {
+    sendError(message.id, -32601, `Method not found: ${message.method}`);
+  }
+}
+
+function processBuffer() {
+  while (true) {
+    const newlineIndex = buffer.indexOf('\n');
+    if (newlineIndex === -1) {
+      return;
+    }
+    const payload = buffer.slice(0, newlineIndex).toString('utf8').trim();
+    buffer = buffer.slice(newlineIndex + 1);
+    if (!payload) {
+      continue;
+    }
+    try {
+      void handleMessage(JSON.parse(payload));
+    } catch (error) {
+      console.error('Failed to process MCP message:', error);
+    }
+  }
+}
+
+process.stdin.on('data', (chunk) => {
+  buffer = Buffer.concat([buffer, chunk]);
+  processBuffer();
+});
+
+process.stdin.on('end', () => {
+  process.exit(0);
+});
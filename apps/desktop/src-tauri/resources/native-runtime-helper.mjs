@@ -42145,6 +42145,40 @@ function ensureClaudeSkillToolAllowed(allowedTools) {
   return [...allowedTools, "Skill"];
 }
 
+// src/sessionsMcp.ts
+var CCEM_SESSIONS_MCP_SERVER_NAME = "ccem-sessions";
+function sessionsMcpLaunch(init) {
+  return {
+    command: process.execPath,
+    args: [init.scriptPath],
+    env: {
+      CCEM_SESSIONS_MCP_ENDPOINT: init.endpoint,
+      CCEM_SESSIONS_MCP_TOKEN: init.token
+    }
+  };
+}
+function buildClaudeSessionsMcpServers(init) {
+  if (!init) {
+    return {};
+  }
+  return {
+    [CCEM_SESSIONS_MCP_SERVER_NAME]: {
+      type: "stdio",
+      ...sessionsMcpLaunch(init)
+    }
+  };
+}
+function buildCodexSessionsMcpConfig(init) {
+  if (!init) {
+    return void 0;
+  }
+  return {
+    mcp_servers: {
+      [CCEM_SESSIONS_MCP_SERVER_NAME]: sessionsMcpLaunch(init)
+    }
+  };
+}
+
 // src/promptContent.ts
 function escapeRegExp(value) {
   return value.replace(/[.*+?^${}()|[\]\\]/g, "\\$&");
@@ -43302,6 +43336,7 @@ var ClaudeBackgroundTaskTracker = class {
 // src/index.ts
 var DEFAULT_CLAUDE_IDLE_TTL_MS = 10 * 60 * 1e3;
 var CLAUDE_INCOMPLETE_RESPONSE_REASON = "Claude response ended before a final result. Partial output was preserved; send the next prompt to retry.";
+var FILE_WRITING_TOOL_NAMES = /* @__PURE__ */ new Set(["Edit", "MultiEdit", "Write", "NotebookEdit"]);
 var initCommand = null;
 var stopped = false;
 var activeTurn = false;
@@ -43820,6 +43855,8 @@ function nonEmptyEnvValue(envVars, key) {
 function resolveClaudeRuntimeModel(envVars) {
   return nonEmptyEnvValue(envVars, "ANTHROPIC_MODEL") || nonEmptyEnvValue(envVars, "ANTHROPIC_DEFAULT_OPUS_MODEL") || nonEmptyEnvValue(envVars, "ANTHROPIC_DEFAULT_SONNET_MODEL") || nonEmptyEnvValue(envVars, "ANTHROPIC_DEFAULT_HAIKU_MODEL") || nonEmptyEnvValue(envVars, "ANTHROPIC_SMALL_FAST_MODEL");
 }
+var TITLE_REQUIREMENTS = "\u8981\u6C42\uFF1A\u53EA\u8F93\u51FA\u6807\u9898\u672C\u8EAB\uFF1B\u4E2D\u6587 4 \u5230 12 \u4E2A\u5B57\u6216\u82F1\u6587 2 \u5230 6 \u4E2A\u8BCD\uFF1B\u4E0D\u8981\u5F15\u53F7\u3001\u6807\u70B9\u3001\u7F16\u53F7\u3001\u89E3\u91CA\u3001Markdown\u3002";
+var TITLE_WITH_TAGS_REQUIREMENTS = "\u8981\u6C42\uFF1A\u53EA\u8F93\u51FA\u4E24\u884C\u3002\u7B2C\u4E00\u884C\u662F\u6807\u9898\uFF1A\u4E2D\u6587 4 \u5230 12 \u4E2A\u5B57\u6216\u82F1\u6587 2 \u5230 6 \u4E2A\u8BCD\uFF0C\u4E0D\u8981\u5F15\u53F7\u3001\u6807\u70B9\u3001\u7F16\u53F7\u3001\u89E3\u91CA\u3001Markdown\uFF1B\u7B2C\u4E8C\u884C\u8F93\u51FA tags: \u52A0\u4E0A\u4ECE bugfix, feature, refactor, infra, docs, test \u4E2D\u9009\u51FA\u7684 1 \u5230 3 \u4E2A\u6807\u7B7E\uFF0C\u7528\u82F1\u6587\u9017\u53F7\u5206\u9694\u3002";
 async function runWorkspaceTitleQuery(command) {
   const titleInput = command.title_input.trim();
   if (!titleInput) {
@@ -43833,7 +43870,7 @@ async function runWorkspaceTitleQuery(command) {
   const model = command.model?.trim() || command.env_vars?.ANTHROPIC_MODEL?.trim() || command.env_vars?.ANTHROPIC_DEFAULT_HAIKU_MODEL?.trim() || command.env_vars?.ANTHROPIC_SMALL_FAST_MODEL?.trim() || "haiku";
   const prompt = [
     "\u8BF7\u6839\u636E\u4E0B\u9762\u8FD9\u6761\u5DE5\u4F5C\u95F4\u4F1A\u8BDD\u7684\u7528\u6237\u8BF7\u6C42\u751F\u6210\u4E00\u4E2A ProjectTree \u77ED\u6807\u9898\u3002",
-    "\u8981\u6C42\uFF1A\u53EA\u8F93\u51FA\u6807\u9898\u672C\u8EAB\uFF1B\u4E2D\u6587 4 \u5230 12 \u4E2A\u5B57\u6216\u82F1\u6587 2 \u5230 6 \u4E2A\u8BCD\uFF1B\u4E0D\u8981\u5F15\u53F7\u3001\u6807\u70B9\u3001\u7F16\u53F7\u3001\u89E3\u91CA\u3001Markdown\u3002",
+    command.include_tags ? TITLE_WITH_TAGS_REQUIREMENTS : TITLE_REQUIREMENTS,
     "",
     "\u7528\u6237\u8BF7\u6C42\uFF1A",
     titleInput
@@ -44120,6 +44157,7 @@ function emitClaudeToolUseStarted(payload) {
     raw_name: payload.rawName,
     input_summary: payload.inputSummary,
     needs_response: payload.needsResponse,
+    ...payload.input && FILE_WRITING_TOOL_NAMES.has(payload.rawName) ? { input: payload.input } : {},
     ...payload.prompt ? { prompt: payload.prompt } : {}
   });
 }
@@ -44754,7 +44792,8 @@ function buildClaudeQueryOptions() {
       "ccem-browser": createCcemBrowserMcpServer(
         () => initCommand?.perm_mode ?? "safe",
         browserToolBridge.sendBrowserToolRequest
-      )
+      ),
+      ...buildClaudeSessionsMcpServers(initCommand.sessions_mcp)
     },
     ...model ? { model } : {},
     ...routerSystemPrompt ? { systemPrompt: routerSystemPrompt } : {},
@@ -45543,7 +45582,8 @@ async function ensureCodexThread() {
       env: {
         ...process5.env,
         ...initCommand.env_vars
-      }
+      },
+      config: buildCodexSessionsMcpConfig(initCommand.sessions_mcp)
     });
   }
   if (!codexThread) {
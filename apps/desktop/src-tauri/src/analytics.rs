@@ -3,12 +3,15 @@
 // Native JSONL scanner for Claude, Codex, and OpenCode usage.
 
 use crate::config;
+use crate::event_bus::{RoutedUsageTotals, SessionEventPayload, SessionEventRecord};
+use crate::native_event_log::NativeEventLog;
 use crate::opencode;
+use crate::session_provenance;
 use chrono::{Datelike, Local, NaiveDate};
 use fs2::FileExt;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs::{self, File, OpenOptions};
 use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
 #[cfg(test)]
@@ -23,13 +26,33 @@ const SOURCE_OPENCODE: &str = "opencode";
 // Version 5 adds per-file parse continuation state to `CacheFileEntry`
 // (`parseOffset`, `lastLineComplete`, `codexState`, `claudeState`) and
 // supersedes the v4 usage-accounting changes (message.id final-snapshot
-// dedup, subagent transcript discovery). Caches stamped with an older
-// version are discarded on read and rebuilt from scratch (one-time full
-// parse after upgrade).
-const USAGE_CACHE_VERSION: u32 = 5;
+// dedup, subagent transcript discovery). Version 6 records the transcript
+// working directory (`project`) so exports can group usage by project.
+// Version 7 caches each OpenCode storage DB as one entry with its own
+// continuation state (`opencodeState`) instead of one entry per session.
+// Version 8 keeps per-message OpenCode usage, grouped by session
+// (`sessionEntryCounts`). Caches stamped with an older version are
+// discarded on read and rebuilt from scratch (one-time full parse after
+// upgrade).
+const USAGE_CACHE_VERSION: u32 = 8;
 const USAGE_SUMMARY_VERSION: u32 = 1;
 const USAGE_STATS_MEMO_TTL: Duration = Duration::from_secs(60);
 const OPENCODE_NATIVE_ENV_NAME: &str = opencode::OPENCODE_NATIVE_ENV_NAME;
+/// Cache key prefix for OpenCode storage DBs. Keeps them under the
+/// `opencode://` namespace that `detect_source_from_path` attributes.
+const OPENCODE_STORAGE_KEY_PREFIX: &str = "opencode://storage/";
+/// How far apart a transcript record and the router ledger entry for the
+/// same request may be: the transcript line lands when the stream ends, the
+/// ledger entry when the forwarded response completes.
+const ROUTER_MATCH_WINDOW_SECS: i64 = 120;
+/// A context snapshot below this fraction of the previous one is read as a
+/// compaction (manual `/compact` or auto-compact).
+const COMPACTION_DROP_RATIO: f64 = 0.5;
+/// A session is listed as repeatedly missing cache once this many requests
+/// after its first one wrote more cache than they read...
+const CACHE_MISS_REPEAT_MIN: u64 = 3;
+/// ...and those misses are at least this share of its follow-up requests.
+const CACHE_MISS_REPEAT_RATIO: f64 = 0.5;
 /// Shadow-compare gate: when enabled, every incremental parse also runs a
 /// full re-parse of the same file and compares the resulting `CacheStats`.
 /// Enabled in test builds (so the fixture matrix below is enforced on every
@@ -50,7 +73,7 @@ static USAGE_SNAPSHOT: OnceLock<Mutex<Option<UsageSnapshot>>> = OnceLock::new();
 // Output types — sent to frontend (must use camelCase)
 // ============================================================================
 
-#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct TokenUsageWithCost {
     pub input_tokens: u64,
@@ -111,6 +134,159 @@ pub struct UsageHistory {
     pub by_environment: HashMap<String, TokenUsageWithCost>,
 }
 
+/// One finance export row: usage summed per day × model × environment ×
+/// project for a single source.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageExportRow {
+    pub date: String,
+    pub source: String,
+    pub model: String,
+    pub environment: String,
+    pub project: String,
+    #[serde(flatten)]
+    pub usage: TokenUsageWithCost,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageExport {
+    pub format: String,
+    pub file_name: String,
+    pub row_count: usize,
+    pub content: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum UsageDiscrepancyKind {
+    /// Router and transcript saw the same request with different usage.
+    UsageMismatch,
+    /// The router forwarded a request whose usage no transcript accounts for.
+    RouterOnly,
+    /// Transcript usage inside a routed session with no router ledger entry.
+    TranscriptOnly,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageDiscrepancy {
+    pub kind: UsageDiscrepancyKind,
+    /// Provider session id (the transcript id).
+    pub session_id: String,
+    pub timestamp: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_env: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub router_tokens: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transcript_tokens: Option<u64>,
+}
+
+/// Outcome of re-attributing transcript usage with the router ledger for
+/// the current usage snapshot.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageReconciliation {
+    pub routed_sessions: u64,
+    pub matched_requests: u64,
+    /// Matched requests served by an env other than the launch env.
+    pub reattributed_requests: u64,
+    pub discrepancies: Vec<UsageDiscrepancy>,
+}
+
+/// Prompt-side token split for one bucket. `hit_ratio` is cache reads over
+/// all prompt tokens (uncached input + cache reads + cache writes).
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheEfficiency {
+    pub key: String,
+    pub requests: u64,
+    pub input_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub cache_creation_tokens: u64,
+    pub hit_ratio: f64,
+    /// Spend on cache writes (creation tokens at the model's write price).
+    pub cache_creation_cost: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionCacheEfficiency {
+    pub session_id: String,
+    pub source: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    #[serde(flatten)]
+    pub efficiency: CacheEfficiency,
+    /// Requests after the first that wrote more cache than they read.
+    pub miss_requests: u64,
+}
+
+/// Estimated savings from compactions seen in `ContextUsage` snapshots.
+/// Freed tokens would otherwise have been resent on every later turn; they
+/// are priced as cache reads, the cheapest way they could have been resent,
+/// so the estimate is a floor.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CompactionSavings {
+    pub compactions: u64,
+    pub tokens_freed: u64,
+    pub avoided_tokens: u64,
+    pub avoided_cost: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheInsights {
+    pub total: CacheEfficiency,
+    pub by_session: Vec<SessionCacheEfficiency>,
+    pub by_model: Vec<CacheEfficiency>,
+    pub by_day: Vec<CacheEfficiency>,
+    pub compaction: CompactionSavings,
+    /// Sessions that keep rebuilding their cache (e.g. a system prompt that
+    /// changes every turn), worst first.
+    pub repeated_miss_sessions: Vec<SessionCacheEfficiency>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum UsageExportFormat {
+    Csv,
+    Json,
+}
+
+impl UsageExportFormat {
+    fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "csv" => Ok(Self::Csv),
+            "json" => Ok(Self::Json),
+            other => Err(format!(
+                "Unsupported export format '{}'. Use csv or json.",
+                other
+            )),
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Json => "json",
+        }
+    }
+}
+
+/// Usage totals per source × model × environment, used by the Prometheus
+/// `/metrics` endpoint on the external control server.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UsageMetricSample {
+    pub source: &'static str,
+    pub model: String,
+    pub environment: String,
+    pub usage: TokenUsageWithCost,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum ModelBreakdownGranularity {
     Hour,
@@ -181,6 +357,9 @@ impl Default for CacheFile {
 //   growing usage; a later record must REPLACE the earlier entry, including
 //   across an incremental append boundary), so the dedup map must survive
 //   between incremental parses. Claude files store Some, codex files None.
+// - `opencode_state`: OpenCode storage DBs are not JSONL; they are refreshed
+//   by re-reading sessions updated since a high-water mark. Only storage DB
+//   entries store Some.
 #[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
 struct CacheFileEntry {
     #[serde(default)]
@@ -195,6 +374,12 @@ struct CacheFileEntry {
     codex_state: Option<CodexParseState>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     claude_state: Option<ClaudeParseState>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    opencode_state: Option<OpenCodeParseState>,
+    /// Working directory the transcript was recorded in (Claude/Codex `cwd`,
+    /// OpenCode session project). Used only for export grouping.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    project: Option<String>,
 }
 
 fn default_last_line_complete() -> bool {
@@ -202,9 +387,9 @@ fn default_last_line_complete() -> bool {
 }
 
 impl CacheFileEntry {
-    /// Entry for sources that are never incrementally parsed (opencode):
-    /// parse continuation fields get inert values.
-    fn from_meta_stats(meta: CacheMeta, stats: CacheStats) -> Self {
+    /// Entry for sources that are never incrementally parsed (OpenCode CLI
+    /// exports): parse continuation fields get inert values.
+    fn from_meta_stats(meta: CacheMeta, stats: CacheStats, project: Option<String>) -> Self {
         Self {
             meta,
             stats,
@@ -212,6 +397,8 @@ impl CacheFileEntry {
             last_line_complete: true,
             codex_state: None,
             claude_state: None,
+            opencode_state: None,
+            project,
         }
     }
 }
@@ -266,6 +453,10 @@ struct CacheUsage {
 struct ClaudeParseState {
     #[serde(default)]
     message_entry_indexes: HashMap<String, usize>,
+    /// First `cwd` seen in the transcript; carried across appends because
+    /// only early records are guaranteed to include it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cwd: Option<String>,
 }
 
 /// Codex parse continuation state persisted in the cache entry (see the
@@ -276,6 +467,23 @@ struct CodexParseState {
     current_model: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     last_total: Option<CodexTotals>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cwd: Option<String>,
+}
+
+/// OpenCode storage DB continuation state. Entries are grouped by root
+/// session in id order, and `session_entry_counts` says how many belong to
+/// each, so a re-read session's entries are REPLACED as a group and deleted
+/// sessions are dropped. `last_updated_at` is the highest raw
+/// `sessions.updated_at` seen; the next refresh re-reads sessions at or
+/// after it (inclusive, so a second update within the same tick is not
+/// missed — replacement keeps it idempotent).
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+struct OpenCodeParseState {
+    #[serde(default)]
+    last_updated_at: i64,
+    #[serde(default)]
+    session_entry_counts: BTreeMap<String, usize>,
 }
 
 // ============================================================================
@@ -458,13 +666,15 @@ fn calculate_cost_or_zero(
 }
 
 // ============================================================================
-// JSONL file discovery
+// Usage file discovery
 // ============================================================================
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum UsageSource {
     Claude,
     Codex,
+    /// OpenCode SQLite storage DB (one discovered file per DB).
+    OpenCode,
 }
 
 #[derive(Debug, Clone)]
@@ -473,13 +683,37 @@ struct DiscoveredFile {
     source: UsageSource,
 }
 
-fn discover_jsonl_files() -> Vec<DiscoveredFile> {
+fn discover_usage_files() -> Vec<DiscoveredFile> {
     let mut files = Vec::new();
     files.extend(discover_claude_jsonl_files());
     files.extend(discover_codex_jsonl_files());
+    files.extend(discover_opencode_storage_dbs());
     files
 }
 
+/// OpenCode storage DBs, located by the same discovery `opencode.rs` uses
+/// for local session history.
+fn discover_opencode_storage_dbs() -> Vec<DiscoveredFile> {
+    opencode::discover_storage_db_paths()
+        .into_iter()
+        .map(|path| DiscoveredFile {
+            path,
+            source: UsageSource::OpenCode,
+        })
+        .collect()
+}
+
+/// Key a discovered file is cached under. JSONL transcripts use their path
+/// (which `detect_source_from_path` recognises); storage DBs live anywhere,
+/// so they are namespaced under `opencode://`.
+fn usage_cache_key(discovered: &DiscoveredFile) -> String {
+    let path = discovered.path.to_string_lossy();
+    match discovered.source {
+        UsageSource::Claude | UsageSource::Codex => path.to_string(),
+        UsageSource::OpenCode => format!("{OPENCODE_STORAGE_KEY_PREFIX}{path}"),
+    }
+}
+
 /// Scan ~/.claude/projects/*/*.jsonl
 fn discover_claude_jsonl_files() -> Vec<DiscoveredFile> {
     let mut files = Vec::new();
@@ -589,6 +823,26 @@ fn get_file_meta(path: &PathBuf) -> Option<CacheMeta> {
     })
 }
 
+/// Storage DB meta folds in the `-wal` sidecar: in WAL mode new rows land
+/// in the sidecar and the main file is untouched until a checkpoint.
+fn get_storage_db_meta(path: &PathBuf) -> Option<CacheMeta> {
+    let mut meta = get_file_meta(path)?;
+    let mut wal_path = path.clone().into_os_string();
+    wal_path.push("-wal");
+    if let Some(wal_meta) = get_file_meta(&PathBuf::from(wal_path)) {
+        meta.mtime = meta.mtime.max(wal_meta.mtime);
+        meta.size += wal_meta.size;
+    }
+    Some(meta)
+}
+
+fn get_discovered_meta(discovered: &DiscoveredFile) -> Option<CacheMeta> {
+    match discovered.source {
+        UsageSource::Claude | UsageSource::Codex => get_file_meta(&discovered.path),
+        UsageSource::OpenCode => get_storage_db_meta(&discovered.path),
+    }
+}
+
 // ============================================================================
 // Claude JSONL parsing
 // ============================================================================
@@ -598,6 +852,7 @@ struct ClaudeJsonlLine {
     #[serde(rename = "type")]
     entry_type: Option<String>,
     timestamp: Option<String>,
+    cwd: Option<String>,
     message: Option<ClaudeJsonlMessage>,
 }
 
@@ -633,6 +888,10 @@ fn accumulate_claude_line(
         Err(_) => return,
     };
 
+    if state.cwd.is_none() {
+        state.cwd = parsed.cwd.clone().filter(|cwd| !cwd.trim().is_empty());
+    }
+
     if parsed.entry_type.as_deref() != Some("assistant") {
         return;
     }
@@ -803,14 +1062,16 @@ fn accumulate_codex_line(
     let payload = parsed.get("payload").unwrap_or(&serde_json::Value::Null);
 
     match line_type {
-        "session_meta" => {
+        "session_meta" | "turn_context" => {
             if let Some(model) = payload.get("model").and_then(|v| v.as_str()) {
                 state.current_model = Some(model.to_string());
             }
-        }
-        "turn_context" => {
-            if let Some(model) = payload.get("model").and_then(|v| v.as_str()) {
-                state.current_model = Some(model.to_string());
+            if state.cwd.is_none() {
+                state.cwd = payload
+                    .get("cwd")
+                    .and_then(|v| v.as_str())
+                    .filter(|cwd| !cwd.trim().is_empty())
+                    .map(|cwd| cwd.to_string());
             }
         }
         "event_msg" => {
@@ -967,6 +1228,8 @@ fn parse_jsonl_tail(
                 UsageSource::Codex => {
                     accumulate_codex_line(&mut codex_state, &mut entries, line, prices)
                 }
+                // Storage DBs go through `refresh_opencode_storage_entry`.
+                UsageSource::OpenCode => {}
             }
         }
         line_start = index + 1;
@@ -1040,24 +1303,32 @@ fn source_aware_entry(
     claude_state: Option<ClaudeParseState>,
     source: UsageSource,
 ) -> CacheFileEntry {
+    let project = match source {
+        UsageSource::Claude => claude_state.as_ref().and_then(|state| state.cwd.clone()),
+        UsageSource::Codex => codex_state.as_ref().and_then(|state| state.cwd.clone()),
+        UsageSource::OpenCode => None,
+    };
     CacheFileEntry {
         meta,
         stats,
         parse_offset,
         last_line_complete,
+        project,
         codex_state: match source {
-            UsageSource::Claude => None,
             UsageSource::Codex => Some(codex_state.unwrap_or_default()),
+            UsageSource::Claude | UsageSource::OpenCode => None,
         },
         claude_state: match source {
             UsageSource::Claude => Some(claude_state.unwrap_or_default()),
-            UsageSource::Codex => None,
+            UsageSource::Codex | UsageSource::OpenCode => None,
         },
+        opencode_state: None,
     }
 }
 
 /// Decide per discovered file: reuse cached stats (meta match), extend them
-/// incrementally (strict growth), or fall back to a full re-parse.
+/// incrementally (strict growth), or fall back to a full re-parse. OpenCode
+/// storage DBs take their own session-level path.
 fn refresh_discovered_entry(
     cached: Option<&CacheFileEntry>,
     discovered: &DiscoveredFile,
@@ -1068,6 +1339,11 @@ fn refresh_discovered_entry(
         if (cached.meta.mtime - meta.mtime).abs() < 1.0 && cached.meta.size == meta.size {
             return cached.clone();
         }
+    }
+    if discovered.source == UsageSource::OpenCode {
+        return refresh_opencode_storage_entry(cached, &discovered.path, meta, prices);
+    }
+    if let Some(cached) = cached {
         if let Some(entry) = incremental_refresh_entry(cached, discovered, &meta, prices) {
             return entry;
         }
@@ -1298,9 +1574,11 @@ fn lock_usage_refresh() -> MutexGuard<'static, ()> {
 // ============================================================================
 
 /// Process-wide shared cache snapshot. Entry stats are read-only after
-/// build; all four usage commands aggregate from the same `Arc`.
+/// build; all four usage commands aggregate from the same `Arc`. Entry
+/// environments are already reconciled with the router ledger.
 struct UsageSnapshot {
     cache: Arc<CacheFile>,
+    reconciliation: Arc<UsageReconciliation>,
     collected_at: Instant,
 }
 
@@ -1321,9 +1599,10 @@ fn snapshot_is_fresh(collected_at: Instant) -> bool {
     collected_at.elapsed() < USAGE_STATS_MEMO_TTL
 }
 
-/// Build a fresh cache snapshot. Tests can stub this out (with a call
-/// counter) to observe how often a real refresh runs.
-fn collect_usage_snapshot() -> CacheFile {
+/// Build a fresh cache snapshot and reconcile it with the router ledger.
+/// Tests can stub this out (with a call counter) to observe how often a
+/// real refresh runs.
+fn collect_usage_snapshot() -> (CacheFile, UsageReconciliation) {
     #[cfg(test)]
     {
         let stub = *TEST_SNAPSHOT_REFRESH
@@ -1331,10 +1610,12 @@ fn collect_usage_snapshot() -> CacheFile {
             .lock()
             .unwrap_or_else(|poisoned| poisoned.into_inner());
         if let Some(stub) = stub {
-            return stub();
+            return (stub(), UsageReconciliation::default());
         }
     }
-    refresh_usage_cache()
+    let mut cache = refresh_usage_cache();
+    let reconciliation = reconcile_router_attribution(&mut cache, &load_router_attribution());
+    (cache, reconciliation)
 }
 
 /// Single-flight shared snapshot: fresh within TTL it is returned as-is;
@@ -1351,11 +1632,17 @@ fn collect_usage_snapshot() -> CacheFile {
 /// refresh — so concurrent force requests still merge into a single
 /// collection. `None` keeps the plain TTL behaviour.
 fn shared_usage_cache(min_collected_at: Option<Instant>) -> Arc<CacheFile> {
+    shared_usage_snapshot(min_collected_at).0
+}
+
+fn shared_usage_snapshot(
+    min_collected_at: Option<Instant>,
+) -> (Arc<CacheFile>, Arc<UsageReconciliation>) {
     {
         let snapshot = lock_usage_snapshot();
         if let Some(current) = snapshot.as_ref() {
             if snapshot_satisfies_request(current.collected_at, min_collected_at) {
-                return current.cache.clone();
+                return (current.cache.clone(), current.reconciliation.clone());
             }
         }
     }
@@ -1367,17 +1654,20 @@ fn shared_usage_cache(min_collected_at: Option<Instant>) -> Arc<CacheFile> {
         let snapshot = lock_usage_snapshot();
         if let Some(current) = snapshot.as_ref() {
             if snapshot_satisfies_request(current.collected_at, min_collected_at) {
-                return current.cache.clone();
+                return (current.cache.clone(), current.reconciliation.clone());
             }
         }
     }
 
-    let cache = Arc::new(collect_usage_snapshot());
+    let (cache, reconciliation) = collect_usage_snapshot();
+    let cache = Arc::new(cache);
+    let reconciliation = Arc::new(reconciliation);
     *lock_usage_snapshot() = Some(UsageSnapshot {
         cache: cache.clone(),
+        reconciliation: reconciliation.clone(),
         collected_at: Instant::now(),
     });
-    cache
+    (cache, reconciliation)
 }
 
 /// Non-force requests reuse any snapshot inside the TTL window. Force
@@ -1461,7 +1751,7 @@ fn refresh_usage_cache() -> CacheFile {
 
 fn refresh_usage_cache_locked() -> CacheFile {
     let prices = load_model_prices();
-    let jsonl_files = discover_jsonl_files();
+    let usage_files = discover_usage_files();
     let existing_cache = read_usage_cache();
 
     let mut new_cache = CacheFile {
@@ -1470,10 +1760,10 @@ fn refresh_usage_cache_locked() -> CacheFile {
         last_updated: Some(Local::now().to_rfc3339()),
     };
 
-    for discovered in jsonl_files {
-        let path_str = discovered.path.to_string_lossy().to_string();
+    for discovered in usage_files {
+        let path_str = usage_cache_key(&discovered);
 
-        let meta = match get_file_meta(&discovered.path) {
+        let meta = match get_discovered_meta(&discovered) {
             Some(m) => m,
             None => continue,
         };
@@ -1484,7 +1774,17 @@ fn refresh_usage_cache_locked() -> CacheFile {
         new_cache.files.insert(path_str, entry);
     }
 
-    for (path_key, entry) in load_opencode_cache_entries(&prices, &existing_cache) {
+    // Sessions already read from a storage DB must not be counted again from
+    // an OpenCode CLI export.
+    let storage_session_ids = new_cache
+        .files
+        .values()
+        .filter_map(|entry| entry.opencode_state.as_ref())
+        .flat_map(|state| state.session_entry_counts.keys().cloned())
+        .collect::<HashSet<_>>();
+    for (path_key, entry) in
+        load_opencode_cache_entries(&prices, &existing_cache, &storage_session_ids)
+    {
         new_cache.files.insert(path_key, entry);
     }
 
@@ -1494,36 +1794,33 @@ fn refresh_usage_cache_locked() -> CacheFile {
     new_cache
 }
 
+/// OpenCode sessions only reachable through the CLI (`opencode session
+/// list` + export), i.e. not present in any discovered storage DB.
 fn load_opencode_cache_entries(
     prices: &HashMap<String, ModelPrice>,
     existing_cache: &CacheFile,
+    storage_session_ids: &HashSet<String>,
 ) -> HashMap<String, CacheFileEntry> {
-    let local_sessions = opencode::list_local_sessions()
-        .unwrap_or_default()
-        .into_iter()
-        .map(|session| (session.id.clone(), session))
-        .collect::<HashMap<_, _>>();
-
     let Some(session_list) = opencode::load_session_list_value_from_cli_or_fixture()
         .ok()
         .flatten()
     else {
-        return build_local_opencode_cache_entries(&local_sessions, existing_cache);
+        return HashMap::new();
     };
 
     let Some(items) = parse_opencode_session_items(&session_list) else {
-        return build_local_opencode_cache_entries(&local_sessions, existing_cache);
+        return HashMap::new();
     };
 
     let mut entries = HashMap::new();
     for session in items {
+        if storage_session_ids.contains(&session.id) {
+            continue;
+        }
+
         let path_key = format!("opencode://session/{}", session.id);
-        let local_session = local_sessions.get(&session.id);
         let meta = CacheMeta {
-            mtime: session
-                .updated_at
-                .or_else(|| local_session.map(|item| item.updated_at))
-                .unwrap_or(0) as f64,
+            mtime: session.updated_at.unwrap_or(0) as f64,
             size: 0,
         };
 
@@ -1538,35 +1835,13 @@ fn load_opencode_cache_entries(
                 .ok()
                 .flatten()
                 .map(|value| parse_opencode_export_stats(&value, &session.environment, prices))
-                .or_else(|| local_session.map(local_opencode_session_to_cache_stats))
                 .unwrap_or_default()
         };
 
-        entries.insert(path_key, CacheFileEntry::from_meta_stats(meta, stats));
-    }
-
-    for (session_id, session) in &local_sessions {
-        let path_key = format!("opencode://session/{session_id}");
-        if entries.contains_key(&path_key) {
-            continue;
-        }
-
-        let meta = CacheMeta {
-            mtime: session.updated_at as f64,
-            size: 0,
-        };
-
-        let cache_valid = existing_cache.files.get(&path_key).is_some_and(|cached| {
-            (cached.meta.mtime - meta.mtime).abs() < 1.0 && cached.meta.size == meta.size
-        });
-
-        let stats = if cache_valid {
-            existing_cache.files[&path_key].stats.clone()
-        } else {
-            local_opencode_session_to_cache_stats(session)
-        };
-
-        entries.insert(path_key, CacheFileEntry::from_meta_stats(meta, stats));
+        entries.insert(
+            path_key,
+            CacheFileEntry::from_meta_stats(meta, stats, session.project.clone()),
+        );
     }
 
     entries
@@ -1577,60 +1852,161 @@ struct OpenCodeSessionItem {
     id: String,
     updated_at: Option<u64>,
     environment: String,
+    project: Option<String>,
 }
 
-fn build_local_opencode_cache_entries(
-    local_sessions: &HashMap<String, opencode::LocalOpenCodeSession>,
-    existing_cache: &CacheFile,
-) -> HashMap<String, CacheFileEntry> {
-    let mut entries = HashMap::new();
-
-    for session in local_sessions.values() {
-        let path_key = format!("opencode://session/{}", session.id);
-        let meta = CacheMeta {
-            mtime: session.updated_at as f64,
-            size: 0,
-        };
+/// Refresh one OpenCode storage DB. With a cached state, only sessions
+/// updated at or after its high-water mark are re-read and their entries
+/// replaced; sessions no longer in the DB lose theirs. A DB that shrank
+/// (vacuum, WAL checkpoint) or has no state is read in full.
+fn refresh_opencode_storage_entry(
+    cached: Option<&CacheFileEntry>,
+    path: &Path,
+    meta: CacheMeta,
+    prices: &HashMap<String, ModelPrice>,
+) -> CacheFileEntry {
+    let (entries, mut state, mut project) = cached
+        .filter(|cached| meta.size >= cached.meta.size)
+        .and_then(|cached| {
+            Some((
+                cached.stats.entries.clone(),
+                cached.opencode_state.clone()?,
+                cached.project.clone(),
+            ))
+        })
+        .filter(|(entries, state, _)| {
+            state.session_entry_counts.values().sum::<usize>() == entries.len()
+        })
+        .unwrap_or_default();
 
-        let cache_valid = existing_cache.files.get(&path_key).is_some_and(|cached| {
-            (cached.meta.mtime - meta.mtime).abs() < 1.0 && cached.meta.size == meta.size
-        });
+    let warn = |error: String| {
+        eprintln!("OpenCode usage warning for {}: {error}", path.display());
+        cached.cloned().unwrap_or_default()
+    };
+    let sessions = match opencode::list_storage_sessions_since(path, state.last_updated_at) {
+        Ok(sessions) => sessions,
+        Err(error) => return warn(error),
+    };
+    let live_session_ids = match opencode::list_storage_root_session_ids(path) {
+        Ok(ids) => ids,
+        Err(error) => return warn(error),
+    };
+    let session_ids = sessions
+        .iter()
+        .map(|stored| stored.session.id.clone())
+        .collect::<Vec<_>>();
+    let mut messages = match opencode::list_storage_messages(path, &session_ids) {
+        Ok(messages) => messages,
+        Err(error) => return warn(error),
+    };
 
-        let stats = if cache_valid {
-            existing_cache.files[&path_key].stats.clone()
-        } else {
-            local_opencode_session_to_cache_stats(session)
-        };
+    let mut entries = entries.into_iter();
+    let mut by_session = state
+        .session_entry_counts
+        .iter()
+        .map(|(session_id, count)| {
+            (
+                session_id.clone(),
+                entries.by_ref().take(*count).collect::<Vec<_>>(),
+            )
+        })
+        .collect::<BTreeMap<_, _>>();
+    by_session.retain(|session_id, _| live_session_ids.contains(session_id));
 
-        entries.insert(path_key, CacheFileEntry::from_meta_stats(meta, stats));
+    for stored in sessions {
+        state.last_updated_at = state.last_updated_at.max(stored.raw_updated_at);
+        if project.is_none() {
+            project = stored.session.project.clone();
+        }
+        let session_messages = messages.remove(&stored.session.id).unwrap_or_default();
+        let session_entries =
+            opencode_session_cache_entries(&stored.session, &session_messages, prices);
+        by_session.insert(stored.session.id, session_entries);
     }
 
-    entries
+    state.session_entry_counts = by_session
+        .iter()
+        .map(|(session_id, entries)| (session_id.clone(), entries.len()))
+        .collect();
+    CacheFileEntry {
+        meta,
+        stats: CacheStats {
+            entries: by_session.into_values().flatten().collect(),
+        },
+        parse_offset: 0,
+        last_line_complete: true,
+        codex_state: None,
+        claude_state: None,
+        opencode_state: Some(state),
+        project,
+    }
 }
 
-fn local_opencode_session_to_cache_stats(session: &opencode::LocalOpenCodeSession) -> CacheStats {
+/// Usage entries for one storage DB session: one per usage report found in
+/// its messages, at the message's own time and with its cache tokens. When
+/// no message reports usage, the session's running totals become a single
+/// entry instead. OpenCode records `cost` only for priced providers;
+/// otherwise it is estimated from the model price table.
+fn opencode_session_cache_entries(
+    session: &opencode::LocalOpenCodeSession,
+    messages: &[opencode::StoredOpenCodeMessage],
+    prices: &HashMap<String, ModelPrice>,
+) -> Vec<CacheEntry> {
+    let environment = session
+        .env_name
+        .clone()
+        .unwrap_or_else(|| OPENCODE_NATIVE_ENV_NAME.to_string());
+
+    let mut entries = Vec::new();
+    let mut seen = HashSet::new();
+    for message in messages {
+        collect_opencode_usage_entries(
+            &message.parts,
+            message.model.clone().or_else(|| session.model.clone()),
+            Some(message.timestamp),
+            &environment,
+            prices,
+            &mut seen,
+            &mut entries,
+        );
+    }
+    if !entries.is_empty() {
+        return entries;
+    }
+
     let timestamp_ms = session.updated_at.max(session.created_at);
     let timestamp = chrono::DateTime::<chrono::Utc>::from_timestamp_millis(timestamp_ms as i64)
         .map(|value| value.to_rfc3339())
         .unwrap_or_else(|| chrono::Utc::now().to_rfc3339());
+    let model = session
+        .model
+        .clone()
+        .unwrap_or_else(|| "unknown".to_string());
+    let cost = if session.cost > 0.0 {
+        session.cost
+    } else {
+        calculate_cost_or_zero(
+            &model,
+            session.prompt_tokens,
+            session.completion_tokens,
+            0,
+            0,
+            prices,
+        )
+    };
 
-    CacheStats {
-        entries: vec![CacheEntry {
-            timestamp,
-            model: session
-                .model
-                .clone()
-                .unwrap_or_else(|| "unknown".to_string()),
-            environment: session.env_name.clone(),
-            usage: CacheUsage {
-                input_tokens: session.prompt_tokens,
-                output_tokens: session.completion_tokens,
-                cache_read_tokens: 0,
-                cache_creation_tokens: 0,
-                cost: session.cost,
-            },
-        }],
-    }
+    vec![CacheEntry {
+        timestamp,
+        model,
+        environment: Some(environment),
+        usage: CacheUsage {
+            input_tokens: session.prompt_tokens,
+            output_tokens: session.completion_tokens,
+            cache_read_tokens: 0,
+            cache_creation_tokens: 0,
+            cost,
+        },
+    }]
 }
 
 fn parse_opencode_session_items(value: &Value) -> Option<Vec<OpenCodeSessionItem>> {
@@ -1664,6 +2040,7 @@ fn parse_opencode_session_items(value: &Value) -> Option<Vec<OpenCodeSessionItem
             id,
             updated_at: extract_opencode_timestamp(item),
             environment,
+            project: extract_opencode_string(item, &["directory", "cwd", "projectPath"]),
         });
     }
 
@@ -1677,13 +2054,24 @@ fn parse_opencode_export_stats(
 ) -> CacheStats {
     let mut entries = Vec::new();
     let mut seen = HashSet::new();
-    collect_opencode_usage_entries(value, None, environment, prices, &mut seen, &mut entries);
+    collect_opencode_usage_entries(
+        value,
+        None,
+        None,
+        environment,
+        prices,
+        &mut seen,
+        &mut entries,
+    );
     CacheStats { entries }
 }
 
+/// `fallback_timestamp` (milliseconds) stamps usage nodes that carry no
+/// time of their own.
 fn collect_opencode_usage_entries(
     value: &Value,
     current_model: Option<String>,
+    fallback_timestamp: Option<u64>,
     environment: &str,
     prices: &HashMap<String, ModelPrice>,
     seen: &mut HashSet<String>,
@@ -1695,6 +2083,7 @@ fn collect_opencode_usage_entries(
                 collect_opencode_usage_entries(
                     item,
                     current_model.clone(),
+                    fallback_timestamp,
                     environment,
                     prices,
                     seen,
@@ -1704,9 +2093,13 @@ fn collect_opencode_usage_entries(
         }
         Value::Object(object) => {
             let next_model = extract_opencode_string(value, &["model"]).or(current_model.clone());
-            if let Some(entry) =
-                build_opencode_cache_entry(value, next_model.as_deref(), environment, prices)
-            {
+            if let Some(entry) = build_opencode_cache_entry(
+                value,
+                next_model.as_deref(),
+                fallback_timestamp,
+                environment,
+                prices,
+            ) {
                 let fingerprint = format!(
                     "{}|{}|{}|{}|{}|{}|{}",
                     entry.timestamp,
@@ -1726,6 +2119,7 @@ fn collect_opencode_usage_entries(
                 collect_opencode_usage_entries(
                     child,
                     next_model.clone(),
+                    fallback_timestamp,
                     environment,
                     prices,
                     seen,
@@ -1740,6 +2134,7 @@ fn collect_opencode_usage_entries(
 fn build_opencode_cache_entry(
     value: &Value,
     fallback_model: Option<&str>,
+    fallback_timestamp: Option<u64>,
     environment: &str,
     prices: &HashMap<String, ModelPrice>,
 ) -> Option<CacheEntry> {
@@ -1747,7 +2142,11 @@ fn build_opencode_cache_entry(
         .get("usage")
         .or_else(|| value.get("tokens"))
         .or_else(|| value.get("stats"))?;
-    let usage = parse_opencode_cache_usage(usage_node)?;
+    let mut usage = parse_opencode_cache_usage(usage_node)?;
+    // OpenCode messages keep `cost` next to `tokens` rather than inside it.
+    if usage.cost == 0.0 {
+        usage.cost = value.get("cost").and_then(Value::as_f64).unwrap_or(0.0);
+    }
     if usage.input_tokens == 0
         && usage.output_tokens == 0
         && usage.cache_read_tokens == 0
@@ -1758,6 +2157,7 @@ fn build_opencode_cache_entry(
     }
 
     let timestamp = extract_opencode_timestamp(value)
+        .or(fallback_timestamp)
         .and_then(|timestamp| {
             chrono::DateTime::<chrono::Utc>::from_timestamp_millis(timestamp as i64)
         })
@@ -1795,28 +2195,35 @@ fn build_opencode_cache_entry(
     })
 }
 
+/// Accepts both the export's flat counters and OpenCode's message token
+/// shape (`input`, `output`, `cache: { read, write }`).
 fn parse_opencode_cache_usage(value: &Value) -> Option<CacheUsage> {
     let object = value.as_object()?;
+    let cache = object.get("cache");
 
     Some(CacheUsage {
         input_tokens: object
             .get("inputTokens")
             .or_else(|| object.get("input_tokens"))
+            .or_else(|| object.get("input"))
             .and_then(|value| value.as_u64())
             .unwrap_or(0),
         output_tokens: object
             .get("outputTokens")
             .or_else(|| object.get("output_tokens"))
+            .or_else(|| object.get("output"))
             .and_then(|value| value.as_u64())
             .unwrap_or(0),
         cache_read_tokens: object
             .get("cacheReadTokens")
             .or_else(|| object.get("cache_read_tokens"))
+            .or_else(|| cache.and_then(|cache| cache.get("read")))
             .and_then(|value| value.as_u64())
             .unwrap_or(0),
         cache_creation_tokens: object
             .get("cacheCreationTokens")
             .or_else(|| object.get("cache_creation_tokens"))
+            .or_else(|| cache.and_then(|cache| cache.get("write")))
             .and_then(|value| value.as_u64())
             .unwrap_or(0),
         cost: object
@@ -1876,71 +2283,587 @@ fn normalize_unix_timestamp(timestamp: u64) -> u64 {
 }
 
 // ============================================================================
-// Timestamp helpers
+// Router reconciliation
 // ============================================================================
 
-fn parse_to_local(timestamp: &str) -> Option<chrono::DateTime<Local>> {
-    if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(timestamp) {
-        return Some(dt.into());
-    }
-
-    if let Ok(dt) = chrono::NaiveDateTime::parse_from_str(
-        timestamp.trim_end_matches('Z'),
-        "%Y-%m-%dT%H:%M:%S%.f",
-    ) {
-        let utc_dt = dt.and_utc();
-        return Some(utc_dt.into());
-    }
+/// One router ledger entry (`RoutedRequest`), reduced to what
+/// reconciliation needs.
+#[derive(Debug, Clone)]
+struct RouterLedgerEntry {
+    occurred_at: chrono::DateTime<chrono::Utc>,
+    target_env: String,
+    model: Option<String>,
+    status: u16,
+    complete: bool,
+    usage: Option<RoutedUsageTotals>,
+}
 
-    None
+/// CCEM-side attribution for one provider session, keyed by the provider's
+/// own session id: the env it was launched with and its runtime's router
+/// ledger.
+#[derive(Debug, Clone, Default)]
+struct SessionAttribution {
+    launch_env: Option<String>,
+    routed: Vec<RouterLedgerEntry>,
 }
 
-fn extract_date(timestamp: &str) -> Option<String> {
-    if let Some(local_dt) = parse_to_local(timestamp) {
-        return Some(local_dt.format("%Y-%m-%d").to_string());
+/// Join session provenance (provider session id ↔ CCEM runtime id + launch
+/// env) with the persisted router ledger.
+fn load_router_attribution() -> HashMap<String, SessionAttribution> {
+    let mut sessions: HashMap<String, SessionAttribution> = HashMap::new();
+    let mut runtime_sessions: HashMap<String, String> = HashMap::new();
+    for client in [SOURCE_CLAUDE, SOURCE_CODEX] {
+        let Ok(records) = session_provenance::list_records_by_client(client) else {
+            continue;
+        };
+        for (source_session_id, record) in records {
+            runtime_sessions.insert(record.ccem_session_id, source_session_id.clone());
+            let launch_env =
+                Some(record.env_name.trim().to_string()).filter(|value| !value.is_empty());
+            sessions.entry(source_session_id).or_default().launch_env = launch_env;
+        }
+    }
+    if runtime_sessions.is_empty() {
+        return sessions;
     }
 
-    if timestamp.len() >= 10 {
-        Some(timestamp[..10].to_string())
-    } else {
-        None
+    let ledger = match NativeEventLog::default().routed_request_records() {
+        Ok(ledger) => ledger,
+        Err(error) => {
+            eprintln!("Usage reconciliation warning: {error}");
+            return sessions;
+        }
+    };
+    for record in ledger {
+        let Some(session_id) = runtime_sessions.get(&record.runtime_id) else {
+            continue;
+        };
+        let SessionEventPayload::RoutedRequest {
+            target_env,
+            model,
+            status,
+            complete,
+            usage,
+            ..
+        } = record.payload
+        else {
+            continue;
+        };
+        sessions
+            .entry(session_id.clone())
+            .or_default()
+            .routed
+            .push(RouterLedgerEntry {
+                occurred_at: record.occurred_at,
+                target_env,
+                model,
+                status,
+                complete,
+                usage,
+            });
     }
+    sessions
 }
 
-fn extract_hour(timestamp: &str) -> Option<String> {
-    parse_to_local(timestamp).map(|dt| dt.format("%Y-%m-%dT%H").to_string())
+/// Provider session id a transcript cache key belongs to: the Claude
+/// transcript stem (subagent transcripts under `<session-id>/subagents/`
+/// belong to their parent), or the trailing UUID of a Codex rollout file.
+fn transcript_session_id(path_key: &str) -> Option<String> {
+    let path = Path::new(path_key);
+    match detect_source_from_path(path_key)? {
+        SOURCE_CLAUDE => {
+            let parent = path.parent()?;
+            if parent.file_name().and_then(|name| name.to_str()) == Some("subagents") {
+                return parent.parent()?.file_name()?.to_str().map(str::to_string);
+            }
+            path.file_stem()?.to_str().map(str::to_string)
+        }
+        SOURCE_CODEX => {
+            let stem = path.file_stem()?.to_str()?;
+            stem.get(stem.len().checked_sub(36)?..).map(str::to_string)
+        }
+        _ => None,
+    }
 }
 
-fn format_week_bucket(date: NaiveDate) -> String {
-    let jan1 = NaiveDate::from_ymd_opt(date.year(), 1, 1).unwrap_or(date);
-    let days_since_jan1 = date.signed_duration_since(jan1).num_days();
-    let week_num =
-        ((days_since_jan1 + jan1.weekday().num_days_from_monday() as i64 + 7) / 7) as u32;
-    format!("{}-W{:02}", date.year(), week_num)
+fn routed_usage_tokens(usage: &RoutedUsageTotals) -> (u64, u64, u64, u64) {
+    (
+        usage.input_tokens,
+        usage.output_tokens,
+        usage.cache_read_tokens,
+        usage.cache_creation_tokens,
+    )
 }
 
-fn extract_model_breakdown_bucket(
-    timestamp: &str,
-    granularity: ModelBreakdownGranularity,
-) -> Option<String> {
-    match granularity {
-        ModelBreakdownGranularity::Hour => extract_hour(timestamp),
-        ModelBreakdownGranularity::Day => extract_date(timestamp),
-        ModelBreakdownGranularity::Week => {
-            let date = NaiveDate::parse_from_str(&extract_date(timestamp)?, "%Y-%m-%d").ok()?;
-            Some(format_week_bucket(date))
-        }
-        ModelBreakdownGranularity::Month => {
-            let date = NaiveDate::parse_from_str(&extract_date(timestamp)?, "%Y-%m-%d").ok()?;
-            Some(date.format("%Y-%m").to_string())
-        }
-    }
+fn cache_usage_tokens(usage: &CacheUsage) -> (u64, u64, u64, u64) {
+    (
+        usage.input_tokens,
+        usage.output_tokens,
+        usage.cache_read_tokens,
+        usage.cache_creation_tokens,
+    )
 }
 
-fn retain_latest_keys(breakdown: &mut ModelBreakdownHistory, max_entries: usize) {
-    if breakdown.len() <= max_entries {
-        return;
-    }
+fn token_sum((input, output, cache_read, cache_creation): (u64, u64, u64, u64)) -> u64 {
+    input + output + cache_read + cache_creation
+}
+
+/// A transcript record of a routed session, addressed by cache key + index.
+struct TranscriptRecordRef {
+    path_key: String,
+    index: usize,
+    at: chrono::DateTime<chrono::Utc>,
+    tokens: (u64, u64, u64, u64),
+}
+
+/// Re-attribute transcript usage with router ledger truth. Claude/Codex
+/// transcripts carry no env, so every record of a CCEM-launched session
+/// first takes the launch env; records the router saw then take the env
+/// that actually served them. Matching is per session across the main and
+/// subagent transcripts: identical usage within the window first, then the
+/// nearest remaining ledger entry within the window (flagged as a usage
+/// mismatch). Ledger entries with usage that nothing matched, and transcript
+/// records inside the routed time span that nothing matched, are flagged.
+fn reconcile_router_attribution(
+    cache: &mut CacheFile,
+    attribution: &HashMap<String, SessionAttribution>,
+) -> UsageReconciliation {
+    let mut report = UsageReconciliation::default();
+    let mut session_keys: HashMap<String, Vec<String>> = HashMap::new();
+    for path_key in cache.files.keys() {
+        if let Some(session_id) = transcript_session_id(path_key) {
+            if attribution.contains_key(&session_id) {
+                session_keys
+                    .entry(session_id)
+                    .or_default()
+                    .push(path_key.clone());
+            }
+        }
+    }
+    let mut session_ids = session_keys.keys().cloned().collect::<Vec<_>>();
+    session_ids.sort();
+
+    let window = chrono::Duration::seconds(ROUTER_MATCH_WINDOW_SECS);
+    for session_id in session_ids {
+        let session = &attribution[&session_id];
+        let mut path_keys = session_keys.remove(&session_id).unwrap_or_default();
+        path_keys.sort();
+
+        let mut transcript = Vec::new();
+        for path_key in &path_keys {
+            let Some(file) = cache.files.get_mut(path_key) else {
+                continue;
+            };
+            for (index, entry) in file.stats.entries.iter_mut().enumerate() {
+                if entry.environment.is_none() {
+                    entry.environment = session.launch_env.clone();
+                }
+                if let Ok(at) = chrono::DateTime::parse_from_rfc3339(&entry.timestamp) {
+                    transcript.push(TranscriptRecordRef {
+                        path_key: path_key.clone(),
+                        index,
+                        at: at.with_timezone(&chrono::Utc),
+                        tokens: cache_usage_tokens(&entry.usage),
+                    });
+                }
+            }
+        }
+        if session.routed.is_empty() {
+            continue;
+        }
+        report.routed_sessions += 1;
+        transcript.sort_by_key(|record| record.at);
+
+        let mut router_used = vec![false; session.routed.len()];
+        let mut matches: Vec<Option<usize>> = vec![None; transcript.len()];
+        for exact in [true, false] {
+            for (record_index, record) in transcript.iter().enumerate() {
+                if matches[record_index].is_some() {
+                    continue;
+                }
+                let nearest = session
+                    .routed
+                    .iter()
+                    .enumerate()
+                    .filter(|(ledger_index, ledger)| {
+                        !router_used[*ledger_index]
+                            && (ledger.occurred_at - record.at).abs() <= window
+                            && (!exact
+                                || ledger.usage.as_ref().map(routed_usage_tokens)
+                                    == Some(record.tokens))
+                    })
+                    .min_by_key(|(_, ledger)| (ledger.occurred_at - record.at).abs())
+                    .map(|(ledger_index, _)| ledger_index);
+                if let Some(ledger_index) = nearest {
+                    router_used[ledger_index] = true;
+                    matches[record_index] = Some(ledger_index);
+                }
+            }
+        }
+
+        let first_routed = session.routed.iter().map(|ledger| ledger.occurred_at).min();
+        let last_routed = session.routed.iter().map(|ledger| ledger.occurred_at).max();
+        for (record, matched) in transcript.iter().zip(&matches) {
+            let Some(entry) = cache
+                .files
+                .get_mut(&record.path_key)
+                .and_then(|file| file.stats.entries.get_mut(record.index))
+            else {
+                continue;
+            };
+            let Some(ledger_index) = *matched else {
+                let in_routed_span = first_routed.zip(last_routed).is_some_and(|(first, last)| {
+                    record.at >= first - window && record.at <= last + window
+                });
+                if in_routed_span {
+                    report.discrepancies.push(UsageDiscrepancy {
+                        kind: UsageDiscrepancyKind::TranscriptOnly,
+                        session_id: session_id.clone(),
+                        timestamp: entry.timestamp.clone(),
+                        model: Some(entry.model.clone()),
+                        target_env: None,
+                        router_tokens: None,
+                        transcript_tokens: Some(token_sum(record.tokens)),
+                    });
+                }
+                continue;
+            };
+
+            let ledger = &session.routed[ledger_index];
+            report.matched_requests += 1;
+            if session.launch_env.as_deref() != Some(ledger.target_env.as_str()) {
+                report.reattributed_requests += 1;
+            }
+            entry.environment = Some(ledger.target_env.clone());
+            if let Some(router_tokens) = ledger
+                .usage
+                .as_ref()
+                .filter(|_| ledger.complete)
+                .map(routed_usage_tokens)
+                .filter(|tokens| *tokens != record.tokens)
+            {
+                report.discrepancies.push(UsageDiscrepancy {
+                    kind: UsageDiscrepancyKind::UsageMismatch,
+                    session_id: session_id.clone(),
+                    timestamp: entry.timestamp.clone(),
+                    model: ledger.model.clone().or_else(|| Some(entry.model.clone())),
+                    target_env: Some(ledger.target_env.clone()),
+                    router_tokens: Some(token_sum(router_tokens)),
+                    transcript_tokens: Some(token_sum(record.tokens)),
+                });
+            }
+        }
+
+        // Failed and interrupted forwards never reach the transcript; only
+        // complete, successful ones with usage are expected there.
+        for (ledger, used) in session.routed.iter().zip(&router_used) {
+            let Some(usage) = ledger.usage.as_ref() else {
+                continue;
+            };
+            if *used || !ledger.complete || ledger.status >= 400 {
+                continue;
+            }
+            report.discrepancies.push(UsageDiscrepancy {
+                kind: UsageDiscrepancyKind::RouterOnly,
+                session_id: session_id.clone(),
+                timestamp: ledger.occurred_at.to_rfc3339(),
+                model: ledger.model.clone(),
+                target_env: Some(ledger.target_env.clone()),
+                router_tokens: Some(token_sum(routed_usage_tokens(usage))),
+                transcript_tokens: None,
+            });
+        }
+    }
+
+    report.discrepancies.sort_by(|left, right| {
+        (&left.session_id, &left.timestamp).cmp(&(&right.session_id, &right.timestamp))
+    });
+    report
+}
+
+// ============================================================================
+// Cache efficiency insights
+// ============================================================================
+
+impl CacheEfficiency {
+    fn add(&mut self, usage: &CacheUsage, creation_cost: f64) {
+        self.requests += 1;
+        self.input_tokens += usage.input_tokens;
+        self.cache_read_tokens += usage.cache_read_tokens;
+        self.cache_creation_tokens += usage.cache_creation_tokens;
+        self.cache_creation_cost += creation_cost;
+    }
+
+    fn finish(mut self, key: String) -> Self {
+        let prompt_tokens = self.input_tokens + self.cache_read_tokens + self.cache_creation_tokens;
+        self.key = key;
+        self.hit_ratio = if prompt_tokens == 0 {
+            0.0
+        } else {
+            self.cache_read_tokens as f64 / prompt_tokens as f64
+        };
+        self
+    }
+}
+
+/// One compaction derived from consecutive `ContextUsage` snapshots of a
+/// runtime: context shrank from `before` to `after` tokens, and
+/// `turns_after` snapshots followed before the next compaction or the end.
+#[derive(Debug, Clone, PartialEq)]
+struct CompactionObservation {
+    model: String,
+    before: u64,
+    after: u64,
+    turns_after: u64,
+}
+
+fn detect_compactions(records: &[SessionEventRecord]) -> Vec<CompactionObservation> {
+    let mut by_runtime: HashMap<&str, Vec<(u64, u64, &str)>> = HashMap::new();
+    for record in records {
+        if let SessionEventPayload::ContextUsage {
+            used_tokens, model, ..
+        } = &record.payload
+        {
+            by_runtime
+                .entry(record.runtime_id.as_str())
+                .or_default()
+                .push((record.seq, *used_tokens, model.as_str()));
+        }
+    }
+
+    let mut compactions = Vec::new();
+    for snapshots in by_runtime.values_mut() {
+        snapshots.sort_by_key(|(seq, _, _)| *seq);
+        let mut open: Option<usize> = None;
+        for pair in snapshots.windows(2) {
+            let (_, before, _) = pair[0];
+            let (_, after, model) = pair[1];
+            if before > 0 && (after as f64) < before as f64 * COMPACTION_DROP_RATIO {
+                compactions.push(CompactionObservation {
+                    model: model.to_string(),
+                    before,
+                    after,
+                    turns_after: 1,
+                });
+                open = Some(compactions.len() - 1);
+            } else if let Some(index) = open {
+                compactions[index].turns_after += 1;
+            }
+        }
+    }
+    compactions
+}
+
+fn summarize_compactions(
+    compactions: &[CompactionObservation],
+    prices: &HashMap<String, ModelPrice>,
+) -> CompactionSavings {
+    let mut savings = CompactionSavings::default();
+    for compaction in compactions {
+        let freed = compaction.before - compaction.after;
+        let avoided = freed * compaction.turns_after;
+        let per_token = get_model_price(&compaction.model, prices)
+            .map(|price| {
+                price
+                    .cache_read_input_token_cost
+                    .unwrap_or(price.input_cost_per_token)
+            })
+            .unwrap_or(0.0);
+        savings.compactions += 1;
+        savings.tokens_freed += freed;
+        savings.avoided_tokens += avoided;
+        savings.avoided_cost += avoided as f64 * per_token;
+    }
+    savings
+}
+
+/// Session a cache file's records belong to: the transcript id for
+/// Claude/Codex, the session id for OpenCode CLI exports. OpenCode storage
+/// DBs only keep session totals without a cache split and are skipped.
+fn insight_session_id(path_key: &str) -> Option<String> {
+    transcript_session_id(path_key).or_else(|| {
+        path_key
+            .strip_prefix("opencode://session/")
+            .map(str::to_string)
+    })
+}
+
+fn build_cache_insights(
+    cache: &CacheFile,
+    source_filter: Option<&'static str>,
+    compactions: &[CompactionObservation],
+    prices: &HashMap<String, ModelPrice>,
+) -> CacheInsights {
+    let mut total = CacheEfficiency::default();
+    let mut by_model: HashMap<String, CacheEfficiency> = HashMap::new();
+    let mut by_day: HashMap<String, CacheEfficiency> = HashMap::new();
+    let mut sessions: HashMap<String, (SessionCacheEfficiency, Vec<&CacheEntry>)> = HashMap::new();
+
+    for (path_key, file) in &cache.files {
+        let Some(source) = detect_source_from_path(path_key) else {
+            continue;
+        };
+        if source_filter.is_some_and(|filter| filter != source) {
+            continue;
+        }
+        let Some(session_id) = insight_session_id(path_key) else {
+            continue;
+        };
+        let (session, records) = sessions.entry(session_id.clone()).or_insert_with(|| {
+            (
+                SessionCacheEfficiency {
+                    session_id,
+                    source: source.to_string(),
+                    ..Default::default()
+                },
+                Vec::new(),
+            )
+        });
+        if session.project.is_none() {
+            session.project = file.project.clone();
+        }
+
+        for entry in &file.stats.entries {
+            let creation_cost = get_model_price(&entry.model, prices)
+                .and_then(|price| price.cache_creation_input_token_cost)
+                .map(|per_token| entry.usage.cache_creation_tokens as f64 * per_token)
+                .unwrap_or(0.0);
+            total.add(&entry.usage, creation_cost);
+            session.efficiency.add(&entry.usage, creation_cost);
+            by_model
+                .entry(entry.model.clone())
+                .or_default()
+                .add(&entry.usage, creation_cost);
+            if let Some(date) = extract_date(&entry.timestamp) {
+                by_day
+                    .entry(date)
+                    .or_default()
+                    .add(&entry.usage, creation_cost);
+            }
+            records.push(entry);
+        }
+    }
+
+    let mut by_session = sessions
+        .into_values()
+        .map(|(mut session, mut records)| {
+            // The first request of a session necessarily writes its cache;
+            // only later writes that outweigh reads are misses.
+            records.sort_by(|left, right| left.timestamp.cmp(&right.timestamp));
+            session.miss_requests = records
+                .iter()
+                .skip(1)
+                .filter(|entry| entry.usage.cache_creation_tokens > entry.usage.cache_read_tokens)
+                .count() as u64;
+            let key = session.session_id.clone();
+            session.efficiency = std::mem::take(&mut session.efficiency).finish(key);
+            session
+        })
+        .collect::<Vec<_>>();
+    by_session.sort_by(|left, right| left.session_id.cmp(&right.session_id));
+
+    let mut repeated_miss_sessions = by_session
+        .iter()
+        .filter(|session| {
+            let follow_ups = session.efficiency.requests.saturating_sub(1);
+            session.miss_requests >= CACHE_MISS_REPEAT_MIN
+                && session.miss_requests as f64 >= follow_ups as f64 * CACHE_MISS_REPEAT_RATIO
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+    repeated_miss_sessions.sort_by(|left, right| {
+        right
+            .efficiency
+            .cache_creation_cost
+            .total_cmp(&left.efficiency.cache_creation_cost)
+            .then_with(|| right.miss_requests.cmp(&left.miss_requests))
+    });
+
+    let finish_buckets = |buckets: HashMap<String, CacheEfficiency>| {
+        let mut buckets = buckets
+            .into_iter()
+            .map(|(key, bucket)| bucket.finish(key))
+            .collect::<Vec<_>>();
+        buckets.sort_by(|left, right| left.key.cmp(&right.key));
+        buckets
+    };
+
+    CacheInsights {
+        total: total.finish("total".to_string()),
+        by_session,
+        by_model: finish_buckets(by_model),
+        by_day: finish_buckets(by_day),
+        compaction: summarize_compactions(compactions, prices),
+        repeated_miss_sessions,
+    }
+}
+
+// ============================================================================
+// Timestamp helpers
+// ============================================================================
+
+fn parse_to_local(timestamp: &str) -> Option<chrono::DateTime<Local>> {
+    if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(timestamp) {
+        return Some(dt.into());
+    }
+
+    if let Ok(dt) = chrono::NaiveDateTime::parse_from_str(
+        timestamp.trim_end_matches('Z'),
+        "%Y-%m-%dT%H:%M:%S%.f",
+    ) {
+        let utc_dt = dt.and_utc();
+        return Some(utc_dt.into());
+    }
+
+    None
+}
+
+fn extract_date(timestamp: &str) -> Option<String> {
+    if let Some(local_dt) = parse_to_local(timestamp) {
+        return Some(local_dt.format("%Y-%m-%d").to_string());
+    }
+
+    if timestamp.len() >= 10 {
+        Some(timestamp[..10].to_string())
+    } else {
+        None
+    }
+}
+
+fn extract_hour(timestamp: &str) -> Option<String> {
+    parse_to_local(timestamp).map(|dt| dt.format("%Y-%m-%dT%H").to_string())
+}
+
+fn format_week_bucket(date: NaiveDate) -> String {
+    let jan1 = NaiveDate::from_ymd_opt(date.year(), 1, 1).unwrap_or(date);
+    let days_since_jan1 = date.signed_duration_since(jan1).num_days();
+    let week_num =
+        ((days_since_jan1 + jan1.weekday().num_days_from_monday() as i64 + 7) / 7) as u32;
+    format!("{}-W{:02}", date.year(), week_num)
+}
+
+fn extract_model_breakdown_bucket(
+    timestamp: &str,
+    granularity: ModelBreakdownGranularity,
+) -> Option<String> {
+    match granularity {
+        ModelBreakdownGranularity::Hour => extract_hour(timestamp),
+        ModelBreakdownGranularity::Day => extract_date(timestamp),
+        ModelBreakdownGranularity::Week => {
+            let date = NaiveDate::parse_from_str(&extract_date(timestamp)?, "%Y-%m-%d").ok()?;
+            Some(format_week_bucket(date))
+        }
+        ModelBreakdownGranularity::Month => {
+            let date = NaiveDate::parse_from_str(&extract_date(timestamp)?, "%Y-%m-%d").ok()?;
+            Some(date.format("%Y-%m").to_string())
+        }
+    }
+}
+
+fn retain_latest_keys(breakdown: &mut ModelBreakdownHistory, max_entries: usize) {
+    if breakdown.len() <= max_entries {
+        return;
+    }
 
     let mut keys: Vec<_> = breakdown.keys().cloned().collect();
     keys.sort();
@@ -2137,6 +3060,206 @@ fn aggregate_model_breakdown(
     breakdown
 }
 
+fn parse_export_date_bound(value: Option<&str>, label: &str) -> Result<Option<NaiveDate>, String> {
+    match value.map(str::trim).filter(|value| !value.is_empty()) {
+        Some(value) => NaiveDate::parse_from_str(value, "%Y-%m-%d")
+            .map(Some)
+            .map_err(|_| format!("Invalid {label} '{value}'. Use YYYY-MM-DD.")),
+        None => Ok(None),
+    }
+}
+
+fn aggregate_usage_export_rows(
+    cache: &CacheFile,
+    source_filter: Option<&'static str>,
+    start_date: Option<NaiveDate>,
+    end_date: Option<NaiveDate>,
+) -> Vec<UsageExportRow> {
+    let mut rows: HashMap<(String, &'static str, String, String, String), TokenUsageWithCost> =
+        HashMap::new();
+
+    for (file_path, file_entry) in &cache.files {
+        let Some(source) = detect_source_from_path(file_path) else {
+            continue;
+        };
+        if source_filter.is_some_and(|filter| filter != source) {
+            continue;
+        }
+        let project = file_entry.project.clone().unwrap_or_default();
+
+        for entry in &file_entry.stats.entries {
+            let Some(date) = extract_date(&entry.timestamp) else {
+                continue;
+            };
+            if let Ok(entry_date) = NaiveDate::parse_from_str(&date, "%Y-%m-%d") {
+                if start_date.is_some_and(|start| entry_date < start)
+                    || end_date.is_some_and(|end| entry_date > end)
+                {
+                    continue;
+                }
+            }
+
+            let token_usage = TokenUsageWithCost {
+                input_tokens: entry.usage.input_tokens,
+                output_tokens: entry.usage.output_tokens,
+                cache_read_tokens: entry.usage.cache_read_tokens,
+                cache_creation_tokens: entry.usage.cache_creation_tokens,
+                cost: entry.usage.cost,
+            };
+            let environment = entry
+                .environment
+                .as_deref()
+                .map(str::trim)
+                .unwrap_or_default()
+                .to_string();
+            rows.entry((
+                date,
+                source,
+                entry.model.clone(),
+                environment,
+                project.clone(),
+            ))
+            .or_default()
+            .add(&token_usage);
+        }
+    }
+
+    let mut rows = rows
+        .into_iter()
+        .map(
+            |((date, source, model, environment, project), usage)| UsageExportRow {
+                date,
+                source: source.to_string(),
+                model,
+                environment,
+                project,
+                usage,
+            },
+        )
+        .collect::<Vec<_>>();
+    rows.sort_by(|left, right| {
+        (
+            &left.date,
+            &left.source,
+            &left.project,
+            &left.environment,
+            &left.model,
+        )
+            .cmp(&(
+                &right.date,
+                &right.source,
+                &right.project,
+                &right.environment,
+                &right.model,
+            ))
+    });
+    rows
+}
+
+fn escape_csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn render_usage_export_csv(rows: &[UsageExportRow]) -> String {
+    let mut output = String::from(
+        "date,source,project,environment,model,input_tokens,output_tokens,cache_read_tokens,cache_creation_tokens,cost_usd\n",
+    );
+    for row in rows {
+        output.push_str(&format!(
+            "{},{},{},{},{},{},{},{},{},{:.6}\n",
+            row.date,
+            row.source,
+            escape_csv_field(&row.project),
+            escape_csv_field(&row.environment),
+            escape_csv_field(&row.model),
+            row.usage.input_tokens,
+            row.usage.output_tokens,
+            row.usage.cache_read_tokens,
+            row.usage.cache_creation_tokens,
+            row.usage.cost,
+        ));
+    }
+    output
+}
+
+fn build_usage_export(
+    rows: Vec<UsageExportRow>,
+    format: UsageExportFormat,
+    now: chrono::DateTime<Local>,
+) -> Result<UsageExport, String> {
+    let content = match format {
+        UsageExportFormat::Csv => render_usage_export_csv(&rows),
+        UsageExportFormat::Json => serde_json::to_string_pretty(&rows)
+            .map_err(|error| format!("Failed to serialize usage export: {error}"))?,
+    };
+    Ok(UsageExport {
+        format: format.as_str().to_string(),
+        file_name: format!(
+            "ccem-usage-{}.{}",
+            now.format("%Y%m%d-%H%M%S"),
+            format.as_str()
+        ),
+        row_count: rows.len(),
+        content,
+    })
+}
+
+fn aggregate_usage_metric_samples(cache: &CacheFile) -> Vec<UsageMetricSample> {
+    let mut samples: HashMap<(&'static str, String, String), TokenUsageWithCost> = HashMap::new();
+    for (file_path, file_entry) in &cache.files {
+        let Some(source) = detect_source_from_path(file_path) else {
+            continue;
+        };
+        for entry in &file_entry.stats.entries {
+            let environment = entry
+                .environment
+                .as_deref()
+                .map(str::trim)
+                .unwrap_or_default()
+                .to_string();
+            samples
+                .entry((source, entry.model.clone(), environment))
+                .or_default()
+                .add(&TokenUsageWithCost {
+                    input_tokens: entry.usage.input_tokens,
+                    output_tokens: entry.usage.output_tokens,
+                    cache_read_tokens: entry.usage.cache_read_tokens,
+                    cache_creation_tokens: entry.usage.cache_creation_tokens,
+                    cost: entry.usage.cost,
+                });
+        }
+    }
+
+    let mut samples = samples
+        .into_iter()
+        .map(|((source, model, environment), usage)| UsageMetricSample {
+            source,
+            model,
+            environment,
+            usage,
+        })
+        .collect::<Vec<_>>();
+    samples.sort_by(|left, right| {
+        (left.source, &left.model, &left.environment).cmp(&(
+            right.source,
+            &right.model,
+            &right.environment,
+        ))
+    });
+    samples
+}
+
+/// Usage totals for the `/metrics` endpoint. Shares the single-flight
+/// snapshot with the analytics commands, so scrapes inside the TTL window
+/// never rescan transcripts.
+pub fn collect_usage_metric_samples() -> Vec<UsageMetricSample> {
+    aggregate_usage_metric_samples(&shared_usage_cache(None))
+}
+
 fn calculate_streak(daily_history: &HashMap<String, TokenUsageWithCost>) -> u32 {
     let today = Local::now().date_naive();
     let mut streak: u32 = 0;
@@ -2266,23 +3389,86 @@ pub async fn get_continuous_usage_days(source: Option<String>) -> Result<u32, St
     .await
 }
 
+/// Export daily × model × environment × project usage rows as CSV or JSON.
+/// Returns the rendered content; the frontend hands it to `save_file_dialog`.
+#[tauri::command]
+pub async fn export_usage_report(
+    format: String,
+    source: Option<String>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+) -> Result<UsageExport, String> {
+    run_blocking(move || {
+        let format = UsageExportFormat::parse(&format)?;
+        let source_filter = normalize_usage_source(source.as_deref())?;
+        let start_date = parse_export_date_bound(start_date.as_deref(), "start date")?;
+        let end_date = parse_export_date_bound(end_date.as_deref(), "end date")?;
+        let cache = shared_usage_cache(None);
+        let rows = aggregate_usage_export_rows(&cache, source_filter, start_date, end_date);
+        build_usage_export(rows, format, Local::now())
+    })
+    .await
+}
+
+/// Cache hit ratios per session/model/day, compaction savings, and sessions
+/// that keep missing cache.
+#[tauri::command]
+pub async fn get_cache_insights(source: Option<String>) -> Result<CacheInsights, String> {
+    run_blocking(move || {
+        let source_filter = normalize_usage_source(source.as_deref())?;
+        let cache = shared_usage_cache(None);
+        let compactions = NativeEventLog::default()
+            .context_usage_records()
+            .map(|records| detect_compactions(&records))
+            .unwrap_or_else(|error| {
+                eprintln!("Cache insights warning: {error}");
+                Vec::new()
+            });
+        Ok(build_cache_insights(
+            &cache,
+            source_filter,
+            &compactions,
+            &load_model_prices(),
+        ))
+    })
+    .await
+}
+
+/// Router-vs-transcript reconciliation behind the current usage snapshot:
+/// how many requests were re-attributed to the env that served them, and
+/// which records disagree.
+#[tauri::command]
+pub async fn get_usage_reconciliation(force: Option<bool>) -> Result<UsageReconciliation, String> {
+    let request_started = Instant::now();
+    run_blocking(move || {
+        let force_requested = force.unwrap_or(false);
+        let (_, reconciliation) = shared_usage_snapshot(force_requested.then_some(request_started));
+        Ok((*reconciliation).clone())
+    })
+    .await
+}
+
 #[cfg(test)]
 mod tests {
     use super::{
-        aggregate_model_breakdown, cache_files_have_same_meta, default_prices,
+        aggregate_model_breakdown, aggregate_usage_export_rows, build_cache_insights,
+        build_usage_export, cache_files_have_same_meta, default_prices, detect_compactions,
         extract_model_breakdown_bucket, format_week_bucket, full_parse_jsonl, get_file_meta,
         lock_usage_snapshot, lock_usage_stats_memo, normalize_usage_source,
         parse_claude_jsonl_reader, parse_codex_jsonl_reader, parse_opencode_export_stats,
         parse_opencode_session_items, read_usage_cache_at, read_usage_summary_from,
-        refresh_discovered_entry, shared_usage_cache, should_reuse_usage_stats, snapshot_is_fresh,
-        write_json_atomic, write_usage_summary_to, CacheEntry, CacheFile, CacheFileEntry,
-        CacheMeta, CacheStats, CacheUsage, ClaudeParseState, CodexParseState, DiscoveredFile,
-        ModelBreakdownGranularity, ModelPrice, UsageSource, UsageStats,
+        reconcile_router_attribution, refresh_discovered_entry, shared_usage_cache,
+        should_reuse_usage_stats, snapshot_is_fresh, transcript_session_id, write_json_atomic,
+        write_usage_summary_to, CacheEntry, CacheFile, CacheFileEntry, CacheMeta, CacheStats,
+        CacheUsage, ClaudeParseState, CodexParseState, DiscoveredFile, ModelBreakdownGranularity,
+        ModelPrice, OpenCodeParseState, RouterLedgerEntry, SessionAttribution,
+        UsageDiscrepancyKind, UsageExportFormat, UsageSource, UsageStats,
         ANALYTICS_SHADOW_INCREMENTAL, OPENCODE_NATIVE_ENV_NAME, SOURCE_CLAUDE,
         TEST_SNAPSHOT_REFRESH,
     };
+    use crate::event_bus::{RoutedUsageTotals, SessionEventPayload, SessionEventRecord};
     use chrono::{Local, TimeZone};
-    use std::collections::HashMap;
+    use std::collections::{BTreeMap, HashMap};
     use std::fs;
     use std::io::BufReader;
     use std::path::Path;
@@ -2467,6 +3653,462 @@ mod tests {
         assert!(!cache_files_have_same_meta(&existing, &HashMap::new()));
     }
 
+    #[test]
+    fn test_transcript_session_id_covers_subagents_and_codex_rollouts() {
+        assert_eq!(
+            transcript_session_id("/h/.claude/projects/-p/ses-1.jsonl").as_deref(),
+            Some("ses-1")
+        );
+        assert_eq!(
+            transcript_session_id("/h/.claude/projects/-p/ses-1/subagents/agent-a.jsonl")
+                .as_deref(),
+            Some("ses-1")
+        );
+        assert_eq!(
+            transcript_session_id(
+                "/h/.codex/sessions/2026/03/07/rollout-2026-03-07T10-00-00-0199a1b2-c3d4-7e5f-8a9b-0c1d2e3f4a5b.jsonl"
+            )
+            .as_deref(),
+            Some("0199a1b2-c3d4-7e5f-8a9b-0c1d2e3f4a5b")
+        );
+        assert_eq!(transcript_session_id("opencode://session/x"), None);
+    }
+
+    #[test]
+    fn test_router_reconciliation_attributes_env_and_flags_discrepancies() {
+        let record = |timestamp: &str, input: u64, output: u64| CacheEntry {
+            timestamp: timestamp.to_string(),
+            model: "claude-sonnet-4-5".to_string(),
+            environment: None,
+            usage: CacheUsage {
+                input_tokens: input,
+                output_tokens: output,
+                cost: 0.01,
+                ..Default::default()
+            },
+        };
+        let ledger =
+            |timestamp: &str, target_env: &str, usage: Option<(u64, u64)>| RouterLedgerEntry {
+                occurred_at: chrono::DateTime::parse_from_rfc3339(timestamp)
+                    .expect("ledger timestamp")
+                    .with_timezone(&chrono::Utc),
+                target_env: target_env.to_string(),
+                model: Some("glm-4.6".to_string()),
+                status: 200,
+                complete: true,
+                usage: usage.map(|(input_tokens, output_tokens)| RoutedUsageTotals {
+                    input_tokens,
+                    output_tokens,
+                    ..Default::default()
+                }),
+            };
+        let file = |entries| CacheFileEntry {
+            stats: CacheStats { entries },
+            ..Default::default()
+        };
+
+        let main_key = "/h/.claude/projects/-p/ses-1.jsonl";
+        let subagent_key = "/h/.claude/projects/-p/ses-1/subagents/agent-a.jsonl";
+        let other_key = "/h/.claude/projects/-p/ses-unrouted.jsonl";
+        let mut cache = CacheFile {
+            version: 1,
+            files: HashMap::from([
+                (
+                    main_key.to_string(),
+                    file(vec![
+                        // Exact match, served by the launch env.
+                        record("2026-03-07T10:00:05Z", 100, 10),
+                        // Same request, router saw different usage.
+                        record("2026-03-07T10:05:03Z", 300, 30),
+                        // Before routing started: launch env, no flag.
+                        record("2026-03-07T08:00:00Z", 50, 5),
+                        // Inside the routed span with no ledger entry.
+                        record("2026-03-07T10:20:00Z", 70, 7),
+                    ]),
+                ),
+                (
+                    subagent_key.to_string(),
+                    file(vec![record("2026-03-07T10:02:01Z", 200, 20)]),
+                ),
+                (
+                    other_key.to_string(),
+                    file(vec![record("2026-03-07T10:00:00Z", 1, 1)]),
+                ),
+            ]),
+            last_updated: None,
+        };
+        let attribution = HashMap::from([(
+            "ses-1".to_string(),
+            SessionAttribution {
+                launch_env: Some("anthropic".to_string()),
+                routed: vec![
+                    ledger("2026-03-07T10:00:06Z", "anthropic", Some((100, 10))),
+                    ledger("2026-03-07T10:02:00Z", "glm", Some((200, 20))),
+                    ledger("2026-03-07T10:05:04Z", "glm", Some((310, 30))),
+                    // Interrupted forward without usage: not flagged.
+                    ledger("2026-03-07T10:10:00Z", "glm", None),
+                    ledger("2026-03-07T10:30:00Z", "deepseek", Some((400, 40))),
+                ],
+            },
+        )]);
+
+        let report = reconcile_router_attribution(&mut cache, &attribution);
+
+        assert_eq!(report.routed_sessions, 1);
+        assert_eq!(report.matched_requests, 3);
+        assert_eq!(report.reattributed_requests, 2);
+        let kinds = report
+            .discrepancies
+            .iter()
+            .map(|discrepancy| (discrepancy.kind, discrepancy.timestamp.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                (UsageDiscrepancyKind::UsageMismatch, "2026-03-07T10:05:03Z"),
+                (UsageDiscrepancyKind::TranscriptOnly, "2026-03-07T10:20:00Z"),
+                (
+                    UsageDiscrepancyKind::RouterOnly,
+                    "2026-03-07T10:30:00+00:00"
+                ),
+            ]
+        );
+
+        let environments = |key: &str| {
+            cache.files[key]
+                .stats
+                .entries
+                .iter()
+                .map(|entry| entry.environment.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            environments(main_key),
+            vec![
+                Some("anthropic".to_string()),
+                Some("glm".to_string()),
+                Some("anthropic".to_string()),
+                Some("anthropic".to_string()),
+            ]
+        );
+        assert_eq!(environments(subagent_key), vec![Some("glm".to_string())]);
+        assert_eq!(environments(other_key), vec![None]);
+
+        let stats = super::aggregate_cache(&cache, None);
+        assert_eq!(stats.by_environment["glm"].input_tokens, 500);
+        assert_eq!(stats.by_environment["anthropic"].input_tokens, 220);
+    }
+
+    #[test]
+    fn test_cache_insights_ratios_and_repeated_misses() {
+        let record = |timestamp: &str, model: &str, input, read, creation| CacheEntry {
+            timestamp: timestamp.to_string(),
+            model: model.to_string(),
+            environment: None,
+            usage: CacheUsage {
+                input_tokens: input,
+                cache_read_tokens: read,
+                cache_creation_tokens: creation,
+                ..Default::default()
+            },
+        };
+        let file = |entries| CacheFileEntry {
+            stats: CacheStats { entries },
+            project: Some("/Users/g/ccem".to_string()),
+            ..Default::default()
+        };
+        let cache = CacheFile {
+            version: 1,
+            files: HashMap::from([
+                (
+                    "/h/.claude/projects/-p/warm.jsonl".to_string(),
+                    file(vec![
+                        record("2026-03-07T12:00:00Z", "claude-sonnet-4-5", 10, 0, 1_000),
+                        record("2026-03-07T12:01:00Z", "claude-sonnet-4-5", 10, 1_000, 0),
+                        record("2026-03-07T12:02:00Z", "claude-sonnet-4-5", 10, 1_000, 0),
+                    ]),
+                ),
+                (
+                    "/h/.claude/projects/-p/churn.jsonl".to_string(),
+                    file(vec![
+                        record("2026-03-08T12:00:00Z", "claude-opus-4-5", 0, 0, 1_000),
+                        record("2026-03-08T12:01:00Z", "claude-opus-4-5", 0, 100, 1_000),
+                        record("2026-03-08T12:02:00Z", "claude-opus-4-5", 0, 100, 1_000),
+                        record("2026-03-08T12:03:00Z", "claude-opus-4-5", 0, 100, 1_000),
+                        record("2026-03-08T12:04:00Z", "claude-opus-4-5", 0, 1_100, 0),
+                    ]),
+                ),
+            ]),
+            last_updated: None,
+        };
+
+        let insights = build_cache_insights(&cache, None, &[], &default_prices());
+
+        assert_eq!(insights.total.requests, 8);
+        assert_eq!(insights.by_session.len(), 2);
+        let warm = &insights.by_session[1];
+        assert_eq!(warm.session_id, "warm");
+        assert_eq!(warm.miss_requests, 0);
+        assert!((warm.efficiency.hit_ratio - 2_000.0 / 3_030.0).abs() < 1e-9);
+        assert_eq!(warm.project.as_deref(), Some("/Users/g/ccem"));
+
+        let days = insights
+            .by_day
+            .iter()
+            .map(|bucket| (bucket.key.as_str(), bucket.requests))
+            .collect::<Vec<_>>();
+        assert_eq!(days, vec![("2026-03-07", 3), ("2026-03-08", 5)]);
+        assert_eq!(insights.by_model[0].key, "claude-opus-4-5");
+        assert!((insights.by_model[0].cache_creation_cost - 4_000.0 * 6.25e-6).abs() < 1e-9);
+
+        let flagged = insights
+            .repeated_miss_sessions
+            .iter()
+            .map(|session| (session.session_id.as_str(), session.miss_requests))
+            .collect::<Vec<_>>();
+        assert_eq!(flagged, vec![("churn", 3)]);
+    }
+
+    #[test]
+    fn test_detect_compactions_prices_freed_tokens_for_later_turns() {
+        let snapshot = |runtime_id: &str, seq: u64, used_tokens: u64| SessionEventRecord {
+            runtime_id: runtime_id.to_string(),
+            seq,
+            occurred_at: chrono::Utc::now(),
+            payload: SessionEventPayload::ContextUsage {
+                provider: "claude".to_string(),
+                used_tokens,
+                max_tokens: 200_000,
+                raw_max_tokens: None,
+                percentage: used_tokens as f64 / 2_000.0,
+                auto_compact_threshold: None,
+                is_auto_compact_enabled: true,
+                model: "claude-sonnet-4-5".to_string(),
+                categories: Vec::new(),
+            },
+        };
+        let records = vec![
+            snapshot("runtime-a", 1, 150_000),
+            snapshot("runtime-a", 2, 180_000),
+            snapshot("runtime-a", 3, 30_000),
+            snapshot("runtime-a", 4, 40_000),
+            snapshot("runtime-a", 5, 50_000),
+            // Steady growth elsewhere is not a compaction.
+            snapshot("runtime-b", 1, 10_000),
+            snapshot("runtime-b", 2, 9_000),
+        ];
+
+        let compactions = detect_compactions(&records);
+        assert_eq!(compactions.len(), 1);
+        assert_eq!(compactions[0].before, 180_000);
+        assert_eq!(compactions[0].after, 30_000);
+        assert_eq!(compactions[0].turns_after, 3);
+
+        let cache = CacheFile {
+            version: 1,
+            files: HashMap::new(),
+            last_updated: None,
+        };
+        let insights = build_cache_insights(&cache, None, &compactions, &default_prices());
+        assert_eq!(insights.compaction.compactions, 1);
+        assert_eq!(insights.compaction.tokens_freed, 150_000);
+        assert_eq!(insights.compaction.avoided_tokens, 450_000);
+        assert!((insights.compaction.avoided_cost - 450_000.0 * 0.3e-6).abs() < 1e-9);
+    }
+
+    fn create_opencode_storage_db(path: &Path) -> rusqlite::Connection {
+        fs::create_dir_all(path.parent().expect("db parent")).expect("create db parent");
+        let conn = rusqlite::Connection::open(path).expect("open sqlite");
+        conn.execute_batch(
+            "CREATE TABLE sessions (
+                id TEXT PRIMARY KEY,
+                parent_session_id TEXT,
+                title TEXT NOT NULL,
+                prompt_tokens INTEGER NOT NULL DEFAULT 0,
+                completion_tokens INTEGER NOT NULL DEFAULT 0,
+                cost REAL NOT NULL DEFAULT 0.0,
+                updated_at INTEGER NOT NULL,
+                created_at INTEGER NOT NULL
+             );
+             CREATE TABLE messages (
+                id TEXT PRIMARY KEY,
+                session_id TEXT NOT NULL,
+                parts TEXT NOT NULL DEFAULT '[]',
+                model TEXT,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL,
+                finished_at INTEGER
+             );",
+        )
+        .expect("create schema");
+        conn
+    }
+
+    fn insert_opencode_session(
+        conn: &rusqlite::Connection,
+        id: &str,
+        parent: Option<&str>,
+        tokens: (u64, u64),
+        cost: f64,
+        updated_at: i64,
+    ) {
+        conn.execute(
+            "INSERT OR REPLACE INTO sessions \
+             (id, parent_session_id, title, prompt_tokens, completion_tokens, cost, updated_at, created_at) \
+             VALUES (?1, ?2, ?1, ?3, ?4, ?5, ?6, 1713170000)",
+            rusqlite::params![id, parent, tokens.0, tokens.1, cost, updated_at],
+        )
+        .expect("insert session");
+        conn.execute(
+            "INSERT OR REPLACE INTO messages (id, session_id, model, created_at, updated_at, finished_at) \
+             VALUES (?1, ?2, 'anthropic/claude-sonnet-4-5', ?3, ?3, ?3)",
+            rusqlite::params![format!("{id}-msg"), id, updated_at],
+        )
+        .expect("insert message");
+    }
+
+    #[test]
+    fn test_opencode_storage_refresh_replaces_updated_sessions() {
+        let temp = tempfile::tempdir().expect("opencode tempdir");
+        let db_path = temp
+            .path()
+            .join("ccem")
+            .join(".opencode")
+            .join("opencode.db");
+        let conn = create_opencode_storage_db(&db_path);
+        insert_opencode_session(&conn, "ses-a", None, (1_000, 200), 0.42, 1_713_170_100);
+        insert_opencode_session(
+            &conn,
+            "ses-child",
+            Some("ses-a"),
+            (50, 5),
+            0.01,
+            1_713_170_100,
+        );
+
+        let discovered = discovered_at(&db_path, UsageSource::OpenCode);
+        let prices = default_prices();
+        let meta = |mtime: f64, size: u64| CacheMeta { mtime, size };
+
+        let first = refresh_discovered_entry(None, &discovered, meta(1_000.0, 8_192), &prices);
+        assert_eq!(
+            first.stats.entries.len(),
+            1,
+            "child sessions are not billed twice"
+        );
+        assert_eq!(first.stats.entries[0].usage.input_tokens, 1_000);
+        assert!((first.stats.entries[0].usage.cost - 0.42).abs() < 1e-9);
+        assert_eq!(first.stats.entries[0].model, "anthropic/claude-sonnet-4-5");
+        assert_eq!(
+            first.project.as_deref(),
+            Some(temp.path().join("ccem").to_string_lossy().as_ref())
+        );
+        assert_eq!(
+            first
+                .opencode_state
+                .as_ref()
+                .map(|state| state.last_updated_at),
+            Some(1_713_170_100)
+        );
+
+        // Session A keeps going and a new, unpriced session appears: A's entry
+        // is replaced in place and B is appended with an estimated cost.
+        insert_opencode_session(&conn, "ses-a", None, (3_000, 600), 0.90, 1_713_170_400);
+        insert_opencode_session(&conn, "ses-b", None, (1_000_000, 0), 0.0, 1_713_170_500);
+        let second =
+            refresh_discovered_entry(Some(&first), &discovered, meta(2_000.0, 8_192), &prices);
+        assert_eq!(second.stats.entries.len(), 2);
+        assert_eq!(second.stats.entries[0].usage.input_tokens, 3_000);
+        assert!((second.stats.entries[0].usage.cost - 0.90).abs() < 1e-9);
+        assert!((second.stats.entries[1].usage.cost - 3.0).abs() < 1e-9);
+        assert_eq!(
+            second
+                .opencode_state
+                .as_ref()
+                .map(|state| state.last_updated_at),
+            Some(1_713_170_500)
+        );
+
+        // Incremental result matches a from-scratch read, and a shrunk DB
+        // (vacuum / checkpoint) falls back to exactly that.
+        let full = refresh_discovered_entry(None, &discovered, meta(2_000.0, 8_192), &prices);
+        assert_eq!(second.stats, full.stats);
+        let shrunk =
+            refresh_discovered_entry(Some(&second), &discovered, meta(3_000.0, 4_096), &prices);
+        assert_eq!(shrunk.stats, full.stats);
+    }
+
+    #[test]
+    fn test_opencode_storage_refresh_records_message_usage_and_prunes_deleted_sessions() {
+        let temp = tempfile::tempdir().expect("opencode tempdir");
+        let db_path = temp.path().join(".opencode").join("opencode.db");
+        let conn = create_opencode_storage_db(&db_path);
+        insert_opencode_session(&conn, "ses-msgs", None, (300, 60), 0.5, 1_713_170_300);
+        insert_opencode_session(&conn, "ses-gone", None, (1_000, 200), 0.42, 1_713_170_100);
+        for (id, at, input) in [
+            ("msg-1", 1_713_170_200_i64, 100),
+            ("msg-2", 1_713_170_300, 200),
+        ] {
+            let parts = serde_json::json!([
+                {"type": "text", "data": {"text": "done"}},
+                {
+                    "type": "finish",
+                    "data": {
+                        "tokens": {"input": input, "output": 30, "cache": {"read": 500, "write": 50}},
+                        "cost": 0.25,
+                    },
+                },
+            ]);
+            conn.execute(
+                "INSERT INTO messages (id, session_id, parts, model, created_at, updated_at, finished_at) \
+                 VALUES (?1, 'ses-msgs', ?2, 'anthropic/claude-sonnet-4-5', ?3, ?3, ?3)",
+                rusqlite::params![id, parts.to_string(), at],
+            )
+            .expect("insert message with usage");
+        }
+
+        let discovered = discovered_at(&db_path, UsageSource::OpenCode);
+        let prices = default_prices();
+        let meta = |mtime: f64| CacheMeta { mtime, size: 8_192 };
+        let first = refresh_discovered_entry(None, &discovered, meta(1_000.0), &prices);
+        assert_eq!(first.stats.entries.len(), 3);
+        let gone = &first.stats.entries[0];
+        assert_eq!(gone.usage.input_tokens, 1_000);
+        assert_eq!(gone.environment.as_deref(), Some(OPENCODE_NATIVE_ENV_NAME));
+        let messages = &first.stats.entries[1..];
+        assert_eq!(
+            messages
+                .iter()
+                .map(|entry| (entry.usage.input_tokens, entry.usage.cache_read_tokens))
+                .collect::<Vec<_>>(),
+            vec![(100, 500), (200, 500)]
+        );
+        assert_eq!(messages[0].usage.cache_creation_tokens, 50);
+        assert!((messages[0].usage.cost - 0.25).abs() < 1e-9);
+        assert_eq!(
+            messages[0].timestamp,
+            chrono::DateTime::<chrono::Utc>::from_timestamp(1_713_170_200, 0)
+                .expect("timestamp")
+                .to_rfc3339()
+        );
+        assert_eq!(
+            messages[1].environment.as_deref(),
+            Some(OPENCODE_NATIVE_ENV_NAME)
+        );
+
+        conn.execute("DELETE FROM sessions WHERE id = 'ses-gone'", [])
+            .expect("delete session");
+        let second = refresh_discovered_entry(Some(&first), &discovered, meta(2_000.0), &prices);
+        assert_eq!(second.stats.entries, first.stats.entries[1..].to_vec());
+        assert_eq!(
+            second
+                .opencode_state
+                .as_ref()
+                .map(|state| state.session_entry_counts.clone()),
+            Some(BTreeMap::from([("ses-msgs".to_string(), 2)]))
+        );
+    }
+
     #[test]
     fn test_parse_opencode_session_items_prefers_env_name_and_native_fallback() {
         let value = serde_json::json!({
@@ -2841,6 +4483,7 @@ mod tests {
                     },
                 }],
             },
+            None,
         );
         CacheFile {
             version: super::USAGE_CACHE_VERSION,
@@ -3312,10 +4955,17 @@ mod tests {
             codex_state: Some(CodexParseState {
                 current_model: Some("gpt-5.3-codex".to_string()),
                 last_total: None,
+                cwd: None,
             }),
             claude_state: Some(ClaudeParseState {
                 message_entry_indexes: HashMap::from([("msg_roundtrip".to_string(), 3)]),
+                cwd: Some("/Users/g/ccem".to_string()),
+            }),
+            opencode_state: Some(OpenCodeParseState {
+                last_updated_at: 1_713_170_100,
+                session_entry_counts: BTreeMap::from([("ses_roundtrip".to_string(), 1)]),
             }),
+            project: Some("/Users/g/ccem".to_string()),
         };
 
         // A cache stamped with an old version is discarded wholesale.
@@ -3356,6 +5006,7 @@ mod tests {
             Some(3),
             "claude message-id dedup map must round-trip through the cache"
         );
+        assert_eq!(round_tripped.project.as_deref(), Some("/Users/g/ccem"));
     }
 
     #[test]
@@ -3397,4 +5048,107 @@ mod tests {
         let codex_reader = parse_codex_jsonl_reader(BufReader::new(codex_content.as_bytes()), &prices);
         assert_eq!(codex_byte_core, codex_reader);
     }
+
+    #[test]
+    fn test_usage_export_groups_rows_by_day_model_environment_and_project() {
+        let mut cache = build_cache(
+            vec![
+                CacheEntry {
+                    timestamp: "2026-03-05T12:00:00Z".to_string(),
+                    model: "claude-sonnet-4-5".to_string(),
+                    environment: Some("kimi".to_string()),
+                    usage: usage_bucket(100, 1.0),
+                },
+                CacheEntry {
+                    timestamp: "2026-03-05T12:30:00Z".to_string(),
+                    model: "claude-sonnet-4-5".to_string(),
+                    environment: Some("kimi".to_string()),
+                    usage: usage_bucket(50, 0.5),
+                },
+                CacheEntry {
+                    timestamp: "2026-03-06T12:00:00Z".to_string(),
+                    model: "claude-opus-4-5".to_string(),
+                    environment: None,
+                    usage: usage_bucket(10, 0.25),
+                },
+            ],
+            vec![CacheEntry {
+                timestamp: "2026-03-01T12:00:00Z".to_string(),
+                model: "gpt-5.3-codex".to_string(),
+                environment: None,
+                usage: usage_bucket(75, 0.75),
+            }],
+        );
+        for entry in cache.files.values_mut() {
+            entry.project = Some("/Users/g/acme, inc".to_string());
+        }
+
+        let start = chrono::NaiveDate::from_ymd_opt(2026, 3, 5);
+        let rows = aggregate_usage_export_rows(&cache, None, start, None);
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].date, "2026-03-05");
+        assert_eq!(rows[0].source, "claude");
+        assert_eq!(rows[0].environment, "kimi");
+        assert_eq!(rows[0].usage.input_tokens, 150);
+        assert_eq!(rows[1].model, "claude-opus-4-5");
+        assert_eq!(rows[1].environment, "");
+
+        let export =
+            build_usage_export(rows, UsageExportFormat::Csv, fixed_now()).expect("csv export");
+        assert_eq!(export.row_count, 2);
+        assert!(export.file_name.ends_with(".csv"));
+        let mut lines = export.content.lines();
+        assert!(lines
+            .next()
+            .expect("csv header")
+            .starts_with("date,source,project,environment,model"));
+        assert_eq!(
+            lines.next(),
+            Some("2026-03-05,claude,\"/Users/g/acme, inc\",kimi,claude-sonnet-4-5,150,0,0,0,1.500000")
+        );
+    }
+
+    #[test]
+    fn test_full_parse_records_transcript_project() {
+        let temp = tempfile::tempdir().expect("project tempdir");
+        let prices = default_prices();
+
+        let claude_path = temp.path().join("claude.jsonl");
+        fs::write(
+            &claude_path,
+            format!(
+                "{}\n{}\n",
+                r#"{"type":"user","cwd":"/Users/g/ccem","timestamp":"2026-03-08T00:00:00.000Z"}"#,
+                claude_line("2026-03-08T00:00:01.000Z", 100, 10),
+            ),
+        )
+        .expect("write claude fixture");
+        let claude = full_parse_jsonl(
+            UsageSource::Claude,
+            &claude_path,
+            meta_of(&claude_path),
+            &prices,
+        );
+        assert_eq!(claude.project.as_deref(), Some("/Users/g/ccem"));
+
+        let codex_path = temp.path().join("codex.jsonl");
+        fs::write(
+            &codex_path,
+            format!(
+                "{}\n{}\n",
+                r#"{"type":"session_meta","payload":{"id":"s1","cwd":"/Users/g/codex","model":"gpt-5.3-codex"}}"#,
+                codex_count_line("2026-03-08T00:00:01.000Z", 100, 20, 10, 5),
+            ),
+        )
+        .expect("write codex fixture");
+        let codex = full_parse_jsonl(
+            UsageSource::Codex,
+            &codex_path,
+            meta_of(&codex_path),
+            &prices,
+        );
+        assert_eq!(codex.project.as_deref(), Some("/Users/g/codex"));
+        assert_eq!(codex.stats.entries[0].model, "gpt-5.3-codex");
+    }
 }
@@ -0,0 +1,742 @@
+//! `ccem-ctl`: command-line client for the desktop's loopback control server.
+//!
+//! Discovers the running app through its control descriptor
+//! (`CCEM_CONTROL_FILE`, else `~/.ccem/control.json`) and speaks the same
+//! JSON-RPC surface as the TypeScript CLI. `CCEM_CONTROL_TOKEN` overrides the
+//! descriptor token so scoped API tokens can be used from scripts.
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Duration;
+
+const USAGE: &str = "\
+Usage: ccem-ctl [--json] <command>
+
+Commands:
+  sessions ls [--cwd DIR] [--provider claude|codex] [--status STATUS]
+  session new (--provider claude|codex | --preset NAME) [--cwd DIR] [--env NAME] [--perm-mode MODE] <prompt>
+  session tail <runtime-id> [--since SEQ]
+  session stop <runtime-id>
+  approve <runtime-id> [request-id]
+  deny <runtime-id> [request-id]
+  router explain <runtime-id>
+
+Options:
+  --json   Print raw JSON instead of tables";
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Deserialize)]
+struct ControlDescriptor {
+    endpoint: String,
+    token: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Command {
+    ListSessions {
+        cwd: Option<String>,
+        provider: Option<String>,
+        status: Option<String>,
+    },
+    NewSession {
+        provider: Option<String>,
+        preset: Option<String>,
+        cwd: Option<String>,
+        env_name: Option<String>,
+        perm_mode: Option<String>,
+        prompt: String,
+    },
+    Tail {
+        runtime_id: String,
+        since_seq: Option<u64>,
+    },
+    Stop {
+        runtime_id: String,
+    },
+    RespondPermission {
+        runtime_id: String,
+        request_id: Option<String>,
+        approved: bool,
+    },
+    RouterExplain {
+        runtime_id: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Invocation {
+    json: bool,
+    command: Command,
+}
+
+fn main() -> ExitCode {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if args.is_empty() || args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+    let result = parse_args(&args).and_then(|invocation| {
+        let client = ControlClient::discover()?;
+        run(&client, invocation)
+    });
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("ccem-ctl: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<Invocation, String> {
+    let mut json = false;
+    let mut flags = HashMap::new();
+    let mut positional = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--json" {
+            json = true;
+        } else if let Some(name) = arg.strip_prefix("--") {
+            let value = iter
+                .next()
+                .ok_or_else(|| format!("--{name} needs a value"))?;
+            flags.insert(name.to_string(), value.clone());
+        } else {
+            positional.push(arg.as_str());
+        }
+    }
+    let flag = |name: &str| flags.get(name).cloned();
+    let command = match positional.as_slice() {
+        ["sessions", "ls"] | ["sessions"] => Command::ListSessions {
+            cwd: flag("cwd"),
+            provider: flag("provider"),
+            status: flag("status"),
+        },
+        ["session", "new", prompt @ ..] if !prompt.is_empty() => {
+            if flag("provider").is_none() && flag("preset").is_none() {
+                return Err(
+                    "session new needs --provider claude|codex or --preset NAME".to_string()
+                );
+            }
+            Command::NewSession {
+                provider: flag("provider"),
+                preset: flag("preset"),
+                cwd: flag("cwd"),
+                env_name: flag("env"),
+                perm_mode: flag("perm-mode"),
+                prompt: prompt.join(" "),
+            }
+        }
+        ["session", "tail", runtime_id] => Command::Tail {
+            runtime_id: runtime_id.to_string(),
+            since_seq: flag("since")
+                .map(|value| {
+                    value
+                        .parse::<u64>()
+                        .map_err(|_| format!("--since must be a number, got {value}"))
+                })
+                .transpose()?,
+        },
+        ["session", "stop", runtime_id] => Command::Stop {
+            runtime_id: runtime_id.to_string(),
+        },
+        [verb @ ("approve" | "deny"), runtime_id, rest @ ..] if rest.len() <= 1 => {
+            Command::RespondPermission {
+                runtime_id: runtime_id.to_string(),
+                request_id: rest.first().map(|id| id.to_string()),
+                approved: *verb == "approve",
+            }
+        }
+        ["router", "explain", runtime_id] => Command::RouterExplain {
+            runtime_id: runtime_id.to_string(),
+        },
+        _ => return Err(format!("unrecognised command\n\n{USAGE}")),
+    };
+    Ok(Invocation { json, command })
+}
+
+struct ControlClient {
+    endpoint: String,
+    token: String,
+    http: reqwest::blocking::Client,
+}
+
+impl ControlClient {
+    fn discover() -> Result<Self, String> {
+        let path = descriptor_path()?;
+        let content = std::fs::read_to_string(&path).map_err(|error| {
+            format!(
+                "CCEM Desktop does not appear to be running ({}: {error})",
+                path.display()
+            )
+        })?;
+        let descriptor = serde_json::from_str::<ControlDescriptor>(&content)
+            .map_err(|error| format!("Invalid control descriptor {}: {error}", path.display()))?;
+        let token = std::env::var("CCEM_CONTROL_TOKEN")
+            .ok()
+            .map(|token| token.trim().to_string())
+            .filter(|token| !token.is_empty())
+            .unwrap_or(descriptor.token);
+        // Tail streams stay open indefinitely, so timeouts are per request.
+        let http = reqwest::blocking::Client::builder()
+            .timeout(None)
+            .build()
+            .map_err(|error| format!("Failed to build HTTP client: {error}"))?;
+        Ok(Self {
+            endpoint: descriptor.endpoint,
+            token,
+            http,
+        })
+    }
+
+    fn send(
+        &self,
+        method: &str,
+        params: Value,
+        timeout: Option<Duration>,
+    ) -> Result<reqwest::blocking::Response, String> {
+        let mut request = self.http.post(&self.endpoint);
+        if let Some(timeout) = timeout {
+            request = request.timeout(timeout);
+        }
+        request
+            .bearer_auth(&self.token)
+            .json(&json!({
+                "jsonrpc": "2.0",
+                "id": format!("ccem-ctl-{}", std::process::id()),
+                "method": method,
+                "params": params,
+            }))
+            .send()
+            .map_err(|error| format!("Failed to reach CCEM Desktop: {error}"))
+    }
+
+    fn call(&self, method: &str, params: Value) -> Result<Value, String> {
+        let response = self.send(method, params, Some(REQUEST_TIMEOUT))?;
+        let status = response.status();
+        let body = response
+            .json::<Value>()
+            .map_err(|error| format!("{method} returned {status} without JSON: {error}"))?;
+        rpc_result(body)
+    }
+}
+
+fn descriptor_path() -> Result<PathBuf, String> {
+    if let Some(path) = std::env::var_os("CCEM_CONTROL_FILE").filter(|path| !path.is_empty()) {
+        return Ok(PathBuf::from(path));
+    }
+    dirs::home_dir()
+        .map(|home| home.join(".ccem").join("control.json"))
+        .ok_or_else(|| "Could not resolve the home directory".to_string())
+}
+
+fn rpc_result(mut body: Value) -> Result<Value, String> {
+    if let Some(error) = body.get("error") {
+        let message = error
+            .get("message")
+            .and_then(Value::as_str)
+            .unwrap_or("unknown error");
+        return Err(message.to_string());
+    }
+    Ok(body
+        .get_mut("result")
+        .map(Value::take)
+        .unwrap_or(Value::Null))
+}
+
+fn run(client: &ControlClient, invocation: Invocation) -> Result<(), String> {
+    let json_output = invocation.json;
+    match invocation.command {
+        Command::ListSessions {
+            cwd,
+            provider,
+            status,
+        } => {
+            let cwd = cwd.map(|dir| absolute_dir(&dir)).transpose()?;
+            let sessions = client.call(
+                "ccem.workspace.listSessions",
+                json!({ "cwd": cwd, "provider": provider, "status": status }),
+            )?;
+            if json_output {
+                return print_json(&sessions);
+            }
+            print!("{}", render_sessions_table(&sessions));
+            Ok(())
+        }
+        Command::NewSession {
+            provider,
+            preset,
+            cwd,
+            env_name,
+            perm_mode,
+            prompt,
+        } => {
+            // A preset may carry its own working directory; only default to
+            // the current one when there is no preset to supply it.
+            let cwd = match (cwd, &preset) {
+                (Some(dir), _) => Some(absolute_dir(&dir)?),
+                (None, Some(_)) => None,
+                (None, None) => Some(absolute_dir(".")?),
+            };
+            let created = client.call(
+                "ccem.workspace.createSession",
+                json!({
+                    "preset": preset,
+                    "provider": provider,
+                    "cwd": cwd,
+                    "prompt": prompt,
+                    "envName": env_name,
+                    "permissionMode": perm_mode,
+                }),
+            )?;
+            if json_output {
+                return print_json(&created);
+            }
+            println!(
+                "{}\t{}",
+                str_field(&created, "runtimeId"),
+                str_field(&created, "status")
+            );
+            Ok(())
+        }
+        Command::Tail {
+            runtime_id,
+            since_seq,
+        } => tail(client, &runtime_id, since_seq, json_output),
+        Command::Stop { runtime_id } => {
+            let result = client.call(
+                "ccem.workspace.stopSession",
+                json!({ "runtimeId": runtime_id }),
+            )?;
+            if json_output {
+                return print_json(&result);
+            }
+            println!("stopped {runtime_id}");
+            Ok(())
+        }
+        Command::RespondPermission {
+            runtime_id,
+            request_id,
+            approved,
+        } => {
+            let request_id = match request_id {
+                Some(request_id) => request_id,
+                None => pending_permission_request(&fetch_all_events(client, &runtime_id)?)
+                    .ok_or_else(|| format!("{runtime_id} has no pending permission request"))?,
+            };
+            let result = client.call(
+                "ccem.workspace.respondToPermission",
+                json!({
+                    "runtimeId": runtime_id,
+                    "requestId": request_id,
+                    "approved": approved,
+                }),
+            )?;
+            if json_output {
+                return print_json(&result);
+            }
+            let verb = if approved { "approved" } else { "denied" };
+            println!("{verb} {request_id}");
+            Ok(())
+        }
+        Command::RouterExplain { runtime_id } => {
+            let routes = client.call(
+                "ccem.workspace.getRouter",
+                json!({ "runtimeId": runtime_id }),
+            )?;
+            let status = client.call("ccem.router.status", json!({}))?;
+            if json_output {
+                return print_json(&json!({ "session": routes, "router": status }));
+            }
+            print!("{}", render_router_explanation(&routes, &status));
+            Ok(())
+        }
+    }
+}
+
+fn tail(
+    client: &ControlClient,
+    runtime_id: &str,
+    since_seq: Option<u64>,
+    json_output: bool,
+) -> Result<(), String> {
+    let mut params = json!({ "runtimeIds": [runtime_id] });
+    if let Some(since_seq) = since_seq {
+        params["sinceSeqs"] = json!({ runtime_id: since_seq });
+    }
+    let response = client.send("ccem.workspace.subscribe", params, None)?;
+    let is_stream = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("text/event-stream"));
+    if !is_stream {
+        let body = response
+            .json::<Value>()
+            .map_err(|error| format!("Subscription failed: {error}"))?;
+        return rpc_result(body).map(|_| ());
+    }
+
+    let stdout = std::io::stdout();
+    let mut out = stdout.lock();
+    let mut renderer = EventRenderer::default();
+    let mut event_name = String::new();
+    let mut data = String::new();
+    for line in BufReader::new(response).lines() {
+        let line = line.map_err(|error| format!("Event stream closed: {error}"))?;
+        if let Some(value) = line.strip_prefix("event: ") {
+            event_name = value.to_string();
+        } else if let Some(value) = line.strip_prefix("data: ") {
+            data.push_str(value);
+        } else if line.is_empty() && !data.is_empty() {
+            let record = serde_json::from_str::<Value>(&data)
+                .map_err(|error| format!("Malformed event frame: {error}"))?;
+            if event_name != "subscribed" {
+                let text = if json_output {
+                    format!("{record}\n")
+                } else {
+                    renderer.render(&record)
+                };
+                out.write_all(text.as_bytes())
+                    .and_then(|_| out.flush())
+                    .map_err(|error| error.to_string())?;
+            }
+            event_name.clear();
+            data.clear();
+        }
+    }
+    Ok(())
+}
+
+/// Turns session event records into terminal lines. Assistant chunks stream
+/// inline; any other event first closes an open chunk line.
+#[derive(Default)]
+struct EventRenderer {
+    in_chunk: bool,
+}
+
+impl EventRenderer {
+    fn render(&mut self, record: &Value) -> String {
+        let payload = &record["payload"];
+        let kind = payload["type"].as_str().unwrap_or("unknown");
+        match kind {
+            "assistant_chunk" => {
+                self.in_chunk = true;
+                return str_field(payload, "text").to_string();
+            }
+            // Raw provider frames and usage ticks are only useful with --json.
+            "claude_json" | "token_usage" => return String::new(),
+            _ => {}
+        }
+        let line = match kind {
+            "user_prompt" => format!("> {}", str_field(payload, "text")),
+            "system_message" => format!("[system] {}", str_field(payload, "message")),
+            "lifecycle" => format!(
+                "[{}] {}",
+                str_field(payload, "stage"),
+                str_field(payload, "detail")
+            ),
+            "stderr_line" => format!("[stderr] {}", str_field(payload, "line")),
+            "tool_use_started" => format!(
+                "[tool] {} {}",
+                str_field(payload, "raw_name"),
+                str_field(payload, "input_summary")
+            ),
+            "tool_use_completed" => format!(
+                "[tool {}] {} {}",
+                if payload["success"].as_bool().unwrap_or(false) {
+                    "ok"
+                } else {
+                    "failed"
+                },
+                str_field(payload, "raw_name"),
+                str_field(payload, "result_summary")
+            ),
+            "permission_required" => format!(
+                "[permission] {} wants {} {} (ccem-ctl approve {} {})",
+                str_field(payload, "request_id"),
+                str_field(payload, "tool_name"),
+                str_field(payload, "input_summary"),
+                str_field(record, "runtime_id"),
+                str_field(payload, "request_id")
+            ),
+            "permission_responded" => format!(
+                "[permission] {} {}",
+                str_field(payload, "request_id"),
+                if payload["approved"].as_bool().unwrap_or(false) {
+                    "approved"
+                } else {
+                    "denied"
+                }
+            ),
+            "gap_notification" => format!(
+                "[gap] events after seq {} were dropped; resuming at {}",
+                payload["last_seen_seq"], payload["oldest_available_seq"]
+            ),
+            "session_completed" => format!("[completed] {}", str_field(payload, "reason")),
+            other => format!("[{other}]"),
+        };
+        let prefix = if std::mem::take(&mut self.in_chunk) {
+            "\n"
+        } else {
+            ""
+        };
+        format!("{prefix}{line}\n")
+    }
+}
+
+/// Replays the whole retained event log, following truncated batches.
+fn fetch_all_events(client: &ControlClient, runtime_id: &str) -> Result<Vec<Value>, String> {
+    let mut events = Vec::new();
+    let mut since_seq = None::<u64>;
+    loop {
+        let mut batch = client.call(
+            "ccem.workspace.getEvents",
+            json!({ "runtimeId": runtime_id, "sinceSeq": since_seq }),
+        )?;
+        let page = match batch["events"].take() {
+            Value::Array(page) => page,
+            _ => Vec::new(),
+        };
+        let last_seq = page.last().and_then(|event| event["seq"].as_u64());
+        events.extend(page);
+        match last_seq {
+            Some(seq) if batch["truncated"].as_bool().unwrap_or(false) => since_seq = Some(seq),
+            _ => return Ok(events),
+        }
+    }
+}
+
+/// The newest `permission_required` request without a matching response.
+fn pending_permission_request(events: &[Value]) -> Option<String> {
+    let mut pending = Vec::<String>::new();
+    for event in events {
+        let payload = &event["payload"];
+        let request_id = payload["request_id"].as_str();
+        match (payload["type"].as_str(), request_id) {
+            (Some("permission_required"), Some(request_id)) => pending.push(request_id.to_string()),
+            (Some("permission_responded"), Some(request_id)) => {
+                pending.retain(|pending_id| pending_id != request_id)
+            }
+            _ => {}
+        }
+    }
+    pending.pop()
+}
+
+fn render_sessions_table(sessions: &Value) -> String {
+    let rows = sessions
+        .as_array()
+        .map(|sessions| {
+            sessions
+                .iter()
+                .map(|session| {
+                    vec![
+                        str_field(session, "runtimeId").to_string(),
+                        str_field(session, "provider").to_string(),
+                        str_field(session, "status").to_string(),
+                        str_field(session, "envName").to_string(),
+                        str_field(session, "projectDir").to_string(),
+                    ]
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    render_table(&["RUNTIME", "PROVIDER", "STATUS", "ENV", "PROJECT"], &rows)
+}
+
+fn render_router_explanation(routes: &Value, status: &Value) -> String {
+    let mut output = format!(
+        "router: {} (port {})\n",
+        str_field(status, "state"),
+        status["actualPort"]
+            .as_u64()
+            .or_else(|| status["requestedPort"].as_u64())
+            .map(|port| port.to_string())
+            .unwrap_or_else(|| "-".to_string())
+    );
+    output.push_str(&format!(
+        "transport: {}\ndefault env: {}\ndynamic routing: {}\n",
+        str_field(routes, "launchTransport"),
+        str_field(routes, "defaultEnv"),
+        routes["dynamicRouting"].as_bool().unwrap_or(false)
+    ));
+    let mut bindings = routes["bindings"]
+        .as_object()
+        .map(|bindings| {
+            bindings
+                .iter()
+                .map(|(tier, env)| vec![tier.clone(), env.as_str().unwrap_or("").to_string()])
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    bindings.sort();
+    if !bindings.is_empty() {
+        output.push_str(&render_table(&["TIER", "ENV"], &bindings));
+    }
+    if let Some(allowed) = routes["allowedEnvs"].as_array() {
+        let allowed = allowed.iter().filter_map(Value::as_str).collect::<Vec<_>>();
+        output.push_str(&format!("allowed envs: {}\n", allowed.join(", ")));
+    }
+    for warning in routes["warnings"].as_array().into_iter().flatten() {
+        output.push_str(&format!("warning: {}\n", warning.as_str().unwrap_or("")));
+    }
+    output
+}
+
+fn render_table(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let mut widths = headers
+        .iter()
+        .map(|header| header.len())
+        .collect::<Vec<_>>();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let format_row = |cells: Vec<&str>| {
+        let line = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+        format!("{}\n", line.trim_end())
+    };
+    let mut output = format_row(headers.to_vec());
+    for row in rows {
+        output.push_str(&format_row(row.iter().map(String::as_str).collect()));
+    }
+    output
+}
+
+fn str_field<'a>(value: &'a Value, key: &str) -> &'a str {
+    value[key].as_str().unwrap_or("")
+}
+
+fn absolute_dir(dir: &str) -> Result<String, String> {
+    let path = Path::new(dir);
+    let absolute = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir()
+            .map_err(|error| format!("Failed to read the current directory: {error}"))?
+            .join(path)
+    };
+    let absolute = absolute.canonicalize().unwrap_or(absolute);
+    Ok(absolute.to_string_lossy().to_string())
+}
+
+fn print_json(value: &Value) -> Result<(), String> {
+    let text = serde_json::to_string_pretty(value).map_err(|error| error.to_string())?;
+    println!("{text}");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn parses_subcommands_and_flags() {
+        assert_eq!(
+            parse_args(&args(
+                "--json session new --provider claude --cwd . fix the build"
+            ))
+            .expect("session new"),
+            Invocation {
+                json: true,
+                command: Command::NewSession {
+                    provider: Some("claude".to_string()),
+                    preset: None,
+                    cwd: Some(".".to_string()),
+                    env_name: None,
+                    perm_mode: None,
+                    prompt: "fix the build".to_string(),
+                },
+            }
+        );
+        assert_eq!(
+            parse_args(&args("deny rt-1")).expect("deny").command,
+            Command::RespondPermission {
+                runtime_id: "rt-1".to_string(),
+                request_id: None,
+                approved: false,
+            }
+        );
+        assert_eq!(
+            parse_args(&args("session tail rt-1 --since 42"))
+                .expect("tail")
+                .command,
+            Command::Tail {
+                runtime_id: "rt-1".to_string(),
+                since_seq: Some(42),
+            }
+        );
+        assert!(parse_args(&args("session new fix it")).is_err());
+        assert_eq!(
+            parse_args(&args(
+                "session new --preset review-opus-readonly check auth"
+            ))
+            .expect("session new with preset")
+            .command,
+            Command::NewSession {
+                provider: None,
+                preset: Some("review-opus-readonly".to_string()),
+                cwd: None,
+                env_name: None,
+                perm_mode: None,
+                prompt: "check auth".to_string(),
+            }
+        );
+        assert!(parse_args(&args("session tail rt-1 --since soon")).is_err());
+    }
+
+    #[test]
+    fn finds_the_newest_unanswered_permission_request() {
+        let events = vec![
+            json!({ "payload": { "type": "permission_required", "request_id": "a" } }),
+            json!({ "payload": { "type": "permission_required", "request_id": "b" } }),
+            json!({ "payload": { "type": "permission_responded", "request_id": "b", "approved": true } }),
+        ];
+        assert_eq!(pending_permission_request(&events).as_deref(), Some("a"));
+        assert_eq!(pending_permission_request(&[]), None);
+    }
+
+    #[test]
+    fn renders_chunks_inline_and_closes_them_before_other_events() {
+        let mut renderer = EventRenderer::default();
+        let chunk = json!({ "payload": { "type": "assistant_chunk", "text": "Hello" } });
+        let tool = json!({ "runtime_id": "rt-1", "payload": {
+            "type": "tool_use_started", "raw_name": "Bash", "input_summary": "cargo test"
+        }});
+        assert_eq!(renderer.render(&chunk), "Hello");
+        assert_eq!(renderer.render(&tool), "\n[tool] Bash cargo test\n");
+        assert_eq!(renderer.render(&tool), "[tool] Bash cargo test\n");
+    }
+
+    #[test]
+    fn renders_aligned_session_tables() {
+        let table = render_sessions_table(&json!([{
+            "runtimeId": "rt-1",
+            "provider": "claude",
+            "status": "processing",
+            "envName": "official",
+            "projectDir": "/work/repo"
+        }]));
+        assert_eq!(
+            table,
+            "RUNTIME  PROVIDER  STATUS      ENV       PROJECT\n\
+             rt-1     claude    processing  official  /work/repo\n"
+        );
+    }
+}
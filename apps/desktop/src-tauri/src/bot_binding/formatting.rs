@@ -16,9 +16,13 @@ pub(super) fn summarize_session_event(event: &SessionEventRecord) -> Option<Even
 
 pub(super) fn summarize_payload(payload: &SessionEventPayload) -> Option<EventSummary> {
     match payload {
-        SessionEventPayload::UserPrompt { text, .. } => Some(EventSummary {
+        SessionEventPayload::UserPrompt { text, queued, .. } => Some(EventSummary {
             kind: BotBindingOutboxFrameKind::EventUpdate,
-            title: "User prompt".to_string(),
+            title: if *queued {
+                "Queued prompt".to_string()
+            } else {
+                "User prompt".to_string()
+            },
             text: truncate_text(text, 1200),
         }),
         SessionEventPayload::SystemMessage { message } => Some(EventSummary {
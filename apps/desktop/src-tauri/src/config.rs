@@ -1,4 +1,6 @@
 use crate::crypto;
+use crate::native_event_retention::NativeEventLogRetention;
+use crate::native_limits::NativeSessionLimits;
 use crate::router::RouterConfig;
 use crate::secure_fs::{ensure_private_dir, open_private_lock_file, write_private_atomic};
 use fs2::FileExt;
@@ -1253,6 +1255,33 @@ pub struct DesktopSettings {
         skip_serializing_if = "Option::is_none"
     )]
     pub enabled_environments: Option<Vec<String>>,
+    /// Default limits for native sessions. Only written through
+    /// `set_native_session_limit_defaults`, never by Settings page saves.
+    #[serde(
+        rename = "nativeSessionLimits",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub native_session_limits: Option<NativeSessionLimits>,
+    /// Resume native sessions interrupted by a crash or update on startup
+    /// instead of only offering them. Only written through
+    /// `set_native_session_auto_resume`.
+    #[serde(rename = "autoResumeNativeSessions", default)]
+    pub auto_resume_native_sessions: bool,
+    /// Hand native sessions the `ccem-sessions` MCP server so their agents can
+    /// spawn and drive other sessions. Off unless the user opts in through
+    /// `set_native_sessions_mcp_enabled`.
+    #[serde(rename = "nativeSessionsMcpEnabled", default)]
+    pub native_sessions_mcp_enabled: bool,
+    /// Compaction, size caps and vacuum schedule for the native event log;
+    /// `None` uses the built-in defaults. Only written through
+    /// `set_native_event_log_retention`.
+    #[serde(
+        rename = "nativeEventLogRetention",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub native_event_log_retention: Option<NativeEventLogRetention>,
 }
 
 fn default_theme() -> String {
@@ -1308,6 +1337,10 @@ impl Default for DesktopSettings {
             ai_enhanced: false,
             ai_env_name: None,
             enabled_environments: None,
+            native_session_limits: None,
+            auto_resume_native_sessions: false,
+            native_sessions_mcp_enabled: false,
+            native_event_log_retention: None,
         }
     }
 }
@@ -0,0 +1,572 @@
+//! Named, revocable API tokens for the external control server.
+//!
+//! The descriptor token published by `ExternalControlManager` keeps full
+//! access for the desktop's own CLI. Scoped tokens are minted here for other
+//! tools (editor plugins, team scripts): each carries a capability set, an
+//! optional directory allowlist for session creation and an optional expiry.
+//! Secrets are stored encrypted with the install key, and every call made with
+//! a scoped token is appended to an audit log.
+
+use crate::crypto;
+use crate::secure_fs::write_private_atomic;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use rand::{distributions::Alphanumeric, Rng};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+
+const TOKEN_PREFIX: &str = "ccem_";
+const DEFAULT_AUDIT_LIMIT: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ControlCapability {
+    /// List sessions, read events and router state, subscribe to streams.
+    ReadSessions,
+    /// Send prompts, answer permission or interactive prompts and ask a
+    /// session to report its usage.
+    SendInput,
+    /// Stop sessions and background tasks, rewind files, change settings.
+    ManageSessions,
+    /// Launch sessions, limited to `allowed_dirs` when that list is set.
+    CreateSessions,
+    /// Change global router settings and rename or delete environments.
+    RouterAdmin,
+    /// List, add, change, toggle and delete scheduled tasks.
+    Cron,
+    /// Search history and read usage statistics.
+    History,
+}
+
+/// Methods every valid token may call regardless of its capabilities.
+const OPEN_METHODS: &[&str] = &["ccem.health", "ccem.rpc.discover"];
+
+pub fn capability_for_method(method: &str) -> Option<ControlCapability> {
+    let capability = match method {
+        "ccem.workspace.listSessions"
+        | "ccem.workspace.getSession"
+        | "ccem.workspace.getEvents"
+        | "ccem.workspace.subscribe"
+        | "ccem.workspace.getRouter"
+        | "ccem.router.status"
+        | "ccem.environment.references" => ControlCapability::ReadSessions,
+        "ccem.workspace.sendInput"
+        | "ccem.workspace.respondToPermission"
+        | "ccem.workspace.respondToPrompt"
+        | "ccem.workspace.queryUsage"
+        | "ccem.workspace.openSession" => ControlCapability::SendInput,
+        "ccem.workspace.stopSession"
+        | "ccem.workspace.stopBackgroundTask"
+        | "ccem.workspace.rewindFiles"
+        | "ccem.workspace.updateSettings"
+        | "ccem.workspace.updateRouter"
+        | "ccem.workspace.restartDirect" => ControlCapability::ManageSessions,
+        "ccem.workspace.createSession" => ControlCapability::CreateSessions,
+        "ccem.router.getSettings"
+        | "ccem.router.updateSettings"
+        | "ccem.environment.rename"
+        | "ccem.environment.delete" => ControlCapability::RouterAdmin,
+        "ccem.history.search" | "ccem.history.searchFullText" | "ccem.usage.stats" => {
+            ControlCapability::History
+        }
+        method if method.starts_with("ccem.cron.") => ControlCapability::Cron,
+        _ => return None,
+    };
+    Some(capability)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ControlTokenRecord {
+    id: String,
+    name: String,
+    /// Encrypted bearer secret (`crypto::encrypt` format).
+    secret: String,
+    capabilities: Vec<ControlCapability>,
+    #[serde(default)]
+    allowed_dirs: Vec<String>,
+    created_at: DateTime<Utc>,
+    #[serde(default)]
+    expires_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    revoked_at: Option<DateTime<Utc>>,
+}
+
+impl ControlTokenRecord {
+    fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.revoked_at.is_none() && self.expires_at.is_none_or(|expires| expires > now)
+    }
+
+    fn summary(&self, now: DateTime<Utc>) -> ControlTokenSummary {
+        ControlTokenSummary {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            capabilities: self.capabilities.clone(),
+            allowed_dirs: self.allowed_dirs.clone(),
+            created_at: self.created_at,
+            expires_at: self.expires_at,
+            revoked_at: self.revoked_at,
+            active: self.is_active(now),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+struct ControlTokenFile {
+    #[serde(default)]
+    tokens: Vec<ControlTokenRecord>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ControlTokenSummary {
+    pub id: String,
+    pub name: String,
+    pub capabilities: Vec<ControlCapability>,
+    pub allowed_dirs: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub active: bool,
+}
+
+/// Returned once at creation; the plaintext secret is never shown again.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatedControlToken {
+    pub token: String,
+    #[serde(flatten)]
+    pub summary: ControlTokenSummary,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateControlTokenRequest {
+    pub name: String,
+    pub capabilities: Vec<ControlCapability>,
+    #[serde(default)]
+    pub allowed_dirs: Vec<String>,
+    #[serde(default)]
+    pub expires_in_days: Option<u32>,
+}
+
+/// What an authenticated scoped token may do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ControlTokenGrant {
+    pub id: String,
+    pub name: String,
+    pub capabilities: Vec<ControlCapability>,
+    pub allowed_dirs: Vec<String>,
+}
+
+impl ControlTokenGrant {
+    pub fn allows_method(&self, method: &str) -> bool {
+        OPEN_METHODS.contains(&method)
+            || capability_for_method(method)
+                .is_some_and(|capability| self.capabilities.contains(&capability))
+    }
+
+    /// `cwd` must sit inside one of `allowed_dirs`; an empty list allows any.
+    /// Both sides are resolved through symlinks, so a link inside an allowed
+    /// directory cannot point a session somewhere else.
+    pub fn allows_directory(&self, cwd: &str) -> bool {
+        if self.allowed_dirs.is_empty() {
+            return true;
+        }
+        let cwd = resolve_dir(Path::new(cwd));
+        self.allowed_dirs
+            .iter()
+            .any(|allowed| cwd.starts_with(resolve_dir(Path::new(allowed))))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ControlAuditEntry {
+    pub at: DateTime<Utc>,
+    pub token_id: String,
+    pub token_name: String,
+    pub method: String,
+    /// `ok`, `error`, `denied` or `stream`.
+    pub outcome: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+pub struct ControlTokenStore {
+    path: PathBuf,
+    audit_path: PathBuf,
+    seal: fn(&str) -> Result<String, String>,
+    unseal: fn(&str) -> Result<String, String>,
+    write_lock: Mutex<()>,
+}
+
+impl Default for ControlTokenStore {
+    fn default() -> Self {
+        let dir = crate::config::get_ccem_dir();
+        Self::new(
+            dir.join("control-tokens.json"),
+            dir.join("control-audit.jsonl"),
+        )
+    }
+}
+
+impl ControlTokenStore {
+    pub fn new(path: PathBuf, audit_path: PathBuf) -> Self {
+        Self {
+            path,
+            audit_path,
+            seal: crypto::encrypt,
+            unseal: crypto::decrypt,
+            write_lock: Mutex::new(()),
+        }
+    }
+
+    pub fn list(&self) -> Result<Vec<ControlTokenSummary>, String> {
+        let now = Utc::now();
+        Ok(self
+            .read()?
+            .tokens
+            .iter()
+            .map(|record| record.summary(now))
+            .collect())
+    }
+
+    pub fn create(
+        &self,
+        request: CreateControlTokenRequest,
+    ) -> Result<CreatedControlToken, String> {
+        let name = request.name.trim();
+        if name.is_empty() {
+            return Err("Token name cannot be empty".to_string());
+        }
+        if request.capabilities.is_empty() {
+            return Err("Token needs at least one capability".to_string());
+        }
+        let _guard = self.lock()?;
+        let mut file = self.read()?;
+        if file
+            .tokens
+            .iter()
+            .any(|record| record.name == name && record.revoked_at.is_none())
+        {
+            return Err(format!("A token named {name} already exists"));
+        }
+        let now = Utc::now();
+        let token = format!("{TOKEN_PREFIX}{}", random_alphanumeric(40));
+        let mut capabilities = request.capabilities;
+        capabilities.sort_by_key(|capability| *capability as u8);
+        capabilities.dedup();
+        let record = ControlTokenRecord {
+            id: format!("tok-{}", random_alphanumeric(10).to_ascii_lowercase()),
+            name: name.to_string(),
+            secret: (self.seal)(&token)?,
+            capabilities,
+            allowed_dirs: request
+                .allowed_dirs
+                .iter()
+                .map(|dir| dir.trim())
+                .filter(|dir| !dir.is_empty())
+                .map(str::to_string)
+                .collect(),
+            created_at: now,
+            expires_at: request
+                .expires_in_days
+                .map(|days| now + ChronoDuration::days(i64::from(days))),
+            revoked_at: None,
+        };
+        let summary = record.summary(now);
+        file.tokens.push(record);
+        self.write(&file)?;
+        Ok(CreatedControlToken { token, summary })
+    }
+
+    pub fn revoke(&self, id: &str) -> Result<ControlTokenSummary, String> {
+        let _guard = self.lock()?;
+        let mut file = self.read()?;
+        let now = Utc::now();
+        let record = file
+            .tokens
+            .iter_mut()
+            .find(|record| record.id == id)
+            .ok_or_else(|| format!("Control token {id} not found"))?;
+        record.revoked_at.get_or_insert(now);
+        let summary = record.summary(now);
+        self.write(&file)?;
+        Ok(summary)
+    }
+
+    /// Resolve a bearer secret to an active scoped token, if any.
+    pub fn authenticate(&self, bearer: &str) -> Option<ControlTokenGrant> {
+        if !bearer.starts_with(TOKEN_PREFIX) {
+            return None;
+        }
+        let now = Utc::now();
+        let file = self.read().ok()?;
+        file.tokens
+            .into_iter()
+            .filter(|record| record.is_active(now))
+            .find(|record| (self.unseal)(&record.secret).is_ok_and(|secret| secret == bearer))
+            .map(|record| ControlTokenGrant {
+                id: record.id,
+                name: record.name,
+                capabilities: record.capabilities,
+                allowed_dirs: record.allowed_dirs,
+            })
+    }
+
+    pub fn record_call(
+        &self,
+        grant: &ControlTokenGrant,
+        method: &str,
+        outcome: &str,
+        detail: Option<String>,
+    ) {
+        let entry = ControlAuditEntry {
+            at: Utc::now(),
+            token_id: grant.id.clone(),
+            token_name: grant.name.clone(),
+            method: method.to_string(),
+            outcome: outcome.to_string(),
+            detail,
+        };
+        if let Err(error) = self.append_audit(&entry) {
+            eprintln!("Failed to write control audit entry: {error}");
+        }
+    }
+
+    /// Most recent audit entries first, optionally for one token.
+    pub fn audit_log(
+        &self,
+        token_id: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<ControlAuditEntry>, String> {
+        let file = match fs::File::open(&self.audit_path) {
+            Ok(file) => file,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(error) => return Err(format!("Failed to read control audit log: {error}")),
+        };
+        let mut entries = BufReader::new(file)
+            .lines()
+            .map_while(Result::ok)
+            .filter_map(|line| serde_json::from_str::<ControlAuditEntry>(&line).ok())
+            .filter(|entry| token_id.is_none_or(|id| entry.token_id == id))
+            .collect::<Vec<_>>();
+        entries.reverse();
+        entries.truncate(limit);
+        Ok(entries)
+    }
+
+    fn append_audit(&self, entry: &ControlAuditEntry) -> Result<(), String> {
+        let mut line = serde_json::to_vec(entry).map_err(|error| error.to_string())?;
+        line.push(b'\n');
+        if let Some(parent) = self.audit_path.parent() {
+            fs::create_dir_all(parent).map_err(|error| error.to_string())?;
+        }
+        let _guard = self.lock()?;
+        let mut options = OpenOptions::new();
+        options.create(true).append(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        options
+            .open(&self.audit_path)
+            .and_then(|mut file| file.write_all(&line))
+            .map_err(|error| error.to_string())
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, ()>, String> {
+        self.write_lock
+            .lock()
+            .map_err(|_| "Control token store is unavailable".to_string())
+    }
+
+    fn read(&self) -> Result<ControlTokenFile, String> {
+        match fs::read(&self.path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .map_err(|error| format!("Failed to parse control tokens: {error}")),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                Ok(ControlTokenFile::default())
+            }
+            Err(error) => Err(format!("Failed to read control tokens: {error}")),
+        }
+    }
+
+    fn write(&self, file: &ControlTokenFile) -> Result<(), String> {
+        let bytes = serde_json::to_vec_pretty(file).map_err(|error| error.to_string())?;
+        write_private_atomic(&self.path, &bytes)
+            .map_err(|error| format!("Failed to write control tokens: {error}"))
+    }
+}
+
+fn random_alphanumeric(len: usize) -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(len)
+        .map(char::from)
+        .collect()
+}
+
+/// Canonicalize `path`. A path that does not exist yet is normalized
+/// lexically and its deepest existing ancestor canonicalized, so the check
+/// still sees through symlinks above the missing part.
+fn resolve_dir(path: &Path) -> PathBuf {
+    if let Ok(canonical) = fs::canonicalize(path) {
+        return canonical;
+    }
+    let normalized = normalize_dir(path);
+    let mut missing = Vec::new();
+    let mut existing = normalized.as_path();
+    loop {
+        if let Ok(canonical) = fs::canonicalize(existing) {
+            return missing
+                .iter()
+                .rev()
+                .fold(canonical, |resolved, name| resolved.join(name));
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                missing.push(name);
+                existing = parent;
+            }
+            _ => return normalized,
+        }
+    }
+}
+
+fn normalize_dir(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other.as_os_str()),
+        }
+    }
+    normalized
+}
+
+#[tauri::command]
+pub async fn list_control_tokens() -> Result<Vec<ControlTokenSummary>, String> {
+    tauri::async_runtime::spawn_blocking(|| ControlTokenStore::default().list())
+        .await
+        .map_err(|error| format!("Control token task failed: {error}"))?
+}
+
+#[tauri::command]
+pub async fn create_control_token(
+    request: CreateControlTokenRequest,
+) -> Result<CreatedControlToken, String> {
+    tauri::async_runtime::spawn_blocking(move || ControlTokenStore::default().create(request))
+        .await
+        .map_err(|error| format!("Control token task failed: {error}"))?
+}
+
+#[tauri::command]
+pub async fn revoke_control_token(id: String) -> Result<ControlTokenSummary, String> {
+    tauri::async_runtime::spawn_blocking(move || ControlTokenStore::default().revoke(&id))
+        .await
+        .map_err(|error| format!("Control token task failed: {error}"))?
+}
+
+#[tauri::command]
+pub async fn get_control_token_audit(
+    token_id: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<ControlAuditEntry>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        ControlTokenStore::default()
+            .audit_log(token_id.as_deref(), limit.unwrap_or(DEFAULT_AUDIT_LIMIT))
+    })
+    .await
+    .map_err(|error| format!("Control token task failed: {error}"))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_store(dir: &Path) -> ControlTokenStore {
+        ControlTokenStore {
+            seal: |plain| Ok(format!("sealed:{plain}")),
+            unseal: |sealed| {
+                sealed
+                    .strip_prefix("sealed:")
+                    .map(str::to_string)
+                    .ok_or_else(|| "not sealed".to_string())
+            },
+            ..ControlTokenStore::new(dir.join("tokens.json"), dir.join("audit.jsonl"))
+        }
+    }
+
+    #[test]
+    fn scoped_tokens_authenticate_until_revoked_and_never_store_plaintext() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let store = test_store(dir.path());
+        let created = store
+            .create(CreateControlTokenRequest {
+                name: "editor".to_string(),
+                capabilities: vec![
+                    ControlCapability::ReadSessions,
+                    ControlCapability::CreateSessions,
+                    ControlCapability::ReadSessions,
+                ],
+                allowed_dirs: vec!["/work/repo".to_string()],
+                expires_in_days: Some(30),
+            })
+            .expect("create token");
+        assert!(created.token.starts_with(TOKEN_PREFIX));
+        assert_eq!(created.summary.capabilities.len(), 2);
+
+        let raw = fs::read_to_string(dir.path().join("tokens.json")).expect("token file");
+        assert!(!raw.contains(&format!("\"{}\"", created.token)));
+
+        let grant = store.authenticate(&created.token).expect("active token");
+        assert!(grant.allows_method("ccem.workspace.getEvents"));
+        assert!(grant.allows_method("ccem.health"));
+        assert!(!grant.allows_method("ccem.environment.delete"));
+        assert!(!grant.allows_method("ccem.workspace.sendInput"));
+        assert!(grant.allows_directory("/work/repo/crates/../src"));
+        assert!(!grant.allows_directory("/work/repo/../other"));
+        assert!(store.authenticate("ccem_wrong").is_none());
+
+        store.record_call(&grant, "ccem.environment.delete", "denied", None);
+        store.record_call(&grant, "ccem.workspace.getEvents", "ok", None);
+        let audit = store.audit_log(Some(&grant.id), 10).expect("audit log");
+        assert_eq!(audit.len(), 2);
+        assert_eq!(audit[0].method, "ccem.workspace.getEvents");
+        assert_eq!(audit[1].outcome, "denied");
+
+        let revoked = store.revoke(&created.summary.id).expect("revoke");
+        assert!(!revoked.active);
+        assert!(store.authenticate(&created.token).is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_inside_allowed_dirs_cannot_escape_the_scope() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let allowed = dir.path().join("allowed");
+        let outside = dir.path().join("outside");
+        fs::create_dir_all(allowed.join("nested")).expect("allowed dir");
+        fs::create_dir_all(&outside).expect("outside dir");
+        std::os::unix::fs::symlink(&outside, allowed.join("escape")).expect("symlink");
+        let grant = ControlTokenGrant {
+            id: "tok-test".to_string(),
+            name: "editor".to_string(),
+            capabilities: vec![ControlCapability::CreateSessions],
+            allowed_dirs: vec![allowed.to_string_lossy().into_owned()],
+        };
+
+        assert!(grant.allows_directory(&allowed.join("nested").to_string_lossy()));
+        assert!(grant.allows_directory(&allowed.join("not-yet").to_string_lossy()));
+        assert!(!grant.allows_directory(&allowed.join("escape").to_string_lossy()));
+        assert!(!grant.allows_directory(&allowed.join("escape/deeper").to_string_lossy()));
+    }
+}
@@ -1,4 +1,6 @@
 use crate::config;
+use crate::launch_presets::{LaunchPreset, LaunchPresets};
+use crate::native_runtime::NativeProvider;
 use crate::session_provenance::{
     register_launch, spawn_claude_source_binding, SessionProvenanceUpsert, DEFAULT_CONFIG_SOURCE,
 };
@@ -53,6 +55,10 @@ pub struct CronTask {
     pub trigger_type: String,
     #[serde(rename = "parentTaskId", default)]
     pub parent_task_id: Option<String>,
+    /// Launch preset supplying env, permission mode and prompt prefix for
+    /// fields the task leaves unset.
+    #[serde(rename = "launchPreset", default)]
+    pub launch_preset: Option<String>,
     #[serde(rename = "createdAt")]
     pub created_at: String,
     #[serde(rename = "updatedAt")]
@@ -767,10 +773,51 @@ fn resolve_execution_profile(value: &str) -> ExecutionProfilePreset {
     }
 }
 
+/// Cron runs `claude -p`, so only Claude presets can back a task.
+fn resolve_cron_launch_preset(name: &str) -> Result<LaunchPreset, String> {
+    let preset = LaunchPresets::load().get(name)?.clone();
+    if preset.provider != NativeProvider::Claude {
+        return Err(format!(
+            "Launch preset {} launches {}; cron tasks only run Claude",
+            preset.name,
+            preset.provider.as_str()
+        ));
+    }
+    Ok(preset)
+}
+
+fn normalize_task_launch_preset(value: &str) -> Result<Option<String>, String> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(None);
+    }
+    resolve_cron_launch_preset(value)?;
+    Ok(Some(value.to_string()))
+}
+
+/// The task as launched: the preset's env and prompt prefix fill in what the
+/// task leaves unset.
+fn task_with_launch_preset(task: &CronTask, preset: &LaunchPreset) -> CronTask {
+    let mut launched = task.clone();
+    if launched.env_name.is_none() {
+        launched.env_name = preset.env_name.clone();
+    }
+    launched.prompt = preset
+        .compose_prompt(Some(&task.prompt))
+        .unwrap_or_default();
+    launched
+}
+
 fn resolve_task_tool_policy(task: &CronTask) -> ResolvedToolPolicy {
     let preset = resolve_execution_profile(&task.execution_profile);
+    // A launch preset's permission mode replaces the execution profile's.
+    let launch_perm_mode = task
+        .launch_preset
+        .as_deref()
+        .and_then(|name| resolve_cron_launch_preset(name).ok())
+        .and_then(|launch| launch.launch_perm_mode().map(str::to_string));
     ResolvedToolPolicy {
-        permission_mode: preset.permission_mode.to_string(),
+        permission_mode: launch_perm_mode.unwrap_or_else(|| preset.permission_mode.to_string()),
         max_budget_usd: task.max_budget_usd.unwrap_or(preset.max_budget_usd),
         allowed_tools: if task.allowed_tools.is_empty() {
             preset.allowed_tools
@@ -999,8 +1046,16 @@ fn execute_task(
     let _ = append_run(&task.id, run.clone());
     let _ = app.emit("cron-task-started", &run);
 
-    let env_vars = build_env_vars(&task.env_name);
-    let tool_policy = resolve_task_tool_policy(&task);
+    let launch = match task.launch_preset.as_deref() {
+        Some(name) => resolve_cron_launch_preset(name).map(Some),
+        None => Ok(None),
+    };
+    let launch_task = match &launch {
+        Ok(Some(preset)) => task_with_launch_preset(&task, preset),
+        _ => task.clone(),
+    };
+    let env_vars = build_env_vars(&launch_task.env_name);
+    let tool_policy = resolve_task_tool_policy(&launch_task);
     let start = std::time::Instant::now();
 
     // Expand PATH to include common Node.js/nvm/fnm/volta install locations
@@ -1011,12 +1066,21 @@ fn execute_task(
     let _ = update_run(&task.id, &run_id, |r| {
         r.working_dir = Some(working_dir.clone());
     });
-    register_cron_launch_provenance(&task, &run_id, &working_dir, started_at_instant);
+    register_cron_launch_provenance(&launch_task, &run_id, &working_dir, started_at_instant);
 
-    let mut cmd =
-        build_cron_claude_command(&task, &working_dir, &env_vars, &expanded_path, &tool_policy);
+    let spawned = launch.and_then(|_| {
+        build_cron_claude_command(
+            &launch_task,
+            &working_dir,
+            &env_vars,
+            &expanded_path,
+            &tool_policy,
+        )
+        .spawn()
+        .map_err(|error| format!("Failed to spawn claude: {}", error))
+    });
 
-    let (status_str, exit_code, stdout, stderr) = match cmd.spawn() {
+    let (status_str, exit_code, stdout, stderr) = match spawned {
         Ok(mut child) => {
             let stdout_reader = spawn_output_collector(child.stdout.take());
             let stderr_reader = spawn_output_collector(child.stderr.take());
@@ -1073,12 +1137,7 @@ fn execute_task(
                 }
             }
         }
-        Err(error) => (
-            "failed".to_string(),
-            None,
-            String::new(),
-            format!("Failed to spawn claude: {}", error),
-        ),
+        Err(message) => ("failed".to_string(), None, String::new(), message),
     };
 
     let duration_ms = start.elapsed().as_millis() as u64;
@@ -1315,9 +1374,14 @@ pub fn add_cron_task(
     timeout_secs: Option<u64>,
     template_id: Option<String>,
     wecom_notification: Option<CronWecomNotification>,
+    launch_preset: Option<String>,
 ) -> Result<CronTask, String> {
     // Validate cron expression (5 fields + per-field token/range checks)
     validate_cron_expression(&cron_expression)?;
+    let launch_preset = match launch_preset {
+        Some(name) => normalize_task_launch_preset(&name)?,
+        None => None,
+    };
 
     let now = chrono::Utc::now().to_rfc3339();
     let task = CronTask {
@@ -1340,6 +1404,7 @@ pub fn add_cron_task(
         wecom_notification,
         trigger_type: "schedule".to_string(),
         parent_task_id: None,
+        launch_preset,
         created_at: now.clone(),
         updated_at: now,
     };
@@ -1365,6 +1430,7 @@ pub fn update_cron_task(
     disallowed_tools: Option<Vec<String>>,
     timeout_secs: Option<u64>,
     wecom_notification: Option<CronWecomNotification>,
+    launch_preset: Option<String>,
 ) -> Result<CronTask, String> {
     let mut tasks = read_tasks()?;
     let task = tasks
@@ -1397,6 +1463,10 @@ pub fn update_cron_task(
         task.timeout_secs = v;
     }
     task.wecom_notification = wecom_notification;
+    // launch_preset: omitted keeps the current preset, "" clears it
+    if let Some(v) = launch_preset {
+        task.launch_preset = normalize_task_launch_preset(&v)?;
+    }
     task.updated_at = chrono::Utc::now().to_rfc3339();
 
     let updated = task.clone();
@@ -1584,9 +1654,11 @@ mod tests {
         cron_matches, enrich_run_with_provenance, expand_cron_working_dir, next_runs,
         normalize_execution_profile, normalize_optional_session_id, normalize_wecom_peer_id,
         parse_cron_field, resolve_cron_env_name, resolve_cron_wecom_notification_target,
-        resolve_execution_profile, resolve_task_tool_policy, validate_cron_expression, CronTask,
-        CronTaskRun, CronWecomNotification, ResolvedToolPolicy,
+        resolve_execution_profile, resolve_task_tool_policy, task_with_launch_preset,
+        validate_cron_expression, CronTask, CronTaskRun, CronWecomNotification, ResolvedToolPolicy,
     };
+    use crate::launch_presets::LaunchPreset;
+    use crate::native_runtime::NativeProvider;
     use crate::wecom::WecomTaskBindingTargetType;
     use std::collections::HashMap;
     use std::ffi::OsStr;
@@ -1786,6 +1858,7 @@ mod tests {
             wecom_notification: None,
             trigger_type: "schedule".to_string(),
             parent_task_id: None,
+            launch_preset: None,
             created_at: "2026-03-08T00:00:00Z".to_string(),
             updated_at: "2026-03-08T00:00:00Z".to_string(),
         };
@@ -1890,6 +1963,7 @@ mod tests {
             wecom_notification: None,
             trigger_type: "schedule".to_string(),
             parent_task_id: None,
+            launch_preset: None,
             created_at: "2026-03-08T00:00:00Z".to_string(),
             updated_at: "2026-03-08T00:00:00Z".to_string(),
         };
@@ -1904,6 +1978,57 @@ mod tests {
         assert_eq!(resolved.disallowed_tools, vec!["WebSearch".to_string()]);
     }
 
+    #[test]
+    fn task_with_launch_preset_fills_env_and_prefixes_prompt() {
+        let task = CronTask {
+            id: "cron-1".to_string(),
+            name: "Nightly review".to_string(),
+            cron_expression: "0 2 * * *".to_string(),
+            prompt: "Review yesterday's commits".to_string(),
+            working_dir: "/tmp/project".to_string(),
+            env_name: None,
+            execution_profile: "conservative".to_string(),
+            max_budget_usd: None,
+            allowed_tools: Vec::new(),
+            disallowed_tools: Vec::new(),
+            enabled: true,
+            timeout_secs: 300,
+            template_id: None,
+            wecom_notification: None,
+            trigger_type: "schedule".to_string(),
+            parent_task_id: None,
+            launch_preset: Some("review-opus-readonly".to_string()),
+            created_at: "2026-03-08T00:00:00Z".to_string(),
+            updated_at: "2026-03-08T00:00:00Z".to_string(),
+        };
+        let preset = LaunchPreset {
+            name: "review-opus-readonly".to_string(),
+            provider: NativeProvider::Claude,
+            env_name: Some("opus".to_string()),
+            perm_mode: Some("readonly".to_string()),
+            runtime_perm_mode: None,
+            effort: None,
+            working_dir: None,
+            prompt_prefix: Some("Do not edit files.".to_string()),
+            router_launch_draft: None,
+            updated_at: 0,
+        };
+
+        let launched = task_with_launch_preset(&task, &preset);
+        assert_eq!(launched.env_name.as_deref(), Some("opus"));
+        assert_eq!(
+            launched.prompt,
+            "Do not edit files.\n\nReview yesterday's commits"
+        );
+
+        let pinned = CronTask {
+            env_name: Some("glm".to_string()),
+            ..task
+        };
+        let launched = task_with_launch_preset(&pinned, &preset);
+        assert_eq!(launched.env_name.as_deref(), Some("glm"));
+    }
+
     #[test]
     fn build_cron_claude_command_uses_one_shot_cli_flags() {
         let task = CronTask {
@@ -1923,6 +2048,7 @@ mod tests {
             wecom_notification: None,
             trigger_type: "schedule".to_string(),
             parent_task_id: None,
+            launch_preset: None,
             created_at: "2026-03-08T00:00:00Z".to_string(),
             updated_at: "2026-03-08T00:00:00Z".to_string(),
         };
@@ -2073,6 +2199,7 @@ mod tests {
             }),
             trigger_type: "schedule".to_string(),
             parent_task_id: None,
+            launch_preset: None,
             created_at: "2026-03-08T00:00:00Z".to_string(),
             updated_at: "2026-03-08T00:00:00Z".to_string(),
         };
@@ -6,6 +6,8 @@ use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
+use crate::native_event_log::{NativeEventLog, NativeEventLogStats};
+use crate::session_provenance::state_db_path;
 use crate::terminal;
 
 const VERSION_OUTPUT_LIMIT: usize = 2000;
@@ -21,6 +23,7 @@ pub struct DoctorReport {
     environment: DoctorEnvironment,
     commands: Vec<DoctorCommandStatus>,
     paths: Vec<DoctorPathStatus>,
+    native_event_log: Option<DoctorNativeEventLog>,
 }
 
 #[derive(Debug, Serialize)]
@@ -79,6 +82,13 @@ struct DoctorPathStatus {
     error: Option<String>,
 }
 
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DoctorNativeEventLog {
+    stats: Option<NativeEventLogStats>,
+    error: Option<String>,
+}
+
 #[tauri::command]
 pub fn collect_doctor_report() -> DoctorReport {
     build_doctor_report()
@@ -114,9 +124,29 @@ fn build_doctor_report() -> DoctorReport {
         },
         commands: collect_command_statuses(&user_path),
         paths: collect_path_statuses(),
+        native_event_log: collect_native_event_log_stats(),
     }
 }
 
+/// `None` until the state database exists; reading the stats must not
+/// create it.
+fn collect_native_event_log_stats() -> Option<DoctorNativeEventLog> {
+    let db_path = state_db_path();
+    if !db_path.is_file() {
+        return None;
+    }
+    Some(match NativeEventLog::new(db_path).storage_stats() {
+        Ok(stats) => DoctorNativeEventLog {
+            stats: Some(stats),
+            error: None,
+        },
+        Err(error) => DoctorNativeEventLog {
+            stats: None,
+            error: Some(error),
+        },
+    })
+}
+
 fn collect_command_statuses(user_path: &str) -> Vec<DoctorCommandStatus> {
     let mut statuses = vec![
         doctor_command(
@@ -332,7 +362,7 @@ const SAFE_ENV_KEYS: &[&str] = &[
     "USERPROFILE",
 ];
 
-fn is_secret_env_key(key: &str) -> bool {
+pub(crate) fn is_secret_env_key(key: &str) -> bool {
     let upper = key.to_ascii_uppercase();
     [
         "API_KEY",
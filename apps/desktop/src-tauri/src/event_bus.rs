@@ -4,6 +4,10 @@ use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 
 pub const DEFAULT_SESSION_EVENT_CAPACITY: usize = 500;
+/// Longest string a `ToolUseStarted` input keeps per field. Digests only need
+/// enough of an edit to show it; whole-file `Write` contents would otherwise
+/// sit in every ring buffer and event log row.
+pub const TOOL_INPUT_MAX_STRING_CHARS: usize = 8 * 1024;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
@@ -226,6 +230,12 @@ pub enum SessionEventPayload {
         annotations: Option<Vec<SessionPromptAnnotation>>,
         #[serde(default, skip_serializing_if = "Option::is_none")]
         canonical_hash: Option<String>,
+        /// Held in the session's prompt queue; not yet sent to the agent.
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        queued: bool,
+        /// Links a queued prompt to the event emitted when it is dispatched.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        queue_item_id: Option<String>,
     },
     SystemMessage {
         message: String,
@@ -253,6 +263,11 @@ pub enum SessionEventPayload {
         raw_name: String,
         input_summary: String,
         needs_response: bool,
+        /// Full tool input, carried only for file-writing tools (`Edit`,
+        /// `MultiEdit`, `Write`, `NotebookEdit`) so their changes can be
+        /// diffed later.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        input: Option<serde_json::Value>,
         #[serde(default, skip_serializing_if = "Option::is_none")]
         prompt: Option<InteractiveToolPrompt>,
         #[serde(default, skip_serializing_if = "Option::is_none")]
@@ -334,7 +349,9 @@ pub enum SessionEventPayload {
     /// per completed forwarded /v1/messages request. This is an INDEPENDENT
     /// sub-route observation channel: it never feeds, corrects, or replaces
     /// the product's primary session total (the SDK /usage snapshot), and the
-    /// two are never summed or reconciled in the UI.
+    /// two are never summed or reconciled in the UI. Persisted entries are the
+    /// env-attribution truth for usage analytics, which moves transcript
+    /// usage to the env that served it but never adds ledger usage on top.
     ///
     /// `sub_route` classifies request IDENTITY (background alias,
     /// subagent:<type> marker, explicit authenticated route override) — not
@@ -460,6 +477,46 @@ pub fn replay_records(records: &[SessionEventRecord], last_seen_seq: Option<u64>
     }
 }
 
+const CAPPED_STRING_MARKER_PREFIX: &str = "\n… [";
+const CAPPED_STRING_MARKER_SUFFIX: &str = " more characters]";
+
+/// Cut every string in a tool input down to `max_chars`, noting how much
+/// was dropped.
+pub fn cap_tool_input_strings(input: &mut serde_json::Value, max_chars: usize) {
+    match input {
+        serde_json::Value::String(text) => {
+            if let Some((cut, _)) = text.char_indices().nth(max_chars) {
+                let omitted = text[cut..].chars().count();
+                text.truncate(cut);
+                text.push_str(&format!(
+                    "{CAPPED_STRING_MARKER_PREFIX}{omitted}{CAPPED_STRING_MARKER_SUFFIX}"
+                ));
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for item in items {
+                cap_tool_input_strings(item, max_chars);
+            }
+        }
+        serde_json::Value::Object(fields) => {
+            for value in fields.values_mut() {
+                cap_tool_input_strings(value, max_chars);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Whether `text` ends with the marker `cap_tool_input_strings` leaves
+/// behind, i.e. the original string was longer.
+pub fn is_capped_tool_input_string(text: &str) -> bool {
+    text.strip_suffix(CAPPED_STRING_MARKER_SUFFIX)
+        .and_then(|rest| rest.rsplit_once(CAPPED_STRING_MARKER_PREFIX))
+        .is_some_and(|(_, omitted)| {
+            !omitted.is_empty() && omitted.bytes().all(|byte| byte.is_ascii_digit())
+        })
+}
+
 pub struct SessionStore {
     runtime_id: String,
     capacity: usize,
@@ -498,7 +555,31 @@ impl SessionStore {
         }
     }
 
-    pub fn append(&mut self, payload: SessionEventPayload) -> SessionEventRecord {
+    /// Start from events persisted by an earlier runtime generation, so a
+    /// resumed runtime keeps the timeline it had before the app restarted.
+    /// New events continue at `start_seq` or after the newest restored one.
+    pub fn with_history(
+        runtime_id: impl Into<String>,
+        history: Vec<SessionEventRecord>,
+        start_seq: u64,
+    ) -> Self {
+        let mut store = Self::with_start_seq(runtime_id, start_seq);
+        let skip = history.len().saturating_sub(store.capacity);
+        for record in history.into_iter().skip(skip) {
+            store.next_seq = store.next_seq.max(record.seq + 1);
+            store.attention.apply(&record);
+            store.events.push_back(record);
+        }
+        store
+    }
+
+    pub fn append(&mut self, mut payload: SessionEventPayload) -> SessionEventRecord {
+        if let SessionEventPayload::ToolUseStarted {
+            input: Some(input), ..
+        } = &mut payload
+        {
+            cap_tool_input_strings(input, TOOL_INPUT_MAX_STRING_CHARS);
+        }
         let record = SessionEventRecord {
             runtime_id: self.runtime_id.clone(),
             seq: self.next_seq,
@@ -548,12 +629,63 @@ impl SessionStore {
 #[cfg(test)]
 mod tests {
     use super::{
-        ContextUsageCategory, InteractiveToolPrompt, NativeBackgroundTask,
-        NativeBackgroundTaskStatus, NativeBackgroundTaskUsage, SessionEventPayload, SessionStore,
-        TodoSnapshotItemV1, TodoSnapshotV1, ToolCategory, ToolQuestionOption, ToolQuestionPrompt,
-        UserInputKind,
+        cap_tool_input_strings, is_capped_tool_input_string, ContextUsageCategory,
+        InteractiveToolPrompt, NativeBackgroundTask, NativeBackgroundTaskStatus,
+        NativeBackgroundTaskUsage, SessionEventPayload, SessionStore, TodoSnapshotItemV1,
+        TodoSnapshotV1, ToolCategory, ToolQuestionOption, ToolQuestionPrompt, UserInputKind,
     };
 
+    #[test]
+    fn session_store_caps_long_tool_input_strings() {
+        let mut store = SessionStore::new("runtime-1");
+        let content = "x".repeat(super::TOOL_INPUT_MAX_STRING_CHARS + 10);
+        let record = store.append(SessionEventPayload::ToolUseStarted {
+            tool_use_id: "toolu-write".to_string(),
+            category: ToolCategory::FileOp {
+                raw_name: "Write".to_string(),
+            },
+            raw_name: "Write".to_string(),
+            input_summary: "notes.md".to_string(),
+            needs_response: false,
+            input: Some(serde_json::json!({
+                "file_path": "/work/notes.md",
+                "content": content,
+            })),
+            prompt: None,
+            todo_snapshot: None,
+        });
+
+        let SessionEventPayload::ToolUseStarted {
+            input: Some(input), ..
+        } = record.payload
+        else {
+            panic!("expected tool use with input");
+        };
+        assert_eq!(input["file_path"], "/work/notes.md");
+        assert_eq!(
+            input["content"].as_str(),
+            Some(
+                format!(
+                    "{}\n… [10 more characters]",
+                    "x".repeat(super::TOOL_INPUT_MAX_STRING_CHARS)
+                )
+                .as_str()
+            )
+        );
+
+        let mut edits = serde_json::json!({"edits": [{"old_string": "héllo", "new_string": "hi"}]});
+        cap_tool_input_strings(&mut edits, 2);
+        assert_eq!(edits["edits"][0]["old_string"], "hé\n… [3 more characters]");
+        assert_eq!(edits["edits"][0]["new_string"], "hi");
+        assert!(is_capped_tool_input_string(
+            edits["edits"][0]["old_string"].as_str().unwrap_or_default()
+        ));
+        assert!(!is_capped_tool_input_string("hi"));
+        assert!(!is_capped_tool_input_string(
+            "see\n… [some more characters]"
+        ));
+    }
+
     #[test]
     fn session_store_emits_monotonic_sequence_numbers() {
         let mut store = SessionStore::new("runtime-1");
@@ -578,6 +710,27 @@ mod tests {
         assert_eq!(store.newest_seq(), Some(3));
     }
 
+    #[test]
+    fn session_store_restored_from_history_continues_the_sequence() {
+        let mut previous = SessionStore::new("runtime-1");
+        let history = (0..3)
+            .map(|index| {
+                previous.append(SessionEventPayload::AssistantChunk {
+                    text: format!("chunk {index}"),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut store = SessionStore::with_history("runtime-1", history, 2);
+        let resumed = store.append(SessionEventPayload::SystemMessage {
+            message: "resumed".to_string(),
+        });
+
+        assert_eq!(resumed.seq, 4);
+        assert_eq!(store.oldest_seq(), Some(1));
+        assert_eq!(store.events_since(Some(2)).events.len(), 2);
+    }
+
     #[test]
     fn session_store_replays_only_unseen_events() {
         let mut store = SessionStore::with_capacity("runtime-1", 5);
@@ -610,6 +763,7 @@ mod tests {
             raw_name: "AskUserQuestion".to_string(),
             input_summary: "需要用户回答 1 个问题".to_string(),
             needs_response: true,
+            input: None,
             prompt: Some(InteractiveToolPrompt::AskUserQuestion {
                 questions: vec![ToolQuestionPrompt {
                     question: "Which one?".to_string(),
@@ -688,6 +842,7 @@ mod tests {
                 raw_name: "TodoWrite".to_string(),
                 input_summary: "1 todo".to_string(),
                 needs_response: false,
+                input: None,
                 prompt: None,
                 todo_snapshot: Some(snapshot.clone()),
             },
@@ -851,6 +1006,7 @@ mod tests {
             raw_name: "ExitPlanMode".to_string(),
             input_summary: String::new(),
             needs_response: true,
+            input: None,
             prompt: Some(InteractiveToolPrompt::PlanExit {
                 allowed_prompts: vec![],
                 plan_summary: None,
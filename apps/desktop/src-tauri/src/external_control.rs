@@ -1,16 +1,25 @@
+use crate::analytics::{self, UsageMetricSample};
 use crate::browser::{BrowserBounds, BrowserManager, BrowserToolRequest};
 use crate::config::{self, resolve_claude_env, resolve_codex_runtime};
-use crate::event_bus::ReplayBatch;
+use crate::control_tokens::{ControlCapability, ControlTokenGrant, ControlTokenStore};
+use crate::cron::{self, CronWecomNotification};
+use crate::event_bus::{
+    ReplayBatch, SessionEventPayload, SessionEventRecord, SessionPromptAnnotation,
+};
+use crate::history::search_conversation_history;
+use crate::history_index::{search_history_fulltext, HistoryFullTextFilters};
+use crate::launch_presets;
 use crate::native_runtime::{
-    NativeProvider, NativeRuntimeManager, NativeSessionOptions, NativeSessionSummary,
-    RouterLaunchDraft,
+    InteractivePromptAnnotation, NativeEventSubscription, NativeProvider, NativeRuntimeManager,
+    NativeSessionOptions, NativeSessionSummary, RouterLaunchDraft,
 };
-use crate::proxy_debug::ProxyDebugManager;
+use crate::proxy_debug::{ProxyDebugManager, ProxyMetrics, RoutedRequestCount};
 use crate::router::{
     rename_router_config_environment, router_config_environment_references, validate_router_config,
     RouterConfig, RouterManager, SessionRouterState, UpdateSessionRouterRequest,
 };
 use crate::session_provenance::{register_launch, SessionProvenanceUpsert, DEFAULT_CONFIG_SOURCE};
+use crate::sessions_mcp;
 use crate::system_proxy;
 use crate::terminal;
 use rand::{distributions::Alphanumeric, Rng};
@@ -25,7 +34,7 @@ use std::io::{ErrorKind, Read, Write};
 use std::net::{TcpListener, TcpStream};
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Arc, Mutex};
+use std::sync::{mpsc, Arc, Mutex};
 use std::thread;
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tauri::{AppHandle, Emitter, Manager};
@@ -36,6 +45,10 @@ const SOCKET_IO_TIMEOUT: Duration = Duration::from_secs(10);
 const SOCKET_RETRY_SLEEP: Duration = Duration::from_millis(10);
 const MIN_SOCKET_READ_TIMEOUT: Duration = Duration::from_millis(1);
 const BROWSER_SMOKE_SESSION_ID: &str = "external-control-smoke";
+const METRICS_PATH: &str = "/metrics";
+const SUBSCRIBE_METHOD: &str = "ccem.workspace.subscribe";
+const SUBSCRIBE_POLL_INTERVAL: Duration = Duration::from_secs(1);
+const SUBSCRIBE_KEEPALIVE_INTERVAL: Duration = Duration::from_secs(15);
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -65,6 +78,19 @@ pub struct ExternalControlManager {
     environment_mutations: Arc<config::EnvironmentMutationCoordinator>,
     native_runtime: Arc<NativeRuntimeManager>,
     token: String,
+    tokens: ControlTokenStore,
+    /// Sessions spawned through the `ccem-sessions` MCP server, keyed by
+    /// child runtime id with the spawning runtime as value.
+    delegated_sessions: Mutex<HashMap<String, String>>,
+}
+
+/// Who is calling: the holder of the published descriptor token, a scoped
+/// API token limited to its capabilities, or an agent inside a native session
+/// using its `ccem-sessions` MCP token.
+enum ControlCaller {
+    Owner,
+    Scoped(ControlTokenGrant),
+    Session(String),
 }
 
 #[derive(Debug, Deserialize)]
@@ -263,16 +289,20 @@ impl From<&str> for ControlRpcError {
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct CreateSessionParams {
-    provider: String,
+    /// Optional when a launch preset supplies it.
+    provider: Option<String>,
     #[serde(alias = "workingDir")]
     cwd: Option<String>,
-    prompt: String,
+    /// Omitted prompts start the session idle, waiting for `sendInput`.
+    prompt: Option<String>,
     env_name: Option<String>,
     permission_mode: Option<String>,
     runtime_permission_mode: Option<String>,
     provider_session_id: Option<String>,
     effort: Option<String>,
     open: Option<bool>,
+    /// Run in a fresh git worktree on branch `ccem/<runtimeId>`.
+    worktree: Option<bool>,
     router_launch_draft: Option<RouterLaunchDraft>,
     routes: Option<HashMap<String, String>>,
     allowed_envs: Option<Vec<String>>,
@@ -340,6 +370,16 @@ struct EventsParams {
     limit: Option<u64>,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SubscribeParams {
+    runtime_ids: Vec<String>,
+    /// Last seq the caller already holds per runtime. Runtimes without an
+    /// entry only receive events appended after the subscription opens.
+    #[serde(default)]
+    since_seqs: HashMap<String, u64>,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct SendInputParams {
@@ -348,6 +388,113 @@ struct SendInputParams {
     display_text: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PermissionResponseParams {
+    runtime_id: String,
+    request_id: String,
+    approved: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PromptResponseParams {
+    runtime_id: String,
+    tool_use_id: String,
+    prompt_type: String,
+    display_text: Option<String>,
+    #[serde(default)]
+    answers: HashMap<String, String>,
+    annotations: Option<HashMap<String, InteractivePromptAnnotation>>,
+    prompt_annotations: Option<Vec<SessionPromptAnnotation>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RewindFilesParams {
+    runtime_id: String,
+    checkpoint_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UpdateSettingsParams {
+    runtime_id: String,
+    env_name: Option<String>,
+    perm_mode: Option<String>,
+    effort: Option<String>,
+    force_restart: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BackgroundTaskParams {
+    runtime_id: String,
+    task_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CronTaskIdParams {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AddCronTaskParams {
+    name: String,
+    cron_expression: String,
+    prompt: String,
+    working_dir: String,
+    env_name: Option<String>,
+    execution_profile: Option<String>,
+    max_budget_usd: Option<f64>,
+    allowed_tools: Option<Vec<String>>,
+    disallowed_tools: Option<Vec<String>>,
+    timeout_secs: Option<u64>,
+    template_id: Option<String>,
+    wecom_notification: Option<CronWecomNotification>,
+    launch_preset: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UpdateCronTaskParams {
+    id: String,
+    name: Option<String>,
+    cron_expression: Option<String>,
+    prompt: Option<String>,
+    working_dir: Option<String>,
+    env_name: Option<String>,
+    execution_profile: Option<String>,
+    max_budget_usd: Option<f64>,
+    allowed_tools: Option<Vec<String>>,
+    disallowed_tools: Option<Vec<String>>,
+    timeout_secs: Option<u64>,
+    wecom_notification: Option<CronWecomNotification>,
+    launch_preset: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HistorySearchParams {
+    query: String,
+    source: Option<String>,
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HistoryFullTextParams {
+    query: String,
+    filters: Option<HistoryFullTextFilters>,
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct UsageStatsParams {
+    source: Option<String>,
+    force: Option<bool>,
+}
+
 #[derive(Debug, Deserialize)]
 struct OpenSessionParams {
     link: String,
@@ -383,6 +530,8 @@ impl ExternalControlManager {
             environment_mutations,
             native_runtime,
             token: generate_token(),
+            tokens: ControlTokenStore::default(),
+            delegated_sessions: Mutex::new(HashMap::new()),
         }
     }
 
@@ -406,7 +555,7 @@ impl ExternalControlManager {
             })?
             .port();
 
-        let endpoint = format!("http://127.0.0.1:{}/rpc", port);
+        let endpoint = control_endpoint(port);
         let descriptor = ExternalControlDescriptor {
             endpoint,
             token: self.token.clone(),
@@ -481,37 +630,65 @@ impl ExternalControlManager {
             .map(|runtime| runtime.port)
     }
 
+    pub fn current_endpoint(&self) -> Option<String> {
+        self.current_port().map(control_endpoint)
+    }
+
     fn handle_connection(self: &Arc<Self>, app: &AppHandle, mut stream: TcpStream) {
         let _ = stream.set_read_timeout(Some(SOCKET_IO_TIMEOUT));
         let _ = stream.set_write_timeout(Some(SOCKET_IO_TIMEOUT));
         let response = match read_http_request(&mut stream) {
-            Ok(request) => self.handle_http_request(app, request),
+            Ok(request) => match self.handle_http_request(app, request) {
+                ControlReply::Response(response) => response,
+                ControlReply::Subscribe(id, params) => {
+                    self.stream_events(&mut stream, id, params);
+                    return;
+                }
+            },
             Err(error) => HttpResponse::json_error(400, None, -32700, &error),
         };
         let _ = stream.write_all(&response.to_bytes());
     }
 
-    fn handle_http_request(&self, app: &AppHandle, request: HttpRequest) -> HttpResponse {
-        if request.method != "POST" || request.path != "/rpc" {
-            return HttpResponse::plain(404, "Not found");
+    fn handle_http_request(&self, app: &AppHandle, request: HttpRequest) -> ControlReply {
+        let is_rpc = request.method == "POST" && request.path == "/rpc";
+        let is_metrics = request.method == "GET" && request.path == METRICS_PATH;
+        if !is_rpc && !is_metrics {
+            return HttpResponse::plain(404, "Not found").into();
         }
 
         // Host header must be loopback to prevent DNS rebinding attacks.
         if let Some(host_header) = request.headers.get("host") {
             if !is_loopback_host_header(host_header) {
-                return HttpResponse::plain(403, "Forbidden: non-loopback Host header");
+                return HttpResponse::plain(403, "Forbidden: non-loopback Host header").into();
             }
         } else {
             // HTTP/1.1 requires a Host header; reject if missing.
-            return HttpResponse::plain(400, "Bad Request: missing Host header");
+            return HttpResponse::plain(400, "Bad Request: missing Host header").into();
         }
 
         // Origin (if present) must be a loopback origin. Non-browser clients
         // (like the ccem CLI) typically omit Origin, so only enforce when set.
         if let Some(origin) = request.headers.get("origin") {
             if !is_loopback_origin(origin) {
-                return HttpResponse::plain(403, "Forbidden: non-loopback Origin header");
+                return HttpResponse::plain(403, "Forbidden: non-loopback Origin header").into();
+            }
+        }
+
+        // Prometheus scrapes carry no body; they share the bearer token with
+        // JSON-RPC callers (`authorization.credentials` in scrape config).
+        if is_metrics {
+            let can_read = match self.authenticate(&request) {
+                Some(ControlCaller::Owner) => true,
+                Some(ControlCaller::Scoped(grant)) => grant
+                    .capabilities
+                    .contains(&ControlCapability::ReadSessions),
+                Some(ControlCaller::Session(_)) | None => false,
+            };
+            if !can_read {
+                return HttpResponse::plain(401, "Unauthorized").into();
             }
+            return HttpResponse::prometheus(self.render_metrics(app)).into();
         }
 
         // Content-Type must be application/json for POST /rpc.
@@ -519,16 +696,15 @@ impl ExternalControlManager {
             return HttpResponse::plain(
                 415,
                 "Unsupported Media Type: Content-Type must be application/json",
-            );
+            )
+            .into();
         }
 
-        let expected_auth = format!("Bearer {}", self.token);
-        if request.headers.get("authorization").map(String::as_str) != Some(expected_auth.as_str())
-        {
-            return HttpResponse::json_error(401, None, -32001, "Unauthorized");
-        }
+        let Some(caller) = self.authenticate(&request) else {
+            return HttpResponse::json_error(401, None, -32001, "Unauthorized").into();
+        };
 
-        let rpc = match serde_json::from_slice::<JsonRpcRequest>(&request.body) {
+        let mut rpc = match serde_json::from_slice::<JsonRpcRequest>(&request.body) {
             Ok(rpc) => rpc,
             Err(error) => {
                 return HttpResponse::json_error(
@@ -536,7 +712,8 @@ impl ExternalControlManager {
                     None,
                     -32700,
                     &format!("Invalid JSON: {}", error),
-                );
+                )
+                .into();
             }
         };
         let id = rpc.id.clone();
@@ -548,15 +725,296 @@ impl ExternalControlManager {
                 id,
                 -32601,
                 &format!("Method not found: {}", rpc.method),
-            );
+            )
+            .into();
+        }
+
+        // Presets expand into plain params first, so the token and session
+        // checks below see what will actually be launched.
+        if rpc.method == "ccem.workspace.createSession" {
+            if let Err(error) = launch_presets::expand_create_session_params(&mut rpc.params) {
+                return HttpResponse::json_error(200, id, -32602, &error).into();
+            }
+        }
+
+        if let ControlCaller::Scoped(grant) = &caller {
+            if let Err(reason) = authorize_scoped_call(grant, &rpc) {
+                self.tokens
+                    .record_call(grant, &rpc.method, "denied", Some(reason.clone()));
+                return HttpResponse::json_error(403, id, -32003, &reason).into();
+            }
+        }
+        if let ControlCaller::Session(caller_id) = &caller {
+            if let Err(reason) = self.authorize_session_call(caller_id, &mut rpc) {
+                return HttpResponse::json_error(403, id, -32003, &reason).into();
+            }
         }
 
-        match self.handle_rpc(app, rpc) {
+        // Subscriptions keep the connection open as a server-sent event
+        // stream instead of answering with a single JSON-RPC envelope.
+        if rpc.method == SUBSCRIBE_METHOD {
+            return match deserialize_params::<SubscribeParams>(rpc.params) {
+                Ok(params) if params.runtime_ids.is_empty() => {
+                    HttpResponse::json_error(200, id, -32602, "runtimeIds cannot be empty").into()
+                }
+                Ok(params) => {
+                    if let ControlCaller::Scoped(grant) = &caller {
+                        self.tokens
+                            .record_call(grant, SUBSCRIBE_METHOD, "stream", None);
+                    }
+                    ControlReply::Subscribe(id, params)
+                }
+                Err(error) => HttpResponse::json_error(200, id, -32602, &error).into(),
+            };
+        }
+
+        let method = rpc.method.clone();
+        let mut result = self.handle_rpc(app, rpc);
+        if let (ControlCaller::Session(caller_id), Ok(value)) = (&caller, &mut result) {
+            match method.as_str() {
+                "ccem.workspace.createSession" => self.record_delegated_session(caller_id, value),
+                "ccem.workspace.listSessions" => self.retain_delegated_sessions(caller_id, value),
+                _ => {}
+            }
+        }
+        if let ControlCaller::Scoped(grant) = &caller {
+            match &result {
+                Ok(_) => self.tokens.record_call(grant, &method, "ok", None),
+                Err(error) => {
+                    self.tokens
+                        .record_call(grant, &method, "error", Some(error.message.clone()))
+                }
+            }
+        }
+        match result {
             Ok(result) => HttpResponse::json_result(id, result),
             Err(error) => {
                 HttpResponse::json_error_with_data(200, id, -32000, &error.message, error.data)
             }
         }
+        .into()
+    }
+
+    fn stream_events(&self, stream: &mut TcpStream, id: Option<Value>, params: SubscribeParams) {
+        let subscription = match self.native_runtime.subscribe_events(&params.runtime_ids) {
+            Ok(subscription) => subscription,
+            Err(error) => {
+                let response = HttpResponse::json_error(200, id, -32000, &error);
+                let _ = stream.write_all(&response.to_bytes());
+                return;
+            }
+        };
+        let mut cursor = EventStreamCursor::new(&params.runtime_ids, &params.since_seqs);
+        let ready = json!({
+            "jsonrpc": "2.0",
+            "id": id.unwrap_or(Value::Null),
+            "result": { "runtimeIds": params.runtime_ids },
+        });
+        let opened = stream
+            .write_all(EVENT_STREAM_HEADER.as_bytes())
+            .and_then(|_| stream.write_all(sse_frame("subscribed", None, &ready).as_bytes()));
+        if opened.is_ok() {
+            if let Err(error) = self.pump_event_stream(stream, &subscription, &mut cursor) {
+                if error.kind() != ErrorKind::BrokenPipe {
+                    eprintln!("External control subscription closed: {}", error);
+                }
+            }
+        }
+        self.native_runtime.unsubscribe_events(&subscription);
+    }
+
+    fn pump_event_stream(
+        &self,
+        stream: &mut TcpStream,
+        subscription: &NativeEventSubscription,
+        cursor: &mut EventStreamCursor,
+    ) -> std::io::Result<()> {
+        for runtime_id in cursor.resumable_runtimes() {
+            self.resync_event_stream(stream, cursor, &runtime_id)?;
+        }
+        let mut last_write = Instant::now();
+        while self.current_port().is_some() {
+            if subscription.lagged.swap(false, Ordering::Relaxed) {
+                for runtime_id in cursor.resumable_runtimes() {
+                    self.resync_event_stream(stream, cursor, &runtime_id)?;
+                }
+            }
+            match subscription.receiver.recv_timeout(SUBSCRIBE_POLL_INTERVAL) {
+                Ok(record) => {
+                    if cursor.admit(&record) == CursorAdmission::Resync {
+                        self.resync_event_stream(stream, cursor, &record.runtime_id)?;
+                    }
+                    if cursor.admit(&record) != CursorAdmission::Skip {
+                        write_event_record(stream, cursor, &record)?;
+                    }
+                    last_write = Instant::now();
+                }
+                Err(mpsc::RecvTimeoutError::Timeout) => {
+                    if last_write.elapsed() >= SUBSCRIBE_KEEPALIVE_INTERVAL {
+                        stream.write_all(b": keep-alive\n\n")?;
+                        last_write = Instant::now();
+                    }
+                }
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            }
+        }
+        Ok(())
+    }
+
+    /// Fill the caller in from the event log after missed pushes, emitting a
+    /// `GapNotification` first when the log no longer holds the next seq.
+    fn resync_event_stream(
+        &self,
+        stream: &mut TcpStream,
+        cursor: &mut EventStreamCursor,
+        runtime_id: &str,
+    ) -> std::io::Result<()> {
+        let Some(last_seen_seq) = cursor.last_seen(runtime_id) else {
+            return Ok(());
+        };
+        let batch = self
+            .native_runtime
+            .replay_events_limited(runtime_id, Some(last_seen_seq), None)
+            .map_err(std::io::Error::other)?;
+        if let Some(gap) = gap_notification_record(runtime_id, last_seen_seq, &batch) {
+            let data = serde_json::to_value(&gap).map_err(std::io::Error::other)?;
+            stream.write_all(sse_frame("gap", None, &data).as_bytes())?;
+            cursor.skip_to(runtime_id, gap_resume_seq(&batch));
+        }
+        for record in &batch.events {
+            if cursor.admit(record) == CursorAdmission::Deliver {
+                write_event_record(stream, cursor, record)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn authenticate(&self, request: &HttpRequest) -> Option<ControlCaller> {
+        let bearer = request
+            .headers
+            .get("authorization")?
+            .strip_prefix("Bearer ")?;
+        if bearer == self.token {
+            return Some(ControlCaller::Owner);
+        }
+        if let Some(runtime_id) = self.native_runtime.sessions_mcp_caller(bearer) {
+            return Some(ControlCaller::Session(runtime_id));
+        }
+        self.tokens.authenticate(bearer).map(ControlCaller::Scoped)
+    }
+
+    /// Confines a session caller to the orchestration methods, makes spawned
+    /// sessions inherit its permission mode and project directory, and only
+    /// lets it drive sessions it spawned.
+    fn authorize_session_call(
+        &self,
+        caller_id: &str,
+        rpc: &mut JsonRpcRequest,
+    ) -> Result<(), String> {
+        if !sessions_mcp::SESSION_CALLER_METHODS.contains(&rpc.method.as_str()) {
+            return Err(format!(
+                "Forbidden: sessions cannot call {} through {}",
+                rpc.method,
+                sessions_mcp::SESSIONS_MCP_SERVER_NAME
+            ));
+        }
+        if sessions_mcp::SESSION_CALLER_CHILD_METHODS.contains(&rpc.method.as_str()) {
+            let target = rpc.params.get("runtimeId").and_then(Value::as_str);
+            let spawned_by_caller = target.is_some_and(|target| {
+                self.delegated_sessions
+                    .lock()
+                    .map(|sessions| {
+                        sessions
+                            .get(target)
+                            .is_some_and(|parent| parent == caller_id)
+                    })
+                    .unwrap_or(false)
+            });
+            if !spawned_by_caller {
+                return Err(format!(
+                    "Forbidden: {} can only act on sessions it started",
+                    caller_id
+                ));
+            }
+        }
+        if rpc.method != "ccem.workspace.createSession" {
+            return Ok(());
+        }
+
+        let caller = self
+            .native_runtime
+            .list_sessions()
+            .into_iter()
+            .find(|session| session.runtime_id == caller_id)
+            .ok_or_else(|| format!("Calling session {} no longer exists", caller_id))?;
+        let caller_mode = caller
+            .runtime_perm_mode
+            .as_deref()
+            .unwrap_or(caller.perm_mode.as_str());
+        if !rpc.params.is_object() {
+            rpc.params = json!({});
+        }
+        let params = rpc
+            .params
+            .as_object_mut()
+            .ok_or_else(|| "Invalid params: expected an object".to_string())?;
+        let requested_mode = params.get("permissionMode").and_then(Value::as_str);
+        let perm_mode = sessions_mcp::delegated_permission_mode(caller_mode, requested_mode)?;
+        if let Some(runtime_mode) = params.get("runtimePermissionMode").and_then(Value::as_str) {
+            sessions_mcp::delegated_permission_mode(caller_mode, Some(runtime_mode))?;
+        }
+        let requested_dir = params
+            .get("cwd")
+            .or_else(|| params.get("workingDir"))
+            .and_then(Value::as_str);
+        let cwd = sessions_mcp::delegated_working_dir(&caller.project_dir, requested_dir)?;
+        params.remove("workingDir");
+        params.insert("cwd".to_string(), Value::String(cwd));
+        params.insert("permissionMode".to_string(), Value::String(perm_mode));
+        Ok(())
+    }
+
+    /// Drops every session from a `listSessions` result that `caller_id` did
+    /// not spawn.
+    fn retain_delegated_sessions(&self, caller_id: &str, sessions: &mut Value) {
+        let Some(sessions) = sessions.as_array_mut() else {
+            return;
+        };
+        let Ok(delegated) = self.delegated_sessions.lock() else {
+            sessions.clear();
+            return;
+        };
+        sessions.retain(|session| {
+            session
+                .get("runtimeId")
+                .and_then(Value::as_str)
+                .and_then(|runtime_id| delegated.get(runtime_id))
+                .is_some_and(|parent| parent == caller_id)
+        });
+    }
+
+    fn record_delegated_session(&self, caller_id: &str, created: &Value) {
+        let Some(child_id) = created.get("runtimeId").and_then(Value::as_str) else {
+            return;
+        };
+        if let Ok(mut sessions) = self.delegated_sessions.lock() {
+            sessions.insert(child_id.to_string(), caller_id.to_string());
+        }
+    }
+
+    fn render_metrics(&self, app: &AppHandle) -> String {
+        let usage = analytics::collect_usage_metric_samples();
+        let sessions = self.native_runtime.list_sessions();
+        let proxy = app
+            .try_state::<Arc<ProxyDebugManager>>()
+            .map(|proxy| (proxy.get_state().metrics, proxy.routed_request_counts()));
+        render_prometheus_metrics(
+            &usage,
+            &sessions,
+            proxy
+                .as_ref()
+                .map(|(metrics, routed)| (metrics, routed.as_slice())),
+        )
     }
 
     fn handle_rpc(&self, app: &AppHandle, rpc: JsonRpcRequest) -> Result<Value, ControlRpcError> {
@@ -632,6 +1090,154 @@ impl ExternalControlManager {
                 )?;
                 Ok(json!({ "ok": true }))
             }
+            "ccem.workspace.stopSession" => {
+                let params = deserialize_params::<RuntimeIdParams>(rpc.params)?;
+                self.native_runtime
+                    .stop_session_from(&params.runtime_id, Some("externalControl"))?;
+                Ok(json!({ "ok": true }))
+            }
+            "ccem.workspace.respondToPermission" => {
+                let params = deserialize_params::<PermissionResponseParams>(rpc.params)?;
+                let _mutation_guard = self.environment_mutations.lock()?;
+                self.native_runtime.respond_to_permission(
+                    &params.runtime_id,
+                    &params.request_id,
+                    params.approved,
+                )?;
+                Ok(json!({ "ok": true }))
+            }
+            "ccem.workspace.respondToPrompt" => {
+                let params = deserialize_params::<PromptResponseParams>(rpc.params)?;
+                let _mutation_guard = self.environment_mutations.lock()?;
+                self.native_runtime.respond_to_prompt(
+                    &params.runtime_id,
+                    &params.tool_use_id,
+                    &params.prompt_type,
+                    params.display_text.as_deref(),
+                    &params.answers,
+                    params.annotations.as_ref(),
+                    params.prompt_annotations.as_ref(),
+                )?;
+                Ok(json!({ "ok": true }))
+            }
+            "ccem.workspace.rewindFiles" => {
+                let params = deserialize_params::<RewindFilesParams>(rpc.params)?;
+                let _mutation_guard = self.environment_mutations.lock()?;
+                self.native_runtime
+                    .rewind_files(app, &params.runtime_id, &params.checkpoint_id)?;
+                Ok(json!({ "ok": true }))
+            }
+            "ccem.workspace.updateSettings" => {
+                let params = deserialize_params::<UpdateSettingsParams>(rpc.params)?;
+                let _mutation_guard = self.environment_mutations.lock()?;
+                self.native_runtime.update_session_settings_by_env_name(
+                    app,
+                    &params.runtime_id,
+                    params.env_name.as_deref(),
+                    params.perm_mode.as_deref(),
+                    params.effort.as_deref(),
+                    params.force_restart.unwrap_or(false),
+                )?;
+                Ok(json!({ "ok": true }))
+            }
+            "ccem.workspace.stopBackgroundTask" => {
+                let params = deserialize_params::<BackgroundTaskParams>(rpc.params)?;
+                self.native_runtime
+                    .stop_background_task(&params.runtime_id, &params.task_id)?;
+                Ok(json!({ "ok": true }))
+            }
+            "ccem.workspace.queryUsage" => {
+                let params = deserialize_params::<RuntimeIdParams>(rpc.params)?;
+                self.native_runtime
+                    .query_session_usage(app, &params.runtime_id)?;
+                Ok(json!({ "ok": true }))
+            }
+            "ccem.cron.list" => Ok(serde_json::to_value(cron::list_cron_tasks()?)
+                .map_err(|error| error.to_string())?),
+            "ccem.cron.add" => {
+                let params = deserialize_params::<AddCronTaskParams>(rpc.params)?;
+                let task = cron::add_cron_task(
+                    params.name,
+                    params.cron_expression,
+                    params.prompt,
+                    params.working_dir,
+                    params.env_name,
+                    params.execution_profile,
+                    params.max_budget_usd,
+                    params.allowed_tools,
+                    params.disallowed_tools,
+                    params.timeout_secs,
+                    params.template_id,
+                    params.wecom_notification,
+                    params.launch_preset,
+                )?;
+                Ok(serde_json::to_value(task).map_err(|error| error.to_string())?)
+            }
+            "ccem.cron.update" => {
+                let params = deserialize_params::<UpdateCronTaskParams>(rpc.params)?;
+                let task = cron::update_cron_task(
+                    params.id,
+                    params.name,
+                    params.cron_expression,
+                    params.prompt,
+                    params.working_dir,
+                    params.env_name,
+                    params.execution_profile,
+                    params.max_budget_usd,
+                    params.allowed_tools,
+                    params.disallowed_tools,
+                    params.timeout_secs,
+                    params.wecom_notification,
+                    params.launch_preset,
+                )?;
+                Ok(serde_json::to_value(task).map_err(|error| error.to_string())?)
+            }
+            "ccem.cron.delete" => {
+                let params = deserialize_params::<CronTaskIdParams>(rpc.params)?;
+                cron::delete_cron_task(params.id)?;
+                Ok(json!({ "ok": true }))
+            }
+            "ccem.cron.toggle" => {
+                let params = deserialize_params::<CronTaskIdParams>(rpc.params)?;
+                let task = cron::toggle_cron_task(params.id)?;
+                Ok(serde_json::to_value(task).map_err(|error| error.to_string())?)
+            }
+            "ccem.cron.runs" => {
+                let params = deserialize_params::<CronTaskIdParams>(rpc.params)?;
+                let runs = cron::get_cron_task_runs(params.id)?;
+                Ok(serde_json::to_value(runs).map_err(|error| error.to_string())?)
+            }
+            "ccem.history.search" => {
+                let params = deserialize_params::<HistorySearchParams>(rpc.params)?;
+                let sessions = tauri::async_runtime::block_on(search_conversation_history(
+                    params.query,
+                    params.source,
+                    params.limit,
+                ))?;
+                Ok(serde_json::to_value(sessions).map_err(|error| error.to_string())?)
+            }
+            "ccem.history.searchFullText" => {
+                let params = deserialize_params::<HistoryFullTextParams>(rpc.params)?;
+                let hits = tauri::async_runtime::block_on(search_history_fulltext(
+                    params.query,
+                    params.filters,
+                    params.limit,
+                ))?;
+                Ok(serde_json::to_value(hits).map_err(|error| error.to_string())?)
+            }
+            "ccem.usage.stats" => {
+                let params = if rpc.params.is_null() {
+                    UsageStatsParams::default()
+                } else {
+                    deserialize_params::<UsageStatsParams>(rpc.params)?
+                };
+                let stats = tauri::async_runtime::block_on(analytics::get_usage_stats(
+                    params.source,
+                    params.force,
+                ))?;
+                Ok(serde_json::to_value(stats).map_err(|error| error.to_string())?)
+            }
+            "ccem.rpc.discover" => Ok(openrpc_document(self.current_port())),
             "ccem.workspace.openSession" => {
                 let params = deserialize_params::<OpenSessionParams>(rpc.params)?;
                 app.emit(
@@ -855,10 +1461,16 @@ impl ExternalControlManager {
     fn create_session(
         &self,
         app: AppHandle,
-        params: CreateSessionParams,
+        mut params: CreateSessionParams,
     ) -> Result<ControlCreateSessionResult, String> {
         let mutation_guard = self.environment_mutations.lock()?;
-        let provider = parse_native_provider(&params.provider)?;
+        let provider = parse_native_provider(params.provider.as_deref().ok_or_else(|| {
+            "provider is required unless a launch preset supplies it".to_string()
+        })?)?;
+        let initial_prompt = params
+            .prompt
+            .take()
+            .filter(|prompt| !prompt.trim().is_empty());
         let router_launch_draft = resolve_external_router_launch_draft(&params)?;
         if provider != NativeProvider::Claude && router_launch_draft.is_some() {
             return Err(
@@ -873,6 +1485,7 @@ impl ExternalControlManager {
             .or_else(resolve_current_env)
             .unwrap_or_else(|| "official".to_string());
         let working_dir = resolve_working_dir(params.cwd)?;
+        let use_git_worktree = params.worktree.unwrap_or(false);
         let perm_mode = resolve_effective_perm_mode(params.permission_mode)?;
         let runtime_perm_mode = params
             .runtime_permission_mode
@@ -897,7 +1510,9 @@ impl ExternalControlManager {
                     perm_mode,
                     runtime_perm_mode,
                     working_dir,
-                    initial_prompt: Some(params.prompt),
+                    use_git_worktree,
+                    limits: None,
+                    initial_prompt,
                     display_prompt: None,
                     initial_images: None,
                     initial_annotations: None,
@@ -928,7 +1543,9 @@ impl ExternalControlManager {
                     perm_mode,
                     runtime_perm_mode,
                     working_dir,
-                    initial_prompt: Some(params.prompt),
+                    use_git_worktree,
+                    limits: None,
+                    initial_prompt,
                     display_prompt: None,
                     initial_images: None,
                     initial_annotations: None,
@@ -981,6 +1598,15 @@ impl ExternalControlManager {
         Ok(result)
     }
 
+    /// Starts an idle session from a launch preset and opens it in the
+    /// workspace; used by the tray menu.
+    pub fn launch_preset(&self, app: AppHandle, preset: &str) -> Result<(), String> {
+        let mut params = json!({ "preset": preset, "open": true });
+        launch_presets::expand_create_session_params(&mut params)?;
+        let params = deserialize_params::<CreateSessionParams>(params)?;
+        self.create_session(app, params).map(|_| ())
+    }
+
     fn browser_smoke_probe(
         &self,
         app: &AppHandle,
@@ -1627,6 +2253,124 @@ impl From<ReplayBatch> for ControlReplayBatch {
     }
 }
 
+enum ControlReply {
+    Response(HttpResponse),
+    Subscribe(Option<Value>, SubscribeParams),
+}
+
+impl From<HttpResponse> for ControlReply {
+    fn from(response: HttpResponse) -> Self {
+        Self::Response(response)
+    }
+}
+
+const EVENT_STREAM_HEADER: &str = "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: close\r\n\r\n";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CursorAdmission {
+    Deliver,
+    Skip,
+    Resync,
+}
+
+/// Per-runtime delivery position of one subscription. Live pushes and log
+/// replays can overlap, so every record is admitted against the last seq
+/// already written to the stream.
+struct EventStreamCursor {
+    last_seen: HashMap<String, Option<u64>>,
+}
+
+impl EventStreamCursor {
+    fn new(runtime_ids: &[String], since_seqs: &HashMap<String, u64>) -> Self {
+        Self {
+            last_seen: runtime_ids
+                .iter()
+                .map(|runtime_id| (runtime_id.clone(), since_seqs.get(runtime_id).copied()))
+                .collect(),
+        }
+    }
+
+    fn last_seen(&self, runtime_id: &str) -> Option<u64> {
+        self.last_seen.get(runtime_id).copied().flatten()
+    }
+
+    fn resumable_runtimes(&self) -> Vec<String> {
+        let mut runtime_ids = self
+            .last_seen
+            .iter()
+            .filter(|(_, seq)| seq.is_some())
+            .map(|(runtime_id, _)| runtime_id.clone())
+            .collect::<Vec<_>>();
+        runtime_ids.sort();
+        runtime_ids
+    }
+
+    fn admit(&self, record: &SessionEventRecord) -> CursorAdmission {
+        match self.last_seen.get(&record.runtime_id) {
+            None => CursorAdmission::Skip,
+            Some(None) => CursorAdmission::Deliver,
+            Some(Some(last)) if record.seq <= *last => CursorAdmission::Skip,
+            Some(Some(last)) if record.seq > last.saturating_add(1) => CursorAdmission::Resync,
+            Some(Some(_)) => CursorAdmission::Deliver,
+        }
+    }
+
+    fn skip_to(&mut self, runtime_id: &str, seq: u64) {
+        if let Some(last_seen) = self.last_seen.get_mut(runtime_id) {
+            *last_seen = Some(seq);
+        }
+    }
+}
+
+fn write_event_record(
+    stream: &mut impl Write,
+    cursor: &mut EventStreamCursor,
+    record: &SessionEventRecord,
+) -> std::io::Result<()> {
+    let data = serde_json::to_value(record).map_err(std::io::Error::other)?;
+    let frame_id = format!("{}:{}", record.runtime_id, record.seq);
+    stream.write_all(sse_frame("session_event", Some(&frame_id), &data).as_bytes())?;
+    cursor.skip_to(&record.runtime_id, record.seq);
+    Ok(())
+}
+
+fn sse_frame(event: &str, id: Option<&str>, data: &Value) -> String {
+    let mut frame = String::new();
+    if let Some(id) = id {
+        frame.push_str(&format!("id: {}\n", id));
+    }
+    frame.push_str(&format!("event: {}\ndata: {}\n\n", event, data));
+    frame
+}
+
+fn gap_notification_record(
+    runtime_id: &str,
+    last_seen_seq: u64,
+    batch: &ReplayBatch,
+) -> Option<SessionEventRecord> {
+    if !batch.gap_detected {
+        return None;
+    }
+    Some(SessionEventRecord {
+        runtime_id: runtime_id.to_string(),
+        seq: last_seen_seq,
+        occurred_at: chrono::Utc::now(),
+        payload: SessionEventPayload::GapNotification {
+            last_seen_seq,
+            oldest_available_seq: batch.oldest_available_seq.unwrap_or(last_seen_seq),
+        },
+    })
+}
+
+/// Seq the cursor jumps to after a gap so the oldest retained event is the
+/// next one delivered.
+fn gap_resume_seq(batch: &ReplayBatch) -> u64 {
+    batch
+        .oldest_available_seq
+        .map(|oldest| oldest.saturating_sub(1))
+        .unwrap_or_default()
+}
+
 struct HttpRequest {
     method: String,
     path: String,
@@ -1688,6 +2432,14 @@ impl HttpResponse {
         )
     }
 
+    fn prometheus(body: String) -> Self {
+        Self {
+            status: 200,
+            content_type: "text/plain; version=0.0.4; charset=utf-8",
+            body: body.into_bytes(),
+        }
+    }
+
     fn json(status: u16, body: Value) -> Self {
         Self {
             status,
@@ -1701,6 +2453,7 @@ impl HttpResponse {
             200 => "OK",
             400 => "Bad Request",
             401 => "Unauthorized",
+            403 => "Forbidden",
             404 => "Not Found",
             _ => "OK",
         };
@@ -1832,6 +2585,162 @@ fn find_header_end(buffer: &[u8]) -> Option<usize> {
     buffer.windows(4).position(|window| window == b"\r\n\r\n")
 }
 
+fn escape_prometheus_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn push_prometheus_header(output: &mut String, name: &str, kind: &str, help: &str) {
+    output.push_str(&format!("# HELP {name} {help}\n# TYPE {name} {kind}\n"));
+}
+
+/// Render the Prometheus text exposition served at `GET /metrics`: token and
+/// cost totals from the analytics snapshot, active native sessions, and
+/// router request counters when the proxy/router is running. Usage totals
+/// are gauges because they shrink when retention prunes old transcripts.
+fn render_prometheus_metrics(
+    usage: &[UsageMetricSample],
+    sessions: &[NativeSessionSummary],
+    proxy: Option<(&ProxyMetrics, &[RoutedRequestCount])>,
+) -> String {
+    let mut output = String::new();
+
+    push_prometheus_header(
+        &mut output,
+        "ccem_usage_tokens",
+        "gauge",
+        "Tokens recorded in local transcripts.",
+    );
+    for sample in usage {
+        let labels = format!(
+            "source=\"{}\",model=\"{}\",environment=\"{}\"",
+            sample.source,
+            escape_prometheus_label(&sample.model),
+            escape_prometheus_label(&sample.environment),
+        );
+        for (kind, value) in [
+            ("input", sample.usage.input_tokens),
+            ("output", sample.usage.output_tokens),
+            ("cache_read", sample.usage.cache_read_tokens),
+            ("cache_creation", sample.usage.cache_creation_tokens),
+        ] {
+            output.push_str(&format!(
+                "ccem_usage_tokens{{{labels},type=\"{kind}\"}} {value}\n"
+            ));
+        }
+    }
+
+    push_prometheus_header(
+        &mut output,
+        "ccem_usage_cost_usd",
+        "gauge",
+        "Estimated cost in USD recorded in local transcripts.",
+    );
+    for sample in usage {
+        output.push_str(&format!(
+            "ccem_usage_cost_usd{{source=\"{}\",model=\"{}\",environment=\"{}\"}} {}\n",
+            sample.source,
+            escape_prometheus_label(&sample.model),
+            escape_prometheus_label(&sample.environment),
+            sample.usage.cost,
+        ));
+    }
+
+    push_prometheus_header(
+        &mut output,
+        "ccem_active_sessions",
+        "gauge",
+        "Active native sessions by provider.",
+    );
+    for provider in [NativeProvider::Claude, NativeProvider::Codex] {
+        let active = sessions
+            .iter()
+            .filter(|session| session.is_active && session.provider == provider)
+            .count();
+        output.push_str(&format!(
+            "ccem_active_sessions{{provider=\"{}\"}} {}\n",
+            provider.as_str(),
+            active
+        ));
+    }
+
+    if let Some((metrics, routed)) = proxy {
+        push_prometheus_header(
+            &mut output,
+            "ccem_router_requests_total",
+            "counter",
+            "Requests handled by the local proxy/router.",
+        );
+        for (outcome, value) in [
+            ("success", metrics.success_requests),
+            ("failed", metrics.failed_requests),
+            ("route_not_found", metrics.route_not_found_requests),
+        ] {
+            output.push_str(&format!(
+                "ccem_router_requests_total{{outcome=\"{outcome}\"}} {value}\n"
+            ));
+        }
+
+        push_prometheus_header(
+            &mut output,
+            "ccem_router_active_connections",
+            "gauge",
+            "Open proxy/router connections.",
+        );
+        output.push_str(&format!(
+            "ccem_router_active_connections {}\n",
+            metrics.active_connections
+        ));
+
+        push_prometheus_header(
+            &mut output,
+            "ccem_routed_requests_total",
+            "counter",
+            "Routed message requests by the environment that served them.",
+        );
+        for count in routed {
+            output.push_str(&format!(
+                "ccem_routed_requests_total{{target_env=\"{}\",sub_route=\"{}\",outcome=\"{}\"}} {}\n",
+                escape_prometheus_label(&count.target_env),
+                count.sub_route,
+                if count.success { "success" } else { "failed" },
+                count.count,
+            ));
+        }
+    }
+
+    output
+}
+
+/// Capability and directory checks for a scoped token; the error is the
+/// reason reported to the caller and written to the audit log.
+fn authorize_scoped_call(grant: &ControlTokenGrant, rpc: &JsonRpcRequest) -> Result<(), String> {
+    if !grant.allows_method(&rpc.method) {
+        return Err(format!(
+            "Forbidden: token {} is not allowed to call {}",
+            grant.name, rpc.method
+        ));
+    }
+    if rpc.method == "ccem.workspace.createSession" && !grant.allowed_dirs.is_empty() {
+        let cwd = rpc
+            .params
+            .get("cwd")
+            .or_else(|| rpc.params.get("workingDir"))
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        let cwd = resolve_working_dir(cwd)?;
+        if !grant.allows_directory(&cwd) {
+            return Err(format!(
+                "Forbidden: token {} cannot create sessions in {}",
+                grant.name, cwd
+            ));
+        }
+    }
+    Ok(())
+}
+
 fn deserialize_params<T: for<'de> Deserialize<'de>>(params: Value) -> Result<T, String> {
     serde_json::from_value(params).map_err(|error| format!("Invalid params: {}", error))
 }
@@ -1892,7 +2801,7 @@ fn resolve_current_env() -> Option<String> {
 /// Known permission mode names accepted by external control.
 const KNOWN_PERM_MODES: &[&str] = &["yolo", "dev", "readonly", "safe", "ci", "audit"];
 
-fn is_known_perm_mode(mode: &str) -> bool {
+pub(crate) fn is_known_perm_mode(mode: &str) -> bool {
     KNOWN_PERM_MODES
         .iter()
         .any(|known| known.eq_ignore_ascii_case(mode))
@@ -2067,6 +2976,10 @@ fn descriptor_owned_by_runtime(
         && current.token == expected.token
 }
 
+fn control_endpoint(port: u16) -> String {
+    format!("http://127.0.0.1:{}/rpc", port)
+}
+
 fn generate_token() -> String {
     rand::thread_rng()
         .sample_iter(&Alphanumeric)
@@ -2158,6 +3071,400 @@ pub fn is_json_content_type(content_type: Option<&str>) -> bool {
     mime == "application/json"
 }
 
+/// One method of the contract published by `ccem.rpc.discover`. Schemas are
+/// shorthand: a JSON Schema type name, `string[]`, `ok` for the
+/// `{ "ok": true }` acknowledgement, or `stream` for the SSE subscription.
+struct RpcMethodSpec {
+    name: &'static str,
+    summary: &'static str,
+    params: &'static [RpcParamSpec],
+    result: &'static str,
+}
+
+struct RpcParamSpec {
+    name: &'static str,
+    schema: &'static str,
+    required: bool,
+}
+
+const fn required(name: &'static str, schema: &'static str) -> RpcParamSpec {
+    RpcParamSpec {
+        name,
+        schema,
+        required: true,
+    }
+}
+
+const fn optional(name: &'static str, schema: &'static str) -> RpcParamSpec {
+    RpcParamSpec {
+        name,
+        schema,
+        required: false,
+    }
+}
+
+const RPC_METHODS: &[RpcMethodSpec] = &[
+    RpcMethodSpec {
+        name: "ccem.health",
+        summary: "Report that the desktop app is running.",
+        params: &[],
+        result: "object",
+    },
+    RpcMethodSpec {
+        name: "ccem.rpc.discover",
+        summary: "Return this OpenRPC document.",
+        params: &[],
+        result: "object",
+    },
+    RpcMethodSpec {
+        name: "ccem.workspace.listSessions",
+        summary: "List native workspace sessions.",
+        params: &[
+            optional("cwd", "string"),
+            optional("provider", "string"),
+            optional("status", "string"),
+        ],
+        result: "array",
+    },
+    RpcMethodSpec {
+        name: "ccem.workspace.getSession",
+        summary: "Fetch one workspace session summary.",
+        params: &[required("runtimeId", "string")],
+        result: "object",
+    },
+    RpcMethodSpec {
+        name: "ccem.workspace.createSession",
+        summary: "Launch a native Claude or Codex session, optionally from a launch preset.",
+        params: &[
+            optional("preset", "string"),
+            optional("provider", "string"),
+            optional("cwd", "string"),
+            optional("prompt", "string"),
+            optional("envName", "string"),
+            optional("permissionMode", "string"),
+            optional("runtimePermissionMode", "string"),
+            optional("providerSessionId", "string"),
+            optional("effort", "string"),
+            optional("open", "boolean"),
+            optional("worktree", "boolean"),
+            optional("routerLaunchDraft", "object"),
+            optional("routes", "object"),
+            optional("allowedEnvs", "string[]"),
+            optional("dynamicRouting", "boolean"),
+        ],
+        result: "object",
+    },
+    RpcMethodSpec {
+        name: "ccem.workspace.getEvents",
+        summary: "Replay session events after sinceSeq.",
+        params: &[
+            required("runtimeId", "string"),
+            optional("sinceSeq", "integer"),
+            optional("limit", "integer"),
+        ],
+        result: "object",
+    },
+    RpcMethodSpec {
+        name: "ccem.workspace.subscribe",
+        summary: "Stream session events as server-sent events until the client disconnects.",
+        params: &[
+            required("runtimeIds", "string[]"),
+            optional("sinceSeqs", "object"),
+        ],
+        result: "stream",
+    },
+    RpcMethodSpec {
+        name: "ccem.workspace.sendInput",
+        summary: "Send a user message to a session.",
+        params: &[
+            required("runtimeId", "string"),
+            required("text", "string"),
+            optional("displayText", "string"),
+        ],
+        result: "ok",
+    },
+    RpcMethodSpec {
+        name: "ccem.workspace.stopSession",
+        summary: "Stop a running session.",
+        params: &[required("runtimeId", "string")],
+        result: "ok",
+    },
+    RpcMethodSpec {
+        name: "ccem.workspace.respondToPermission",
+        summary: "Approve or deny a pending tool permission request.",
+        params: &[
+            required("runtimeId", "string"),
+            required("requestId", "string"),
+            required("approved", "boolean"),
+        ],
+        result: "ok",
+    },
+    RpcMethodSpec {
+        name: "ccem.workspace.respondToPrompt",
+        summary: "Answer a pending interactive prompt.",
+        params: &[
+            required("runtimeId", "string"),
+            required("toolUseId", "string"),
+            required("promptType", "string"),
+            optional("displayText", "string"),
+            optional("answers", "object"),
+            optional("annotations", "object"),
+            optional("promptAnnotations", "array"),
+        ],
+        result: "ok",
+    },
+    RpcMethodSpec {
+        name: "ccem.workspace.rewindFiles",
+        summary: "Restore files to a checkpoint.",
+        params: &[
+            required("runtimeId", "string"),
+            required("checkpointId", "string"),
+        ],
+        result: "ok",
+    },
+    RpcMethodSpec {
+        name: "ccem.workspace.updateSettings",
+        summary: "Change a session's environment, permission mode or effort.",
+        params: &[
+            required("runtimeId", "string"),
+            optional("envName", "string"),
+            optional("permMode", "string"),
+            optional("effort", "string"),
+            optional("forceRestart", "boolean"),
+        ],
+        result: "ok",
+    },
+    RpcMethodSpec {
+        name: "ccem.workspace.stopBackgroundTask",
+        summary: "Stop one background task of a session.",
+        params: &[
+            required("runtimeId", "string"),
+            required("taskId", "string"),
+        ],
+        result: "ok",
+    },
+    RpcMethodSpec {
+        name: "ccem.workspace.queryUsage",
+        summary: "Ask the session for its usage; the answer arrives as a session event.",
+        params: &[required("runtimeId", "string")],
+        result: "ok",
+    },
+    RpcMethodSpec {
+        name: "ccem.workspace.openSession",
+        summary: "Focus a session in the desktop window.",
+        params: &[required("link", "string")],
+        result: "ok",
+    },
+    RpcMethodSpec {
+        name: "ccem.workspace.getRouter",
+        summary: "Read a session's router state.",
+        params: &[required("runtimeId", "string")],
+        result: "object",
+    },
+    RpcMethodSpec {
+        name: "ccem.workspace.updateRouter",
+        summary: "Patch a session's router state.",
+        params: &[
+            required("runtimeId", "string"),
+            required("expectedRevision", "integer"),
+            required("patch", "object"),
+        ],
+        result: "object",
+    },
+    RpcMethodSpec {
+        name: "ccem.workspace.restartDirect",
+        summary: "Restart a routed session without the router.",
+        params: &[required("runtimeId", "string")],
+        result: "object",
+    },
+    RpcMethodSpec {
+        name: "ccem.environment.references",
+        summary: "List sessions and router settings that reference an environment.",
+        params: &[required("name", "string")],
+        result: "object",
+    },
+    RpcMethodSpec {
+        name: "ccem.environment.rename",
+        summary: "Rename an environment and its references.",
+        params: &[required("oldName", "string"), required("newName", "string")],
+        result: "object",
+    },
+    RpcMethodSpec {
+        name: "ccem.environment.delete",
+        summary: "Delete an unreferenced environment.",
+        params: &[required("name", "string")],
+        result: "object",
+    },
+    RpcMethodSpec {
+        name: "ccem.router.getSettings",
+        summary: "Read the global router configuration.",
+        params: &[],
+        result: "object",
+    },
+    RpcMethodSpec {
+        name: "ccem.router.updateSettings",
+        summary: "Replace the global router configuration.",
+        params: &[
+            optional("port", "integer"),
+            optional("bindings", "object"),
+            optional("profiles", "array"),
+            optional("dynamicRouting", "boolean"),
+            optional("defaultAllowedEnvs", "string[]"),
+        ],
+        result: "object",
+    },
+    RpcMethodSpec {
+        name: "ccem.router.status",
+        summary: "Report router listener status.",
+        params: &[],
+        result: "object",
+    },
+    RpcMethodSpec {
+        name: "ccem.cron.list",
+        summary: "List scheduled tasks.",
+        params: &[],
+        result: "array",
+    },
+    RpcMethodSpec {
+        name: "ccem.cron.add",
+        summary: "Create a scheduled task.",
+        params: &[
+            required("name", "string"),
+            required("cronExpression", "string"),
+            required("prompt", "string"),
+            required("workingDir", "string"),
+            optional("envName", "string"),
+            optional("executionProfile", "string"),
+            optional("maxBudgetUsd", "number"),
+            optional("allowedTools", "string[]"),
+            optional("disallowedTools", "string[]"),
+            optional("timeoutSecs", "integer"),
+            optional("templateId", "string"),
+            optional("wecomNotification", "object"),
+            optional("launchPreset", "string"),
+        ],
+        result: "object",
+    },
+    RpcMethodSpec {
+        name: "ccem.cron.update",
+        summary: "Update fields of a scheduled task.",
+        params: &[
+            required("id", "string"),
+            optional("name", "string"),
+            optional("cronExpression", "string"),
+            optional("prompt", "string"),
+            optional("workingDir", "string"),
+            optional("envName", "string"),
+            optional("executionProfile", "string"),
+            optional("maxBudgetUsd", "number"),
+            optional("allowedTools", "string[]"),
+            optional("disallowedTools", "string[]"),
+            optional("timeoutSecs", "integer"),
+            optional("wecomNotification", "object"),
+            optional("launchPreset", "string"),
+        ],
+        result: "object",
+    },
+    RpcMethodSpec {
+        name: "ccem.cron.delete",
+        summary: "Delete a scheduled task and its run log.",
+        params: &[required("id", "string")],
+        result: "ok",
+    },
+    RpcMethodSpec {
+        name: "ccem.cron.toggle",
+        summary: "Enable or disable a scheduled task.",
+        params: &[required("id", "string")],
+        result: "object",
+    },
+    RpcMethodSpec {
+        name: "ccem.cron.runs",
+        summary: "List past runs of a scheduled task.",
+        params: &[required("id", "string")],
+        result: "array",
+    },
+    RpcMethodSpec {
+        name: "ccem.history.search",
+        summary: "Search conversation history by title and summary.",
+        params: &[
+            required("query", "string"),
+            optional("source", "string"),
+            optional("limit", "integer"),
+        ],
+        result: "array",
+    },
+    RpcMethodSpec {
+        name: "ccem.history.searchFullText",
+        summary: "Full-text search across indexed transcripts.",
+        params: &[
+            required("query", "string"),
+            optional("filters", "object"),
+            optional("limit", "integer"),
+        ],
+        result: "array",
+    },
+    RpcMethodSpec {
+        name: "ccem.usage.stats",
+        summary: "Aggregate token and cost usage.",
+        params: &[optional("source", "string"), optional("force", "boolean")],
+        result: "object",
+    },
+];
+
+fn rpc_schema(shorthand: &str) -> Value {
+    match shorthand {
+        "string[]" => json!({ "type": "array", "items": { "type": "string" } }),
+        "ok" => json!({
+            "type": "object",
+            "properties": { "ok": { "type": "boolean" } },
+            "required": ["ok"],
+        }),
+        "stream" => json!({ "type": "string", "contentMediaType": "text/event-stream" }),
+        kind => json!({ "type": kind }),
+    }
+}
+
+fn openrpc_document(port: Option<u16>) -> Value {
+    let methods = RPC_METHODS
+        .iter()
+        .filter(|method| is_allowed_method(method.name))
+        .map(|method| {
+            let params = method
+                .params
+                .iter()
+                .map(|param| {
+                    json!({
+                        "name": param.name,
+                        "required": param.required,
+                        "schema": rpc_schema(param.schema),
+                    })
+                })
+                .collect::<Vec<_>>();
+            json!({
+                "name": method.name,
+                "summary": method.summary,
+                "paramStructure": "by-name",
+                "params": params,
+                "result": { "name": "result", "schema": rpc_schema(method.result) },
+            })
+        })
+        .collect::<Vec<_>>();
+    let servers = port
+        .map(|port| {
+            vec![json!({ "name": "loopback", "url": format!("http://127.0.0.1:{}/rpc", port) })]
+        })
+        .unwrap_or_default();
+    json!({
+        "openrpc": "1.2.6",
+        "info": {
+            "title": "CCEM Desktop external control",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "servers": servers,
+        "methods": methods,
+    })
+}
+
 /// Allowlist of JSON-RPC method names accepted by the external control server.
 /// Any method not in this list is rejected with -32601 before dispatch.
 pub fn is_allowed_method(method: &str) -> bool {
@@ -2174,6 +3481,7 @@ fn is_allowed_method_for_build(method: &str, debug_assertions: bool) -> bool {
             | "ccem.environment.delete"
             | "ccem.workspace.getSession"
             | "ccem.workspace.getEvents"
+            | "ccem.workspace.subscribe"
             | "ccem.workspace.sendInput"
             | "ccem.workspace.openSession"
             | "ccem.workspace.createSession"
@@ -2183,6 +3491,23 @@ fn is_allowed_method_for_build(method: &str, debug_assertions: bool) -> bool {
             | "ccem.workspace.getRouter"
             | "ccem.workspace.updateRouter"
             | "ccem.workspace.restartDirect"
+            | "ccem.workspace.stopSession"
+            | "ccem.workspace.respondToPermission"
+            | "ccem.workspace.respondToPrompt"
+            | "ccem.workspace.rewindFiles"
+            | "ccem.workspace.updateSettings"
+            | "ccem.workspace.stopBackgroundTask"
+            | "ccem.workspace.queryUsage"
+            | "ccem.cron.list"
+            | "ccem.cron.add"
+            | "ccem.cron.update"
+            | "ccem.cron.delete"
+            | "ccem.cron.toggle"
+            | "ccem.cron.runs"
+            | "ccem.history.search"
+            | "ccem.history.searchFullText"
+            | "ccem.usage.stats"
+            | "ccem.rpc.discover"
     ) || (debug_assertions && method == "ccem.browser.smokeProbe")
 }
 
@@ -2211,6 +3536,7 @@ fn is_loopback_ipv6_literal(literal: &str) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::control_tokens::capability_for_method;
 
     fn create_session_params(value: Value) -> CreateSessionParams {
         deserialize_params(value).expect("deserialize create-session params")
@@ -2451,6 +3777,7 @@ mod tests {
             "ccem.environment.delete",
             "ccem.workspace.getSession",
             "ccem.workspace.getEvents",
+            "ccem.workspace.subscribe",
             "ccem.workspace.sendInput",
             "ccem.workspace.openSession",
             "ccem.workspace.createSession",
@@ -2460,12 +3787,197 @@ mod tests {
             "ccem.workspace.getRouter",
             "ccem.workspace.updateRouter",
             "ccem.workspace.restartDirect",
+            "ccem.workspace.stopSession",
+            "ccem.workspace.respondToPermission",
+            "ccem.workspace.respondToPrompt",
+            "ccem.workspace.rewindFiles",
+            "ccem.workspace.updateSettings",
+            "ccem.workspace.stopBackgroundTask",
+            "ccem.workspace.queryUsage",
+            "ccem.cron.list",
+            "ccem.cron.add",
+            "ccem.cron.update",
+            "ccem.cron.delete",
+            "ccem.cron.toggle",
+            "ccem.cron.runs",
+            "ccem.history.search",
+            "ccem.history.searchFullText",
+            "ccem.usage.stats",
+            "ccem.rpc.discover",
             "ccem.browser.smokeProbe",
         ] {
             assert!(is_allowed_method(method), "{} should be allowed", method);
         }
     }
 
+    fn event_record(runtime_id: &str, seq: u64) -> SessionEventRecord {
+        SessionEventRecord {
+            runtime_id: runtime_id.to_string(),
+            seq,
+            occurred_at: chrono::Utc::now(),
+            payload: SessionEventPayload::Lifecycle {
+                stage: "turn".to_string(),
+                detail: String::new(),
+                assistant_message_uuid: None,
+            },
+        }
+    }
+
+    #[test]
+    fn event_stream_cursor_dedupes_replays_and_resyncs_on_missed_pushes() {
+        let runtime_ids = vec!["rt-a".to_string(), "rt-b".to_string()];
+        let since_seqs = HashMap::from([("rt-a".to_string(), 4)]);
+        let mut cursor = EventStreamCursor::new(&runtime_ids, &since_seqs);
+        assert_eq!(cursor.resumable_runtimes(), vec!["rt-a"]);
+
+        assert_eq!(
+            cursor.admit(&event_record("rt-a", 4)),
+            CursorAdmission::Skip
+        );
+        assert_eq!(
+            cursor.admit(&event_record("rt-a", 5)),
+            CursorAdmission::Deliver
+        );
+        assert_eq!(
+            cursor.admit(&event_record("rt-a", 7)),
+            CursorAdmission::Resync
+        );
+        assert_eq!(
+            cursor.admit(&event_record("rt-b", 42)),
+            CursorAdmission::Deliver
+        );
+        assert_eq!(
+            cursor.admit(&event_record("rt-c", 1)),
+            CursorAdmission::Skip
+        );
+
+        let mut output = Vec::new();
+        write_event_record(&mut output, &mut cursor, &event_record("rt-b", 42))
+            .expect("write event frame");
+        let frame = String::from_utf8(output).expect("utf8 frame");
+        assert!(frame.starts_with("id: rt-b:42\nevent: session_event\ndata: {"));
+        assert!(frame.ends_with("}\n\n"));
+        assert_eq!(cursor.last_seen("rt-b"), Some(42));
+        assert_eq!(
+            cursor.admit(&event_record("rt-b", 42)),
+            CursorAdmission::Skip
+        );
+    }
+
+    #[test]
+    fn gap_notification_marks_events_lost_from_the_ring_buffer() {
+        let batch = ReplayBatch {
+            gap_detected: true,
+            truncated: false,
+            oldest_available_seq: Some(120),
+            newest_available_seq: Some(130),
+            events: Vec::new(),
+        };
+        let gap = gap_notification_record("rt-a", 10, &batch).expect("gap record");
+        assert_eq!(gap.seq, 10);
+        assert_eq!(
+            gap.payload,
+            SessionEventPayload::GapNotification {
+                last_seen_seq: 10,
+                oldest_available_seq: 120,
+            }
+        );
+        assert_eq!(gap_resume_seq(&batch), 119);
+        assert!(gap_notification_record(
+            "rt-a",
+            10,
+            &ReplayBatch {
+                gap_detected: false,
+                ..batch
+            }
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn openrpc_document_covers_every_allowed_method() {
+        let document = openrpc_document(Some(4821));
+        assert_eq!(document["servers"][0]["url"], "http://127.0.0.1:4821/rpc");
+        let methods = document["methods"].as_array().expect("methods array");
+        let names = methods
+            .iter()
+            .filter_map(|method| method["name"].as_str())
+            .collect::<Vec<_>>();
+        for spec in RPC_METHODS {
+            assert!(
+                is_allowed_method_for_build(spec.name, false),
+                "{} is documented but not allowed",
+                spec.name
+            );
+            assert!(names.contains(&spec.name));
+        }
+        assert_eq!(names.len(), RPC_METHODS.len());
+
+        let respond = methods
+            .iter()
+            .find(|method| method["name"] == "ccem.workspace.respondToPermission")
+            .expect("respondToPermission documented");
+        assert_eq!(respond["params"][2]["name"], "approved");
+        assert_eq!(respond["params"][2]["schema"]["type"], "boolean");
+        assert_eq!(respond["result"]["schema"]["required"][0], "ok");
+    }
+
+    #[test]
+    fn every_documented_method_maps_to_a_capability() {
+        let grant = ControlTokenGrant {
+            id: "tok-test".to_string(),
+            name: "none".to_string(),
+            capabilities: Vec::new(),
+            allowed_dirs: Vec::new(),
+        };
+        for spec in RPC_METHODS {
+            assert!(
+                grant.allows_method(spec.name) || capability_for_method(spec.name).is_some(),
+                "{} has no capability",
+                spec.name
+            );
+        }
+        assert_eq!(capability_for_method("ccem.browser.smokeProbe"), None);
+    }
+
+    #[test]
+    fn scoped_tokens_are_limited_to_their_capabilities_and_directories() {
+        let grant = ControlTokenGrant {
+            id: "tok-editor".to_string(),
+            name: "editor".to_string(),
+            capabilities: vec![
+                ControlCapability::ReadSessions,
+                ControlCapability::CreateSessions,
+            ],
+            allowed_dirs: vec!["/work/repo".to_string()],
+        };
+        let call = |method: &str, params: Value| JsonRpcRequest {
+            id: None,
+            method: method.to_string(),
+            params,
+        };
+
+        assert!(
+            authorize_scoped_call(&grant, &call("ccem.workspace.getEvents", json!({}))).is_ok()
+        );
+        let denied = authorize_scoped_call(&grant, &call("ccem.environment.delete", json!({})))
+            .expect_err("router admin denied");
+        assert!(denied.contains("not allowed to call ccem.environment.delete"));
+        assert!(authorize_scoped_call(
+            &grant,
+            &call(
+                "ccem.workspace.createSession",
+                json!({ "cwd": "/work/repo/app" })
+            )
+        )
+        .is_ok());
+        assert!(authorize_scoped_call(
+            &grant,
+            &call("ccem.workspace.createSession", json!({ "cwd": "/etc" }))
+        )
+        .is_err());
+    }
+
     #[test]
     fn test_browser_smoke_probe_is_debug_only() {
         assert!(is_allowed_method_for_build("ccem.browser.smokeProbe", true));
@@ -2475,6 +3987,16 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn session_caller_methods_are_allowed_control_methods() {
+        for method in sessions_mcp::SESSION_CALLER_METHODS {
+            assert!(is_allowed_method(method), "{method} is not allowed");
+        }
+        for method in sessions_mcp::SESSION_CALLER_CHILD_METHODS {
+            assert!(sessions_mcp::SESSION_CALLER_METHODS.contains(method));
+        }
+    }
+
     #[test]
     fn test_disallowed_methods() {
         assert!(!is_allowed_method("ccem.evil"));
@@ -2706,4 +4228,49 @@ mod tests {
         assert!(path.exists());
         let _ = fs::remove_file(path);
     }
+
+    #[test]
+    fn prometheus_metrics_render_usage_and_router_counters() {
+        let usage = vec![UsageMetricSample {
+            source: "claude",
+            model: "claude-sonnet-4-5".to_string(),
+            environment: "team \"kimi\"".to_string(),
+            usage: analytics::TokenUsageWithCost {
+                input_tokens: 120,
+                output_tokens: 30,
+                cache_read_tokens: 5,
+                cache_creation_tokens: 0,
+                cost: 0.5,
+            },
+        }];
+        let metrics = ProxyMetrics {
+            total_requests: 3,
+            success_requests: 2,
+            failed_requests: 1,
+            route_not_found_requests: 0,
+            avg_response_ms: 10,
+            active_connections: 1,
+        };
+        let routed = vec![RoutedRequestCount {
+            target_env: "kimi".to_string(),
+            sub_route: true,
+            success: true,
+            count: 2,
+        }];
+
+        let text = render_prometheus_metrics(&usage, &[], Some((&metrics, routed.as_slice())));
+
+        assert!(text.contains("# TYPE ccem_usage_tokens gauge"));
+        assert!(text.contains(
+            "ccem_usage_tokens{source=\"claude\",model=\"claude-sonnet-4-5\",environment=\"team \\\"kimi\\\"\",type=\"input\"} 120"
+        ));
+        assert!(text.contains("ccem_active_sessions{provider=\"codex\"} 0"));
+        assert!(text.contains("ccem_router_requests_total{outcome=\"failed\"} 1"));
+        assert!(text.contains(
+            "ccem_routed_requests_total{target_env=\"kimi\",sub_route=\"true\",outcome=\"success\"} 2"
+        ));
+
+        let without_router = render_prometheus_metrics(&usage, &[], None);
+        assert!(!without_router.contains("ccem_router_requests_total"));
+    }
 }
@@ -0,0 +1,382 @@
+// apps/desktop/src-tauri/src/git_worktree.rs
+//
+// Throwaway git worktrees for native sessions that should not touch the
+// user's checkout. Each worktree lives under ~/.ccem/worktrees/ on its own
+// `ccem/...` branch cut from the project's HEAD; uncommitted changes in the
+// main checkout are not carried over.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::config::get_ccem_dir;
+
+/// Untracked files larger than this are counted as changed without lines.
+const MAX_UNTRACKED_LINE_COUNT_BYTES: u64 = 2 * 1024 * 1024;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct GitWorktree {
+    pub repo_root: String,
+    pub path: String,
+    pub branch: String,
+    pub base_sha: String,
+    /// Where the session runs: the worktree counterpart of the project dir,
+    /// which may be a subdirectory of the repository.
+    pub session_dir: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct WorktreeFileStat {
+    pub path: String,
+    /// `None` for binary files.
+    pub insertions: Option<u64>,
+    pub deletions: Option<u64>,
+    pub untracked: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct WorktreeDiffStat {
+    pub files_changed: usize,
+    pub insertions: u64,
+    pub deletions: u64,
+    pub files: Vec<WorktreeFileStat>,
+}
+
+impl GitWorktree {
+    /// The project dir in the main checkout that this worktree mirrors.
+    pub fn source_dir(&self) -> String {
+        match Path::new(&self.session_dir).strip_prefix(&self.path) {
+            Ok(relative) if !relative.as_os_str().is_empty() => Path::new(&self.repo_root)
+                .join(relative)
+                .to_string_lossy()
+                .to_string(),
+            _ => self.repo_root.clone(),
+        }
+    }
+}
+
+pub fn worktrees_root() -> PathBuf {
+    get_ccem_dir().join("worktrees")
+}
+
+/// Cut `branch` from the HEAD of the repository containing `project_dir` and
+/// check it out in a fresh worktree.
+pub fn create_worktree(project_dir: &str, branch: &str) -> Result<GitWorktree, String> {
+    let project = Path::new(project_dir);
+    let repo_root = run_git(project, &["rev-parse", "--show-toplevel"])
+        .map_err(|error| format!("{} is not inside a git repository: {}", project_dir, error))?;
+    let prefix = run_git(project, &["rev-parse", "--show-prefix"])?;
+    let base_sha = run_git(project, &["rev-parse", "--verify", "HEAD"])
+        .map_err(|_| format!("{} has no commits to branch from", repo_root))?;
+    run_git(
+        Path::new(&repo_root),
+        &["check-ref-format", "--branch", branch],
+    )
+    .map_err(|_| format!("Invalid worktree branch name '{}'", branch))?;
+
+    let path = worktree_path(&repo_root, branch);
+    if path.exists() {
+        return Err(format!("Worktree path {} already exists", path.display()));
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|error| format!("Failed to create {}: {}", parent.display(), error))?;
+    }
+    let path_str = path.to_string_lossy().to_string();
+    run_git(
+        Path::new(&repo_root),
+        &["worktree", "add", "-b", branch, &path_str, &base_sha],
+    )?;
+
+    let session_dir = if prefix.is_empty() {
+        path_str.clone()
+    } else {
+        path.join(&prefix).to_string_lossy().to_string()
+    };
+    Ok(GitWorktree {
+        repo_root,
+        path: path_str,
+        branch: branch.to_string(),
+        base_sha,
+        session_dir,
+    })
+}
+
+/// Everything the worktree changed relative to its base commit: commits on
+/// the branch, uncommitted edits and untracked files.
+pub fn diff_stat(worktree: &GitWorktree) -> Result<WorktreeDiffStat, String> {
+    let path = Path::new(&worktree.path);
+    let numstat = run_git(path, &["diff", "--numstat", &worktree.base_sha])?;
+    let mut files = parse_numstat(&numstat);
+    let untracked = run_git(path, &["ls-files", "--others", "--exclude-standard"])?;
+    files.extend(
+        untracked
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(|relative| WorktreeFileStat {
+                path: relative.to_string(),
+                insertions: count_untracked_lines(&path.join(relative)),
+                deletions: Some(0),
+                untracked: true,
+            }),
+    );
+    Ok(summarize_file_stats(files))
+}
+
+/// Commit whatever the session left uncommitted. Returns whether a commit
+/// was made.
+pub fn commit_pending_changes(worktree: &GitWorktree, message: &str) -> Result<bool, String> {
+    let path = Path::new(&worktree.path);
+    run_git(path, &["add", "-A"])?;
+    if run_git(path, &["diff", "--cached", "--quiet"]).is_ok() {
+        return Ok(false);
+    }
+    run_git(path, &["commit", "--no-verify", "-m", message])?;
+    Ok(true)
+}
+
+/// Merge the worktree branch into whatever the main checkout has checked out.
+/// A conflicting merge is aborted so the checkout is never left mid-merge.
+pub fn merge_into_checkout(worktree: &GitWorktree) -> Result<String, String> {
+    let repo_root = Path::new(&worktree.repo_root);
+    run_git(
+        repo_root,
+        &["merge", "--no-ff", "--no-edit", &worktree.branch],
+    )
+    .map_err(|error| {
+        let _ = run_git(repo_root, &["merge", "--abort"]);
+        format!("Merging {} failed: {}", worktree.branch, error)
+    })
+}
+
+/// Replay the worktree branch on top of the main checkout's HEAD, then
+/// fast-forward the checkout to it. A conflicting rebase is aborted.
+pub fn rebase_onto_checkout(worktree: &GitWorktree) -> Result<String, String> {
+    let path = Path::new(&worktree.path);
+    let repo_root = Path::new(&worktree.repo_root);
+    let target = run_git(repo_root, &["rev-parse", "--verify", "HEAD"])?;
+    if let Err(error) = run_git(path, &["rebase", &target]) {
+        let _ = run_git(path, &["rebase", "--abort"]);
+        return Err(format!("Rebase onto {} failed: {}", target, error));
+    }
+    run_git(repo_root, &["merge", "--ff-only", &worktree.branch])
+}
+
+pub fn head_sha(worktree: &GitWorktree) -> Result<String, String> {
+    run_git(
+        Path::new(&worktree.path),
+        &["rev-parse", "--verify", "HEAD"],
+    )
+}
+
+/// Remove the worktree directory and, optionally, its branch.
+pub fn remove_worktree(worktree: &GitWorktree, delete_branch: bool) -> Result<(), String> {
+    let repo_root = Path::new(&worktree.repo_root);
+    if Path::new(&worktree.path).exists() {
+        run_git(
+            repo_root,
+            &["worktree", "remove", "--force", &worktree.path],
+        )?;
+    } else {
+        let _ = run_git(repo_root, &["worktree", "prune"]);
+    }
+    if delete_branch {
+        run_git(repo_root, &["branch", "-D", &worktree.branch])?;
+    }
+    Ok(())
+}
+
+/// Shell one-liner that commits the worktree's pending changes and merges
+/// its branch into the main checkout. Uses POSIX `sh` quoting and grouping,
+/// so it is only meant for Unix shells.
+pub fn adopt_command(worktree: &GitWorktree) -> String {
+    let path = shell_quote(&worktree.path);
+    let message = shell_quote(&format!("Adopt {}", worktree.branch));
+    format!(
+        "git -C {path} add -A && {{ git -C {path} diff --cached --quiet || git -C {path} commit --no-verify -m {message}; }} && git -C {} merge --no-ff --no-edit {}",
+        shell_quote(&worktree.repo_root),
+        shell_quote(&worktree.branch),
+    )
+}
+
+fn worktree_path(repo_root: &str, branch: &str) -> PathBuf {
+    let repo_name = Path::new(repo_root)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "repo".to_string());
+    let digest = Sha256::digest(repo_root.as_bytes());
+    worktrees_root()
+        .join(format!("{}-{}", repo_name, &hex::encode(digest)[..8]))
+        .join(branch.replace('/', "-"))
+}
+
+fn parse_numstat(output: &str) -> Vec<WorktreeFileStat> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, '\t');
+            let insertions = parts.next()?.trim();
+            let deletions = parts.next()?.trim();
+            let path = parts.next()?.trim();
+            if path.is_empty() {
+                return None;
+            }
+            Some(WorktreeFileStat {
+                path: path.to_string(),
+                insertions: insertions.parse().ok(),
+                deletions: deletions.parse().ok(),
+                untracked: false,
+            })
+        })
+        .collect()
+}
+
+fn summarize_file_stats(files: Vec<WorktreeFileStat>) -> WorktreeDiffStat {
+    WorktreeDiffStat {
+        files_changed: files.len(),
+        insertions: files.iter().filter_map(|file| file.insertions).sum(),
+        deletions: files.iter().filter_map(|file| file.deletions).sum(),
+        files,
+    }
+}
+
+fn count_untracked_lines(path: &Path) -> Option<u64> {
+    let metadata = fs::metadata(path).ok()?;
+    if !metadata.is_file() || metadata.len() > MAX_UNTRACKED_LINE_COUNT_BYTES {
+        return None;
+    }
+    let bytes = fs::read(path).ok()?;
+    if bytes.contains(&0) {
+        return None;
+    }
+    let lines = bytes.iter().filter(|byte| **byte == b'\n').count() as u64;
+    Some(if bytes.last().is_some_and(|byte| *byte != b'\n') {
+        lines + 1
+    } else {
+        lines
+    })
+}
+
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+fn run_git(working_dir: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(working_dir)
+        .args(args)
+        .output()
+        .map_err(|error| format!("Failed to run git: {}", error))?;
+
+    if output.status.success() {
+        return Ok(String::from_utf8_lossy(&output.stdout).trim().to_string());
+    }
+
+    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+    Err(if stderr.is_empty() {
+        format!("git exited with status {}", output.status)
+    } else {
+        stderr
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numstat_parsing_keeps_binary_files_without_line_counts() {
+        let stat = summarize_file_stats(parse_numstat(
+            "3\t1\tsrc/lib.rs\n-\t-\tassets/logo.png\n10\t0\tdocs/new file.md\n",
+        ));
+
+        assert_eq!(stat.files_changed, 3);
+        assert_eq!(stat.insertions, 13);
+        assert_eq!(stat.deletions, 1);
+        assert_eq!(stat.files[1].path, "assets/logo.png");
+        assert_eq!(stat.files[1].insertions, None);
+        assert_eq!(stat.files[2].path, "docs/new file.md");
+    }
+
+    #[test]
+    fn source_dir_maps_the_session_dir_back_into_the_checkout() {
+        let mut worktree = GitWorktree {
+            repo_root: "/work/app".to_string(),
+            path: "/home/me/.ccem/worktrees/app-1234/ccem-native-1".to_string(),
+            branch: "ccem/native-1".to_string(),
+            base_sha: "abc123".to_string(),
+            session_dir: "/home/me/.ccem/worktrees/app-1234/ccem-native-1/web".to_string(),
+        };
+        assert_eq!(worktree.source_dir(), "/work/app/web");
+
+        worktree.session_dir = worktree.path.clone();
+        assert_eq!(worktree.source_dir(), "/work/app");
+    }
+
+    #[test]
+    fn adopt_command_quotes_paths_and_merges_the_branch() {
+        let worktree = GitWorktree {
+            repo_root: "/work/it's here".to_string(),
+            path: "/home/me/.ccem/worktrees/repo/ccem-fanout-1".to_string(),
+            branch: "ccem/fanout-1".to_string(),
+            base_sha: "abc123".to_string(),
+            session_dir: "/home/me/.ccem/worktrees/repo/ccem-fanout-1".to_string(),
+        };
+
+        let command = adopt_command(&worktree);
+
+        assert!(command.starts_with("git -C '/home/me/.ccem/worktrees/repo/ccem-fanout-1' add -A"));
+        assert!(command
+            .ends_with("git -C '/work/it'\\''s here' merge --no-ff --no-edit 'ccem/fanout-1'"));
+    }
+
+    #[test]
+    fn conflicting_merge_is_aborted_and_leaves_the_checkout_clean() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let repo = dir.path().join("repo");
+        fs::create_dir_all(&repo).expect("repo dir");
+        let git = |cwd: &Path, args: &[&str]| run_git(cwd, args).expect("git");
+        git(&repo, &["init", "-q"]);
+        git(&repo, &["config", "user.email", "ccem@example.com"]);
+        git(&repo, &["config", "user.name", "ccem"]);
+        fs::write(repo.join("notes.txt"), "base\n").expect("write base");
+        git(&repo, &["add", "-A"]);
+        git(&repo, &["commit", "-q", "-m", "base"]);
+        let base_sha = git(&repo, &["rev-parse", "HEAD"]);
+
+        let path = dir.path().join("worktree");
+        let path_str = path.to_string_lossy().to_string();
+        git(
+            &repo,
+            &["worktree", "add", "-q", "-b", "ccem/conflict", &path_str],
+        );
+        fs::write(path.join("notes.txt"), "session\n").expect("write session");
+        git(&path, &["commit", "-q", "-am", "session"]);
+        fs::write(repo.join("notes.txt"), "user\n").expect("write user");
+        git(&repo, &["commit", "-q", "-am", "user"]);
+
+        let worktree = GitWorktree {
+            repo_root: repo.to_string_lossy().to_string(),
+            path: path_str.clone(),
+            branch: "ccem/conflict".to_string(),
+            base_sha,
+            session_dir: path_str,
+        };
+        let error = merge_into_checkout(&worktree).expect_err("conflict");
+
+        assert!(error.contains("ccem/conflict"));
+        assert!(run_git(&repo, &["rev-parse", "--verify", "MERGE_HEAD"]).is_err());
+        assert_eq!(git(&repo, &["status", "--porcelain"]), "");
+        assert_eq!(
+            fs::read_to_string(repo.join("notes.txt")).expect("read"),
+            "user\n"
+        );
+    }
+}
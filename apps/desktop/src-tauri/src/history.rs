@@ -2,6 +2,7 @@
 //
 // Conversation history support for both Claude and Codex sources.
 
+use crate::storage_manager::StorageManager;
 use crate::{opencode, runtime, session_provenance};
 use rusqlite::{Connection, OpenFlags};
 use serde::{Deserialize, Serialize};
@@ -17,6 +18,9 @@ const SOURCE_CODEX: &str = "codex";
 const SOURCE_OPENCODE: &str = "opencode";
 const HISTORY_NEAR_DUPLICATE_WINDOW_MS: u64 = 1_500;
 const HISTORY_LIMIT_MAX: usize = 1_000;
+const HISTORY_CLUSTER_DEFAULT_WINDOW_DAYS: u32 = 7;
+const HISTORY_CLUSTER_MAX_WINDOW_DAYS: u32 = 90;
+const HISTORY_CLUSTER_MAX_FILES: usize = 20;
 const HISTORY_SCAN_LIMIT_MULTIPLIER: usize = 2;
 
 // ============================================================================
@@ -62,6 +66,22 @@ pub struct WorkspaceOverviewSnapshot {
     pub total_projects: usize,
 }
 
+/// Sessions of one project sharing a tag within one time window — a
+/// "what we worked on this week" entry for the workspace overview.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryCluster {
+    pub project: String,
+    pub project_name: String,
+    /// `None` groups sessions that have not been tagged yet.
+    pub tag: Option<String>,
+    pub window_start: u64,
+    pub window_end: u64,
+    pub session_keys: Vec<String>,
+    pub files_touched: Vec<String>,
+    pub latest_timestamp: u64,
+}
+
 struct WorkspaceProjectNodeDraft {
     project: String,
     project_name: String,
@@ -322,6 +342,30 @@ pub async fn get_workspace_overview_snapshot(
     .await
 }
 
+/// Group sessions by project, tag and time window (newest window first).
+#[tauri::command]
+pub async fn get_history_clusters(
+    window_days: Option<u32>,
+    limit: Option<usize>,
+) -> Result<Vec<HistoryCluster>, String> {
+    run_blocking(move || {
+        let limit = normalize_history_limit(limit).unwrap_or(HISTORY_LIMIT_MAX);
+        let sessions = load_history_sessions(None, Some(limit))?;
+        let annotations = crate::session_annotations::SessionAnnotations::load();
+        let window_days = window_days
+            .unwrap_or(HISTORY_CLUSTER_DEFAULT_WINDOW_DAYS)
+            .clamp(1, HISTORY_CLUSTER_MAX_WINDOW_DAYS);
+        let now_ms = chrono::Utc::now().timestamp_millis().max(0) as u64;
+        Ok(build_history_clusters(
+            &sessions,
+            &annotations,
+            now_ms,
+            u64::from(window_days) * 24 * 60 * 60 * 1000,
+        ))
+    })
+    .await
+}
+
 #[tauri::command]
 pub async fn search_conversation_history(
     query: String,
@@ -490,6 +534,68 @@ fn build_workspace_project_nodes(sessions: &[HistorySession]) -> Vec<WorkspacePr
     nodes
 }
 
+/// Windows are counted back from `now_ms`, so the first window is always
+/// "the last N days". A session with several tags joins each tag's cluster.
+fn build_history_clusters(
+    sessions: &[HistorySession],
+    annotations: &crate::session_annotations::SessionAnnotations,
+    now_ms: u64,
+    window_ms: u64,
+) -> Vec<HistoryCluster> {
+    let window_ms = window_ms.max(1);
+    let mut clusters: HashMap<(String, Option<String>, u64), HistoryCluster> = HashMap::new();
+
+    for session in sessions {
+        let window_index = now_ms.saturating_sub(session.timestamp) / window_ms;
+        let window_end = now_ms.saturating_sub(window_index * window_ms);
+        let annotation = annotations.get(&session.source, &session.id);
+        let tags = annotation
+            .map(|annotation| annotation.tags.clone())
+            .filter(|tags| !tags.is_empty())
+            .map(|tags| tags.into_iter().map(Some).collect::<Vec<_>>())
+            .unwrap_or_else(|| vec![None]);
+
+        for tag in tags {
+            let cluster = clusters
+                .entry((session.project.clone(), tag.clone(), window_end))
+                .or_insert_with(|| HistoryCluster {
+                    project: session.project.clone(),
+                    project_name: session.project_name.clone(),
+                    tag,
+                    window_start: window_end.saturating_sub(window_ms),
+                    window_end,
+                    session_keys: Vec::new(),
+                    files_touched: Vec::new(),
+                    latest_timestamp: 0,
+                });
+            cluster.session_keys.push(history_session_key(session));
+            cluster.latest_timestamp = cluster.latest_timestamp.max(session.timestamp);
+            for file in annotation
+                .map(|annotation| annotation.files_touched.as_slice())
+                .unwrap_or_default()
+            {
+                if cluster.files_touched.len() < HISTORY_CLUSTER_MAX_FILES
+                    && !cluster.files_touched.contains(file)
+                {
+                    cluster.files_touched.push(file.clone());
+                }
+            }
+        }
+    }
+
+    let mut clusters = clusters.into_values().collect::<Vec<_>>();
+    clusters.sort_by(|left, right| {
+        right
+            .window_end
+            .cmp(&left.window_end)
+            .then_with(|| right.session_keys.len().cmp(&left.session_keys.len()))
+            .then_with(|| right.latest_timestamp.cmp(&left.latest_timestamp))
+            .then_with(|| left.project.cmp(&right.project))
+            .then_with(|| left.tag.cmp(&right.tag))
+    });
+    clusters
+}
+
 fn history_session_key(session: &HistorySession) -> String {
     format!("{}:{}", session.source, session.id)
 }
@@ -571,15 +677,21 @@ pub async fn get_conversation_detail(
     session_id: String,
     source: Option<String>,
 ) -> Result<ConversationDetailPayload, String> {
-    run_blocking(move || {
-        let (messages, segments) = load_conversation_detail(&session_id, source.as_deref())?;
-        Ok(ConversationDetailPayload {
-            messages: merge_tool_results_into_messages(messages),
-            segments,
-            tool_results_merged: true,
-        })
+    run_blocking(move || load_merged_conversation_detail(&session_id, source.as_deref())).await
+}
+
+/// Conversation detail with tool results folded into their `tool_use` blocks,
+/// as shown in the review drawer and used by conversation export.
+pub(crate) fn load_merged_conversation_detail(
+    session_id: &str,
+    source: Option<&str>,
+) -> Result<ConversationDetailPayload, String> {
+    let (messages, segments) = load_conversation_detail(session_id, source)?;
+    Ok(ConversationDetailPayload {
+        messages: merge_tool_results_into_messages(messages),
+        segments,
+        tool_results_merged: true,
     })
-    .await
 }
 
 fn load_conversation_detail(
@@ -596,22 +708,25 @@ fn load_conversation_detail(
         SOURCE_CLAUDE => {
             let home = dirs::home_dir().ok_or("Could not find home directory")?;
             let projects_dir = home.join(".claude").join("projects");
-            if !projects_dir.exists() {
-                return Ok((vec![], vec![]));
+            match find_claude_session_file(&projects_dir, session_id) {
+                Some(path) => parse_claude_conversation_file(&path),
+                None => match StorageManager::default()
+                    .open_archived_transcript(SOURCE_CLAUDE, session_id)?
+                {
+                    Some(reader) => parse_claude_conversation_reader(reader),
+                    None => Ok((vec![], vec![])),
+                },
             }
-            let path = match find_claude_session_file(&projects_dir, session_id) {
-                Some(p) => p,
-                None => return Ok((vec![], vec![])),
-            };
-            parse_claude_conversation_file(&path)
-        }
-        SOURCE_CODEX => {
-            let path = match find_codex_session_file(session_id) {
-                Some(p) => p,
-                None => return Ok((vec![], vec![])),
-            };
-            parse_codex_conversation_file(&path)
         }
+        SOURCE_CODEX => match find_codex_session_file(session_id) {
+            Some(path) => parse_codex_conversation_file(&path),
+            None => match StorageManager::default()
+                .open_archived_transcript(SOURCE_CODEX, session_id)?
+            {
+                Some(reader) => parse_codex_conversation_reader(reader),
+                None => Ok((vec![], vec![])),
+            },
+        },
         SOURCE_OPENCODE => {
             let export = match load_opencode_export(session_id)? {
                 Some(value) => value,
@@ -819,6 +934,38 @@ pub async fn get_session_subagents(
     .await
 }
 
+/// Every sub-agent transcript of a Claude session (tool results merged),
+/// ordered by start time. Other sources have no sub-agent files.
+pub(crate) fn load_subagent_transcripts(
+    session_id: &str,
+    source: Option<&str>,
+) -> Result<Vec<(SubagentMeta, Vec<ConversationMessage>)>, String> {
+    let source_hint = normalize_history_source(source)?;
+    if resolve_history_source_for_session(session_id, source_hint) != Some(SOURCE_CLAUDE) {
+        return Ok(Vec::new());
+    }
+    let home = dirs::home_dir().ok_or("Could not find home directory")?;
+    let projects_dir = home.join(".claude").join("projects");
+    let Some(main_path) = find_claude_session_file(&projects_dir, session_id) else {
+        return Ok(Vec::new());
+    };
+    let Some(project_dir) = main_path.parent() else {
+        return Ok(Vec::new());
+    };
+    let subagents_dir = project_dir.join(session_id).join("subagents");
+
+    let mut records = discover_subagent_records(&subagents_dir);
+    enrich_subagent_records(&mut records, &main_path);
+    records.sort_by_key(|record| record.meta.started_at);
+
+    let mut transcripts = Vec::with_capacity(records.len());
+    for record in records {
+        let (messages, _) = parse_claude_conversation_file(&record.path)?;
+        transcripts.push((record.meta, merge_tool_results_into_messages(messages)));
+    }
+    Ok(transcripts)
+}
+
 #[derive(Debug, Clone)]
 struct SubagentRecord {
     meta: SubagentMeta,
@@ -2128,8 +2275,12 @@ fn parse_claude_conversation_file(
 ) -> Result<(Vec<ConversationMessage>, Vec<CompactSegment>), String> {
     let file =
         fs::File::open(path).map_err(|e| format!("Failed to open conversation file: {}", e))?;
+    parse_claude_conversation_reader(BufReader::new(file))
+}
 
-    let reader = BufReader::new(file);
+fn parse_claude_conversation_reader(
+    reader: impl BufRead,
+) -> Result<(Vec<ConversationMessage>, Vec<CompactSegment>), String> {
     let mut messages: Vec<ConversationMessage> = Vec::new();
     let mut segments: Vec<CompactSegment> = Vec::new();
     let mut current_segment: usize = 0;
@@ -2570,6 +2721,13 @@ fn find_codex_session_file(session_id: &str) -> Option<PathBuf> {
     find_codex_session_file_with_config(session_id, &config)
 }
 
+/// Root of Codex rollout transcripts (`$CODEX_HOME/sessions`).
+pub(crate) fn codex_sessions_root() -> Option<PathBuf> {
+    resolve_codex_path_config()
+        .ok()
+        .map(|config| config.sessions_root)
+}
+
 fn resolve_codex_path_config() -> Result<CodexPathConfig, String> {
     let home_dir = dirs::home_dir().ok_or("Could not find home directory")?;
     let codex_home_env = std::env::var("CODEX_HOME").ok();
@@ -3215,8 +3373,12 @@ fn parse_codex_conversation_file(
 ) -> Result<(Vec<ConversationMessage>, Vec<CompactSegment>), String> {
     let file =
         fs::File::open(path).map_err(|e| format!("Failed to open codex session file: {}", e))?;
+    parse_codex_conversation_reader(BufReader::new(file))
+}
 
-    let reader = BufReader::new(file);
+fn parse_codex_conversation_reader(
+    reader: impl BufRead,
+) -> Result<(Vec<ConversationMessage>, Vec<CompactSegment>), String> {
     let mut lines: Vec<CodexLine> = Vec::new();
 
     for line_result in reader.lines() {
@@ -4102,7 +4264,8 @@ fn resolve_history_source_for_session(
         return Some(SOURCE_OPENCODE);
     }
 
-    None
+    // Archived transcripts still open (read-only) from their bundles.
+    StorageManager::default().archived_source(session_id)
 }
 
 fn has_claude_session(session_id: &str) -> bool {
@@ -4542,11 +4705,11 @@ fn extract_usage_fields(
 #[cfg(test)]
 mod tests {
     use super::{
-        build_workspace_overview_snapshot, collect_codex_session_files_limited,
-        dedupe_history_sessions_with_provenance_records, dedupe_near_duplicate_history_sessions,
-        discover_subagent_metas, discover_subagent_records, enrich_subagent_metas,
-        enrich_subagent_records, find_codex_session_file_with_config, is_noise_display,
-        load_claude_history_from_paths_limited_with_provenance_records,
+        build_history_clusters, build_workspace_overview_snapshot,
+        collect_codex_session_files_limited, dedupe_history_sessions_with_provenance_records,
+        dedupe_near_duplicate_history_sessions, discover_subagent_metas, discover_subagent_records,
+        enrich_subagent_metas, enrich_subagent_records, find_codex_session_file_with_config,
+        is_noise_display, load_claude_history_from_paths_limited_with_provenance_records,
         load_codex_history_from_config, merge_opencode_history_session,
         merge_tool_results_into_messages, normalize_history_display, normalize_history_limit,
         normalize_history_source, parse_claude_conversation_file, parse_codex_history_timestamp,
@@ -4877,6 +5040,80 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_history_clusters_group_by_project_tag_and_window() {
+        use crate::session_annotations::{SessionAnnotation, SessionAnnotations};
+
+        let day_ms = 24 * 60 * 60 * 1000;
+        let now_ms = 30 * day_ms;
+        let session = |id: &str, project: &str, timestamp: u64| HistorySession {
+            id: id.to_string(),
+            source: SOURCE_CLAUDE.to_string(),
+            display: id.to_string(),
+            timestamp,
+            project: project.to_string(),
+            project_name: project.trim_start_matches("/repo/").to_string(),
+            env_name: None,
+            config_source: None,
+            task_stage: None,
+            task_sticker: None,
+            task_label: None,
+        };
+        let sessions = vec![
+            session("fix-login", "/repo/alpha", now_ms - day_ms),
+            session("fix-logout", "/repo/alpha", now_ms - 2 * day_ms),
+            session("ci-cache", "/repo/alpha", now_ms - 3 * day_ms),
+            session("old-fix", "/repo/alpha", now_ms - 10 * day_ms),
+            session("untagged", "/repo/beta", now_ms - day_ms),
+        ];
+        let mut annotations = SessionAnnotations::default();
+        let tagged = |tags: &[&str], files: &[&str]| SessionAnnotation {
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            files_touched: files.iter().map(|file| file.to_string()).collect(),
+            ..Default::default()
+        };
+        annotations.set(
+            SOURCE_CLAUDE,
+            "fix-login",
+            tagged(&["bugfix"], &["src/login.rs"]),
+        );
+        annotations.set(
+            SOURCE_CLAUDE,
+            "fix-logout",
+            tagged(&["bugfix", "test"], &["src/login.rs", "tests/logout.rs"]),
+        );
+        annotations.set(SOURCE_CLAUDE, "ci-cache", tagged(&["infra"], &[]));
+        annotations.set(SOURCE_CLAUDE, "old-fix", tagged(&["bugfix"], &[]));
+
+        let clusters = build_history_clusters(&sessions, &annotations, now_ms, 7 * day_ms);
+        let summary = clusters
+            .iter()
+            .map(|cluster| {
+                (
+                    cluster.project.as_str(),
+                    cluster.tag.as_deref(),
+                    cluster.window_end,
+                    cluster.session_keys.len(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![
+                ("/repo/alpha", Some("bugfix"), now_ms, 2),
+                ("/repo/beta", None, now_ms, 1),
+                ("/repo/alpha", Some("test"), now_ms, 1),
+                ("/repo/alpha", Some("infra"), now_ms, 1),
+                ("/repo/alpha", Some("bugfix"), now_ms - 7 * day_ms, 1),
+            ]
+        );
+        assert_eq!(
+            clusters[0].files_touched,
+            vec!["src/login.rs".to_string(), "tests/logout.rs".to_string()]
+        );
+        assert_eq!(clusters[4].window_start, now_ms - 14 * day_ms);
+    }
+
     #[test]
     fn test_history_search_score_prefers_title_matches() {
         let session = |display: &str, project_name: &str| HistorySession {
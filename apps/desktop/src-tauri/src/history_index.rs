@@ -0,0 +1,1392 @@
+// apps/desktop/src-tauri/src/history_index.rs
+//
+// Persistent full-text index over Claude, Codex and OpenCode transcripts,
+// stored as an FTS5 table in the shared state DB and fed incrementally.
+// The same pass records every file-writing tool call, so a file's change
+// history can be traced back to the sessions that edited it.
+
+use crate::session_provenance::state_db_path;
+use crate::session_titles::{apply_patch_file_paths, FILE_WRITING_TOOLS};
+use crate::{history, opencode};
+use chrono::{Local, NaiveDate, TimeZone};
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+const SOURCE_CLAUDE: &str = "claude";
+const SOURCE_CODEX: &str = "codex";
+const SOURCE_OPENCODE: &str = "opencode";
+const OPENCODE_KEY_PREFIX: &str = "opencode://session/";
+/// Searches within this window of the previous refresh reuse the index as-is.
+const INDEX_REFRESH_TTL: Duration = Duration::from_secs(10);
+/// Per-message cap; tool inputs and results beyond this add noise, not recall.
+const MAX_INDEXED_TEXT_CHARS: usize = 8_000;
+const MAX_TOOL_TEXT_CHARS: usize = 2_000;
+/// Request/explanation text kept alongside each recorded file change.
+const MAX_CHANGE_CONTEXT_CHARS: usize = 600;
+const DEFAULT_SEARCH_LIMIT: usize = 50;
+const MAX_SEARCH_LIMIT: usize = 200;
+const DEFAULT_FILE_HISTORY_LIMIT: usize = 100;
+const SNIPPET_TOKENS: i64 = 16;
+// Private-use sentinels mark matches inside SQL snippets; the snippet is
+// HTML-escaped afterwards and the sentinels become <mark> tags.
+const SNIPPET_OPEN: char = '\u{E000}';
+const SNIPPET_CLOSE: char = '\u{E001}';
+
+static INDEX_REFRESHED_AT: Mutex<Option<Instant>> = Mutex::new(None);
+
+// ============================================================================
+// Output types — sent to frontend (camelCase for TypeScript)
+// ============================================================================
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryFullTextFilters {
+    #[serde(default)]
+    pub source: Option<String>,
+    /// Project directory; also matches sessions in its subdirectories.
+    #[serde(default)]
+    pub project: Option<String>,
+    /// Inclusive local date bounds, `YYYY-MM-DD`.
+    #[serde(default)]
+    pub since: Option<String>,
+    #[serde(default)]
+    pub until: Option<String>,
+    #[serde(default)]
+    pub tool: Option<String>,
+    /// Substring of the model id (`sonnet`, `gpt-5`).
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryFullTextHit {
+    pub source: String,
+    pub session_id: String,
+    pub project: Option<String>,
+    pub role: String,
+    pub model: Option<String>,
+    pub tool_names: Vec<String>,
+    pub timestamp: u64,
+    /// HTML-escaped excerpt with matches wrapped in `<mark>`.
+    pub snippet_html: String,
+}
+
+/// One tool call that wrote a file, newest first in `get_file_history`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct FileHistoryEntry {
+    pub source: String,
+    pub session_id: String,
+    pub project: Option<String>,
+    pub file_path: String,
+    /// `Edit`, `Write`, `MultiEdit`, `NotebookEdit` or `apply_patch`.
+    pub tool_name: String,
+    pub timestamp: u64,
+    /// The user request the session was working on when it made the change.
+    pub request: Option<String>,
+    /// The assistant's own words leading up to the tool call.
+    pub explanation: Option<String>,
+}
+
+// ============================================================================
+// Index
+// ============================================================================
+
+/// One indexed message.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct IndexedMessage {
+    role: String,
+    text: String,
+    model: Option<String>,
+    tool_names: Vec<String>,
+    timestamp: u64,
+    file_changes: Vec<FileChange>,
+}
+
+/// A file written by a tool call, with the conversation context around it.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct FileChange {
+    tool_name: String,
+    file_path: String,
+    request: Option<String>,
+    explanation: Option<String>,
+}
+
+/// Per-transcript continuation state: the byte offset of the next unparsed
+/// line plus the session context (project, model, latest request and reply)
+/// that earlier lines set.
+#[derive(Debug, Clone, Default)]
+struct IndexedFile {
+    parse_offset: u64,
+    size: u64,
+    mtime_ms: u64,
+    project: Option<String>,
+    model: Option<String>,
+    last_request: Option<String>,
+    last_reply: Option<String>,
+}
+
+/// Transcript locations to feed from; tests point these at fixtures.
+#[derive(Debug, Clone, Default)]
+pub struct TranscriptRoots {
+    pub claude_projects: Option<PathBuf>,
+    pub codex_sessions: Option<PathBuf>,
+    pub include_opencode: bool,
+}
+
+impl TranscriptRoots {
+    pub fn discover() -> Self {
+        Self {
+            claude_projects: dirs::home_dir().map(|home| home.join(".claude").join("projects")),
+            codex_sessions: history::codex_sessions_root(),
+            include_opencode: true,
+        }
+    }
+}
+
+pub struct HistoryIndex {
+    db_path: PathBuf,
+}
+
+impl Default for HistoryIndex {
+    fn default() -> Self {
+        Self::new(state_db_path())
+    }
+}
+
+impl HistoryIndex {
+    pub fn new(db_path: PathBuf) -> Self {
+        Self { db_path }
+    }
+
+    fn open(&self) -> Result<Connection, String> {
+        if let Some(parent) = self.db_path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|error| format!("Failed to create sqlite state dir: {}", error))?;
+        }
+        let conn = Connection::open(&self.db_path).map_err(|error| {
+            format!(
+                "Failed to open sqlite state db {}: {}",
+                self.db_path.display(),
+                error
+            )
+        })?;
+        conn.busy_timeout(Duration::from_secs(3))
+            .map_err(|error| format!("Failed to configure sqlite busy timeout: {}", error))?;
+        // File changes are only recorded while parsing, so an index built
+        // before they existed is dropped and fed again from scratch.
+        let needs_backfill = table_exists(&conn, "history_index_files")?
+            && !table_exists(&conn, "history_file_changes")?;
+        if needs_backfill {
+            conn.execute_batch(
+                "DROP TABLE IF EXISTS history_index_files;
+                 DROP TABLE IF EXISTS history_fts;",
+            )
+            .map_err(|error| format!("Failed to reset history index: {}", error))?;
+        }
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             PRAGMA synchronous = NORMAL;
+             CREATE VIRTUAL TABLE IF NOT EXISTS history_fts USING fts5(
+                 text,
+                 tool_names,
+                 source UNINDEXED,
+                 session_id UNINDEXED,
+                 path UNINDEXED,
+                 project UNINDEXED,
+                 role UNINDEXED,
+                 model UNINDEXED,
+                 occurred_at UNINDEXED,
+                 tokenize = 'unicode61 remove_diacritics 2'
+             );
+             CREATE TABLE IF NOT EXISTS history_index_files (
+                 path TEXT PRIMARY KEY,
+                 source TEXT NOT NULL,
+                 session_id TEXT NOT NULL,
+                 parse_offset INTEGER NOT NULL,
+                 size INTEGER NOT NULL,
+                 mtime_ms INTEGER NOT NULL,
+                 project TEXT,
+                 model TEXT,
+                 last_request TEXT,
+                 last_reply TEXT,
+                 updated_at TEXT NOT NULL
+             );
+             CREATE TABLE IF NOT EXISTS history_file_changes (
+                 id INTEGER PRIMARY KEY AUTOINCREMENT,
+                 file_path TEXT NOT NULL,
+                 tool_name TEXT NOT NULL,
+                 source TEXT NOT NULL,
+                 session_id TEXT NOT NULL,
+                 path TEXT NOT NULL,
+                 project TEXT,
+                 request TEXT,
+                 explanation TEXT,
+                 occurred_at INTEGER NOT NULL
+             );
+             CREATE INDEX IF NOT EXISTS idx_history_file_changes_file
+                 ON history_file_changes(file_path, occurred_at DESC);
+             CREATE INDEX IF NOT EXISTS idx_history_file_changes_path
+                 ON history_file_changes(path);",
+        )
+        .map_err(|error| format!("Failed to initialize history index schema: {}", error))?;
+        Ok(conn)
+    }
+
+    /// Bring the index up to date with every transcript under `roots`.
+    /// Returns how many messages were added.
+    pub fn refresh(&self, roots: &TranscriptRoots) -> Result<usize, String> {
+        let mut conn = self.open()?;
+        let mut added = 0;
+        if let Some(root) = roots.claude_projects.as_deref() {
+            for path in collect_jsonl_files(root) {
+                added += index_transcript(&mut conn, SOURCE_CLAUDE, &path)?;
+            }
+        }
+        if let Some(root) = roots.codex_sessions.as_deref() {
+            for path in collect_jsonl_files(root) {
+                added += index_transcript(&mut conn, SOURCE_CODEX, &path)?;
+            }
+        }
+        if roots.include_opencode {
+            for session in opencode::list_local_sessions().unwrap_or_default() {
+                added += index_opencode_session(&mut conn, &session)?;
+            }
+        }
+        Ok(added)
+    }
+
+    /// Drop everything indexed from these transcripts, e.g. once storage
+    /// retention archived or deleted them.
+    pub fn forget_transcripts(&self, paths: &[PathBuf]) -> Result<(), String> {
+        let mut conn = self.open()?;
+        let tx = conn
+            .transaction()
+            .map_err(|error| format!("Failed to begin history index transaction: {}", error))?;
+        for path in paths {
+            let path_key = path.to_string_lossy();
+            delete_transcript_rows(&tx, &path_key)?;
+            tx.execute(
+                "DELETE FROM history_index_files WHERE path = ?1",
+                [path_key.as_ref()],
+            )
+            .map_err(|error| format!("Failed to forget indexed transcript: {}", error))?;
+        }
+        tx.commit()
+            .map_err(|error| format!("Failed to commit history index transaction: {}", error))
+    }
+
+    pub fn search(
+        &self,
+        query: &str,
+        filters: &HistoryFullTextFilters,
+        limit: usize,
+    ) -> Result<Vec<HistoryFullTextHit>, String> {
+        let Some(match_expression) = build_match_expression(query) else {
+            return Ok(Vec::new());
+        };
+        let since = parse_date_bound(filters.since.as_deref(), "since")?
+            .map(|date| local_day_start_ms(date) as i64);
+        let until = parse_date_bound(filters.until.as_deref(), "until")?
+            .and_then(|date| date.succ_opt())
+            .map(|date| local_day_start_ms(date) as i64);
+        let source = filters
+            .source
+            .as_deref()
+            .map(str::trim)
+            .filter(|value| !value.is_empty() && !value.eq_ignore_ascii_case("all"))
+            .map(str::to_lowercase);
+        let project = non_empty(filters.project.as_deref())
+            .map(|value| value.trim_end_matches(['/', '\\']).to_string());
+        let tool = non_empty(filters.tool.as_deref());
+        let model = non_empty(filters.model.as_deref());
+
+        let conn = self.open()?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT source, session_id, project, role, model, tool_names, occurred_at,
+                        snippet(history_fts, 0, ?9, ?10, '…', ?11)
+                 FROM history_fts
+                 WHERE history_fts MATCH ?1
+                   AND (?2 IS NULL OR source = ?2)
+                   AND (?3 IS NULL OR project = ?3 OR project LIKE ?3 || '/%')
+                   AND (?4 IS NULL OR occurred_at >= ?4)
+                   AND (?5 IS NULL OR occurred_at < ?5)
+                   AND (?6 IS NULL OR (' ' || tool_names || ' ') LIKE '% ' || ?6 || ' %')
+                   AND (?7 IS NULL OR model LIKE '%' || ?7 || '%')
+                 ORDER BY bm25(history_fts), occurred_at DESC
+                 LIMIT ?8",
+            )
+            .map_err(|error| format!("Failed to prepare history search: {}", error))?;
+        let rows = stmt
+            .query_map(
+                params![
+                    match_expression,
+                    source,
+                    project,
+                    since,
+                    until,
+                    tool,
+                    model,
+                    limit.clamp(1, MAX_SEARCH_LIMIT) as i64,
+                    SNIPPET_OPEN.to_string(),
+                    SNIPPET_CLOSE.to_string(),
+                    SNIPPET_TOKENS,
+                ],
+                |row| {
+                    Ok(HistoryFullTextHit {
+                        source: row.get(0)?,
+                        session_id: row.get(1)?,
+                        project: row.get(2)?,
+                        role: row.get(3)?,
+                        model: row.get(4)?,
+                        tool_names: row
+                            .get::<_, String>(5)?
+                            .split_whitespace()
+                            .map(str::to_string)
+                            .collect(),
+                        timestamp: row.get::<_, i64>(6)?.max(0) as u64,
+                        snippet_html: render_snippet_html(&row.get::<_, String>(7)?),
+                    })
+                },
+            )
+            .map_err(|error| format!("Failed to search history index: {}", error))?;
+
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|error| format!("Failed to read history search row: {}", error))
+    }
+
+    /// Every recorded write to `path`, newest first. A relative `path` is
+    /// resolved against `project`.
+    pub fn file_history(
+        &self,
+        project: &str,
+        path: &str,
+        limit: usize,
+    ) -> Result<Vec<FileHistoryEntry>, String> {
+        let Some(file_path) = resolve_file_path(non_empty(Some(project)), path) else {
+            return Ok(Vec::new());
+        };
+        let conn = self.open()?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT source, session_id, project, file_path, tool_name, occurred_at,
+                        request, explanation
+                 FROM history_file_changes
+                 WHERE file_path = ?1
+                 ORDER BY occurred_at DESC, id DESC
+                 LIMIT ?2",
+            )
+            .map_err(|error| format!("Failed to prepare file history query: {}", error))?;
+        let rows = stmt
+            .query_map(
+                params![file_path, limit.clamp(1, MAX_SEARCH_LIMIT) as i64],
+                |row| {
+                    Ok(FileHistoryEntry {
+                        source: row.get(0)?,
+                        session_id: row.get(1)?,
+                        project: row.get(2)?,
+                        file_path: row.get(3)?,
+                        tool_name: row.get(4)?,
+                        timestamp: row.get::<_, i64>(5)?.max(0) as u64,
+                        request: row.get(6)?,
+                        explanation: row.get(7)?,
+                    })
+                },
+            )
+            .map_err(|error| format!("Failed to query file history: {}", error))?;
+
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|error| format!("Failed to read file history row: {}", error))
+    }
+}
+
+fn table_exists(conn: &Connection, name: &str) -> Result<bool, String> {
+    conn.query_row(
+        "SELECT 1 FROM sqlite_master WHERE name = ?1",
+        [name],
+        |_| Ok(()),
+    )
+    .optional()
+    .map(|found| found.is_some())
+    .map_err(|error| format!("Failed to inspect history index schema: {}", error))
+}
+
+fn load_indexed_file(tx: &Transaction<'_>, path: &str) -> Result<Option<IndexedFile>, String> {
+    tx.query_row(
+        "SELECT parse_offset, size, mtime_ms, project, model, last_request, last_reply
+         FROM history_index_files WHERE path = ?1",
+        [path],
+        |row| {
+            Ok(IndexedFile {
+                parse_offset: row.get::<_, i64>(0)?.max(0) as u64,
+                size: row.get::<_, i64>(1)?.max(0) as u64,
+                mtime_ms: row.get::<_, i64>(2)?.max(0) as u64,
+                project: row.get(3)?,
+                model: row.get(4)?,
+                last_request: row.get(5)?,
+                last_reply: row.get(6)?,
+            })
+        },
+    )
+    .optional()
+    .map_err(|error| format!("Failed to read history index state: {}", error))
+}
+
+fn store_indexed_file(
+    tx: &Transaction<'_>,
+    path: &str,
+    source: &str,
+    session_id: &str,
+    state: &IndexedFile,
+) -> Result<(), String> {
+    tx.execute(
+        "INSERT OR REPLACE INTO history_index_files
+             (path, source, session_id, parse_offset, size, mtime_ms, project, model,
+              last_request, last_reply, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        params![
+            path,
+            source,
+            session_id,
+            state.parse_offset as i64,
+            state.size as i64,
+            state.mtime_ms as i64,
+            state.project,
+            state.model,
+            state.last_request,
+            state.last_reply,
+            chrono::Utc::now().to_rfc3339(),
+        ],
+    )
+    .map_err(|error| format!("Failed to store history index state: {}", error))?;
+    Ok(())
+}
+
+fn insert_messages(
+    tx: &Transaction<'_>,
+    path: &str,
+    source: &str,
+    session_id: &str,
+    project: Option<&str>,
+    messages: &[IndexedMessage],
+) -> Result<(), String> {
+    let mut stmt = tx
+        .prepare_cached(
+            "INSERT INTO history_fts
+                 (text, tool_names, source, session_id, path, project, role, model, occurred_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        )
+        .map_err(|error| format!("Failed to prepare history index insert: {}", error))?;
+    let mut change_stmt = tx
+        .prepare_cached(
+            "INSERT INTO history_file_changes
+                 (file_path, tool_name, source, session_id, path, project, request,
+                  explanation, occurred_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        )
+        .map_err(|error| format!("Failed to prepare file change insert: {}", error))?;
+    for message in messages {
+        stmt.execute(params![
+            segment_cjk(&message.text),
+            message.tool_names.join(" "),
+            source,
+            session_id,
+            path,
+            project,
+            message.role,
+            message.model,
+            message.timestamp as i64,
+        ])
+        .map_err(|error| format!("Failed to insert history index row: {}", error))?;
+        for change in &message.file_changes {
+            change_stmt
+                .execute(params![
+                    change.file_path,
+                    change.tool_name,
+                    source,
+                    session_id,
+                    path,
+                    project,
+                    change.request,
+                    change.explanation,
+                    message.timestamp as i64,
+                ])
+                .map_err(|error| format!("Failed to insert file change row: {}", error))?;
+        }
+    }
+    Ok(())
+}
+
+fn delete_transcript_rows(tx: &Transaction<'_>, path: &str) -> Result<(), String> {
+    tx.execute("DELETE FROM history_fts WHERE path = ?1", [path])
+        .map_err(|error| format!("Failed to reset history index rows: {}", error))?;
+    tx.execute("DELETE FROM history_file_changes WHERE path = ?1", [path])
+        .map_err(|error| format!("Failed to reset file change rows: {}", error))?;
+    Ok(())
+}
+
+/// Index the lines appended to a JSONL transcript since the last pass. A
+/// shrunk or rewritten file is dropped and re-indexed from the start; a
+/// trailing partial line waits for its newline.
+fn index_transcript(conn: &mut Connection, source: &str, path: &Path) -> Result<usize, String> {
+    let Some(session_id) = transcript_session_id(source, path) else {
+        return Ok(0);
+    };
+    let Ok(metadata) = fs::metadata(path) else {
+        return Ok(0);
+    };
+    let size = metadata.len();
+    let mtime_ms = metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0);
+    let path_key = path.to_string_lossy().to_string();
+
+    let tx = conn
+        .transaction()
+        .map_err(|error| format!("Failed to begin history index transaction: {}", error))?;
+    let mut state = load_indexed_file(&tx, &path_key)?.unwrap_or_default();
+    if state.size == size && state.mtime_ms == mtime_ms {
+        return Ok(0);
+    }
+    if size < state.parse_offset || mtime_ms < state.mtime_ms {
+        delete_transcript_rows(&tx, &path_key)?;
+        state = IndexedFile::default();
+    }
+
+    let bytes = read_from_offset(path, state.parse_offset).unwrap_or_default();
+    let consumed = bytes
+        .iter()
+        .rposition(|byte| *byte == b'\n')
+        .map(|index| index + 1)
+        .unwrap_or(0);
+    let mut messages = Vec::new();
+    for line in bytes[..consumed].split(|byte| *byte == b'\n') {
+        let Ok(value) = serde_json::from_slice::<Value>(line) else {
+            continue;
+        };
+        let message = match source {
+            SOURCE_CLAUDE => parse_claude_line(&value, &mut state),
+            _ => parse_codex_line(&value, &mut state),
+        };
+        messages.extend(message);
+    }
+
+    insert_messages(
+        &tx,
+        &path_key,
+        source,
+        &session_id,
+        state.project.as_deref(),
+        &messages,
+    )?;
+    state.parse_offset += consumed as u64;
+    state.size = size;
+    state.mtime_ms = mtime_ms;
+    store_indexed_file(&tx, &path_key, source, &session_id, &state)?;
+    tx.commit()
+        .map_err(|error| format!("Failed to commit history index transaction: {}", error))?;
+    Ok(messages.len())
+}
+
+/// OpenCode keeps messages in SQLite, so a changed session is re-indexed
+/// whole (keyed by its `updated_at`).
+fn index_opencode_session(
+    conn: &mut Connection,
+    session: &opencode::LocalOpenCodeSession,
+) -> Result<usize, String> {
+    let path_key = format!("{OPENCODE_KEY_PREFIX}{}", session.id);
+    let tx = conn
+        .transaction()
+        .map_err(|error| format!("Failed to begin history index transaction: {}", error))?;
+    if load_indexed_file(&tx, &path_key)?.is_some_and(|state| state.mtime_ms == session.updated_at)
+    {
+        return Ok(0);
+    }
+    let Some(local_messages) = opencode::load_local_messages(&session.id)? else {
+        return Ok(0);
+    };
+
+    let messages = local_messages
+        .into_iter()
+        .filter_map(|message| {
+            let content = message
+                .content
+                .filter(|content| !content.trim().is_empty())?;
+            Some(IndexedMessage {
+                role: message.role,
+                text: truncate_chars(&content, MAX_INDEXED_TEXT_CHARS),
+                model: message.model,
+                tool_names: Vec::new(),
+                timestamp: message.timestamp,
+                file_changes: Vec::new(),
+            })
+        })
+        .collect::<Vec<_>>();
+    delete_transcript_rows(&tx, &path_key)?;
+    insert_messages(
+        &tx,
+        &path_key,
+        SOURCE_OPENCODE,
+        &session.id,
+        session.project.as_deref(),
+        &messages,
+    )?;
+    let state = IndexedFile {
+        mtime_ms: session.updated_at,
+        project: session.project.clone(),
+        ..Default::default()
+    };
+    store_indexed_file(&tx, &path_key, SOURCE_OPENCODE, &session.id, &state)?;
+    tx.commit()
+        .map_err(|error| format!("Failed to commit history index transaction: {}", error))?;
+    Ok(messages.len())
+}
+
+// ============================================================================
+// Transcript line extraction
+// ============================================================================
+
+fn parse_claude_line(value: &Value, state: &mut IndexedFile) -> Option<IndexedMessage> {
+    if state.project.is_none() {
+        state.project = value
+            .get("cwd")
+            .and_then(Value::as_str)
+            .and_then(|cwd| non_empty(Some(cwd)))
+            .map(str::to_string);
+    }
+    let role = value.get("type").and_then(Value::as_str)?;
+    if role != "user" && role != "assistant" {
+        return None;
+    }
+    if value.get("isMeta").and_then(Value::as_bool) == Some(true) {
+        return None;
+    }
+    let message = value.get("message")?;
+    let model = message
+        .get("model")
+        .and_then(Value::as_str)
+        .filter(|model| !model.is_empty() && *model != "<synthetic>")
+        .map(str::to_string);
+    if model.is_some() {
+        state.model = model.clone();
+    }
+
+    let mut parts = Vec::new();
+    let mut tool_names = Vec::new();
+    let mut file_changes = Vec::new();
+    match message.get("content") {
+        Some(Value::String(text)) => {
+            note_conversation_text(state, role, text);
+            parts.push(text.clone());
+        }
+        Some(Value::Array(blocks)) => {
+            for block in blocks {
+                match block.get("type").and_then(Value::as_str) {
+                    Some("text") => {
+                        if let Some(text) = block.get("text").and_then(Value::as_str) {
+                            note_conversation_text(state, role, text);
+                            parts.push(text.to_string());
+                        }
+                    }
+                    Some("tool_use") => {
+                        let name = block.get("name").and_then(Value::as_str);
+                        if let Some(name) = name {
+                            tool_names.push(name.to_string());
+                        }
+                        if let Some(input) = block.get("input") {
+                            if let Some(name) =
+                                name.filter(|name| FILE_WRITING_TOOLS.contains(name))
+                            {
+                                let written = input
+                                    .get("file_path")
+                                    .or_else(|| input.get("notebook_path"))
+                                    .and_then(Value::as_str);
+                                file_changes.extend(file_change(state, name, written));
+                            }
+                            parts.push(truncate_chars(&input.to_string(), MAX_TOOL_TEXT_CHARS));
+                        }
+                    }
+                    Some("tool_result") => {
+                        let text = match block.get("content") {
+                            Some(Value::String(text)) => text.clone(),
+                            Some(Value::Array(items)) => items
+                                .iter()
+                                .filter_map(|item| item.get("text").and_then(Value::as_str))
+                                .collect::<Vec<_>>()
+                                .join("\n"),
+                            _ => String::new(),
+                        };
+                        parts.push(truncate_chars(&text, MAX_TOOL_TEXT_CHARS));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        _ => {}
+    }
+
+    build_message(
+        role,
+        parts,
+        model.or_else(|| state.model.clone()),
+        tool_names,
+        value,
+    )
+    .map(|message| IndexedMessage {
+        file_changes,
+        ..message
+    })
+}
+
+fn parse_codex_line(value: &Value, state: &mut IndexedFile) -> Option<IndexedMessage> {
+    let payload = value.get("payload")?;
+    match value.get("type").and_then(Value::as_str)? {
+        "session_meta" | "turn_context" => {
+            if let Some(cwd) = payload.get("cwd").and_then(Value::as_str) {
+                if state.project.is_none() && !cwd.trim().is_empty() {
+                    state.project = Some(cwd.to_string());
+                }
+            }
+            if let Some(model) = payload.get("model").and_then(Value::as_str) {
+                state.model = Some(model.to_string());
+            }
+            None
+        }
+        "response_item" => match payload.get("type").and_then(Value::as_str)? {
+            "message" => {
+                let role = payload.get("role").and_then(Value::as_str)?;
+                if role != "user" && role != "assistant" {
+                    return None;
+                }
+                let parts = payload
+                    .get("content")
+                    .and_then(Value::as_array)
+                    .map(|items| {
+                        items
+                            .iter()
+                            .filter_map(|item| item.get("text").and_then(Value::as_str))
+                            // Injected environment/instructions blocks are
+                            // identical across sessions and drown real hits.
+                            .filter(|text| {
+                                !text.starts_with("<environment_context>")
+                                    && !text.starts_with("<user_instructions>")
+                            })
+                            .map(str::to_string)
+                            .collect::<Vec<_>>()
+                    })
+                    .unwrap_or_default();
+                for part in &parts {
+                    note_conversation_text(state, role, part);
+                }
+                build_message(role, parts, state.model.clone(), Vec::new(), value)
+            }
+            "function_call" | "custom_tool_call" => {
+                let name = payload.get("name").and_then(Value::as_str)?.to_string();
+                let arguments = payload
+                    .get("arguments")
+                    .or_else(|| payload.get("input"))
+                    .map(|arguments| match arguments {
+                        Value::String(text) => text.clone(),
+                        other => other.to_string(),
+                    })
+                    .unwrap_or_default();
+                // `apply_patch` arrives as its own tool or inside a shell
+                // command; either way the patch headers name the files.
+                let file_changes = patch_bodies(&arguments)
+                    .iter()
+                    .flat_map(|patch| apply_patch_file_paths(patch))
+                    .filter_map(|path| file_change(state, "apply_patch", Some(path)))
+                    .collect::<Vec<_>>();
+                build_message(
+                    "assistant",
+                    vec![truncate_chars(&arguments, MAX_TOOL_TEXT_CHARS)],
+                    state.model.clone(),
+                    vec![name],
+                    value,
+                )
+                .map(|message| IndexedMessage {
+                    file_changes,
+                    ..message
+                })
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Track the latest user request and assistant reply so file changes can
+/// carry the conversation that led to them.
+fn note_conversation_text(state: &mut IndexedFile, role: &str, text: &str) {
+    let text = text.trim();
+    if text.is_empty() {
+        return;
+    }
+    let text = Some(truncate_chars(text, MAX_CHANGE_CONTEXT_CHARS));
+    if role == "user" {
+        state.last_request = text;
+        state.last_reply = None;
+    } else {
+        state.last_reply = text;
+    }
+}
+
+fn file_change(state: &IndexedFile, tool_name: &str, path: Option<&str>) -> Option<FileChange> {
+    Some(FileChange {
+        tool_name: tool_name.to_string(),
+        file_path: resolve_file_path(state.project.as_deref(), path?)?,
+        request: state.last_request.clone(),
+        explanation: state.last_reply.clone(),
+    })
+}
+
+/// Patch bodies inside tool-call arguments: the raw text for a custom
+/// `apply_patch` tool, or any JSON string (e.g. a shell argv entry) that
+/// holds one.
+fn patch_bodies(arguments: &str) -> Vec<String> {
+    fn collect(value: &Value, bodies: &mut Vec<String>) {
+        match value {
+            Value::String(text) if text.contains("*** Begin Patch") => bodies.push(text.clone()),
+            Value::Array(items) => items.iter().for_each(|item| collect(item, bodies)),
+            Value::Object(map) => map.values().for_each(|item| collect(item, bodies)),
+            _ => {}
+        }
+    }
+    let mut bodies = Vec::new();
+    match serde_json::from_str::<Value>(arguments) {
+        Ok(value) => collect(&value, &mut bodies),
+        Err(_) => collect(&Value::String(arguments.to_string()), &mut bodies),
+    }
+    bodies
+}
+
+/// Absolute, lexically normalized form of a tool-call path; relative paths
+/// are resolved against the session's working directory.
+fn resolve_file_path(project: Option<&str>, path: &str) -> Option<String> {
+    let path = Path::new(path.trim());
+    if path.as_os_str().is_empty() {
+        return None;
+    }
+    let absolute = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        Path::new(project?).join(path)
+    };
+    let mut normalized = PathBuf::new();
+    for component in absolute.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    Some(normalized.to_string_lossy().to_string())
+}
+
+fn build_message(
+    role: &str,
+    parts: Vec<String>,
+    model: Option<String>,
+    tool_names: Vec<String>,
+    line: &Value,
+) -> Option<IndexedMessage> {
+    let text = parts
+        .iter()
+        .map(|part| part.trim())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n");
+    if text.is_empty() && tool_names.is_empty() {
+        return None;
+    }
+    Some(IndexedMessage {
+        role: role.to_string(),
+        text: truncate_chars(&text, MAX_INDEXED_TEXT_CHARS),
+        model,
+        tool_names,
+        file_changes: Vec::new(),
+        timestamp: line
+            .get("timestamp")
+            .and_then(Value::as_str)
+            .and_then(|raw| chrono::DateTime::parse_from_rfc3339(raw).ok())
+            .map(|timestamp| timestamp.timestamp_millis().max(0) as u64)
+            .unwrap_or(0),
+    })
+}
+
+// ============================================================================
+// Query + snippet helpers
+// ============================================================================
+
+fn is_cjk(ch: char) -> bool {
+    matches!(
+        ch,
+        '\u{3040}'..='\u{30FF}'
+            | '\u{3400}'..='\u{4DBF}'
+            | '\u{4E00}'..='\u{9FFF}'
+            | '\u{AC00}'..='\u{D7AF}'
+            | '\u{F900}'..='\u{FAFF}'
+    )
+}
+
+/// unicode61 treats a run of CJK characters as one token, so "别名" would
+/// never match inside "修复别名配置". Index (and query) every CJK character
+/// as its own token; quoted phrases then match consecutive characters.
+fn segment_cjk(text: &str) -> String {
+    let mut segmented = String::with_capacity(text.len());
+    let mut previous_cjk = false;
+    for ch in text.chars() {
+        let cjk = is_cjk(ch);
+        if (cjk || previous_cjk)
+            && !ch.is_whitespace()
+            && !segmented.is_empty()
+            && !segmented.ends_with(char::is_whitespace)
+        {
+            segmented.push(' ');
+        }
+        segmented.push(ch);
+        previous_cjk = cjk;
+    }
+    segmented
+}
+
+/// Undo `segment_cjk` for display: drop a single space between two CJK
+/// characters, looking through snippet sentinels.
+fn unsegment_cjk(text: &str) -> String {
+    let chars = text.chars().collect::<Vec<_>>();
+    let is_marker = |ch: char| ch == SNIPPET_OPEN || ch == SNIPPET_CLOSE;
+    let mut result = String::with_capacity(text.len());
+    for (index, ch) in chars.iter().enumerate() {
+        if *ch == ' ' {
+            let before = chars[..index].iter().rev().find(|ch| !is_marker(**ch));
+            let after = chars[index + 1..].iter().find(|ch| !is_marker(**ch));
+            if before.is_some_and(|ch| is_cjk(*ch)) && after.is_some_and(|ch| is_cjk(*ch)) {
+                continue;
+            }
+        }
+        result.push(*ch);
+    }
+    result
+}
+
+/// Turn user input into an FTS5 MATCH expression: `"quoted phrases"` stay
+/// phrases, every other word is matched as a literal term (so `-`, `:` and
+/// friends never reach the FTS5 parser), and a trailing `*` keeps prefix
+/// matching. All parts must match.
+fn build_match_expression(query: &str) -> Option<String> {
+    let mut parts = Vec::new();
+    let mut rest = query.trim();
+    while !rest.is_empty() {
+        if let Some(quoted) = rest.strip_prefix('"') {
+            let end = quoted.find('"').unwrap_or(quoted.len());
+            parts.push((quoted[..end].to_string(), false));
+            rest = quoted.get(end + 1..).unwrap_or("").trim_start();
+            continue;
+        }
+        let end = rest
+            .find(|ch: char| ch.is_whitespace() || ch == '"')
+            .unwrap_or(rest.len());
+        let word = &rest[..end];
+        match word.strip_suffix('*') {
+            Some(prefix) => parts.push((prefix.to_string(), true)),
+            None => parts.push((word.to_string(), false)),
+        }
+        rest = rest[end..].trim_start();
+    }
+
+    let terms = parts
+        .into_iter()
+        .filter(|(text, _)| text.chars().any(char::is_alphanumeric))
+        .map(|(text, prefix)| {
+            let quoted = format!("\"{}\"", segment_cjk(text.trim()).replace('"', "\"\""));
+            if prefix {
+                format!("{quoted}*")
+            } else {
+                quoted
+            }
+        })
+        .collect::<Vec<_>>();
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+fn render_snippet_html(snippet: &str) -> String {
+    let mut html = String::with_capacity(snippet.len() + 16);
+    for ch in unsegment_cjk(snippet).chars() {
+        match ch {
+            SNIPPET_OPEN => html.push_str("<mark>"),
+            SNIPPET_CLOSE => html.push_str("</mark>"),
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            _ => html.push(ch),
+        }
+    }
+    html
+}
+
+// ============================================================================
+// Small helpers
+// ============================================================================
+
+fn non_empty(value: Option<&str>) -> Option<&str> {
+    value.map(str::trim).filter(|value| !value.is_empty())
+}
+
+fn truncate_chars(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((index, _)) => text[..index].to_string(),
+        None => text.to_string(),
+    }
+}
+
+fn parse_date_bound(value: Option<&str>, label: &str) -> Result<Option<NaiveDate>, String> {
+    match non_empty(value) {
+        Some(value) => NaiveDate::parse_from_str(value, "%Y-%m-%d")
+            .map(Some)
+            .map_err(|_| format!("Invalid {label} '{value}'. Use YYYY-MM-DD.")),
+        None => Ok(None),
+    }
+}
+
+fn local_day_start_ms(date: NaiveDate) -> u64 {
+    date.and_hms_opt(0, 0, 0)
+        .and_then(|start| Local.from_local_datetime(&start).earliest())
+        .map(|start| start.timestamp_millis().max(0) as u64)
+        .unwrap_or(0)
+}
+
+fn read_from_offset(path: &Path, offset: u64) -> Option<Vec<u8>> {
+    let mut file = fs::File::open(path).ok()?;
+    file.seek(SeekFrom::Start(offset)).ok()?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes).ok()?;
+    Some(bytes)
+}
+
+pub(crate) fn collect_jsonl_files(root: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut stack = vec![root.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                stack.push(path);
+            } else if path.extension().and_then(|ext| ext.to_str()) == Some("jsonl") {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+/// Claude transcripts are `<session-id>.jsonl` (subagents live under
+/// `<session-id>/subagents/`); Codex rollouts end in the session UUID.
+pub(crate) fn transcript_session_id(source: &str, path: &Path) -> Option<String> {
+    let stem = path.file_stem()?.to_str()?;
+    if source == SOURCE_CODEX {
+        return stem.get(stem.len().checked_sub(36)?..).map(str::to_string);
+    }
+    let parent = path.parent()?;
+    if parent.file_name().and_then(|name| name.to_str()) == Some("subagents") {
+        return parent.parent()?.file_name()?.to_str().map(str::to_string);
+    }
+    Some(stem.to_string())
+}
+
+// ============================================================================
+// Tauri commands
+// ============================================================================
+
+/// Refresh the shared index unless it was refreshed within
+/// `INDEX_REFRESH_TTL`.
+fn refresh_if_stale(index: &HistoryIndex) -> Result<(), String> {
+    let mut refreshed_at = INDEX_REFRESHED_AT
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if refreshed_at.is_none_or(|at| at.elapsed() >= INDEX_REFRESH_TTL) {
+        index.refresh(&TranscriptRoots::discover())?;
+        *refreshed_at = Some(Instant::now());
+    }
+    Ok(())
+}
+
+/// Full-text search over every transcript message. The index is refreshed
+/// incrementally before searching (at most once per `INDEX_REFRESH_TTL`).
+#[tauri::command]
+pub async fn search_history_fulltext(
+    query: String,
+    filters: Option<HistoryFullTextFilters>,
+    limit: Option<usize>,
+) -> Result<Vec<HistoryFullTextHit>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let index = HistoryIndex::default();
+        refresh_if_stale(&index)?;
+        index.search(
+            &query,
+            &filters.unwrap_or_default(),
+            limit.unwrap_or(DEFAULT_SEARCH_LIMIT),
+        )
+    })
+    .await
+    .map_err(|error| format!("Blocking history search task failed: {error}"))?
+}
+
+/// Which sessions wrote `path` (relative to `project`, or absolute), newest
+/// first, with the request and reasoning behind each change.
+#[tauri::command]
+pub async fn get_file_history(
+    project: String,
+    path: String,
+    limit: Option<usize>,
+) -> Result<Vec<FileHistoryEntry>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let index = HistoryIndex::default();
+        refresh_if_stale(&index)?;
+        index.file_history(&project, &path, limit.unwrap_or(DEFAULT_FILE_HISTORY_LIMIT))
+    })
+    .await
+    .map_err(|error| format!("Blocking file history task failed: {error}"))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        build_match_expression, render_snippet_html, resolve_file_path, segment_cjk,
+        HistoryFullTextFilters, HistoryIndex, TranscriptRoots,
+    };
+    use std::fs;
+    use std::io::Write;
+
+    fn claude_line(kind: &str, timestamp: &str, content: serde_json::Value) -> String {
+        serde_json::json!({
+            "type": kind,
+            "timestamp": timestamp,
+            "cwd": "/Users/g/webapp",
+            "message": {
+                "role": kind,
+                "model": "claude-sonnet-4-5",
+                "content": content,
+            },
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn match_expression_quotes_terms_and_keeps_phrases() {
+        assert_eq!(
+            build_match_expression(r#"webpack "resolve alias" vite.config* AND"#).as_deref(),
+            Some(r#""webpack" "resolve alias" "vite.config"* "AND""#)
+        );
+        assert_eq!(
+            build_match_expression("修复别名").as_deref(),
+            Some("\"修 复 别 名\"")
+        );
+        assert_eq!(build_match_expression("  - : "), None);
+    }
+
+    #[test]
+    fn snippet_html_escapes_text_and_restores_cjk() {
+        let snippet = format!(
+            "<div> 修 {}复 别{} 名 & more",
+            super::SNIPPET_OPEN,
+            super::SNIPPET_CLOSE
+        );
+        assert_eq!(
+            render_snippet_html(&snippet),
+            "&lt;div&gt; 修<mark>复别</mark>名 &amp; more"
+        );
+        assert_eq!(segment_cjk("fix 别名 alias"), "fix 别 名 alias");
+    }
+
+    #[test]
+    fn history_index_feeds_incrementally_and_filters() {
+        let temp = tempfile::tempdir().expect("history index tempdir");
+        let projects = temp.path().join("projects").join("-Users-g-webapp");
+        fs::create_dir_all(&projects).expect("create projects dir");
+        let transcript = projects.join("ses-webpack.jsonl");
+        let lines = [
+            claude_line(
+                "user",
+                "2026-03-07T10:00:00Z",
+                serde_json::json!("Why does the webpack alias for @shared break?"),
+            ),
+            claude_line(
+                "assistant",
+                "2026-03-07T10:00:05Z",
+                serde_json::json!([
+                    {"type": "text", "text": "The resolve alias points at the old path."},
+                    {"type": "tool_use", "name": "Edit", "input": {"file_path": "webpack.config.js"}},
+                ]),
+            ),
+        ];
+        fs::write(&transcript, format!("{}\n{}\n", lines[0], lines[1])).expect("write transcript");
+
+        let index = HistoryIndex::new(temp.path().join("state.sqlite"));
+        let roots = TranscriptRoots {
+            claude_projects: Some(temp.path().join("projects")),
+            codex_sessions: None,
+            include_opencode: false,
+        };
+        assert_eq!(index.refresh(&roots).expect("first refresh"), 2);
+        assert_eq!(index.refresh(&roots).expect("unchanged refresh"), 0);
+
+        let filters = HistoryFullTextFilters::default();
+        let hits = index
+            .search("\"resolve alias\"", &filters, 10)
+            .expect("phrase search");
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].session_id, "ses-webpack");
+        assert_eq!(hits[0].project.as_deref(), Some("/Users/g/webapp"));
+        assert_eq!(hits[0].tool_names, vec!["Edit".to_string()]);
+        assert!(hits[0].snippet_html.contains("<mark>resolve"));
+
+        // Appended lines are picked up without re-indexing earlier ones; the
+        // trailing partial line waits for its newline.
+        let appended = claude_line(
+            "user",
+            "2026-03-09T09:00:00Z",
+            serde_json::json!("webpack alias fixed, thanks"),
+        );
+        let mut file = fs::OpenOptions::new()
+            .append(true)
+            .open(&transcript)
+            .expect("open transcript");
+        write!(file, "{appended}\n{{\"type\":\"user\"").expect("append");
+        drop(file);
+        assert_eq!(index.refresh(&roots).expect("append refresh"), 1);
+
+        let all = index.search("webpack", &filters, 10).expect("term search");
+        assert_eq!(all.len(), 3);
+
+        let dated = HistoryFullTextFilters {
+            since: Some("2026-03-08".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(index.search("webpack", &dated, 10).expect("dated").len(), 1);
+
+        let by_tool = HistoryFullTextFilters {
+            tool: Some("Edit".to_string()),
+            project: Some("/Users/g/webapp/".to_string()),
+            model: Some("sonnet".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            index.search("webpack", &by_tool, 10).expect("tool").len(),
+            1
+        );
+
+        let other_project = HistoryFullTextFilters {
+            project: Some("/Users/g/web".to_string()),
+            ..Default::default()
+        };
+        assert!(index
+            .search("webpack", &other_project, 10)
+            .expect("project")
+            .is_empty());
+    }
+
+    #[test]
+    fn file_history_traces_edits_back_to_their_sessions() {
+        let temp = tempfile::tempdir().expect("file history tempdir");
+        let projects = temp.path().join("projects").join("-Users-g-webapp");
+        fs::create_dir_all(&projects).expect("create projects dir");
+        let claude = [
+            claude_line(
+                "user",
+                "2026-03-07T10:00:00Z",
+                serde_json::json!("Add a /health route to the router"),
+            ),
+            claude_line(
+                "assistant",
+                "2026-03-07T10:00:05Z",
+                serde_json::json!([
+                    {"type": "text", "text": "I'll register the route next to /status."},
+                    {"type": "tool_use", "name": "Edit", "input": {"file_path": "/Users/g/webapp/src/router.ts"}},
+                ]),
+            ),
+        ];
+        fs::write(
+            projects.join("ses-health.jsonl"),
+            format!("{}\n{}\n", claude[0], claude[1]),
+        )
+        .expect("write claude transcript");
+
+        let codex_dir = temp.path().join("codex").join("2026").join("03").join("08");
+        fs::create_dir_all(&codex_dir).expect("create codex dir");
+        let patch =
+            "*** Begin Patch\n*** Update File: src/router.ts\n@@\n-old\n+new\n*** End Patch";
+        let codex = [
+            serde_json::json!({"type": "session_meta", "payload": {"cwd": "/Users/g/webapp"}}),
+            serde_json::json!({"type": "response_item", "timestamp": "2026-03-08T09:00:00Z",
+                "payload": {"type": "message", "role": "user",
+                    "content": [{"type": "input_text", "text": "Rename /health to /healthz"}]}}),
+            serde_json::json!({"type": "response_item", "timestamp": "2026-03-08T09:00:04Z",
+                "payload": {"type": "function_call", "name": "shell",
+                    "arguments": serde_json::json!({"command": ["apply_patch", patch]}).to_string()}}),
+        ];
+        fs::write(
+            codex_dir
+                .join("rollout-2026-03-08T09-00-00-0199a1b2-c3d4-7e5f-8a9b-0c1d2e3f4a5b.jsonl"),
+            codex
+                .iter()
+                .map(|line| format!("{line}\n"))
+                .collect::<String>(),
+        )
+        .expect("write codex transcript");
+
+        let index = HistoryIndex::new(temp.path().join("state.sqlite"));
+        index
+            .refresh(&TranscriptRoots {
+                claude_projects: Some(temp.path().join("projects")),
+                codex_sessions: Some(temp.path().join("codex")),
+                include_opencode: false,
+            })
+            .expect("refresh");
+
+        let history = index
+            .file_history("/Users/g/webapp", "./src/router.ts", 10)
+            .expect("file history");
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].source, "codex");
+        assert_eq!(history[0].tool_name, "apply_patch");
+        assert_eq!(history[0].file_path, "/Users/g/webapp/src/router.ts");
+        assert_eq!(
+            history[0].request.as_deref(),
+            Some("Rename /health to /healthz")
+        );
+        assert_eq!(history[1].session_id, "ses-health");
+        assert_eq!(history[1].tool_name, "Edit");
+        assert_eq!(
+            history[1].explanation.as_deref(),
+            Some("I'll register the route next to /status.")
+        );
+
+        let absolute = index
+            .file_history("", "/Users/g/webapp/src/router.ts", 1)
+            .expect("absolute file history");
+        assert_eq!(absolute.len(), 1);
+        assert_eq!(
+            resolve_file_path(Some("/Users/g/webapp"), "../api/./index.ts").as_deref(),
+            Some("/Users/g/api/index.ts")
+        );
+    }
+}
@@ -2,6 +2,7 @@ use crate::event_bus::{
     InteractiveToolPrompt, SessionEventPayload, ToolCategory, ToolQuestionOption,
     ToolQuestionPrompt, UserInputKind,
 };
+use crate::session_titles::FILE_WRITING_TOOLS;
 use chrono::{DateTime, Utc};
 use serde_json::Value;
 use std::collections::HashMap;
@@ -268,6 +269,9 @@ fn parse_assistant_line(
                             raw_name: raw_name.to_string(),
                             input_summary: summarize_tool_input(raw_name, &input),
                             needs_response,
+                            input: FILE_WRITING_TOOLS
+                                .contains(&raw_name)
+                                .then(|| input.clone()),
                             prompt,
                             todo_snapshot: None,
                         });
@@ -0,0 +1,521 @@
+// apps/desktop/src-tauri/src/launch_presets.rs
+//
+// Named launch presets for native sessions ("frontend-kimi-dev",
+// "review-opus-readonly") plus per-project default presets.
+// Persisted to ~/.ccem/launch_presets.json through `LockedJsonStore`.
+//
+// A preset only fills in what a launch leaves unset: explicit createSession
+// params, Telegram `/new` tokens and cron task fields always win.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::AppHandle;
+
+use crate::external_control::is_known_perm_mode;
+use crate::locked_json_store::LockedJsonStore;
+use crate::native_runtime::{NativeProvider, RouterLaunchDraft};
+
+const MAX_PRESET_NAME_LEN: usize = 64;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct LaunchPreset {
+    pub name: String,
+    pub provider: NativeProvider,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub perm_mode: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub runtime_perm_mode: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub effort: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub working_dir: Option<String>,
+    /// Prepended to the launch prompt, e.g. standing review instructions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt_prefix: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub router_launch_draft: Option<RouterLaunchDraft>,
+    #[serde(default)]
+    pub updated_at: u64,
+}
+
+impl LaunchPreset {
+    /// Trims optional fields to `None` and rejects values a launch would
+    /// refuse later, so a broken preset fails when it is saved rather than
+    /// when a cron task or Telegram topic first uses it.
+    fn normalized(mut self) -> Result<Self, String> {
+        self.name = self.name.trim().to_string();
+        validate_preset_name(&self.name)?;
+        for field in [
+            &mut self.env_name,
+            &mut self.perm_mode,
+            &mut self.runtime_perm_mode,
+            &mut self.effort,
+            &mut self.working_dir,
+            &mut self.prompt_prefix,
+        ] {
+            *field = field
+                .take()
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty());
+        }
+        for mode in [&self.perm_mode, &self.runtime_perm_mode]
+            .into_iter()
+            .flatten()
+        {
+            if !is_known_perm_mode(mode) {
+                return Err(format!("Invalid permission mode '{}' in preset", mode));
+            }
+        }
+        if let Some(dir) = self.working_dir.as_deref() {
+            if !Path::new(dir).is_absolute() {
+                return Err(format!(
+                    "Invalid working directory '{}': presets require an absolute path",
+                    dir
+                ));
+            }
+        }
+        if self.provider != NativeProvider::Claude && self.router_launch_draft.is_some() {
+            return Err(
+                "ROUTER_PROVIDER_UNSUPPORTED: dynamic routing is only available for Claude presets"
+                    .to_string(),
+            );
+        }
+        Ok(self)
+    }
+
+    /// The mode a launch without a separate runtime mode should start in:
+    /// the preset's runtime mode when it sets one, else its permission mode.
+    pub fn launch_perm_mode(&self) -> Option<&str> {
+        self.runtime_perm_mode
+            .as_deref()
+            .or(self.perm_mode.as_deref())
+    }
+
+    /// Joins the preset's prompt prefix with the launch prompt.
+    pub fn compose_prompt(&self, prompt: Option<&str>) -> Option<String> {
+        let prompt = prompt.map(str::trim).filter(|value| !value.is_empty());
+        match (self.prompt_prefix.as_deref(), prompt) {
+            (Some(prefix), Some(prompt)) => Some(format!("{}\n\n{}", prefix, prompt)),
+            (Some(prefix), None) => Some(prefix.to_string()),
+            (None, prompt) => prompt.map(str::to_string),
+        }
+    }
+}
+
+/// Preset names appear in Telegram `key=value` tokens and tray menu ids, so
+/// they stay within a conservative character set.
+fn validate_preset_name(name: &str) -> Result<(), String> {
+    if name.is_empty() {
+        return Err("Preset name cannot be empty".to_string());
+    }
+    if name.len() > MAX_PRESET_NAME_LEN {
+        return Err(format!(
+            "Preset name cannot exceed {} characters",
+            MAX_PRESET_NAME_LEN
+        ));
+    }
+    if !name
+        .chars()
+        .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_' | '.'))
+    {
+        return Err(format!(
+            "Invalid preset name '{}': use letters, digits, '-', '_' or '.'",
+            name
+        ));
+    }
+    Ok(())
+}
+
+fn normalize_project_dir(dir: &str) -> String {
+    let trimmed = dir.trim();
+    if trimmed.len() > 1 {
+        trimmed.trim_end_matches('/').to_string()
+    } else {
+        trimmed.to_string()
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LaunchPresets {
+    #[serde(default)]
+    presets: BTreeMap<String, LaunchPreset>,
+    /// Project directory -> preset name used when a launch in that project
+    /// (or below it) names no preset.
+    #[serde(default)]
+    project_defaults: BTreeMap<String, String>,
+}
+
+impl LaunchPresets {
+    fn store() -> LockedJsonStore {
+        LockedJsonStore::new("launch_presets.json")
+    }
+
+    /// Acquire an exclusive lock and load from disk.
+    fn load_locked() -> std::io::Result<(Self, fs::File)> {
+        Self::store().load_locked()
+    }
+
+    /// Load without locking, for launch paths that only read presets.
+    pub fn load() -> Self {
+        Self::store().load()
+    }
+
+    /// Caller must hold the lock.
+    fn save_locked(&self) -> std::io::Result<()> {
+        Self::store().save_locked(self)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.presets.keys().map(String::as_str)
+    }
+
+    pub fn get(&self, name: &str) -> Result<&LaunchPreset, String> {
+        self.presets
+            .get(name.trim())
+            .ok_or_else(|| format!("Unknown launch preset '{}'", name.trim()))
+    }
+
+    /// The default preset of the closest configured ancestor of `dir`. A
+    /// launch that already names a provider only picks up a default for that
+    /// provider, so a Codex project default never turns a Claude launch into
+    /// a Codex one.
+    pub fn default_for_project(&self, dir: &str, provider: Option<&str>) -> Option<&LaunchPreset> {
+        let dir = normalize_project_dir(dir);
+        let dir = Path::new(&dir);
+        self.project_defaults
+            .iter()
+            .filter(|(project, _)| dir.starts_with(Path::new(project.as_str())))
+            .max_by_key(|(project, _)| project.len())
+            .and_then(|(_, name)| self.presets.get(name))
+            .filter(|preset| provider.is_none_or(|provider| preset.provider.as_str() == provider))
+    }
+
+    /// An explicitly named preset, else the project default for
+    /// `project_dir` that matches `provider`.
+    pub fn resolve(
+        &self,
+        name: Option<&str>,
+        project_dir: Option<&str>,
+        provider: Option<&str>,
+    ) -> Result<Option<&LaunchPreset>, String> {
+        match name.map(str::trim).filter(|value| !value.is_empty()) {
+            Some(name) => self.get(name).map(Some),
+            None => Ok(project_dir.and_then(|dir| self.default_for_project(dir, provider))),
+        }
+    }
+
+    fn upsert(&mut self, preset: LaunchPreset) -> Result<LaunchPreset, String> {
+        let mut preset = preset.normalized()?;
+        preset.updated_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        self.presets.insert(preset.name.clone(), preset.clone());
+        Ok(preset)
+    }
+
+    fn remove(&mut self, name: &str) {
+        self.presets.remove(name);
+        self.project_defaults.retain(|_, preset| preset != name);
+    }
+
+    fn set_project_default(
+        &mut self,
+        project_dir: &str,
+        preset: Option<&str>,
+    ) -> Result<(), String> {
+        let project_dir = normalize_project_dir(project_dir);
+        if !Path::new(&project_dir).is_absolute() {
+            return Err(format!(
+                "Invalid project directory '{}': expected an absolute path",
+                project_dir
+            ));
+        }
+        match preset.map(str::trim).filter(|value| !value.is_empty()) {
+            Some(name) => {
+                self.get(name)?;
+                self.project_defaults.insert(project_dir, name.to_string());
+            }
+            None => {
+                self.project_defaults.remove(&project_dir);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Fills the unset fields of `ccem.workspace.createSession` params from the
+/// named preset, or from the project default for the requested `cwd` and
+/// provider. Runs
+/// before authorization so token and session-caller checks see the values
+/// that will actually be launched.
+pub fn expand_create_session_params(params: &mut Value) -> Result<(), String> {
+    if params.is_null() {
+        *params = Value::Object(Map::new());
+    }
+    let Some(object) = params.as_object_mut() else {
+        return Ok(());
+    };
+    let name = match object.remove("preset") {
+        None | Some(Value::Null) => None,
+        Some(Value::String(name)) => Some(name),
+        Some(_) => return Err("Invalid params: preset must be a string".to_string()),
+    };
+    let project_dir = object
+        .get("cwd")
+        .or_else(|| object.get("workingDir"))
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    let provider = object
+        .get("provider")
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(str::to_string);
+    let presets = LaunchPresets::load();
+    let Some(preset) =
+        presets.resolve(name.as_deref(), project_dir.as_deref(), provider.as_deref())?
+    else {
+        return Ok(());
+    };
+    apply_preset_to_params(preset, object);
+    Ok(())
+}
+
+fn param_is_unset(object: &Map<String, Value>, key: &str) -> bool {
+    object
+        .get(key)
+        .is_none_or(|value| value.is_null() || value.as_str() == Some(""))
+}
+
+fn fill_param(object: &mut Map<String, Value>, key: &str, value: Option<&String>) {
+    if let Some(value) = value {
+        if param_is_unset(object, key) {
+            object.insert(key.to_string(), Value::String(value.clone()));
+        }
+    }
+}
+
+fn apply_preset_to_params(preset: &LaunchPreset, object: &mut Map<String, Value>) {
+    let provider = preset.provider.as_str().to_string();
+    fill_param(object, "provider", Some(&provider));
+    fill_param(object, "envName", preset.env_name.as_ref());
+    fill_param(object, "permissionMode", preset.perm_mode.as_ref());
+    fill_param(
+        object,
+        "runtimePermissionMode",
+        preset.runtime_perm_mode.as_ref(),
+    );
+    fill_param(object, "effort", preset.effort.as_ref());
+    if param_is_unset(object, "workingDir") {
+        fill_param(object, "cwd", preset.working_dir.as_ref());
+    }
+    let has_router_params = [
+        "routerLaunchDraft",
+        "routes",
+        "allowedEnvs",
+        "dynamicRouting",
+    ]
+    .iter()
+    .any(|key| !param_is_unset(object, key));
+    if let (false, Some(draft)) = (has_router_params, preset.router_launch_draft.as_ref()) {
+        if let Ok(draft) = serde_json::to_value(draft) {
+            object.insert("routerLaunchDraft".to_string(), draft);
+        }
+    }
+    let prompt = object.get("prompt").and_then(Value::as_str);
+    if let Some(prompt) = preset.compose_prompt(prompt) {
+        object.insert("prompt".to_string(), Value::String(prompt));
+    }
+}
+
+fn refresh_tray(app: &AppHandle) {
+    if let Err(error) = crate::tray::rebuild_tray_menu(app) {
+        eprintln!("Failed to rebuild tray menu: {}", error);
+    }
+}
+
+#[tauri::command]
+pub async fn list_launch_presets() -> Result<LaunchPresets, String> {
+    tauri::async_runtime::spawn_blocking(LaunchPresets::load)
+        .await
+        .map_err(|e| format!("Task error: {}", e))
+}
+
+#[tauri::command]
+pub async fn save_launch_preset(
+    app: AppHandle,
+    preset: LaunchPreset,
+) -> Result<LaunchPreset, String> {
+    let saved = tauri::async_runtime::spawn_blocking(move || {
+        let (mut presets, _lock) =
+            LaunchPresets::load_locked().map_err(|e| format!("Failed to acquire lock: {}", e))?;
+        let saved = presets.upsert(preset)?;
+        presets
+            .save_locked()
+            .map_err(|e| format!("Failed to save launch preset: {}", e))?;
+        Ok::<_, String>(saved)
+    })
+    .await
+    .map_err(|e| format!("Task error: {}", e))??;
+    refresh_tray(&app);
+    Ok(saved)
+}
+
+#[tauri::command]
+pub async fn delete_launch_preset(app: AppHandle, name: String) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let (mut presets, _lock) =
+            LaunchPresets::load_locked().map_err(|e| format!("Failed to acquire lock: {}", e))?;
+        presets.remove(name.trim());
+        presets
+            .save_locked()
+            .map_err(|e| format!("Failed to save launch presets: {}", e))
+    })
+    .await
+    .map_err(|e| format!("Task error: {}", e))??;
+    refresh_tray(&app);
+    Ok(())
+}
+
+/// Sets (or clears, with `preset: null`) the default preset for a project.
+#[tauri::command]
+pub async fn set_project_default_launch_preset(
+    project_dir: String,
+    preset: Option<String>,
+) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let (mut presets, _lock) =
+            LaunchPresets::load_locked().map_err(|e| format!("Failed to acquire lock: {}", e))?;
+        presets.set_project_default(&project_dir, preset.as_deref())?;
+        presets
+            .save_locked()
+            .map_err(|e| format!("Failed to save launch presets: {}", e))
+    })
+    .await
+    .map_err(|e| format!("Task error: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn preset(name: &str) -> LaunchPreset {
+        LaunchPreset {
+            name: name.to_string(),
+            provider: NativeProvider::Claude,
+            env_name: Some("kimi".to_string()),
+            perm_mode: Some("dev".to_string()),
+            runtime_perm_mode: None,
+            effort: None,
+            working_dir: None,
+            prompt_prefix: None,
+            router_launch_draft: None,
+            updated_at: 0,
+        }
+    }
+
+    #[test]
+    fn upsert_normalizes_and_rejects_invalid_presets() {
+        let mut presets = LaunchPresets::default();
+        let mut draft = preset(" frontend-kimi-dev ");
+        draft.effort = Some("  ".to_string());
+        let saved = presets.upsert(draft).expect("valid preset");
+        assert_eq!(saved.name, "frontend-kimi-dev");
+        assert_eq!(saved.effort, None);
+
+        assert!(presets.upsert(preset("has space")).is_err());
+        let mut bad_mode = preset("bad-mode");
+        bad_mode.perm_mode = Some("root".to_string());
+        assert!(presets.upsert(bad_mode).is_err());
+        let mut relative = preset("relative");
+        relative.working_dir = Some("src".to_string());
+        assert!(presets.upsert(relative).is_err());
+        let mut routed_codex = preset("routed-codex");
+        routed_codex.provider = NativeProvider::Codex;
+        routed_codex.router_launch_draft = Some(RouterLaunchDraft::default());
+        assert!(presets.upsert(routed_codex).is_err());
+    }
+
+    #[test]
+    fn project_defaults_resolve_to_the_closest_ancestor() {
+        let mut presets = LaunchPresets::default();
+        presets.upsert(preset("outer")).expect("outer");
+        presets.upsert(preset("inner")).expect("inner");
+        presets
+            .set_project_default("/work/mono/", Some("outer"))
+            .expect("outer default");
+        presets
+            .set_project_default("/work/mono/web", Some("inner"))
+            .expect("inner default");
+        assert!(presets
+            .set_project_default("/work/x", Some("missing"))
+            .is_err());
+
+        let resolved = |dir| {
+            presets
+                .resolve(None, Some(dir), None)
+                .expect("resolve")
+                .map(|preset| preset.name.as_str())
+        };
+        assert_eq!(resolved("/work/mono/web/src"), Some("inner"));
+        assert_eq!(resolved("/work/mono/api"), Some("outer"));
+        assert_eq!(resolved("/work/monorepo"), None);
+        assert_eq!(
+            presets
+                .resolve(Some("outer"), Some("/work/mono/web"), Some("codex"))
+                .expect("explicit")
+                .map(|preset| preset.name.as_str()),
+            Some("outer")
+        );
+        let for_provider = |provider| {
+            presets
+                .resolve(None, Some("/work/mono/api"), Some(provider))
+                .expect("resolve")
+                .map(|preset| preset.name.as_str())
+        };
+        assert_eq!(for_provider("claude"), Some("outer"));
+        assert_eq!(for_provider("codex"), None);
+
+        presets.remove("inner");
+        assert_eq!(
+            presets
+                .resolve(None, Some("/work/mono/web"), None)
+                .expect("resolve")
+                .map(|preset| preset.name.as_str()),
+            Some("outer")
+        );
+    }
+
+    #[test]
+    fn preset_fills_only_unset_create_session_params() {
+        let mut review = preset("review-opus-readonly");
+        review.perm_mode = Some("readonly".to_string());
+        review.working_dir = Some("/work/app".to_string());
+        review.prompt_prefix = Some("Review only; do not edit files.".to_string());
+        let mut params = json!({
+            "prompt": "check the auth module",
+            "envName": "opus",
+            "permissionMode": null,
+        });
+        apply_preset_to_params(&review, params.as_object_mut().expect("object"));
+
+        assert_eq!(params["provider"], "claude");
+        assert_eq!(params["envName"], "opus");
+        assert_eq!(params["permissionMode"], "readonly");
+        assert_eq!(params["cwd"], "/work/app");
+        assert_eq!(
+            params["prompt"],
+            "Review only; do not edit files.\n\ncheck the auth module"
+        );
+    }
+}
@@ -0,0 +1,158 @@
+// apps/desktop/src-tauri/src/locked_json_store.rs
+//
+// Small JSON documents under ~/.ccem that the UI, the tray and background
+// jobs all update (title overrides, launch presets, ...). Read-modify-write
+// cycles hold an exclusive lock on a sibling `.lock` file, and saves go
+// through write-to-tmp + rename.
+//
+// A document that no longer parses is moved aside to `<name>.corrupt-<secs>`
+// before the locked load starts from an empty value, so the next save cannot
+// silently overwrite what was there.
+
+use fs2::FileExt;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs::{self, OpenOptions};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::get_ccem_dir;
+
+pub struct LockedJsonStore {
+    path: PathBuf,
+}
+
+impl LockedJsonStore {
+    /// A document named `file_name` in the CCEM data directory.
+    pub fn new(file_name: &str) -> Self {
+        Self::at(get_ccem_dir().join(file_name))
+    }
+
+    pub fn at(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    fn lock_path(&self) -> PathBuf {
+        let mut name = self.path.file_name().unwrap_or_default().to_os_string();
+        name.push(".lock");
+        self.path.with_file_name(name)
+    }
+
+    /// Acquire the exclusive lock and load the document. The lock is held
+    /// until the returned file is dropped.
+    pub fn load_locked<T: DeserializeOwned + Default>(&self) -> std::io::Result<(T, fs::File)> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let lock_file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(self.lock_path())?;
+
+        lock_file.lock_exclusive()?;
+
+        let data = match fs::read_to_string(&self.path) {
+            Ok(content) => match serde_json::from_str(&content) {
+                Ok(data) => data,
+                Err(error) => {
+                    let backup = self.back_up_corrupt()?;
+                    eprintln!(
+                        "{} could not be parsed ({}); moved it to {}",
+                        self.path.display(),
+                        error,
+                        backup.display()
+                    );
+                    T::default()
+                }
+            },
+            Err(error) if error.kind() == ErrorKind::NotFound => T::default(),
+            Err(error) => return Err(error),
+        };
+
+        Ok((data, lock_file))
+    }
+
+    /// Load without locking, for paths that only read. A corrupt document
+    /// reads as empty and is left in place for the next locked load.
+    pub fn load<T: DeserializeOwned + Default>(&self) -> T {
+        match fs::read_to_string(&self.path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|error| {
+                eprintln!("{} could not be parsed: {}", self.path.display(), error);
+                T::default()
+            }),
+            Err(_) => T::default(),
+        }
+    }
+
+    /// Atomic write: write-to-tmp + rename. Caller must hold the lock.
+    pub fn save_locked<T: Serialize>(&self, value: &T) -> std::io::Result<()> {
+        let json = serde_json::to_string_pretty(value)
+            .map_err(|e| std::io::Error::new(ErrorKind::InvalidData, e))?;
+        let tmp = self.path.with_extension("tmp");
+        fs::write(&tmp, json)?;
+        fs::rename(tmp, &self.path)
+    }
+
+    fn back_up_corrupt(&self) -> std::io::Result<PathBuf> {
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let backup = corrupt_backup_path(&self.path, secs);
+        fs::rename(&self.path, &backup)?;
+        Ok(backup)
+    }
+}
+
+fn corrupt_backup_path(path: &Path, secs: u64) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".corrupt-{}", secs));
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LockedJsonStore;
+    use std::collections::BTreeMap;
+    use std::fs;
+
+    #[test]
+    fn corrupt_documents_are_backed_up_before_the_next_save() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("presets.json");
+        fs::write(&path, "{ not json").expect("write corrupt file");
+        let store = LockedJsonStore::at(path.clone());
+
+        assert!(store.load::<BTreeMap<String, String>>().is_empty());
+        assert!(path.exists());
+
+        let (mut data, _lock) = store
+            .load_locked::<BTreeMap<String, String>>()
+            .expect("locked load");
+        assert!(data.is_empty());
+        data.insert("a".to_string(), "b".to_string());
+        store.save_locked(&data).expect("save");
+
+        let backups = fs::read_dir(dir.path())
+            .expect("read dir")
+            .filter_map(Result::ok)
+            .filter(|entry| {
+                entry
+                    .file_name()
+                    .to_string_lossy()
+                    .starts_with("presets.json.corrupt-")
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(backups.len(), 1);
+        assert_eq!(
+            fs::read_to_string(backups[0].path()).expect("backup"),
+            "{ not json"
+        );
+        assert_eq!(store.load::<BTreeMap<String, String>>()["a"], "b");
+        assert!(dir.path().join("presets.json.lock").exists());
+    }
+}
@@ -11,6 +11,8 @@ mod channel;
 mod codex_migration;
 mod companion;
 mod config;
+mod control_tokens;
+mod conversation_export;
 mod cron;
 mod crypto;
 mod desktop_instance_lock;
@@ -20,14 +22,23 @@ mod doctor;
 mod event_bus;
 mod event_dispatcher;
 mod external_control;
+mod git_worktree;
 mod history;
+mod history_index;
 mod interactive_runtime;
 #[cfg(test)]
 mod ipc_isolation_tests;
 mod jsonl_watcher;
+mod launch_presets;
+mod locked_json_store;
 mod native_event_log;
+mod native_event_retention;
+mod native_fanout;
 mod native_helper_resource;
+mod native_limits;
 mod native_runtime;
+mod native_session_archive;
+mod native_session_tree;
 mod notifications;
 mod opencode;
 mod permission;
@@ -42,10 +53,14 @@ mod runtime;
 mod secure_fs;
 mod session;
 mod session_annotations;
+mod session_continuation;
+mod session_digest;
 mod session_provenance;
 mod session_titles;
+mod sessions_mcp;
 mod skills;
 mod slash_commands;
+mod storage_manager;
 mod system_proxy;
 mod telegram;
 mod terminal;
@@ -80,15 +95,17 @@ use event_dispatcher::EventDispatcher;
 use external_control::ExternalControlManager;
 use history::{
     get_conversation_detail, get_conversation_history, get_conversation_messages,
-    get_conversation_segments, get_session_subagents, get_workspace_overview_snapshot,
-    search_conversation_history,
+    get_conversation_segments, get_history_clusters, get_session_subagents,
+    get_workspace_overview_snapshot, search_conversation_history,
 };
 use interactive_runtime::{
     InteractiveReplayBatch, InteractiveRuntimeManager, InteractiveSessionOptions,
 };
+use native_limits::NativeSessionLimits;
 use native_runtime::{
     validate_router_create_selection, InteractivePromptAnnotation, NativeHandoffResult,
-    NativeProvider, NativeRuntimeManager, NativeSessionOptions, NativeSessionSummary, PromptImage,
+    NativeProvider, NativeQueuedPrompt, NativeRecoveryCandidate, NativeRuntimeManager,
+    NativeSessionOptions, NativeSessionSummary, NativeWorktreeAction, PromptImage,
     RouterLaunchDraft,
 };
 use opencode::{snapshot_known_session_ids, track_launched_session};
@@ -99,8 +116,8 @@ use proxy_debug::{
 use remote::RemotePlatform;
 use router::{
     rename_router_config_environment, router_config_environment_references, validate_router_config,
-    RouterConfig, RouterManager, RouterServiceError, RouterStatus, SessionRouterPatch,
-    SessionRouterState, UpdateSessionRouterRequest,
+    RouterConfig, RouterManager, RouterServiceError, RouterStatus, SessionRouterState,
+    UpdateSessionRouterRequest,
 };
 use runtime::{
     cleanup_orphaned_runtime_processes, clear_runtime_recovery_candidates_by_claude_session_id,
@@ -1319,6 +1336,8 @@ async fn create_native_session(
     perm_mode: Option<String>,
     runtime_perm_mode: Option<String>,
     working_dir: Option<String>,
+    use_git_worktree: Option<bool>,
+    limits: Option<NativeSessionLimits>,
     initial_prompt: String,
     initial_display_prompt: Option<String>,
     initial_images: Option<Vec<PromptImage>>,
@@ -1327,136 +1346,155 @@ async fn create_native_session(
     effort: Option<String>,
     seed_boundary_message_count: Option<u64>,
     fork_from_message_id: Option<String>,
+    fork_parent_runtime_id: Option<String>,
     router_launch_draft: Option<RouterLaunchDraft>,
     resume_router_from_runtime_id: Option<String>,
     codex_migration_proof_token: Option<String>,
+    continued_from: Option<session_continuation::SessionContinuationSource>,
 ) -> Result<NativeSessionSummary, String> {
-    let mutation_guard = environment_mutations.lock()?;
-    let provider = parse_native_provider(&provider)?;
-    validate_router_create_selection(
-        router_launch_draft.as_ref(),
-        resume_router_from_runtime_id.as_deref(),
-    )?;
-    if fork_from_message_id.is_some() {
-        if provider != NativeProvider::Claude {
-            return Err(
-                "FORK_PROVIDER_UNSUPPORTED: forking from a message is only available for Claude sessions"
-                    .to_string(),
-            );
+    let native_state = native_state.inner().clone();
+    let environment_mutations = environment_mutations.inner().clone();
+    let fork_parent_lookup = Arc::clone(&native_state);
+    // A worktree launch runs `git worktree add`; keep that and the environment
+    // lock off the async runtime.
+    let (summary, fork_link) = tauri::async_runtime::spawn_blocking(move || {
+        let _mutation_guard = environment_mutations.lock()?;
+        let provider = parse_native_provider(&provider)?;
+        validate_router_create_selection(
+            router_launch_draft.as_ref(),
+            resume_router_from_runtime_id.as_deref(),
+        )?;
+        if fork_from_message_id.is_some() {
+            if provider != NativeProvider::Claude {
+                return Err(
+                    "FORK_PROVIDER_UNSUPPORTED: forking from a message is only available for Claude sessions"
+                        .to_string(),
+                );
+            }
+            if provider_session_id.as_deref().map(str::trim).unwrap_or("").is_empty() {
+                return Err(
+                    "FORK_MISSING_PARENT: forking requires provider_session_id of the parent session"
+                        .to_string(),
+                );
+            }
         }
-        if provider_session_id.as_deref().map(str::trim).unwrap_or("").is_empty() {
+        let fork_from_message_id = fork_from_message_id.filter(|value| !value.trim().is_empty());
+        let fork_link = fork_from_message_id
+            .clone()
+            .zip(provider_session_id.clone())
+            .filter(|_| provider == NativeProvider::Claude);
+        if provider != NativeProvider::Claude && router_launch_draft.is_some() {
             return Err(
-                "FORK_MISSING_PARENT: forking requires provider_session_id of the parent session"
+                "ROUTER_PROVIDER_UNSUPPORTED: dynamic routing is only available for Claude sessions"
                     .to_string(),
             );
         }
-    }
-    let fork_from_message_id = fork_from_message_id.filter(|value| !value.trim().is_empty());
-    if provider != NativeProvider::Claude && router_launch_draft.is_some() {
-        return Err(
-            "ROUTER_PROVIDER_UNSUPPORTED: dynamic routing is only available for Claude sessions"
-                .to_string(),
-        );
-    }
-    let effective_working_dir = resolve_headless_working_dir(working_dir);
-    let verified_codex_path = if provider == NativeProvider::Codex {
-        codex_migration::runtime_path_for_verified_launch(
-            &env_name,
-            &effective_working_dir,
-            codex_migration_proof_token.as_deref(),
-        )?
-    } else {
-        None
-    };
-    let resumed_router_record = resume_router_from_runtime_id
-        .as_deref()
-        .map(|source_runtime_id| {
-            native_state.clone_router_record_for_history_resume(
-                source_runtime_id,
-                provider,
-                provider_session_id.as_deref(),
+        let effective_working_dir = resolve_headless_working_dir(working_dir);
+        let verified_codex_path = if provider == NativeProvider::Codex {
+            codex_migration::runtime_path_for_verified_launch(
+                &env_name,
                 &effective_working_dir,
-            )
-        })
-        .transpose()?;
-    let effective_perm_mode = resolve_effective_perm_mode(perm_mode);
-    let effective_runtime_perm_mode = runtime_perm_mode
-        .map(|mode| mode.trim().to_string())
-        .filter(|mode| !mode.is_empty() && mode != &effective_perm_mode);
-    let initial_images = initial_images.filter(|images| !images.is_empty());
-
-    let options = match provider {
-        NativeProvider::Claude => {
-            let effective_env_name = resumed_router_record
-                .as_ref()
-                .map(|router| router.default_env.as_str())
-                .unwrap_or(&env_name);
-            let resolved = resolve_claude_env(effective_env_name)?;
-            NativeSessionOptions {
-                provider,
-                env_name: resolved.env_name,
-                perm_mode: effective_perm_mode,
-                runtime_perm_mode: effective_runtime_perm_mode.clone(),
-                working_dir: effective_working_dir,
-                initial_prompt: Some(prepend_write_tool_limit_system_tip(
-                    &initial_prompt,
-                    resolved.limit_write_tools,
-                )),
-                display_prompt: initial_display_prompt.clone(),
-                initial_images: initial_images.clone(),
-                initial_annotations: initial_annotations.clone(),
-                provider_session_id: provider_session_id.clone(),
-                fork_from_message_id,
-                seed_boundary_message_count,
-                helper_env_vars: resolved.env_vars.clone(),
-                terminal_env_vars: resolved.env_vars,
-                claude_path: terminal::resolve_claude_path(),
-                codex_path: None,
-                codex_base_url: None,
-                codex_api_key: None,
-                effort: effort.clone(),
-                router_launch_draft,
-                router_record: resumed_router_record,
+                codex_migration_proof_token.as_deref(),
+            )?
+        } else {
+            None
+        };
+        let resumed_router_record = resume_router_from_runtime_id
+            .as_deref()
+            .map(|source_runtime_id| {
+                native_state.clone_router_record_for_history_resume(
+                    source_runtime_id,
+                    provider,
+                    provider_session_id.as_deref(),
+                    &effective_working_dir,
+                )
+            })
+            .transpose()?;
+        let effective_perm_mode = resolve_effective_perm_mode(perm_mode);
+        let effective_runtime_perm_mode = runtime_perm_mode
+            .map(|mode| mode.trim().to_string())
+            .filter(|mode| !mode.is_empty() && mode != &effective_perm_mode);
+        let initial_images = initial_images.filter(|images| !images.is_empty());
+
+        let options = match provider {
+            NativeProvider::Claude => {
+                let effective_env_name = resumed_router_record
+                    .as_ref()
+                    .map(|router| router.default_env.as_str())
+                    .unwrap_or(&env_name);
+                let resolved = resolve_claude_env(effective_env_name)?;
+                NativeSessionOptions {
+                    provider,
+                    env_name: resolved.env_name,
+                    perm_mode: effective_perm_mode,
+                    runtime_perm_mode: effective_runtime_perm_mode.clone(),
+                    working_dir: effective_working_dir,
+                    use_git_worktree: use_git_worktree.unwrap_or(false),
+                    limits: limits.clone(),
+                    initial_prompt: Some(prepend_write_tool_limit_system_tip(
+                        &initial_prompt,
+                        resolved.limit_write_tools,
+                    )),
+                    display_prompt: initial_display_prompt.clone(),
+                    initial_images: initial_images.clone(),
+                    initial_annotations: initial_annotations.clone(),
+                    provider_session_id: provider_session_id.clone(),
+                    fork_from_message_id,
+                    seed_boundary_message_count,
+                    helper_env_vars: resolved.env_vars.clone(),
+                    terminal_env_vars: resolved.env_vars,
+                    claude_path: terminal::resolve_claude_path(),
+                    codex_path: None,
+                    codex_base_url: None,
+                    codex_api_key: None,
+                    effort: effort.clone(),
+                    router_launch_draft,
+                    router_record: resumed_router_record,
+                }
             }
-        }
-        NativeProvider::Codex => {
-            let resolved = resolve_codex_runtime(&env_name)?;
-            let proxy_env_vars = system_proxy::resolve_codex_proxy_env();
-            NativeSessionOptions {
-                provider,
-                env_name: if resolved.env_name.is_empty() {
-                    env_name
-                } else {
-                    resolved.env_name
-                },
-                perm_mode: effective_perm_mode,
-                runtime_perm_mode: effective_runtime_perm_mode,
-                working_dir: effective_working_dir,
-                initial_prompt: Some(prepend_write_tool_limit_system_tip(
-                    &initial_prompt,
-                    resolved.limit_write_tools,
-                )),
-                display_prompt: initial_display_prompt.clone(),
-                initial_images: initial_images.clone(),
-                initial_annotations: initial_annotations.clone(),
-                provider_session_id: provider_session_id.clone(),
-                fork_from_message_id: None,
-                seed_boundary_message_count,
-                helper_env_vars: proxy_env_vars.clone(),
-                terminal_env_vars: proxy_env_vars,
-                claude_path: None,
-                codex_path: verified_codex_path.or_else(terminal::resolve_codex_path),
-                codex_base_url: None,
-                codex_api_key: None,
-                effort,
-                router_launch_draft: None,
-                router_record: None,
+            NativeProvider::Codex => {
+                let resolved = resolve_codex_runtime(&env_name)?;
+                let proxy_env_vars = system_proxy::resolve_codex_proxy_env();
+                NativeSessionOptions {
+                    provider,
+                    env_name: if resolved.env_name.is_empty() {
+                        env_name
+                    } else {
+                        resolved.env_name
+                    },
+                    perm_mode: effective_perm_mode,
+                    runtime_perm_mode: effective_runtime_perm_mode,
+                    working_dir: effective_working_dir,
+                    use_git_worktree: use_git_worktree.unwrap_or(false),
+                    limits: limits.clone(),
+                    initial_prompt: Some(prepend_write_tool_limit_system_tip(
+                        &initial_prompt,
+                        resolved.limit_write_tools,
+                    )),
+                    display_prompt: initial_display_prompt.clone(),
+                    initial_images: initial_images.clone(),
+                    initial_annotations: initial_annotations.clone(),
+                    provider_session_id: provider_session_id.clone(),
+                    fork_from_message_id: None,
+                    seed_boundary_message_count,
+                    helper_env_vars: proxy_env_vars.clone(),
+                    terminal_env_vars: proxy_env_vars,
+                    claude_path: None,
+                    codex_path: verified_codex_path.or_else(terminal::resolve_codex_path),
+                    codex_base_url: None,
+                    codex_api_key: None,
+                    effort,
+                    router_launch_draft: None,
+                    router_record: None,
+                }
             }
-        }
-    };
+        };
 
-    let summary = native_state.create_session(app, options)?;
-    drop(mutation_guard);
+        let summary = native_state.create_session(app, options)?;
+        Ok::<_, String>((summary, fork_link))
+    })
+    .await
+    .map_err(|error| format!("Blocking native session launch task failed: {error}"))??;
 
     if let Err(error) = register_launch(SessionProvenanceUpsert {
         ccem_session_id: summary.runtime_id.clone(),
@@ -1474,6 +1512,38 @@ async fn create_native_session(
             summary.runtime_id, error
         );
     }
+    if let Some(parent) = continued_from {
+        if let Err(error) = session_provenance::record_continuation(
+            &summary.runtime_id,
+            &parent.source,
+            &parent.session_id,
+        ) {
+            eprintln!(
+                "Failed to link continued session {} to {}:{}: {}",
+                summary.runtime_id, parent.source, parent.session_id, error
+            );
+        }
+    }
+    if let Some((fork_message_id, parent_session_id)) = fork_link {
+        let parent_runtime_id = fork_parent_runtime_id
+            .filter(|runtime_id| !runtime_id.trim().is_empty())
+            .or_else(|| {
+                fork_parent_lookup
+                    .runtime_for_provider_session(&parent_session_id, &summary.runtime_id)
+            });
+        if let Err(error) = session_provenance::record_fork(
+            &summary.runtime_id,
+            parent_runtime_id.as_deref(),
+            &parent_session_id,
+            Some(&fork_message_id),
+            seed_boundary_message_count,
+        ) {
+            eprintln!(
+                "Failed to record fork of {} from {}: {}",
+                summary.runtime_id, parent_session_id, error
+            );
+        }
+    }
 
     Ok(summary)
 }
@@ -1493,6 +1563,20 @@ fn get_native_session_summary(
     native_state.get_session_summary(&runtime_id)
 }
 
+#[tauri::command]
+async fn apply_native_session_worktree_action(
+    native_state: State<'_, Arc<NativeRuntimeManager>>,
+    runtime_id: String,
+    action: NativeWorktreeAction,
+) -> Result<NativeSessionSummary, String> {
+    let native_state = native_state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        native_state.apply_worktree_action(&runtime_id, action)
+    })
+    .await
+    .map_err(|error| format!("Blocking worktree action task failed: {error}"))?
+}
+
 #[tauri::command]
 fn send_native_session_input(
     app: tauri::AppHandle,
@@ -1515,6 +1599,32 @@ fn send_native_session_input(
     )
 }
 
+#[tauri::command]
+fn list_native_session_prompt_queue(
+    native_state: State<'_, Arc<NativeRuntimeManager>>,
+    runtime_id: String,
+) -> Result<Vec<NativeQueuedPrompt>, String> {
+    native_state.list_queued_prompts(&runtime_id)
+}
+
+#[tauri::command]
+fn reorder_native_session_prompt_queue(
+    native_state: State<'_, Arc<NativeRuntimeManager>>,
+    runtime_id: String,
+    item_ids: Vec<String>,
+) -> Result<Vec<NativeQueuedPrompt>, String> {
+    native_state.reorder_queued_prompts(&runtime_id, &item_ids)
+}
+
+#[tauri::command]
+fn cancel_native_session_queued_prompt(
+    native_state: State<'_, Arc<NativeRuntimeManager>>,
+    runtime_id: String,
+    item_id: String,
+) -> Result<Vec<NativeQueuedPrompt>, String> {
+    native_state.cancel_queued_prompt(&runtime_id, &item_id)
+}
+
 #[tauri::command]
 fn respond_native_session_permission(
     native_state: State<'_, Arc<NativeRuntimeManager>>,
@@ -1605,73 +1715,11 @@ fn update_native_session_settings(
     // whole operation even when the environment itself is unchanged so a
     // reconnect cannot snapshot router defaults while an environment is deleted.
     let _environment_mutation_guard = environment_mutations.lock()?;
-    let current = native_state
-        .list_sessions()
-        .into_iter()
-        .find(|session| session.runtime_id == runtime_id)
-        .ok_or_else(|| format!("Native runtime {} not found", runtime_id))?;
-    if let (Some(name), Some(router)) = (env_name.as_deref(), current.router.as_ref()) {
-        if !name.trim().is_empty() {
-            let mut allowed_envs = router.allowed_envs.clone();
-            if !allowed_envs.iter().any(|allowed| allowed == name) {
-                allowed_envs.push(name.to_string());
-            }
-            native_state
-                .update_session_router(
-                    &app,
-                    UpdateSessionRouterRequest {
-                        runtime_id: runtime_id.clone(),
-                        expected_revision: router.revision,
-                        patch: SessionRouterPatch {
-                            default_env: Some(name.to_string()),
-                            allowed_envs: Some(allowed_envs),
-                            ..SessionRouterPatch::default()
-                        },
-                    },
-                    "environment",
-                )
-                .map_err(|error| error.to_string())?;
-            if perm_mode.is_none() && effort.is_none() {
-                return Ok(());
-            }
-            return native_state.update_session_settings(
-                &app,
-                &runtime_id,
-                None,
-                perm_mode.as_deref(),
-                None,
-                effort.as_deref(),
-                force_restart.unwrap_or(false),
-            );
-        }
-    }
-    let (resolved_env_name, env_vars) = match env_name.as_deref() {
-        Some(name) if !name.trim().is_empty() => match current.provider {
-            NativeProvider::Claude => {
-                let resolved = resolve_claude_env(name)?;
-                (Some(resolved.env_name), Some(resolved.env_vars))
-            }
-            NativeProvider::Codex => {
-                let resolved = resolve_codex_runtime(name)?;
-                let resolved_name = if resolved.env_name.is_empty() {
-                    name.to_string()
-                } else {
-                    resolved.env_name
-                };
-                (
-                    Some(resolved_name),
-                    Some(system_proxy::resolve_codex_proxy_env()),
-                )
-            }
-        },
-        _ => (None, None),
-    };
-    native_state.update_session_settings(
+    native_state.update_session_settings_by_env_name(
         &app,
         &runtime_id,
-        resolved_env_name.as_deref(),
+        env_name.as_deref(),
         perm_mode.as_deref(),
-        env_vars.as_ref(),
         effort.as_deref(),
         force_restart.unwrap_or(false),
     )
@@ -1689,6 +1737,66 @@ fn set_native_session_runtime_perm_mode(
     native_state.update_session_runtime_perm_mode(&app, &runtime_id, runtime_perm_mode.as_deref())
 }
 
+#[tauri::command]
+fn set_native_session_limits(
+    native_state: State<'_, Arc<NativeRuntimeManager>>,
+    runtime_id: String,
+    limits: Option<NativeSessionLimits>,
+) -> Result<NativeSessionSummary, String> {
+    native_state.set_session_limits(&runtime_id, limits)
+}
+
+#[tauri::command]
+fn set_native_session_limit_defaults(limits: Option<NativeSessionLimits>) -> Result<(), String> {
+    if let Some(limits) = limits.as_ref() {
+        limits.validate()?;
+    }
+    config::update_settings(|settings| {
+        settings.native_session_limits = limits;
+    })?;
+    Ok(())
+}
+
+#[tauri::command]
+fn list_native_recovery_candidates(
+    native_state: State<'_, Arc<NativeRuntimeManager>>,
+) -> Result<Vec<NativeRecoveryCandidate>, String> {
+    native_state.list_recovery_candidates()
+}
+
+#[tauri::command]
+fn resume_native_session(
+    app: tauri::AppHandle,
+    native_state: State<'_, Arc<NativeRuntimeManager>>,
+    runtime_id: String,
+) -> Result<NativeSessionSummary, String> {
+    native_state.resume_recovered_session(app, &runtime_id)
+}
+
+#[tauri::command]
+fn dismiss_native_recovery_candidate(
+    native_state: State<'_, Arc<NativeRuntimeManager>>,
+    runtime_id: String,
+) -> Result<(), String> {
+    native_state.dismiss_recovery_candidate(&runtime_id)
+}
+
+#[tauri::command]
+fn set_native_session_auto_resume(enabled: bool) -> Result<(), String> {
+    config::update_settings(|settings| {
+        settings.auto_resume_native_sessions = enabled;
+    })?;
+    Ok(())
+}
+
+#[tauri::command]
+fn set_native_sessions_mcp_enabled(enabled: bool) -> Result<(), String> {
+    config::update_settings(|settings| {
+        settings.native_sessions_mcp_enabled = enabled;
+    })?;
+    Ok(())
+}
+
 #[tauri::command]
 fn stop_native_session(
     native_state: State<'_, Arc<NativeRuntimeManager>>,
@@ -5325,7 +5433,11 @@ fn main() {
             create_native_session,
             list_native_sessions,
             get_native_session_summary,
+            apply_native_session_worktree_action,
             send_native_session_input,
+            list_native_session_prompt_queue,
+            reorder_native_session_prompt_queue,
+            cancel_native_session_queued_prompt,
             respond_native_session_permission,
             respond_native_session_prompt,
             rewind_native_session_files,
@@ -5335,6 +5447,13 @@ fn main() {
             update_native_session_settings,
             restart_native_session_direct,
             set_native_session_runtime_perm_mode,
+            set_native_session_limits,
+            set_native_session_limit_defaults,
+            list_native_recovery_candidates,
+            resume_native_session,
+            dismiss_native_recovery_candidate,
+            set_native_session_auto_resume,
+            set_native_sessions_mcp_enabled,
             stop_native_session,
             stop_native_background_task,
             handoff_native_session_to_terminal,
@@ -5366,6 +5485,9 @@ fn main() {
             get_usage_history,
             get_usage_model_breakdown,
             get_continuous_usage_days,
+            analytics::export_usage_report,
+            analytics::get_usage_reconciliation,
+            analytics::get_cache_insights,
             check_ccem_installed,
             check_claude_installed,
             check_codex_installed,
@@ -5378,12 +5500,37 @@ fn main() {
             get_conversation_detail,
             get_conversation_history,
             get_workspace_overview_snapshot,
+            get_history_clusters,
             get_conversation_messages,
             get_conversation_segments,
             get_session_subagents,
             search_conversation_history,
+            history_index::search_history_fulltext,
+            history_index::get_file_history,
+            conversation_export::export_conversation,
+            storage_manager::get_storage_usage,
+            storage_manager::apply_storage_retention,
+            storage_manager::list_archived_sessions,
             title_overrides::set_session_title,
+            launch_presets::list_launch_presets,
+            launch_presets::save_launch_preset,
+            launch_presets::delete_launch_preset,
+            launch_presets::set_project_default_launch_preset,
             session_titles::generate_workspace_session_title,
+            session_titles::generate_session_tags,
+            session_continuation::prepare_session_continuation,
+            session_digest::get_session_digest,
+            native_fanout::create_native_fanout,
+            native_fanout::list_native_fanouts,
+            native_fanout::get_native_fanout_comparison,
+            native_session_archive::export_native_session,
+            native_session_archive::import_native_session,
+            native_session_tree::get_session_tree,
+            native_session_tree::compare_session_branches,
+            native_fanout::adopt_native_fanout_branch,
+            native_fanout::discard_native_fanout,
+            native_event_retention::compact_native_event_log,
+            native_event_retention::set_native_event_log_retention,
             session_annotations::set_session_annotation,
             session_annotations::clear_session_annotation,
             slash_commands::list_workspace_commands,
@@ -5441,6 +5588,10 @@ fn main() {
             router_status,
             get_session_router,
             update_session_router,
+            control_tokens::list_control_tokens,
+            control_tokens::create_control_token,
+            control_tokens::revoke_control_token,
+            control_tokens::get_control_token_audit,
             get_proxy_debug_state,
             set_proxy_debug_enabled,
             update_proxy_debug_config,
@@ -5517,6 +5668,19 @@ fn main() {
                     Ok(_) => {}
                     Err(error) => eprintln!("Native runtime reconcile warning: {}", error),
                 }
+                if config::read_settings()
+                    .map(|settings| settings.auto_resume_native_sessions)
+                    .unwrap_or(false)
+                {
+                    let manager = native_runtime_manager.clone();
+                    let app_handle = app.handle().clone();
+                    std::thread::spawn(move || {
+                        let resumed = manager.resume_recovery_candidates(app_handle);
+                        if resumed > 0 {
+                            eprintln!("Resumed {} interrupted native session(s)", resumed);
+                        }
+                    });
+                }
                 if let Err(error) = interactive_manager_for_setup
                     .rehydrate_existing(app.handle().clone(), session_manager_for_setup.clone())
                 {
@@ -5600,6 +5764,8 @@ fn main() {
 
             if automatic_background_services_enabled {
                 start_session_monitor(app.handle().clone(), session_manager_for_setup.clone());
+                native_runtime_manager.start_limit_watchdog();
+                native_runtime_manager.start_event_log_maintenance();
 
                 let cron_app = app.handle().clone();
                 start_cron_scheduler(cron_app, cron_scheduler, unified_session_manager.clone());
@@ -3,7 +3,8 @@ use crate::session_provenance::state_db_path;
 use crate::workspace_decorations::AttentionSummary;
 use chrono::{DateTime, Utc};
 use rusqlite::{params, Connection, OptionalExtension};
-use std::collections::HashMap;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::path::PathBuf;
 use std::sync::Mutex;
@@ -11,6 +12,40 @@ use std::time::Duration;
 
 const EVENT_LOG_FLUSH_BATCH_SIZE: usize = 64;
 const ATTENTION_SUMMARY_TAIL_FALLBACK_LIMIT: u64 = 2000;
+const LAST_COMPACTED_AT_KEY: &str = "last_compacted_at";
+const LAST_VACUUM_AT_KEY: &str = "last_vacuum_at";
+
+/// Stored size of one runtime's event history.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuntimeEventStorage {
+    pub runtime_id: String,
+    pub event_count: u64,
+    pub payload_bytes: u64,
+    pub newest_seq: u64,
+    pub newest_occurred_at: Option<DateTime<Utc>>,
+    /// Newest seq at the time the runtime was last compacted.
+    pub compacted_through_seq: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NativeEventLogStats {
+    /// Size of the shared state database file, which also holds the history
+    /// index, provenance and archive manifest; `payload_bytes` is the event
+    /// log's own share.
+    pub state_database_bytes: u64,
+    pub state_database_wal_bytes: u64,
+    pub runtime_count: u64,
+    pub event_count: u64,
+    pub payload_bytes: u64,
+    /// Events per event type, e.g. `claude_json`.
+    pub event_count_by_type: BTreeMap<String, u64>,
+    /// Payload bytes per event type.
+    pub payload_bytes_by_type: BTreeMap<String, u64>,
+    pub compacted_runtime_count: u64,
+    pub last_compacted_at: Option<DateTime<Utc>>,
+    pub last_vacuum_at: Option<DateTime<Utc>>,
+}
 
 pub struct NativeEventLog {
     db_path: PathBuf,
@@ -90,8 +125,11 @@ impl NativeEventLog {
         self.flush_pending()?;
         self.with_conn(|conn| {
             let (oldest_available_seq, newest_available_seq) = event_seq_bounds(conn, runtime_id)?;
+            let compacted_through_seq = compacted_through_seq(conn, runtime_id)?;
             let gap_detected = match (since_seq, oldest_available_seq) {
-                (Some(last_seen), Some(oldest)) => last_seen.saturating_add(1) < oldest,
+                (Some(last_seen), Some(oldest)) => {
+                    is_seq_hole(last_seen.saturating_add(1), oldest, compacted_through_seq)
+                }
                 _ => false,
             };
 
@@ -102,6 +140,7 @@ impl NativeEventLog {
                 oldest_available_seq,
                 newest_available_seq,
                 gap_detected,
+                compacted_through_seq,
             );
 
             Ok(ReplayBatch {
@@ -114,6 +153,227 @@ impl NativeEventLog {
         })
     }
 
+    /// Write a whole imported event history for a runtime that has none yet.
+    pub fn import_records(
+        &self,
+        runtime_id: &str,
+        records: &[SessionEventRecord],
+    ) -> Result<(), String> {
+        if let Some(record) = records
+            .iter()
+            .find(|record| record.runtime_id != runtime_id)
+        {
+            return Err(format!(
+                "Imported event for {} does not belong to {}",
+                record.runtime_id, runtime_id
+            ));
+        }
+        if self.has_events(runtime_id)? {
+            return Err(format!(
+                "Native runtime {} already has an event log",
+                runtime_id
+            ));
+        }
+        self.write_records(records)
+    }
+
+    pub fn runtime_storage(&self) -> Result<Vec<RuntimeEventStorage>, String> {
+        self.flush_pending()?;
+        self.with_conn(|conn| {
+            let mut stmt = conn
+                .prepare(
+                    "SELECT e.runtime_id,
+                            COUNT(*),
+                            COALESCE(SUM(LENGTH(CAST(e.payload_json AS BLOB))), 0),
+                            MAX(e.seq),
+                            MAX(e.occurred_at),
+                            MAX(c.compacted_through_seq)
+                     FROM native_session_events e
+                     LEFT JOIN native_event_compactions c ON c.runtime_id = e.runtime_id
+                     GROUP BY e.runtime_id",
+                )
+                .map_err(|error| {
+                    format!("Failed to prepare native event storage query: {}", error)
+                })?;
+            let rows = stmt
+                .query_map([], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, i64>(1)?,
+                        row.get::<_, i64>(2)?,
+                        row.get::<_, i64>(3)?,
+                        row.get::<_, Option<String>>(4)?,
+                        row.get::<_, Option<i64>>(5)?,
+                    ))
+                })
+                .map_err(|error| format!("Failed to query native event storage: {}", error))?;
+            let mut storage = Vec::new();
+            for row in rows {
+                let (runtime_id, count, bytes, newest_seq, newest_at, compacted) =
+                    row.map_err(|error| {
+                        format!("Failed to read native event storage row: {}", error)
+                    })?;
+                storage.push(RuntimeEventStorage {
+                    runtime_id,
+                    event_count: non_negative_i64_to_u64(count).unwrap_or_default(),
+                    payload_bytes: non_negative_i64_to_u64(bytes).unwrap_or_default(),
+                    newest_seq: non_negative_i64_to_u64(newest_seq).unwrap_or_default(),
+                    newest_occurred_at: newest_at.as_deref().and_then(parse_timestamp),
+                    compacted_through_seq: compacted.and_then(non_negative_i64_to_u64),
+                });
+            }
+            Ok(storage)
+        })
+    }
+
+    /// Delete `removed_seqs` and store the new payloads of `updated` in
+    /// place, keeping every seq, then mark the runtime compacted through
+    /// `newest_seq`. Replays tolerate the holes this leaves at or below the
+    /// marker.
+    pub fn rewrite_runtime(
+        &self,
+        runtime_id: &str,
+        removed_seqs: &[u64],
+        updated: &[SessionEventRecord],
+        newest_seq: u64,
+    ) -> Result<(), String> {
+        self.flush_pending()?;
+        self.with_conn(|conn| {
+            let tx = conn.transaction().map_err(|error| {
+                format!("Failed to begin native event log transaction: {}", error)
+            })?;
+            {
+                let mut stmt = tx
+                    .prepare_cached(
+                        "DELETE FROM native_session_events WHERE runtime_id = ?1 AND seq = ?2",
+                    )
+                    .map_err(|error| {
+                        format!("Failed to prepare native session event removal: {}", error)
+                    })?;
+                for seq in removed_seqs {
+                    stmt.execute(params![runtime_id, *seq as i64])
+                        .map_err(|error| {
+                            format!("Failed to remove native session event: {}", error)
+                        })?;
+                }
+            }
+            {
+                let mut stmt = tx
+                    .prepare_cached(
+                        "UPDATE native_session_events
+                         SET payload_json = ?3
+                         WHERE runtime_id = ?1 AND seq = ?2",
+                    )
+                    .map_err(|error| {
+                        format!("Failed to prepare native session event rewrite: {}", error)
+                    })?;
+                for record in updated {
+                    let payload_json = serde_json::to_string(&record.payload).map_err(|error| {
+                        format!("Failed to serialize native event payload: {}", error)
+                    })?;
+                    stmt.execute(params![runtime_id, record.seq as i64, payload_json])
+                        .map_err(|error| {
+                            format!("Failed to rewrite native session event: {}", error)
+                        })?;
+                }
+            }
+            upsert_compaction_marker(&tx, runtime_id, newest_seq)?;
+            tx.commit()
+                .map_err(|error| format!("Failed to commit native event log rewrite: {}", error))?;
+            Ok(())
+        })
+    }
+
+    /// Rebuild the whole state database, not just the event log: the history
+    /// index, provenance and archive manifest tables share the file. Every
+    /// writer, event appends included, waits until it finishes, so callers
+    /// only run it while no native session is live.
+    pub fn vacuum(&self) -> Result<(), String> {
+        self.flush_pending()?;
+        self.with_conn(|conn| {
+            conn.execute_batch(
+                "PRAGMA wal_checkpoint(TRUNCATE);
+                 VACUUM;
+                 PRAGMA wal_checkpoint(TRUNCATE);",
+            )
+            .map_err(|error| format!("Failed to vacuum native event log: {}", error))?;
+            set_maintenance_timestamp(conn, LAST_VACUUM_AT_KEY, Utc::now())
+        })
+    }
+
+    pub fn last_vacuum_at(&self) -> Result<Option<DateTime<Utc>>, String> {
+        self.with_conn(|conn| maintenance_timestamp(conn, LAST_VACUUM_AT_KEY))
+    }
+
+    pub fn record_compaction_run(&self, at: DateTime<Utc>) -> Result<(), String> {
+        self.with_conn(|conn| set_maintenance_timestamp(conn, LAST_COMPACTED_AT_KEY, at))
+    }
+
+    /// Size breakdown of the event log for the doctor report. Sums every
+    /// payload's length, so it is not meant for hot paths.
+    pub fn storage_stats(&self) -> Result<NativeEventLogStats, String> {
+        let storage = self.runtime_storage()?;
+        let state_database_bytes = fs::metadata(&self.db_path)
+            .map(|metadata| metadata.len())
+            .unwrap_or_default();
+        let mut wal_path = self.db_path.clone().into_os_string();
+        wal_path.push("-wal");
+        let state_database_wal_bytes = fs::metadata(PathBuf::from(wal_path))
+            .map(|metadata| metadata.len())
+            .unwrap_or_default();
+        self.with_conn(|conn| {
+            let mut stmt = conn
+                .prepare(
+                    "SELECT COALESCE(payload_type, ''), COUNT(*),
+                            SUM(LENGTH(CAST(payload_json AS BLOB)))
+                     FROM native_session_events
+                     GROUP BY payload_type",
+                )
+                .map_err(|error| {
+                    format!("Failed to prepare native event type size query: {}", error)
+                })?;
+            let rows = stmt
+                .query_map([], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, i64>(1)?,
+                        row.get::<_, i64>(2)?,
+                    ))
+                })
+                .map_err(|error| format!("Failed to query native event type sizes: {}", error))?;
+            let mut event_count_by_type = BTreeMap::new();
+            let mut payload_bytes_by_type = BTreeMap::new();
+            for row in rows {
+                let (payload_type, count, bytes) = row.map_err(|error| {
+                    format!("Failed to read native event type size row: {}", error)
+                })?;
+                event_count_by_type.insert(
+                    payload_type.clone(),
+                    non_negative_i64_to_u64(count).unwrap_or_default(),
+                );
+                payload_bytes_by_type.insert(
+                    payload_type,
+                    non_negative_i64_to_u64(bytes).unwrap_or_default(),
+                );
+            }
+            Ok(NativeEventLogStats {
+                state_database_bytes,
+                state_database_wal_bytes,
+                runtime_count: storage.len() as u64,
+                event_count: storage.iter().map(|runtime| runtime.event_count).sum(),
+                payload_bytes: storage.iter().map(|runtime| runtime.payload_bytes).sum(),
+                event_count_by_type,
+                payload_bytes_by_type,
+                compacted_runtime_count: storage
+                    .iter()
+                    .filter(|runtime| runtime.compacted_through_seq.is_some())
+                    .count() as u64,
+                last_compacted_at: maintenance_timestamp(conn, LAST_COMPACTED_AT_KEY)?,
+                last_vacuum_at: maintenance_timestamp(conn, LAST_VACUUM_AT_KEY)?,
+            })
+        })
+    }
+
     pub fn has_events(&self, runtime_id: &str) -> Result<bool, String> {
         self.flush_pending()?;
         self.with_conn(|conn| runtime_has_events(conn, runtime_id))
@@ -220,6 +480,207 @@ impl NativeEventLog {
         })
     }
 
+    /// When the conversation reached provider message `message_id`: the
+    /// latest lifecycle event that reported it as the assistant message.
+    pub fn assistant_message_time(
+        &self,
+        runtime_id: &str,
+        message_id: &str,
+    ) -> Result<Option<DateTime<Utc>>, String> {
+        self.flush_pending()?;
+        self.with_conn(|conn| {
+            let occurred_at = conn
+                .query_row(
+                    "SELECT occurred_at
+                     FROM native_session_events
+                     WHERE runtime_id = ?1
+                       AND payload_type = 'lifecycle'
+                       AND json_extract(payload_json, '$.assistant_message_uuid') = ?2
+                     ORDER BY seq DESC
+                     LIMIT 1",
+                    params![runtime_id, message_id],
+                    |row| row.get::<_, String>(0),
+                )
+                .optional()
+                .map_err(|error| {
+                    format!("Failed to query native assistant message time: {}", error)
+                })?;
+            occurred_at
+                .map(|occurred_at| {
+                    DateTime::parse_from_rfc3339(&occurred_at)
+                        .map(|occurred_at| occurred_at.with_timezone(&Utc))
+                        .map_err(|error| {
+                            format!("Failed to parse native event timestamp: {}", error)
+                        })
+                })
+                .transpose()
+        })
+    }
+
+    /// Cost from the latest `SessionUsage` report that carried one.
+    pub fn latest_session_cost(&self, runtime_id: &str) -> Result<Option<f64>, String> {
+        self.flush_pending()?;
+        self.with_conn(|conn| {
+            conn.query_row(
+                "SELECT json_extract(payload_json, '$.cost_usd')
+                 FROM native_session_events
+                 WHERE runtime_id = ?1
+                   AND payload_type = 'session_usage'
+                   AND json_extract(payload_json, '$.cost_usd') IS NOT NULL
+                 ORDER BY seq DESC
+                 LIMIT 1",
+                [runtime_id],
+                |row| row.get::<_, f64>(0),
+            )
+            .optional()
+            .map_err(|error| format!("Failed to query native session cost: {}", error))
+        })
+    }
+
+    /// When and why the first turn ended: the first `turn_completed`
+    /// lifecycle event (reason `completed`) or `SessionCompleted`.
+    pub fn first_turn_end(
+        &self,
+        runtime_id: &str,
+    ) -> Result<Option<(DateTime<Utc>, String)>, String> {
+        self.flush_pending()?;
+        self.with_conn(|conn| {
+            let end = conn
+                .query_row(
+                    "SELECT occurred_at,
+                            CASE payload_type
+                                WHEN 'session_completed' THEN json_extract(payload_json, '$.reason')
+                                ELSE 'completed'
+                            END
+                     FROM native_session_events
+                     WHERE runtime_id = ?1
+                       AND (payload_type = 'session_completed'
+                            OR (payload_type = 'lifecycle'
+                                AND json_extract(payload_json, '$.stage') = 'turn_completed'))
+                     ORDER BY seq ASC
+                     LIMIT 1",
+                    [runtime_id],
+                    |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+                )
+                .optional()
+                .map_err(|error| format!("Failed to query native first turn end: {}", error))?;
+            end.map(|(occurred_at, reason)| {
+                DateTime::parse_from_rfc3339(&occurred_at)
+                    .map(|occurred_at| (occurred_at.with_timezone(&Utc), reason))
+                    .map_err(|error| format!("Failed to parse native event timestamp: {}", error))
+            })
+            .transpose()
+        })
+    }
+
+    /// Text of the last non-empty assistant reply: the newest run of
+    /// assistant chunks between prompts and tool calls. Reads backwards from
+    /// the tail and stops at the run's start.
+    pub fn last_assistant_reply(&self, runtime_id: &str) -> Result<Option<String>, String> {
+        self.flush_pending()?;
+        self.with_conn(|conn| {
+            let mut stmt = conn
+                .prepare(
+                    "SELECT payload_json
+                     FROM native_session_events
+                     WHERE runtime_id = ?1
+                       AND payload_type IN ('assistant_chunk', 'user_prompt', 'tool_use_started')
+                     ORDER BY seq DESC",
+                )
+                .map_err(|error| format!("Failed to prepare native last reply query: {}", error))?;
+            let mut rows = stmt
+                .query([runtime_id])
+                .map_err(|error| format!("Failed to query native last reply: {}", error))?;
+
+            // Chunks of the run being read, newest first.
+            let mut run: Vec<String> = Vec::new();
+            let reply = |run: &[String]| {
+                let text = run.iter().rev().map(String::as_str).collect::<String>();
+                Some(text.trim().to_string()).filter(|text| !text.is_empty())
+            };
+            while let Some(row) = rows
+                .next()
+                .map_err(|error| format!("Failed to read native last reply row: {}", error))?
+            {
+                let payload_json = row
+                    .get::<_, String>(0)
+                    .map_err(|error| format!("Failed to read native last reply row: {}", error))?;
+                match serde_json::from_str::<SessionEventPayload>(&payload_json) {
+                    Ok(SessionEventPayload::AssistantChunk { text }) => run.push(text),
+                    // A queued prompt is logged again when it is dispatched.
+                    Ok(SessionEventPayload::UserPrompt { queued: true, .. }) | Err(_) => {}
+                    Ok(_) => {
+                        if let Some(text) = reply(&run) {
+                            return Ok(Some(text));
+                        }
+                        run.clear();
+                    }
+                }
+            }
+            Ok(reply(&run))
+        })
+    }
+
+    /// Every persisted router ledger entry (`RoutedRequest`) across all
+    /// runtimes, oldest first. Usage analytics re-attributes transcript
+    /// usage to the env that actually served each request from these.
+    pub fn routed_request_records(&self) -> Result<Vec<SessionEventRecord>, String> {
+        self.records_with_payload_type("routed_request")
+    }
+
+    /// Every persisted `ContextUsage` snapshot across all runtimes, oldest
+    /// first. Usage analytics derives compactions from these.
+    pub fn context_usage_records(&self) -> Result<Vec<SessionEventRecord>, String> {
+        self.records_with_payload_type("context_usage")
+    }
+
+    fn records_with_payload_type(
+        &self,
+        payload_type: &str,
+    ) -> Result<Vec<SessionEventRecord>, String> {
+        self.flush_pending()?;
+        self.with_conn(|conn| {
+            let mut stmt = conn
+                .prepare(
+                    "SELECT runtime_id, seq, occurred_at, payload_json
+                     FROM native_session_events
+                     WHERE payload_type = ?1
+                     ORDER BY occurred_at ASC, runtime_id ASC, seq ASC",
+                )
+                .map_err(|error| {
+                    format!("Failed to prepare native {} query: {}", payload_type, error)
+                })?;
+            let rows = stmt
+                .query_map([payload_type], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        (
+                            row.get::<_, i64>(1)?,
+                            row.get::<_, String>(2)?,
+                            row.get::<_, String>(3)?,
+                        ),
+                    ))
+                })
+                .map_err(|error| {
+                    format!("Failed to query native {} events: {}", payload_type, error)
+                })?;
+
+            let mut records = Vec::new();
+            for row in rows {
+                let (runtime_id, row) = row.map_err(|error| {
+                    format!(
+                        "Failed to read native {} event row: {}",
+                        payload_type, error
+                    )
+                })?;
+                if let Some(record) = event_row_to_record_lossy(&runtime_id, row) {
+                    records.push(record);
+                }
+            }
+            Ok(records)
+        })
+    }
+
     fn write_records(&self, records: &[SessionEventRecord]) -> Result<(), String> {
         self.with_conn(|conn| {
             let tx = conn.transaction().map_err(|error| {
@@ -312,6 +773,8 @@ impl NativeEventLog {
                      occurred_at TEXT NOT NULL,
                      payload_json TEXT NOT NULL,
                      created_at TEXT NOT NULL,
+                     payload_type TEXT GENERATED ALWAYS AS
+                         (json_extract(payload_json, '$.type')) VIRTUAL,
                      PRIMARY KEY(runtime_id, seq)
                  );
                  CREATE TABLE IF NOT EXISTS native_attention_summaries (
@@ -319,9 +782,19 @@ impl NativeEventLog {
                      summary_json TEXT NOT NULL,
                      updated_at TEXT NOT NULL
                  );
+                 CREATE TABLE IF NOT EXISTS native_event_compactions (
+                     runtime_id TEXT PRIMARY KEY,
+                     compacted_through_seq INTEGER NOT NULL,
+                     compacted_at TEXT NOT NULL
+                 );
+                 CREATE TABLE IF NOT EXISTS native_event_log_maintenance (
+                     key TEXT PRIMARY KEY,
+                     value TEXT NOT NULL
+                 );
                  PRAGMA optimize;",
             )
             .map_err(|error| format!("Failed to initialize native event log schema: {}", error))?;
+            ensure_payload_type_index(&conn)?;
             *guard = Some(conn);
         }
 
@@ -332,6 +805,34 @@ impl NativeEventLog {
     }
 }
 
+/// `payload_type` mirrors the payload's `type` tag so ledger queries across
+/// all runtimes can use an index instead of scanning every payload. Logs
+/// created before the column existed get it added here.
+fn ensure_payload_type_index(conn: &Connection) -> Result<(), String> {
+    let has_column = conn
+        .query_row(
+            "SELECT COUNT(*)
+             FROM pragma_table_xinfo('native_session_events')
+             WHERE name = 'payload_type'",
+            [],
+            |row| row.get::<_, i64>(0),
+        )
+        .map_err(|error| format!("Failed to inspect native event log schema: {}", error))?
+        > 0;
+    if !has_column {
+        conn.execute_batch(
+            "ALTER TABLE native_session_events ADD COLUMN payload_type TEXT
+                 GENERATED ALWAYS AS (json_extract(payload_json, '$.type')) VIRTUAL;",
+        )
+        .map_err(|error| format!("Failed to add native event payload type column: {}", error))?;
+    }
+    conn.execute_batch(
+        "CREATE INDEX IF NOT EXISTS idx_native_session_events_payload_type
+             ON native_session_events(payload_type, occurred_at);",
+    )
+    .map_err(|error| format!("Failed to index native event payload types: {}", error))
+}
+
 fn todo_snapshot_from_payload(payload: &SessionEventPayload) -> Option<&TodoSnapshotV1> {
     match payload {
         SessionEventPayload::ToolUseStarted { todo_snapshot, .. }
@@ -596,6 +1097,67 @@ fn event_row_to_record_lossy(
     }
 }
 
+fn compacted_through_seq(conn: &Connection, runtime_id: &str) -> Result<Option<u64>, String> {
+    let seq = conn
+        .query_row(
+            "SELECT compacted_through_seq FROM native_event_compactions WHERE runtime_id = ?1",
+            [runtime_id],
+            |row| row.get::<_, i64>(0),
+        )
+        .optional()
+        .map_err(|error| format!("Failed to query native event compaction: {}", error))?;
+    Ok(seq.and_then(non_negative_i64_to_u64))
+}
+
+fn upsert_compaction_marker(
+    conn: &Connection,
+    runtime_id: &str,
+    newest_seq: u64,
+) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO native_event_compactions (runtime_id, compacted_through_seq, compacted_at)
+         VALUES (?1, ?2, ?3)
+         ON CONFLICT(runtime_id) DO UPDATE SET
+             compacted_through_seq = excluded.compacted_through_seq,
+             compacted_at = excluded.compacted_at",
+        params![runtime_id, newest_seq as i64, Utc::now().to_rfc3339()],
+    )
+    .map_err(|error| format!("Failed to record native event compaction: {}", error))?;
+    Ok(())
+}
+
+fn maintenance_timestamp(conn: &Connection, key: &str) -> Result<Option<DateTime<Utc>>, String> {
+    let value = conn
+        .query_row(
+            "SELECT value FROM native_event_log_maintenance WHERE key = ?1",
+            [key],
+            |row| row.get::<_, String>(0),
+        )
+        .optional()
+        .map_err(|error| format!("Failed to read native event log {}: {}", key, error))?;
+    Ok(value.as_deref().and_then(parse_timestamp))
+}
+
+fn set_maintenance_timestamp(
+    conn: &Connection,
+    key: &str,
+    at: DateTime<Utc>,
+) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO native_event_log_maintenance (key, value) VALUES (?1, ?2)
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        params![key, at.to_rfc3339()],
+    )
+    .map_err(|error| format!("Failed to record native event log {}: {}", key, error))?;
+    Ok(())
+}
+
+fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|timestamp| timestamp.with_timezone(&Utc))
+}
+
 fn non_negative_i64_to_u64(value: i64) -> Option<u64> {
     if value < 0 {
         None
@@ -610,6 +1172,7 @@ fn replay_batch_is_truncated(
     oldest_available_seq: Option<u64>,
     newest_available_seq: Option<u64>,
     gap_detected: bool,
+    compacted_through_seq: Option<u64>,
 ) -> bool {
     if events.is_empty() {
         return false;
@@ -637,14 +1200,10 @@ fn replay_batch_is_truncated(
         return false;
     };
 
-    if first_event.seq != expected_first_seq || last_event.seq != newest_available_seq {
-        return true;
-    }
-
-    let expected_len = newest_available_seq
-        .saturating_sub(expected_first_seq)
-        .saturating_add(1);
-    if events.len() as u64 != expected_len {
+    if first_event.seq < expected_first_seq
+        || is_seq_hole(expected_first_seq, first_event.seq, compacted_through_seq)
+        || last_event.seq != newest_available_seq
+    {
         return true;
     }
 
@@ -652,10 +1211,21 @@ fn replay_batch_is_truncated(
         let [previous, next] = window else {
             return false;
         };
-        next.seq != previous.seq.saturating_add(1)
+        next.seq <= previous.seq
+            || is_seq_hole(
+                previous.seq.saturating_add(1),
+                next.seq,
+                compacted_through_seq,
+            )
     })
 }
 
+/// Whether seqs `expected..found` are missing for a reason other than
+/// compaction, which deletes events at or below its marker in place.
+fn is_seq_hole(expected: u64, found: u64, compacted_through_seq: Option<u64>) -> bool {
+    found > expected && compacted_through_seq.is_none_or(|through| found - 1 > through)
+}
+
 fn should_flush_after_append(payload: &SessionEventPayload) -> bool {
     match payload {
         SessionEventPayload::Lifecycle { stage, .. } => {
@@ -741,6 +1311,213 @@ mod tests {
         let _ = std::fs::remove_file(db_path);
     }
 
+    #[test]
+    fn native_event_log_indexes_payload_types_of_existing_logs() {
+        let db_path = std::env::temp_dir().join(format!(
+            "ccem-native-event-log-payload-type-{}.sqlite",
+            Utc::now().timestamp_nanos_opt().unwrap_or_default(),
+        ));
+        // A log written before `payload_type` existed.
+        {
+            let conn = Connection::open(&db_path).expect("open db");
+            conn.execute_batch(
+                "CREATE TABLE native_session_events (
+                     runtime_id TEXT NOT NULL,
+                     seq INTEGER NOT NULL,
+                     occurred_at TEXT NOT NULL,
+                     payload_json TEXT NOT NULL,
+                     created_at TEXT NOT NULL,
+                     PRIMARY KEY(runtime_id, seq)
+                 );",
+            )
+            .expect("create old schema");
+            let payload = serde_json::json!({
+                "type": "routed_request",
+                "provider": "claude",
+                "request_id": "req-1",
+                "target_env": "glm",
+                "status": 200,
+                "complete": true,
+            });
+            conn.execute(
+                "INSERT INTO native_session_events (runtime_id, seq, occurred_at, payload_json, created_at)
+                 VALUES ('runtime-old', 1, '2026-08-18T00:00:00+00:00', ?1, '2026-08-18T00:00:00+00:00')",
+                [payload.to_string()],
+            )
+            .expect("seed old log");
+        }
+
+        let log = NativeEventLog::new(db_path.clone());
+        let ledger = log.routed_request_records().expect("routed ledger");
+        assert_eq!(ledger.len(), 1);
+        assert_eq!(ledger[0].runtime_id, "runtime-old");
+        drop(log);
+
+        let conn = Connection::open(&db_path).expect("reopen db");
+        let plan = conn
+            .prepare(
+                "EXPLAIN QUERY PLAN
+                 SELECT seq FROM native_session_events WHERE payload_type = 'routed_request'",
+            )
+            .expect("prepare plan")
+            .query_map([], |row| row.get::<_, String>(3))
+            .expect("query plan")
+            .collect::<Result<Vec<_>, _>>()
+            .expect("plan rows");
+        assert!(plan
+            .iter()
+            .any(|step| step.contains("idx_native_session_events_payload_type")));
+
+        let _ = std::fs::remove_file(db_path);
+    }
+
+    #[test]
+    fn native_event_log_answers_branch_tree_queries_without_replay() {
+        let db_path = std::env::temp_dir().join(format!(
+            "ccem-native-event-log-tree-{}.sqlite",
+            Utc::now().timestamp_nanos_opt().unwrap_or_default(),
+        ));
+        let log = NativeEventLog::new(db_path.clone());
+        let started = Utc::now();
+        let payloads = [
+            serde_json::json!({"type": "user_prompt", "text": "plan it", "image_count": 0}),
+            serde_json::json!({"type": "assistant_chunk", "text": "Reading "}),
+            serde_json::json!({"type": "assistant_chunk", "text": "the code."}),
+            serde_json::json!({
+                "type": "lifecycle",
+                "stage": "turn_completed",
+                "detail": "",
+                "assistant_message_uuid": "msg-2",
+            }),
+            serde_json::json!({
+                "type": "session_usage",
+                "provider": "claude",
+                "input_tokens": 10,
+                "output_tokens": 5,
+                "cache_read_tokens": 0,
+                "cache_creation_tokens": 0,
+                "cost_usd": 0.25,
+            }),
+            serde_json::json!({
+                "type": "tool_use_started",
+                "tool_use_id": "toolu-1",
+                "category": {"category": "execution", "raw_name": "Bash"},
+                "raw_name": "Bash",
+                "input_summary": "ls",
+                "needs_response": false,
+            }),
+            // Mentions the message id outside the message-id field.
+            serde_json::json!({
+                "type": "claude_json",
+                "message_type": "assistant",
+                "raw_json": "{\"parent\":\"msg-2\"}",
+            }),
+            serde_json::json!({"type": "assistant_chunk", "text": "  "}),
+            serde_json::json!({
+                "type": "session_usage",
+                "provider": "claude",
+                "input_tokens": 20,
+                "output_tokens": 9,
+                "cache_read_tokens": 0,
+                "cache_creation_tokens": 0,
+            }),
+        ];
+        for (index, payload) in payloads.into_iter().enumerate() {
+            log.append(&SessionEventRecord {
+                runtime_id: "runtime-tree".to_string(),
+                seq: index as u64 + 1,
+                occurred_at: started + chrono::Duration::seconds(index as i64),
+                payload: serde_json::from_value(payload).expect("payload"),
+            })
+            .expect("append");
+        }
+
+        let reached = log
+            .assistant_message_time("runtime-tree", "msg-2")
+            .expect("message time")
+            .expect("message reported");
+        assert_eq!(
+            reached.timestamp(),
+            (started + chrono::Duration::seconds(3)).timestamp()
+        );
+        assert_eq!(
+            log.assistant_message_time("runtime-tree", "msg-9")
+                .expect("message time"),
+            None
+        );
+        assert_eq!(
+            log.latest_session_cost("runtime-tree").expect("cost"),
+            Some(0.25)
+        );
+        assert_eq!(
+            log.last_assistant_reply("runtime-tree").expect("reply"),
+            Some("Reading the code.".to_string())
+        );
+        assert_eq!(
+            log.last_assistant_reply("runtime-none").expect("reply"),
+            None
+        );
+
+        let _ = std::fs::remove_file(db_path);
+    }
+
+    #[test]
+    fn native_event_log_lists_routed_request_ledger_across_runtimes() {
+        let db_path = std::env::temp_dir().join(format!(
+            "ccem-native-event-log-routed-{}.sqlite",
+            Utc::now().timestamp_nanos_opt().unwrap_or_default(),
+        ));
+        let log = NativeEventLog::new(db_path.clone());
+        let routed = |target_env: &str| SessionEventPayload::RoutedRequest {
+            provider: "claude".to_string(),
+            request_id: format!("req-{target_env}"),
+            target_env: target_env.to_string(),
+            sub_route: true,
+            model: None,
+            logical_key: None,
+            status: 200,
+            complete: true,
+            usage: None,
+        };
+        for (runtime_id, seq, payload) in [
+            ("runtime-a", 1, routed("glm")),
+            (
+                "runtime-a",
+                2,
+                SessionEventPayload::AssistantChunk {
+                    text: "not a ledger entry".to_string(),
+                },
+            ),
+            ("runtime-b", 1, routed("deepseek")),
+        ] {
+            log.append(&SessionEventRecord {
+                runtime_id: runtime_id.to_string(),
+                seq,
+                occurred_at: Utc::now(),
+                payload,
+            })
+            .expect("append event");
+        }
+
+        let ledger = log.routed_request_records().expect("routed ledger");
+        let targets = ledger
+            .iter()
+            .map(|record| match &record.payload {
+                SessionEventPayload::RoutedRequest { target_env, .. } => {
+                    (record.runtime_id.as_str(), target_env.as_str())
+                }
+                other => panic!("unexpected ledger payload: {other:?}"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            targets,
+            vec![("runtime-a", "glm"), ("runtime-b", "deepseek")]
+        );
+
+        drop(log);
+        let _ = std::fs::remove_file(db_path);
+    }
+
     #[test]
     fn native_event_log_replays_events_after_reopen() {
         let db_path = std::env::temp_dir().join(format!(
@@ -990,6 +1767,8 @@ mod tests {
                 images: None,
                 annotations: None,
                 canonical_hash: None,
+                queued: false,
+                queue_item_id: None,
             },
         })
         .expect("append prompt");
@@ -1053,6 +1832,8 @@ mod tests {
                 images: None,
                 annotations: None,
                 canonical_hash: None,
+                queued: false,
+                queue_item_id: None,
             },
         })
         .expect("append prompt");
@@ -1159,4 +1940,69 @@ mod tests {
 
         let _ = std::fs::remove_file(db_path);
     }
+
+    #[test]
+    fn rewriting_a_runtime_keeps_seqs_and_records_compaction() {
+        let db_path = std::env::temp_dir().join(format!(
+            "ccem-native-event-log-rewrite-{}.sqlite",
+            Utc::now().timestamp_nanos_opt().unwrap_or_default(),
+        ));
+        let log = NativeEventLog::new(db_path.clone());
+        let runtime_id = "runtime-rewrite";
+        let events = (3..=8)
+            .map(|seq| SessionEventRecord {
+                runtime_id: runtime_id.to_string(),
+                seq,
+                occurred_at: Utc::now(),
+                payload: SessionEventPayload::AssistantChunk {
+                    text: format!("chunk-{seq}"),
+                },
+            })
+            .collect::<Vec<_>>();
+        for event in &events {
+            log.append(event).expect("append chunk");
+        }
+
+        let merged = SessionEventRecord {
+            payload: SessionEventPayload::AssistantChunk {
+                text: "chunk-3chunk-4chunk-5chunk-6chunk-7".to_string(),
+            },
+            ..events[0].clone()
+        };
+        log.rewrite_runtime(runtime_id, &[4, 5, 6, 7], std::slice::from_ref(&merged), 8)
+            .expect("rewrite");
+
+        let replay = log.replay(runtime_id, None, None).expect("replay");
+        assert!(!replay.truncated);
+        assert_eq!(
+            replay
+                .events
+                .iter()
+                .map(|event| (event.seq, event.payload.clone()))
+                .collect::<Vec<_>>(),
+            vec![(3, merged.payload), (8, events[5].payload.clone())],
+        );
+        let since_merged = log.replay(runtime_id, Some(3), None).expect("replay");
+        assert!(!since_merged.gap_detected);
+        assert!(!since_merged.truncated);
+        assert_eq!(since_merged.events.len(), 1);
+        let storage = log.runtime_storage().expect("storage");
+        assert_eq!(storage.len(), 1);
+        assert_eq!(storage[0].event_count, 2);
+        assert_eq!(storage[0].newest_seq, 8);
+        assert_eq!(storage[0].compacted_through_seq, Some(8));
+        let stats = log.storage_stats().expect("stats");
+        assert_eq!(stats.compacted_runtime_count, 1);
+        assert_eq!(
+            stats.payload_bytes_by_type.get("assistant_chunk").copied(),
+            Some(stats.payload_bytes)
+        );
+        assert_eq!(
+            stats.event_count_by_type.get("assistant_chunk").copied(),
+            Some(2)
+        );
+
+        drop(log);
+        let _ = std::fs::remove_file(db_path);
+    }
 }
@@ -63,6 +63,7 @@ fn native_event_log_persists_and_restores_attention_summary() {
             raw_name: "AskUserQuestion".to_string(),
             input_summary: String::new(),
             needs_response: true,
+            input: None,
             prompt: None,
             todo_snapshot: None,
         },
@@ -203,6 +203,7 @@ fn todo_snapshot_started_payload(revision: u64, text: &str) -> SessionEventPaylo
         raw_name: "TodoWrite".to_string(),
         input_summary: "1 todo".to_string(),
         needs_response: false,
+        input: None,
         prompt: None,
         todo_snapshot: Some(TodoSnapshotV1 {
             version: 1,
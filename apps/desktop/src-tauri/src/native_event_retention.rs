@@ -0,0 +1,435 @@
+// apps/desktop/src-tauri/src/native_event_retention.rs
+//
+// Retention for the native event log. Sessions that have been quiet for a
+// while are compacted: raw `ClaudeJson` lines are dropped and runs of
+// `AssistantChunk` events are coalesced into single messages. Size caps per
+// session and for the whole database then trim the oldest bulk events of
+// sessions idle for at least `SIZE_CAP_MIN_IDLE_DAYS`, giving up transcript
+// text last. The anchors replay and usage analytics rely on (prompts,
+// checkpoints, todo snapshots, usage and routing ledgers) are always kept.
+// Events keep their seqs; removed ones leave holes. The job runs
+// periodically from `NativeRuntimeManager` and vacuums the database on its
+// own interval.
+
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::State;
+
+use crate::config;
+use crate::event_bus::{SessionEventPayload, SessionEventRecord};
+use crate::native_runtime::NativeRuntimeManager;
+
+pub const EVENT_LOG_MAINTENANCE_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+/// Keeps the first run away from app startup, when replays are busiest.
+pub const EVENT_LOG_MAINTENANCE_STARTUP_DELAY: Duration = Duration::from_secs(10 * 60);
+const BYTES_PER_MB: u64 = 1024 * 1024;
+/// Size caps leave sessions alone that were active more recently than this.
+pub const SIZE_CAP_MIN_IDLE_DAYS: i64 = 3;
+
+/// `0` switches the corresponding step off.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NativeEventLogRetention {
+    /// Sessions without events for this many days are compacted.
+    pub compact_after_days: u32,
+    pub max_session_mb: u64,
+    pub max_total_mb: u64,
+    pub vacuum_interval_hours: u32,
+}
+
+impl Default for NativeEventLogRetention {
+    fn default() -> Self {
+        Self {
+            compact_after_days: 14,
+            max_session_mb: 64,
+            max_total_mb: 1024,
+            vacuum_interval_hours: 24,
+        }
+    }
+}
+
+impl NativeEventLogRetention {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_total_mb > 0 && self.max_session_mb > self.max_total_mb {
+            return Err(format!(
+                "Per-session event log cap ({} MB) exceeds the total cap ({} MB)",
+                self.max_session_mb, self.max_total_mb
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn compact_before(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        (self.compact_after_days > 0)
+            .then(|| now - ChronoDuration::days(i64::from(self.compact_after_days)))
+    }
+
+    /// Sessions whose newest event is older than this may be size-capped.
+    pub fn cap_before(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        now - ChronoDuration::days(SIZE_CAP_MIN_IDLE_DAYS)
+    }
+
+    pub fn max_session_bytes(&self) -> Option<u64> {
+        (self.max_session_mb > 0).then(|| self.max_session_mb.saturating_mul(BYTES_PER_MB))
+    }
+
+    pub fn max_total_bytes(&self) -> Option<u64> {
+        (self.max_total_mb > 0).then(|| self.max_total_mb.saturating_mul(BYTES_PER_MB))
+    }
+
+    pub fn vacuum_interval(&self) -> Option<ChronoDuration> {
+        (self.vacuum_interval_hours > 0)
+            .then(|| ChronoDuration::hours(i64::from(self.vacuum_interval_hours)))
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct NativeEventCompactionReport {
+    pub ran_at: DateTime<Utc>,
+    pub runtimes_compacted: usize,
+    pub events_removed: u64,
+    pub bytes_reclaimed: u64,
+    pub vacuumed: bool,
+}
+
+impl NativeEventCompactionReport {
+    pub fn new(ran_at: DateTime<Utc>) -> Self {
+        Self {
+            ran_at,
+            runtimes_compacted: 0,
+            events_removed: 0,
+            bytes_reclaimed: 0,
+            vacuumed: false,
+        }
+    }
+}
+
+/// Events compaction and size caps never remove.
+pub fn is_retained_event(payload: &SessionEventPayload) -> bool {
+    match payload {
+        SessionEventPayload::UserPrompt { .. }
+        | SessionEventPayload::CheckpointCreated { .. }
+        | SessionEventPayload::RoutedRequest { .. }
+        | SessionEventPayload::ContextUsage { .. }
+        | SessionEventPayload::SessionUsage { .. } => true,
+        SessionEventPayload::ToolUseStarted { todo_snapshot, .. }
+        | SessionEventPayload::ToolUseCompleted { todo_snapshot, .. } => todo_snapshot.is_some(),
+        _ => false,
+    }
+}
+
+/// Raw provider output: the first thing size caps give up.
+fn is_bulk_event(payload: &SessionEventPayload) -> bool {
+    matches!(
+        payload,
+        SessionEventPayload::ClaudeJson { .. } | SessionEventPayload::StdErrLine { .. }
+    )
+}
+
+/// Stored size of an event, as `payload_json` in the event log.
+pub fn event_size(record: &SessionEventRecord) -> u64 {
+    serde_json::to_string(&record.payload)
+        .map(|payload| payload.len() as u64)
+        .unwrap_or_default()
+}
+
+pub fn events_size(events: &[SessionEventRecord]) -> u64 {
+    events.iter().map(event_size).sum()
+}
+
+/// Transcript text: kept by size caps until nothing else is left to trim.
+fn is_transcript_event(payload: &SessionEventPayload) -> bool {
+    matches!(payload, SessionEventPayload::AssistantChunk { .. })
+}
+
+/// Drop raw `ClaudeJson` lines and merge consecutive `AssistantChunk`s into
+/// the first one of each run. Transcripts append chunk text in order, so a
+/// merged chunk renders the same as the run it replaces. The newest event
+/// stays as it is so the log keeps its place in the sequence.
+pub fn compact_events(events: Vec<SessionEventRecord>) -> Vec<SessionEventRecord> {
+    let last = events.len().saturating_sub(1);
+    let mut compacted: Vec<SessionEventRecord> = Vec::with_capacity(events.len());
+    for (index, event) in events.into_iter().enumerate() {
+        if index == last {
+            compacted.push(event);
+            break;
+        }
+        if matches!(event.payload, SessionEventPayload::ClaudeJson { .. }) {
+            continue;
+        }
+        if let SessionEventPayload::AssistantChunk { text } = &event.payload {
+            if let Some(SessionEventPayload::AssistantChunk { text: run }) =
+                compacted.last_mut().map(|previous| &mut previous.payload)
+            {
+                run.push_str(text);
+                continue;
+            }
+        }
+        compacted.push(event);
+    }
+    compacted
+}
+
+/// Remove the oldest removable events until the history fits `max_bytes`:
+/// raw provider output first, then anything that is not retained apart from
+/// transcript text, then transcript text. The newest event always stays so
+/// the log keeps its place in the sequence.
+pub fn trim_events_to_size(
+    events: Vec<SessionEventRecord>,
+    max_bytes: u64,
+) -> Vec<SessionEventRecord> {
+    let mut total = events_size(&events);
+    if total <= max_bytes {
+        return events;
+    }
+    let last = events.len().saturating_sub(1);
+    let mut dropped = vec![false; events.len()];
+    let passes: [fn(&SessionEventPayload) -> bool; 3] = [
+        is_bulk_event,
+        |payload| !is_retained_event(payload) && !is_transcript_event(payload),
+        |payload| !is_retained_event(payload),
+    ];
+    for removable in passes {
+        for (index, event) in events.iter().enumerate() {
+            if total <= max_bytes {
+                break;
+            }
+            if index == last || dropped[index] || !removable(&event.payload) {
+                continue;
+            }
+            dropped[index] = true;
+            total = total.saturating_sub(event_size(event));
+        }
+    }
+    events
+        .into_iter()
+        .zip(dropped)
+        .filter_map(|(event, dropped)| (!dropped).then_some(event))
+        .collect()
+}
+
+/// Run compaction now and vacuum regardless of the vacuum interval.
+#[tauri::command]
+pub async fn compact_native_event_log(
+    native_state: State<'_, Arc<NativeRuntimeManager>>,
+) -> Result<NativeEventCompactionReport, String> {
+    let native_state = native_state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || native_state.compact_event_log(true))
+        .await
+        .map_err(|error| format!("Blocking event log compaction task failed: {error}"))?
+}
+
+#[tauri::command]
+pub fn set_native_event_log_retention(
+    retention: Option<NativeEventLogRetention>,
+) -> Result<(), String> {
+    if let Some(retention) = retention.as_ref() {
+        retention.validate()?;
+    }
+    config::update_settings(|settings| {
+        settings.native_event_log_retention = retention;
+    })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event_bus::{TodoSnapshotItemV1, TodoSnapshotV1};
+
+    fn record(seq: u64, payload: SessionEventPayload) -> SessionEventRecord {
+        SessionEventRecord {
+            runtime_id: "native-retention".to_string(),
+            seq,
+            occurred_at: Utc::now(),
+            payload,
+        }
+    }
+
+    fn chunk(seq: u64, text: &str) -> SessionEventRecord {
+        record(
+            seq,
+            SessionEventPayload::AssistantChunk {
+                text: text.to_string(),
+            },
+        )
+    }
+
+    fn raw(seq: u64, size: usize) -> SessionEventRecord {
+        record(
+            seq,
+            SessionEventPayload::ClaudeJson {
+                message_type: Some("assistant".to_string()),
+                raw_json: "x".repeat(size),
+            },
+        )
+    }
+
+    fn prompt(seq: u64) -> SessionEventRecord {
+        record(
+            seq,
+            SessionEventPayload::UserPrompt {
+                text: "fix it".to_string(),
+                image_count: 0,
+                images: None,
+                annotations: None,
+                canonical_hash: None,
+                queued: false,
+                queue_item_id: None,
+            },
+        )
+    }
+
+    #[test]
+    fn compaction_drops_raw_json_and_coalesces_chunk_runs() {
+        let compacted = compact_events(vec![
+            prompt(1),
+            chunk(2, "Hello"),
+            raw(3, 10),
+            chunk(4, ", world"),
+            record(
+                5,
+                SessionEventPayload::StdErrLine {
+                    line: "warning".to_string(),
+                },
+            ),
+            chunk(6, "Done."),
+        ]);
+
+        assert_eq!(
+            compacted
+                .iter()
+                .map(|event| (event.seq, event.payload.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                (1, prompt(1).payload),
+                (2, chunk(2, "Hello, world").payload),
+                (
+                    5,
+                    SessionEventPayload::StdErrLine {
+                        line: "warning".to_string(),
+                    }
+                ),
+                (6, chunk(6, "Done.").payload),
+            ]
+        );
+    }
+
+    #[test]
+    fn compaction_leaves_the_newest_event_in_place() {
+        let compacted = compact_events(vec![chunk(1, "a"), chunk(2, "b"), raw(3, 10)]);
+        assert_eq!(
+            compacted.iter().map(|event| event.seq).collect::<Vec<_>>(),
+            vec![1, 3]
+        );
+
+        let compacted = compact_events(vec![raw(1, 10), chunk(2, "a"), chunk(3, "b")]);
+        assert_eq!(
+            compacted
+                .iter()
+                .map(|event| (event.seq, event.payload.clone()))
+                .collect::<Vec<_>>(),
+            vec![(2, chunk(2, "a").payload), (3, chunk(3, "b").payload)]
+        );
+    }
+
+    #[test]
+    fn size_caps_trim_bulk_output_before_keeping_anchors() {
+        let todo = record(
+            4,
+            SessionEventPayload::ToolUseCompleted {
+                tool_use_id: "toolu-1".to_string(),
+                raw_name: "TodoWrite".to_string(),
+                result_summary: "Updated todos".to_string(),
+                result_content: None,
+                success: true,
+                todo_snapshot: Some(TodoSnapshotV1 {
+                    version: 1,
+                    provider: "claude".to_string(),
+                    source: "TodoWrite".to_string(),
+                    revision: 1,
+                    items: vec![TodoSnapshotItemV1 {
+                        id: "1".to_string(),
+                        text: "ship".to_string(),
+                        status: "pending".to_string(),
+                        active_text: None,
+                    }],
+                }),
+            },
+        );
+        let events = vec![
+            prompt(1),
+            raw(2, 500),
+            chunk(3, &"y".repeat(500)),
+            todo,
+            raw(5, 500),
+            chunk(6, "tail"),
+        ];
+        let without_raw = events_size(&events) - event_size(&events[1]) - event_size(&events[4]);
+
+        let trimmed = trim_events_to_size(events.clone(), without_raw);
+        assert_eq!(
+            trimmed.iter().map(|event| event.seq).collect::<Vec<_>>(),
+            vec![1, 3, 4, 6]
+        );
+
+        let anchors_only = trim_events_to_size(events, 0);
+        assert_eq!(
+            anchors_only
+                .iter()
+                .map(|event| event.seq)
+                .collect::<Vec<_>>(),
+            vec![1, 4, 6]
+        );
+    }
+
+    #[test]
+    fn size_caps_give_up_transcript_text_last() {
+        let system = |seq: u64| {
+            record(
+                seq,
+                SessionEventPayload::SystemMessage {
+                    message: "z".repeat(300),
+                },
+            )
+        };
+        let events = vec![
+            prompt(1),
+            chunk(2, &"y".repeat(300)),
+            system(3),
+            system(4),
+            chunk(5, "tail"),
+        ];
+        let without_system = events_size(&events) - event_size(&events[2]) - event_size(&events[3]);
+
+        let trimmed = trim_events_to_size(events, without_system);
+        assert_eq!(
+            trimmed.iter().map(|event| event.seq).collect::<Vec<_>>(),
+            vec![1, 2, 5]
+        );
+    }
+
+    #[test]
+    fn retention_caps_switch_off_at_zero() {
+        let retention = NativeEventLogRetention {
+            compact_after_days: 0,
+            max_session_mb: 0,
+            max_total_mb: 2,
+            vacuum_interval_hours: 0,
+        };
+
+        assert_eq!(retention.compact_before(Utc::now()), None);
+        assert_eq!(retention.max_session_bytes(), None);
+        assert_eq!(retention.max_total_bytes(), Some(2 * BYTES_PER_MB));
+        assert_eq!(retention.vacuum_interval(), None);
+        assert!(NativeEventLogRetention {
+            max_session_mb: 4,
+            ..retention
+        }
+        .validate()
+        .is_err());
+    }
+}
@@ -0,0 +1,557 @@
+// apps/desktop/src-tauri/src/native_fanout.rs
+//
+// Multi-agent fan-out: one prompt launched as sibling native sessions across
+// several (provider, env) targets, each in its own git worktree of the
+// project, then compared side by side once every member has finished.
+// Groups are persisted to ~/.ccem/native_fanouts.json through
+// `LockedJsonStore`; comparisons are rebuilt from the event log and worktrees.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use tauri::{AppHandle, State};
+
+use crate::config;
+use crate::external_control::is_known_perm_mode;
+use crate::git_worktree::{self, GitWorktree, WorktreeDiffStat};
+use crate::locked_json_store::LockedJsonStore;
+use crate::native_event_log::NativeEventLog;
+use crate::native_runtime::{
+    NativeProvider, NativeRuntimeManager, NativeSessionSummary, NativeWorktreeAction,
+};
+use crate::session_provenance::{register_launch, SessionProvenanceUpsert, DEFAULT_CONFIG_SOURCE};
+
+pub const MAX_FANOUT_TARGETS: usize = 8;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct NativeFanoutTarget {
+    pub provider: NativeProvider,
+    pub env_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub effort: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct NativeFanoutRequest {
+    pub prompt: String,
+    pub working_dir: String,
+    pub targets: Vec<NativeFanoutTarget>,
+    #[serde(default)]
+    pub perm_mode: Option<String>,
+}
+
+impl NativeFanoutRequest {
+    /// Trim and validate; fills the permission mode from the config default.
+    pub fn normalized(mut self) -> Result<Self, String> {
+        self.prompt = self.prompt.trim().to_string();
+        if self.prompt.is_empty() {
+            return Err("A fan-out needs a prompt".to_string());
+        }
+        self.working_dir = self.working_dir.trim().to_string();
+        if !Path::new(&self.working_dir).is_absolute() {
+            return Err("Fan-out working directory must be an absolute path".to_string());
+        }
+        if self.targets.is_empty() || self.targets.len() > MAX_FANOUT_TARGETS {
+            return Err(format!(
+                "A fan-out takes between 1 and {} targets",
+                MAX_FANOUT_TARGETS
+            ));
+        }
+        for target in &mut self.targets {
+            target.env_name = target.env_name.trim().to_string();
+            if target.env_name.is_empty() {
+                return Err("Every fan-out target needs an environment".to_string());
+            }
+            target.effort = target
+                .effort
+                .take()
+                .map(|effort| effort.trim().to_string())
+                .filter(|effort| !effort.is_empty());
+        }
+        let perm_mode = self
+            .perm_mode
+            .take()
+            .map(|mode| mode.trim().to_string())
+            .filter(|mode| !mode.is_empty())
+            .or_else(|| config::read_config().ok().and_then(|cfg| cfg.default_mode))
+            .unwrap_or_else(|| "dev".to_string());
+        if !is_known_perm_mode(&perm_mode) {
+            return Err(format!("Unknown permission mode '{}'", perm_mode));
+        }
+        self.perm_mode = Some(perm_mode);
+        Ok(self)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct NativeFanoutMember {
+    pub runtime_id: String,
+    pub provider: NativeProvider,
+    pub env_name: String,
+    pub worktree: GitWorktree,
+    pub started_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct NativeFanoutGroup {
+    pub group_id: String,
+    pub prompt: String,
+    pub project_dir: String,
+    pub perm_mode: String,
+    pub created_at: DateTime<Utc>,
+    pub members: Vec<NativeFanoutMember>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completed_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub adopted_runtime_id: Option<String>,
+}
+
+impl NativeFanoutGroup {
+    pub fn member(&self, runtime_id: &str) -> Result<&NativeFanoutMember, String> {
+        self.members
+            .iter()
+            .find(|member| member.runtime_id == runtime_id)
+            .ok_or_else(|| {
+                format!(
+                    "Native runtime {} is not part of fan-out {}",
+                    runtime_id, self.group_id
+                )
+            })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NativeFanoutMemberComparison {
+    pub runtime_id: String,
+    pub provider: NativeProvider,
+    pub env_name: String,
+    pub branch: String,
+    pub worktree_path: String,
+    /// `None` once the runtime record is gone.
+    pub status: Option<String>,
+    pub finished: bool,
+    pub completion_reason: Option<String>,
+    pub duration_ms: Option<i64>,
+    pub cost_usd: Option<f64>,
+    pub diff: Option<WorktreeDiffStat>,
+    pub diff_error: Option<String>,
+    pub final_message: Option<String>,
+    /// POSIX shell one-liner for adopting the branch by hand; `None` on
+    /// Windows, where only the adopt action is offered.
+    pub adopt_command: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NativeFanoutComparison {
+    pub group_id: String,
+    pub prompt: String,
+    pub project_dir: String,
+    pub created_at: DateTime<Utc>,
+    pub all_finished: bool,
+    pub adopted_runtime_id: Option<String>,
+    pub members: Vec<NativeFanoutMemberComparison>,
+}
+
+/// What a member's event log says about its first turn.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FanoutMemberOutcome {
+    pub finished_at: Option<DateTime<Utc>>,
+    pub completion_reason: Option<String>,
+    pub cost_usd: Option<f64>,
+    pub final_message: Option<String>,
+}
+
+/// The first turn ends at the first `turn_completed` lifecycle event or
+/// `SessionCompleted`; cost is the latest `SessionUsage` report, which may
+/// land shortly after the turn itself. Cost and final message come from the
+/// same queries the branch tree uses, so neither replays the whole log.
+pub fn fanout_member_outcome(
+    event_log: &NativeEventLog,
+    runtime_id: &str,
+) -> Result<FanoutMemberOutcome, String> {
+    let (finished_at, completion_reason) = event_log.first_turn_end(runtime_id)?.unzip();
+    Ok(FanoutMemberOutcome {
+        finished_at,
+        completion_reason,
+        cost_usd: event_log.latest_session_cost(runtime_id)?,
+        final_message: event_log.last_assistant_reply(runtime_id)?,
+    })
+}
+
+/// `terminal` marks a runtime that stopped without reporting a finished turn.
+pub fn build_member_comparison(
+    member: &NativeFanoutMember,
+    summary: Option<&NativeSessionSummary>,
+    terminal: bool,
+    outcome: FanoutMemberOutcome,
+) -> NativeFanoutMemberComparison {
+    let finished_at = outcome.finished_at.or_else(|| {
+        summary
+            .filter(|_| terminal)
+            .map(|summary| summary.updated_at)
+    });
+    let (diff, diff_error) = match git_worktree::diff_stat(&member.worktree) {
+        Ok(diff) => (Some(diff), None),
+        Err(error) => (None, Some(error)),
+    };
+    NativeFanoutMemberComparison {
+        runtime_id: member.runtime_id.clone(),
+        provider: member.provider,
+        env_name: member.env_name.clone(),
+        branch: member.worktree.branch.clone(),
+        worktree_path: member.worktree.path.clone(),
+        status: summary.map(|summary| summary.status.clone()),
+        finished: outcome.finished_at.is_some() || terminal || summary.is_none(),
+        completion_reason: outcome.completion_reason,
+        duration_ms: finished_at.map(|at| (at - member.started_at).num_milliseconds()),
+        cost_usd: outcome.cost_usd,
+        diff,
+        diff_error,
+        final_message: outcome.final_message,
+        adopt_command: cfg!(unix).then(|| git_worktree::adopt_command(&member.worktree)),
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct NativeFanoutGroups {
+    groups: BTreeMap<String, NativeFanoutGroup>,
+}
+
+impl NativeFanoutGroups {
+    fn store() -> LockedJsonStore {
+        LockedJsonStore::new("native_fanouts.json")
+    }
+
+    /// Acquire an exclusive lock and load from disk.
+    fn load_locked() -> std::io::Result<(Self, fs::File)> {
+        Self::store().load_locked()
+    }
+
+    /// Load without locking, for read-only paths.
+    pub fn load() -> Self {
+        Self::store().load()
+    }
+
+    /// Caller must hold the lock.
+    fn save_locked(&self) -> std::io::Result<()> {
+        Self::store().save_locked(self)
+    }
+
+    pub fn get(&self, group_id: &str) -> Result<&NativeFanoutGroup, String> {
+        self.groups
+            .get(group_id)
+            .ok_or_else(|| format!("Unknown fan-out group '{}'", group_id))
+    }
+
+    pub fn list(&self) -> Vec<NativeFanoutGroup> {
+        let mut groups: Vec<_> = self.groups.values().cloned().collect();
+        groups.sort_by_key(|group| Reverse(group.created_at));
+        groups
+    }
+
+    pub fn insert(group: NativeFanoutGroup) -> Result<(), String> {
+        Self::update(&group.group_id.clone(), |groups| {
+            groups.groups.insert(group.group_id.clone(), group);
+            Ok(())
+        })
+    }
+
+    /// Apply `change` to the group under the lock and persist it.
+    pub fn modify(
+        group_id: &str,
+        change: impl FnOnce(&mut NativeFanoutGroup),
+    ) -> Result<NativeFanoutGroup, String> {
+        let mut updated = None;
+        Self::update(group_id, |groups| {
+            let group = groups
+                .groups
+                .get_mut(group_id)
+                .ok_or_else(|| format!("Unknown fan-out group '{}'", group_id))?;
+            change(group);
+            updated = Some(group.clone());
+            Ok(())
+        })?;
+        updated.ok_or_else(|| format!("Unknown fan-out group '{}'", group_id))
+    }
+
+    pub fn remove(group_id: &str) -> Result<(), String> {
+        Self::update(group_id, |groups| {
+            groups.groups.remove(group_id);
+            Ok(())
+        })
+    }
+
+    fn update(
+        group_id: &str,
+        change: impl FnOnce(&mut Self) -> Result<(), String>,
+    ) -> Result<(), String> {
+        let (mut groups, _lock) = Self::load_locked()
+            .map_err(|e| format!("Failed to load fan-out group {}: {}", group_id, e))?;
+        change(&mut groups)?;
+        groups
+            .save_locked()
+            .map_err(|e| format!("Failed to save fan-out group {}: {}", group_id, e))
+    }
+}
+
+#[tauri::command]
+pub async fn create_native_fanout(
+    app: AppHandle,
+    native_state: State<'_, Arc<NativeRuntimeManager>>,
+    environment_mutations: State<'_, Arc<config::EnvironmentMutationCoordinator>>,
+    request: NativeFanoutRequest,
+) -> Result<NativeFanoutGroup, String> {
+    let native_state = native_state.inner().clone();
+    let environment_mutations = environment_mutations.inner().clone();
+    // Creating worktrees runs `git worktree add` once per target; keep that
+    // and the environment lock off the async runtime.
+    let group = tauri::async_runtime::spawn_blocking(move || {
+        let _mutation_guard = environment_mutations.lock()?;
+        native_state.create_fanout(app, request.normalized()?)
+    })
+    .await
+    .map_err(|error| format!("Blocking fan-out launch task failed: {error}"))??;
+
+    for member in &group.members {
+        if let Err(error) = register_launch(SessionProvenanceUpsert {
+            ccem_session_id: member.runtime_id.clone(),
+            client: member.provider.as_str().to_string(),
+            env_name: member.env_name.clone(),
+            config_source: Some(DEFAULT_CONFIG_SOURCE.to_string()),
+            working_dir: member.worktree.session_dir.clone(),
+            perm_mode: Some(group.perm_mode.clone()),
+            launch_mode: "native_fanout".to_string(),
+            started_via: "desktop".to_string(),
+            source_session_id: None,
+        }) {
+            eprintln!(
+                "Failed to register fan-out launch provenance for {}: {}",
+                member.runtime_id, error
+            );
+        }
+    }
+    Ok(group)
+}
+
+#[tauri::command]
+pub fn list_native_fanouts() -> Vec<NativeFanoutGroup> {
+    NativeFanoutGroups::load().list()
+}
+
+#[tauri::command]
+pub async fn get_native_fanout_comparison(
+    native_state: State<'_, Arc<NativeRuntimeManager>>,
+    group_id: String,
+) -> Result<NativeFanoutComparison, String> {
+    let native_state = native_state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || native_state.fanout_comparison(&group_id))
+        .await
+        .map_err(|error| format!("Blocking fan-out comparison task failed: {error}"))?
+}
+
+/// Merge the member's branch into the main checkout and retire its
+/// worktree. The other members are left untouched.
+#[tauri::command]
+pub async fn adopt_native_fanout_branch(
+    native_state: State<'_, Arc<NativeRuntimeManager>>,
+    group_id: String,
+    runtime_id: String,
+) -> Result<NativeFanoutGroup, String> {
+    let native_state = native_state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        NativeFanoutGroups::load()
+            .get(&group_id)?
+            .member(&runtime_id)?;
+        native_state.apply_worktree_action(&runtime_id, NativeWorktreeAction::Merge)?;
+        if let Err(error) =
+            native_state.apply_worktree_action(&runtime_id, NativeWorktreeAction::Discard)
+        {
+            eprintln!(
+                "Failed to retire adopted fan-out worktree of {}: {}",
+                runtime_id, error
+            );
+        }
+        NativeFanoutGroups::modify(&group_id, |group| {
+            group.adopted_runtime_id = Some(runtime_id.clone());
+        })
+    })
+    .await
+    .map_err(|error| format!("Blocking fan-out adopt task failed: {error}"))?
+}
+
+/// Stop the group's sessions and delete their worktrees and branches.
+#[tauri::command]
+pub async fn discard_native_fanout(
+    native_state: State<'_, Arc<NativeRuntimeManager>>,
+    group_id: String,
+) -> Result<(), String> {
+    let native_state = native_state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let group = NativeFanoutGroups::load().get(&group_id)?.clone();
+        let mut errors = Vec::new();
+        for member in &group.members {
+            // Adopted or garbage-collected members have no worktree left.
+            if native_state.session_worktree(&member.runtime_id)?.is_none() {
+                continue;
+            }
+            if let Err(error) = native_state
+                .apply_worktree_action(&member.runtime_id, NativeWorktreeAction::Discard)
+            {
+                errors.push(error);
+            }
+        }
+        if !errors.is_empty() {
+            return Err(errors.join("; "));
+        }
+        NativeFanoutGroups::remove(&group_id)
+    })
+    .await
+    .map_err(|error| format!("Blocking fan-out discard task failed: {error}"))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event_bus::{SessionEventPayload, SessionEventRecord};
+    use chrono::TimeZone;
+
+    fn event(seq: u64, second: u32, payload: SessionEventPayload) -> SessionEventRecord {
+        SessionEventRecord {
+            runtime_id: "native-fanout-a".to_string(),
+            seq,
+            occurred_at: Utc.with_ymd_and_hms(2026, 10, 1, 9, 0, second).unwrap(),
+            payload,
+        }
+    }
+
+    fn usage(cost_usd: Option<f64>) -> SessionEventPayload {
+        SessionEventPayload::SessionUsage {
+            provider: "claude".to_string(),
+            input_tokens: 10,
+            output_tokens: 20,
+            cache_read_tokens: 0,
+            cache_creation_tokens: 0,
+            cost_usd,
+            model_usage: Vec::new(),
+            subscription_type: None,
+            rate_limits_available: false,
+            rate_limits: None,
+        }
+    }
+
+    fn outcome_of(name: &str, events: Vec<SessionEventRecord>) -> FanoutMemberOutcome {
+        let db_path = std::env::temp_dir().join(format!(
+            "ccem-native-fanout-{}-{}.sqlite",
+            name,
+            Utc::now().timestamp_nanos_opt().unwrap_or_default(),
+        ));
+        let log = NativeEventLog::new(db_path.clone());
+        for event in &events {
+            log.append(event).expect("append event");
+        }
+        let outcome = fanout_member_outcome(&log, "native-fanout-a").expect("outcome");
+        drop(log);
+        let _ = fs::remove_file(db_path);
+        outcome
+    }
+
+    #[test]
+    fn outcome_ends_at_first_turn_and_keeps_latest_session_usage_cost() {
+        let outcome = outcome_of(
+            "first-turn",
+            vec![
+                event(1, 0, usage(Some(0.01))),
+                event(
+                    2,
+                    10,
+                    SessionEventPayload::AssistantChunk {
+                        text: "All done.".to_string(),
+                    },
+                ),
+                event(
+                    3,
+                    30,
+                    SessionEventPayload::Lifecycle {
+                        stage: "turn_completed".to_string(),
+                        detail: "Done".to_string(),
+                        assistant_message_uuid: None,
+                    },
+                ),
+                event(4, 31, usage(Some(0.42))),
+                event(5, 32, usage(None)),
+                event(
+                    6,
+                    50,
+                    SessionEventPayload::SessionCompleted {
+                        reason: "Stopped from desktop workspace.".to_string(),
+                    },
+                ),
+            ],
+        );
+
+        assert_eq!(
+            outcome.finished_at,
+            Some(Utc.with_ymd_and_hms(2026, 10, 1, 9, 0, 30).unwrap())
+        );
+        assert_eq!(outcome.completion_reason.as_deref(), Some("completed"));
+        assert_eq!(outcome.cost_usd, Some(0.42));
+        assert_eq!(outcome.final_message.as_deref(), Some("All done."));
+    }
+
+    #[test]
+    fn failed_turn_reports_the_session_completed_reason() {
+        let outcome = outcome_of(
+            "failed-turn",
+            vec![event(
+                1,
+                5,
+                SessionEventPayload::SessionCompleted {
+                    reason: "error_max_turns".to_string(),
+                },
+            )],
+        );
+
+        assert_eq!(
+            outcome.completion_reason.as_deref(),
+            Some("error_max_turns")
+        );
+        assert_eq!(outcome.cost_usd, None);
+        assert!(outcome.finished_at.is_some());
+    }
+
+    #[test]
+    fn request_normalization_rejects_empty_prompts_and_unknown_modes() {
+        let request = |prompt: &str, perm_mode: &str| NativeFanoutRequest {
+            prompt: prompt.to_string(),
+            working_dir: "/work/app".to_string(),
+            targets: vec![NativeFanoutTarget {
+                provider: NativeProvider::Claude,
+                env_name: " kimi ".to_string(),
+                effort: Some(" ".to_string()),
+            }],
+            perm_mode: Some(perm_mode.to_string()),
+        };
+
+        assert!(request("  ", "dev").normalized().is_err());
+        assert!(request("Fix the flaky test", "godmode")
+            .normalized()
+            .is_err());
+        let normalized = request(" Fix the flaky test ", "readonly")
+            .normalized()
+            .expect("valid request");
+        assert_eq!(normalized.prompt, "Fix the flaky test");
+        assert_eq!(normalized.targets[0].env_name, "kimi");
+        assert_eq!(normalized.targets[0].effort, None);
+        assert_eq!(normalized.perm_mode.as_deref(), Some("readonly"));
+    }
+}
@@ -0,0 +1,524 @@
+// apps/desktop/src-tauri/src/native_limits.rs
+//
+// Resource policies for native sessions. Idle, wall-clock and cost limits are
+// checked by a watchdog in `NativeRuntimeManager`, which warns once before it
+// stops a runtime. Memory and CPU ceilings confine the helper's process tree
+// to a cgroup v2 group on Linux, when this process sits in a delegated
+// cgroup subtree; elsewhere they are reported as unavailable.
+
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+pub const LIMIT_WATCHDOG_INTERVAL: Duration = Duration::from_secs(30);
+/// Share of a cost ceiling at which the warning goes out.
+const COST_WARNING_RATIO: f64 = 0.8;
+/// Time limits warn a fifth of the limit ahead, capped at this many minutes.
+const MAX_TIME_WARNING_LEAD_MINUTES: u64 = 5;
+const MIN_MEMORY_LIMIT_MB: u64 = 64;
+/// 1 TiB; keeps the byte count written to `memory.max` far from overflow.
+const MAX_MEMORY_LIMIT_MB: u64 = 1024 * 1024;
+
+/// Global defaults live in the desktop settings; a session's own limits win
+/// field by field, and `0` switches a global limit off for that session.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NativeSessionLimits {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idle_timeout_minutes: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_runtime_minutes: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_cost_usd: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_limit_mb: Option<u64>,
+    /// Share of one CPU core; 200 allows two full cores.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_limit_percent: Option<u32>,
+}
+
+impl NativeSessionLimits {
+    pub fn overlay(&self, session: Option<&NativeSessionLimits>) -> NativeSessionLimits {
+        let session = session.cloned().unwrap_or_default();
+        NativeSessionLimits {
+            idle_timeout_minutes: session
+                .idle_timeout_minutes
+                .or(self.idle_timeout_minutes)
+                .filter(|minutes| *minutes > 0),
+            max_runtime_minutes: session
+                .max_runtime_minutes
+                .or(self.max_runtime_minutes)
+                .filter(|minutes| *minutes > 0),
+            max_cost_usd: session
+                .max_cost_usd
+                .or(self.max_cost_usd)
+                .filter(|cost| *cost > 0.0),
+            memory_limit_mb: session
+                .memory_limit_mb
+                .or(self.memory_limit_mb)
+                .filter(|mb| *mb > 0),
+            cpu_limit_percent: session
+                .cpu_limit_percent
+                .or(self.cpu_limit_percent)
+                .filter(|percent| *percent > 0),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(cost) = self.max_cost_usd {
+            if !cost.is_finite() || cost < 0.0 {
+                return Err(format!("Invalid cost limit {}", cost));
+            }
+        }
+        if let Some(mb) = self.memory_limit_mb.filter(|mb| *mb > 0) {
+            if !(MIN_MEMORY_LIMIT_MB..=MAX_MEMORY_LIMIT_MB).contains(&mb) {
+                return Err(format!(
+                    "Memory limit must be between {} MB and {} MB",
+                    MIN_MEMORY_LIMIT_MB, MAX_MEMORY_LIMIT_MB
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn has_resource_ceiling(&self) -> bool {
+        self.memory_limit_mb.is_some() || self.cpu_limit_percent.is_some()
+    }
+
+    pub fn is_unbounded(&self) -> bool {
+        self.idle_timeout_minutes.is_none()
+            && self.max_runtime_minutes.is_none()
+            && self.max_cost_usd.is_none()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NativeLimitKind {
+    IdleTimeout,
+    MaxRuntime,
+    MaxCost,
+}
+
+impl NativeLimitKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NativeLimitKind::IdleTimeout => "idle_timeout",
+            NativeLimitKind::MaxRuntime => "max_runtime",
+            NativeLimitKind::MaxCost => "max_cost",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum NativeLimitVerdict {
+    Warn {
+        kind: NativeLimitKind,
+        detail: String,
+    },
+    Stop {
+        kind: NativeLimitKind,
+        detail: String,
+    },
+}
+
+/// What the watchdog knows about one helper generation.
+#[derive(Debug, Clone)]
+pub struct NativeLimitUsage {
+    pub started_at: DateTime<Utc>,
+    pub last_activity_at: DateTime<Utc>,
+    pub cost_usd: Option<f64>,
+    /// Idle time only counts while the session waits for a prompt.
+    pub waiting_for_input: bool,
+}
+
+pub fn evaluate_limits(
+    limits: &NativeSessionLimits,
+    usage: &NativeLimitUsage,
+    now: DateTime<Utc>,
+) -> Vec<NativeLimitVerdict> {
+    let mut verdicts = Vec::new();
+    if let Some(minutes) = limits.idle_timeout_minutes {
+        if usage.waiting_for_input {
+            verdicts.extend(time_verdict(
+                NativeLimitKind::IdleTimeout,
+                "Idle",
+                now - usage.last_activity_at,
+                minutes,
+            ));
+        }
+    }
+    if let Some(minutes) = limits.max_runtime_minutes {
+        verdicts.extend(time_verdict(
+            NativeLimitKind::MaxRuntime,
+            "Running",
+            now - usage.started_at,
+            minutes,
+        ));
+    }
+    if let (Some(max_cost), Some(cost)) = (limits.max_cost_usd, usage.cost_usd) {
+        let detail = format!("Spent ${:.2} of the ${:.2} limit.", cost, max_cost);
+        if cost >= max_cost {
+            verdicts.push(NativeLimitVerdict::Stop {
+                kind: NativeLimitKind::MaxCost,
+                detail,
+            });
+        } else if cost >= max_cost * COST_WARNING_RATIO {
+            verdicts.push(NativeLimitVerdict::Warn {
+                kind: NativeLimitKind::MaxCost,
+                detail,
+            });
+        }
+    }
+    verdicts
+}
+
+fn time_verdict(
+    kind: NativeLimitKind,
+    label: &str,
+    elapsed: ChronoDuration,
+    limit_minutes: u64,
+) -> Option<NativeLimitVerdict> {
+    let elapsed_minutes = elapsed.num_minutes().max(0) as u64;
+    let detail = format!(
+        "{} for {} min of the {} min limit.",
+        label, elapsed_minutes, limit_minutes
+    );
+    if elapsed >= ChronoDuration::minutes(limit_minutes as i64) {
+        return Some(NativeLimitVerdict::Stop { kind, detail });
+    }
+    let lead = (limit_minutes / 5).clamp(1, MAX_TIME_WARNING_LEAD_MINUTES);
+    (elapsed >= ChronoDuration::minutes(limit_minutes.saturating_sub(lead) as i64))
+        .then_some(NativeLimitVerdict::Warn { kind, detail })
+}
+
+#[cfg(target_os = "linux")]
+pub use linux_cgroup::HelperCgroup;
+
+#[cfg(target_os = "linux")]
+mod linux_cgroup {
+    use super::NativeSessionLimits;
+    use std::fs;
+    use std::io::ErrorKind;
+    use std::path::{Path, PathBuf};
+    use std::thread;
+    use std::time::Duration;
+
+    const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+    const CPU_PERIOD_US: u64 = 100_000;
+
+    /// Leaf that CCEM's own processes move into so that its cgroup can
+    /// enable controllers for the helper groups created beside the leaf.
+    const APP_LEAF: &str = "ccem-app";
+
+    /// A cgroup v2 group holding one helper's process tree, created below
+    /// this process's own group. Controllers are only ever enabled in that
+    /// group, never in the slice or scope above it.
+    #[derive(Debug)]
+    pub struct HelperCgroup {
+        path: PathBuf,
+    }
+
+    impl HelperCgroup {
+        pub fn create(name: &str) -> Result<Self, String> {
+            let root = delegated_root()?;
+            enable_controllers(&root, &["memory", "cpu"])?;
+
+            let path = root.join(name);
+            match fs::create_dir(&path) {
+                Ok(()) => {}
+                Err(error) if error.kind() == ErrorKind::AlreadyExists => {}
+                Err(error) => {
+                    return Err(format!(
+                        "Failed to create cgroup {}: {}",
+                        path.display(),
+                        error
+                    ))
+                }
+            }
+            Ok(Self { path })
+        }
+
+        pub fn apply(&self, limits: &NativeSessionLimits) -> Result<(), String> {
+            let memory_max = limits
+                .memory_limit_mb
+                .and_then(|mb| mb.checked_mul(1024 * 1024))
+                .map(|bytes| bytes.to_string())
+                .unwrap_or_else(|| "max".to_string());
+            self.write("memory.max", &memory_max)?;
+            self.write("cpu.max", &cpu_max_value(limits.cpu_limit_percent))
+        }
+
+        /// Move the given processes, the helper and the provider processes it
+        /// has already spawned, into the group. Later children inherit the
+        /// group on their own.
+        pub fn adopt(&self, pids: &[u32]) -> Result<(), String> {
+            let mut moved = 0;
+            for pid in pids {
+                match fs::write(self.path.join("cgroup.procs"), pid.to_string()) {
+                    Ok(()) => moved += 1,
+                    // The process exited between the scan and the move.
+                    Err(error) if error.raw_os_error() == Some(libc::ESRCH) => {}
+                    Err(error) => {
+                        return Err(format!(
+                            "Failed to move process {} into {}: {}",
+                            pid,
+                            self.path.display(),
+                            error
+                        ))
+                    }
+                }
+            }
+            if moved == 0 {
+                return Err(format!(
+                    "No helper process is left to move into {}",
+                    self.path.display()
+                ));
+            }
+            Ok(())
+        }
+
+        /// Kill whatever is left in the group and remove it. The directory
+        /// only goes away once the last member has exited.
+        pub fn remove(&self) {
+            let _ = fs::write(self.path.join("cgroup.kill"), "1");
+            for _ in 0..40 {
+                match fs::remove_dir(&self.path) {
+                    Ok(()) => return,
+                    Err(error) if error.kind() == ErrorKind::NotFound => return,
+                    Err(_) => thread::sleep(Duration::from_millis(50)),
+                }
+            }
+            eprintln!(
+                "Failed to remove native helper cgroup {}",
+                self.path.display()
+            );
+        }
+
+        fn write(&self, file: &str, value: &str) -> Result<(), String> {
+            fs::write(self.path.join(file), value).map_err(|error| {
+                format!(
+                    "Failed to write {} in {}: {}",
+                    file,
+                    self.path.display(),
+                    error
+                )
+            })
+        }
+    }
+
+    fn enable_controllers(parent: &Path, controllers: &[&str]) -> Result<(), String> {
+        let enabled = fs::read_to_string(parent.join("cgroup.subtree_control")).unwrap_or_default();
+        let available = fs::read_to_string(parent.join("cgroup.controllers")).unwrap_or_default();
+        for controller in controllers {
+            if enabled.split_whitespace().any(|item| item == *controller) {
+                continue;
+            }
+            if !available.split_whitespace().any(|item| item == *controller) {
+                return Err(format!(
+                    "The {} cgroup controller is not delegated to {}",
+                    controller,
+                    parent.display()
+                ));
+            }
+            fs::write(
+                parent.join("cgroup.subtree_control"),
+                format!("+{}", controller),
+            )
+            .map_err(|error| {
+                format!(
+                    "Failed to enable the {} controller in {}: {}",
+                    controller,
+                    parent.display(),
+                    error
+                )
+            })?;
+        }
+        Ok(())
+    }
+
+    /// The cgroup that helper groups are created in: CCEM's own group. A
+    /// group with member processes cannot enable controllers for its
+    /// children, so the first call moves those processes into the
+    /// `ccem-app` leaf; after that CCEM reports the leaf and its parent is
+    /// the root.
+    fn delegated_root() -> Result<PathBuf, String> {
+        let own = fs::read_to_string("/proc/self/cgroup")
+            .map_err(|error| format!("Failed to read /proc/self/cgroup: {}", error))?;
+        let relative = unified_cgroup_path(&own)
+            .ok_or_else(|| "cgroup v2 is not mounted for this process".to_string())?;
+        let own_dir = Path::new(CGROUP_ROOT).join(relative.trim_start_matches('/'));
+        if own_dir == Path::new(CGROUP_ROOT) {
+            return Err("CCEM runs in the root cgroup".to_string());
+        }
+        if own_dir.file_name().and_then(|name| name.to_str()) == Some(APP_LEAF) {
+            if let Some(parent) = own_dir
+                .parent()
+                .filter(|parent| *parent != Path::new(CGROUP_ROOT))
+            {
+                return Ok(parent.to_path_buf());
+            }
+        }
+
+        let leaf = own_dir.join(APP_LEAF);
+        match fs::create_dir(&leaf) {
+            Ok(()) => {}
+            Err(error) if error.kind() == ErrorKind::AlreadyExists => {}
+            Err(error) => {
+                return Err(format!(
+                    "Failed to create cgroup {}: {}",
+                    leaf.display(),
+                    error
+                ))
+            }
+        }
+        let members = fs::read_to_string(own_dir.join("cgroup.procs")).map_err(|error| {
+            format!(
+                "Failed to read the processes of {}: {}",
+                own_dir.display(),
+                error
+            )
+        })?;
+        for pid in members.split_whitespace() {
+            match fs::write(leaf.join("cgroup.procs"), pid) {
+                Ok(()) => {}
+                Err(error) if error.raw_os_error() == Some(libc::ESRCH) => {}
+                Err(error) => {
+                    return Err(format!(
+                        "Failed to move process {} into {}: {}",
+                        pid,
+                        leaf.display(),
+                        error
+                    ))
+                }
+            }
+        }
+        Ok(own_dir)
+    }
+
+    pub(super) fn unified_cgroup_path(content: &str) -> Option<&str> {
+        content
+            .lines()
+            .find_map(|line| line.strip_prefix("0::"))
+            .map(str::trim)
+            .filter(|path| path.starts_with('/'))
+    }
+
+    pub(super) fn cpu_max_value(percent: Option<u32>) -> String {
+        match percent {
+            Some(percent) => format!(
+                "{} {}",
+                u64::from(percent) * CPU_PERIOD_US / 100,
+                CPU_PERIOD_US
+            ),
+            None => format!("max {}", CPU_PERIOD_US),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn usage(idle_minutes: i64, running_minutes: i64, cost_usd: Option<f64>) -> NativeLimitUsage {
+        let now = now();
+        NativeLimitUsage {
+            started_at: now - ChronoDuration::minutes(running_minutes),
+            last_activity_at: now - ChronoDuration::minutes(idle_minutes),
+            cost_usd,
+            waiting_for_input: true,
+        }
+    }
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap()
+    }
+
+    fn kinds(verdicts: &[NativeLimitVerdict]) -> Vec<(&'static str, NativeLimitKind)> {
+        verdicts
+            .iter()
+            .map(|verdict| match verdict {
+                NativeLimitVerdict::Warn { kind, .. } => ("warn", *kind),
+                NativeLimitVerdict::Stop { kind, .. } => ("stop", *kind),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn session_limits_override_globals_and_zero_turns_a_limit_off() {
+        let global = NativeSessionLimits {
+            idle_timeout_minutes: Some(60),
+            max_cost_usd: Some(5.0),
+            ..NativeSessionLimits::default()
+        };
+        let session = NativeSessionLimits {
+            idle_timeout_minutes: Some(0),
+            max_runtime_minutes: Some(240),
+            ..NativeSessionLimits::default()
+        };
+
+        let effective = global.overlay(Some(&session));
+
+        assert_eq!(effective.idle_timeout_minutes, None);
+        assert_eq!(effective.max_runtime_minutes, Some(240));
+        assert_eq!(effective.max_cost_usd, Some(5.0));
+        assert_eq!(global.overlay(None), global);
+    }
+
+    #[test]
+    fn limits_warn_ahead_of_time_and_stop_once_reached() {
+        let limits = NativeSessionLimits {
+            idle_timeout_minutes: Some(30),
+            max_runtime_minutes: Some(120),
+            max_cost_usd: Some(2.0),
+            ..NativeSessionLimits::default()
+        };
+
+        assert!(evaluate_limits(&limits, &usage(10, 60, Some(0.5)), now()).is_empty());
+        assert_eq!(
+            kinds(&evaluate_limits(&limits, &usage(26, 60, Some(1.7)), now())),
+            vec![
+                ("warn", NativeLimitKind::IdleTimeout),
+                ("warn", NativeLimitKind::MaxCost),
+            ]
+        );
+        assert_eq!(
+            kinds(&evaluate_limits(&limits, &usage(1, 120, Some(2.0)), now())),
+            vec![
+                ("stop", NativeLimitKind::MaxRuntime),
+                ("stop", NativeLimitKind::MaxCost),
+            ]
+        );
+
+        let mut busy = usage(45, 10, None);
+        busy.waiting_for_input = false;
+        assert!(evaluate_limits(&limits, &busy, now()).is_empty());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn cgroup_helpers_parse_the_unified_hierarchy_and_cpu_quota() {
+        let content = "12:pids:/legacy\n0::/user.slice/user-1000.slice/app.slice/ccem.scope\n";
+        assert_eq!(
+            linux_cgroup::unified_cgroup_path(content),
+            Some("/user.slice/user-1000.slice/app.slice/ccem.scope")
+        );
+        assert_eq!(linux_cgroup::unified_cgroup_path("3:cpu:/\n"), None);
+        assert_eq!(linux_cgroup::cpu_max_value(Some(50)), "50000 100000");
+        assert_eq!(linux_cgroup::cpu_max_value(Some(200)), "200000 100000");
+        assert_eq!(linux_cgroup::cpu_max_value(None), "max 100000");
+    }
+
+    #[test]
+    fn memory_limits_are_bounded() {
+        let limits = |mb| NativeSessionLimits {
+            memory_limit_mb: Some(mb),
+            ..NativeSessionLimits::default()
+        };
+        assert!(limits(0).validate().is_ok());
+        assert!(limits(512).validate().is_ok());
+        assert!(limits(32).validate().is_err());
+        assert!(limits(u64::MAX / 1024).validate().is_err());
+    }
+}
@@ -1,21 +1,50 @@
 use crate::browser::{authorize_browser_tool, BrowserManager, BrowserToolRequest};
-use crate::config::{resolve_claude_env, resolve_codex_runtime};
+use crate::config::{read_settings, resolve_claude_env, resolve_codex_runtime};
 use crate::event_bus::{
     NativeBackgroundTask, NativeBackgroundTaskStatus, ReplayBatch, SessionEventPayload,
-    SessionPromptAnnotation, SessionPromptImage, SessionStore, TodoSnapshotV1,
+    SessionEventRecord, SessionPromptAnnotation, SessionPromptImage, SessionStore, TodoSnapshotV1,
+    DEFAULT_SESSION_EVENT_CAPACITY,
+};
+use crate::external_control::ExternalControlManager;
+use crate::git_worktree::{self, GitWorktree, WorktreeDiffStat};
+use crate::native_event_log::{NativeEventLog, RuntimeEventStorage};
+use crate::native_event_retention::{
+    compact_events, events_size, trim_events_to_size, NativeEventCompactionReport,
+    EVENT_LOG_MAINTENANCE_INTERVAL, EVENT_LOG_MAINTENANCE_STARTUP_DELAY,
+};
+use crate::native_fanout::{
+    build_member_comparison, fanout_member_outcome, NativeFanoutComparison, NativeFanoutGroup,
+    NativeFanoutGroups, NativeFanoutMember, NativeFanoutRequest, NativeFanoutTarget,
 };
-use crate::native_event_log::NativeEventLog;
 use crate::native_helper_resource::native_helper_script_path;
+#[cfg(target_os = "linux")]
+use crate::native_limits::HelperCgroup;
+use crate::native_limits::{
+    evaluate_limits, NativeLimitKind, NativeLimitUsage, NativeLimitVerdict, NativeSessionLimits,
+    LIMIT_WATCHDOG_INTERVAL,
+};
+use crate::native_session_archive::{
+    read_archive, rebind_events, referenced_images, write_archive, NativeArchivedImage,
+    NativeArchivedSession, NativeSessionArchive, NativeSessionExport, NativeSessionImport,
+    NATIVE_SESSION_ARCHIVE_FORMAT, NATIVE_SESSION_ARCHIVE_VERSION,
+};
+use crate::native_session_tree::{
+    compare_file_changes, divergent_segments, segment_events, segment_file_changes,
+    BranchPathEntry, NativeBranchComparison, NativeSessionTree, NativeSessionTreeNode,
+};
 use crate::prompt_image_store::PromptImageStore;
 use crate::router::{
     apply_session_router_patch, describe_router_environment, is_valid_router_environment_alias,
     validate_session_router_targets, LaunchAuthKind, LaunchTransport, RouterAuthCapability,
-    RouterConfig, RouterEnvironmentAuthKind, RouterManager, RouterServiceError,
+    RouterConfig, RouterEnvironmentAuthKind, RouterManager, RouterServiceError, SessionRouterPatch,
     SessionRouterRecord, SessionRouterState, SessionRouterUpdatedEvent, UpdateSessionRouterRequest,
     MY_DEFAULT_ROUTER_PROFILE_ID, OAUTH_ROUTING_VERIFIED,
 };
 use crate::secure_fs::write_private_atomic;
-use crate::session_provenance::bind_source_session_id;
+use crate::session_provenance::{
+    bind_source_session_id, find_fork_parent, list_forks_of, SessionForkLink,
+};
+use crate::sessions_mcp;
 use crate::system_proxy::resolve_codex_proxy_env;
 use crate::terminal::{self, resolve_claude_path, resolve_codex_path, TerminalType};
 use crate::workspace_decorations::AttentionSummary;
@@ -34,7 +63,7 @@ use std::path::{Path, PathBuf};
 use std::process::{ChildStdin, Command as StdCommand, Stdio};
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::OnceLock;
-use std::sync::{Arc, Mutex, RwLock};
+use std::sync::{mpsc, Arc, Mutex, RwLock};
 use std::thread;
 use std::time::Duration;
 #[cfg(unix)]
@@ -58,6 +87,10 @@ const MAX_PROMPT_ANNOTATIONS: usize = 20;
 const MAX_PROMPT_ANNOTATION_QUOTE_CHARS: usize = 12_000;
 const MAX_PROMPT_ANNOTATION_NOTE_CHARS: usize = 4_000;
 const MAX_PROMPT_ANNOTATION_TOTAL_CHARS: usize = 60_000;
+/// Quiet period a fan-out waits for after its last member finishes, so the
+/// trailing `SessionUsage` reports make it into the comparison.
+const FANOUT_SETTLE_INTERVAL: Duration = Duration::from_secs(3);
+const MAX_QUEUED_PROMPTS: usize = 20;
 
 fn is_background_task_shutdown_safety_error(message: &str) -> bool {
     message.starts_with("Cannot close this native runtime while ")
@@ -159,6 +192,34 @@ pub struct NativeSessionRecord {
     pub last_error: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub router: Option<SessionRouterRecord>,
+    /// Set when the session runs in its own worktree; `project_dir` then
+    /// points inside it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub worktree: Option<GitWorktree>,
+    /// Refreshed whenever a turn finishes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub worktree_diff_stat: Option<WorktreeDiffStat>,
+    /// Prompts sent while a turn was running, oldest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub prompt_queue: Vec<NativeQueuedPrompt>,
+    /// Per-session overrides of the global native session limits.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limits: Option<NativeSessionLimits>,
+    /// Set when the desktop app went away while this session was live and
+    /// it can be resumed through its provider session id. Cleared once the
+    /// session reconnects or the user dismisses the offer.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub restart_interrupted_at: Option<DateTime<Utc>>,
+    /// Set on read-only replay sessions imported from an archive; they are
+    /// never reconnected.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub imported: Option<NativeSessionImport>,
+}
+
+fn parse_fork_time(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|time| time.with_timezone(&Utc))
 }
 
 /// True while this app still owns the runtime's helper child process.
@@ -205,6 +266,92 @@ pub struct NativeSessionSummary {
     pub last_error: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub router: Option<SessionRouterState>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub worktree: Option<NativeSessionWorktree>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub prompt_queue: Vec<NativeQueuedPrompt>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limits: Option<NativeSessionLimits>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub restart_interrupted_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub imported: Option<NativeSessionImport>,
+}
+
+/// A session that was live when the desktop app last went away and can be
+/// resumed where its provider left off.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct NativeRecoveryCandidate {
+    pub runtime_id: String,
+    pub provider: NativeProvider,
+    pub provider_session_id: String,
+    pub project_dir: String,
+    pub env_name: String,
+    pub perm_mode: String,
+    /// Whether the session ran through the router and will be routed again.
+    pub routed: bool,
+    pub queued_prompts: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_event_seq: Option<u64>,
+    pub interrupted_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NativeWorktreeAction {
+    Merge,
+    Rebase,
+    Discard,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct NativeSessionWorktree {
+    pub branch: String,
+    pub path: String,
+    pub repo_root: String,
+    pub base_sha: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diff_stat: Option<WorktreeDiffStat>,
+    /// What the session can do with its branch right now; empty while a
+    /// turn is still running.
+    #[serde(default)]
+    pub actions: Vec<NativeWorktreeAction>,
+}
+
+/// A prompt held back because the session was busy. Images are kept in the
+/// prompt image store and re-read when the prompt is dispatched.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct NativeQueuedPrompt {
+    pub item_id: String,
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_text: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub images: Vec<SessionPromptImage>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<Vec<SessionPromptAnnotation>>,
+    pub queued_at: DateTime<Utc>,
+}
+
+fn session_worktree_summary(record: &NativeSessionRecord) -> Option<NativeSessionWorktree> {
+    let worktree = record.worktree.as_ref()?;
+    let actions = if native_status_allows_worktree_actions(&record.status) {
+        vec![
+            NativeWorktreeAction::Merge,
+            NativeWorktreeAction::Rebase,
+            NativeWorktreeAction::Discard,
+        ]
+    } else {
+        Vec::new()
+    };
+    Some(NativeSessionWorktree {
+        branch: worktree.branch.clone(),
+        path: worktree.path.clone(),
+        repo_root: worktree.repo_root.clone(),
+        base_sha: worktree.base_sha.clone(),
+        diff_stat: record.worktree_diff_stat.clone(),
+        actions,
+    })
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -240,6 +387,10 @@ pub struct NativeSessionOptions {
     pub perm_mode: String,
     pub runtime_perm_mode: Option<String>,
     pub working_dir: String,
+    /// Run in a fresh worktree of `working_dir` on branch `ccem/<runtime_id>`.
+    pub use_git_worktree: bool,
+    /// Overrides the global native session limits for this session.
+    pub limits: Option<NativeSessionLimits>,
     pub initial_prompt: Option<String>,
     pub display_prompt: Option<String>,
     pub initial_images: Option<Vec<PromptImage>>,
@@ -437,6 +588,8 @@ enum HelperInputCommand<'a> {
         todo_snapshot_seed: Option<&'a TodoSnapshotV1>,
         #[serde(skip_serializing_if = "Option::is_none")]
         router: Option<&'a HelperRouterInit>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        sessions_mcp: Option<&'a HelperSessionsMcpInit>,
     },
     Prompt {
         text: &'a str,
@@ -506,6 +659,16 @@ struct HelperRouterInit {
     menu: Option<String>,
 }
 
+/// Launch details for the `ccem-sessions` MCP server the helper registers
+/// with Claude and Codex; see `sessions_mcp`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct HelperSessionsMcpInit {
+    script_path: String,
+    endpoint: String,
+    token: String,
+}
+
 fn is_bypass_permission_mode(mode: &str) -> bool {
     matches!(mode, "yolo" | "bypassPermissions")
 }
@@ -539,6 +702,40 @@ fn authorize_browser_tool_for_record(
     )
 }
 
+/// Statuses in which the head of the prompt queue may be sent.
+fn native_status_dispatches_queued_prompts(status: &str) -> bool {
+    matches!(status, "idle" | "ready")
+}
+
+/// Move the queued items into the order given by `item_ids`, which must
+/// name every queued item exactly once.
+fn reorder_prompt_queue(
+    queue: &mut Vec<NativeQueuedPrompt>,
+    item_ids: &[String],
+) -> Result<(), String> {
+    if item_ids.len() != queue.len() {
+        return Err("Prompt queue changed; refresh it and try again".to_string());
+    }
+    let mut remaining = queue.clone();
+    let mut reordered = Vec::with_capacity(remaining.len());
+    for item_id in item_ids {
+        let index = remaining
+            .iter()
+            .position(|item| &item.item_id == item_id)
+            .ok_or_else(|| format!("Prompt {item_id} is not queued"))?;
+        reordered.push(remaining.remove(index));
+    }
+    *queue = reordered;
+    Ok(())
+}
+
+fn native_status_allows_worktree_actions(status: &str) -> bool {
+    matches!(
+        status,
+        "idle" | "ready" | "interrupted" | "closed_idle" | "stopped" | "error"
+    )
+}
+
 fn native_status_allows_file_rewind(status: &str) -> bool {
     matches!(status, "idle" | "ready" | "interrupted" | "closed_idle")
 }
@@ -912,6 +1109,8 @@ impl Drop for NativeHelperChild {
 struct NativeProcessTree {
     process_group_id: i32,
     terminated: Mutex<bool>,
+    #[cfg(target_os = "linux")]
+    cgroup: Mutex<Option<HelperCgroup>>,
 }
 
 fn terminate_process_tree_once(
@@ -952,6 +1151,8 @@ impl NativeProcessTree {
         Ok(Self {
             process_group_id,
             terminated: Mutex::new(false),
+            #[cfg(target_os = "linux")]
+            cgroup: Mutex::new(None),
         })
     }
 
@@ -1093,6 +1294,95 @@ impl NativeProcessTree {
     }
 }
 
+#[cfg(target_os = "linux")]
+impl NativeProcessTree {
+    /// Move the helper into its own cgroup with the given memory and CPU
+    /// ceiling, or update the ceiling of the cgroup it already runs in.
+    fn apply_resource_ceiling(
+        &self,
+        name: &str,
+        limits: &NativeSessionLimits,
+    ) -> Result<(), String> {
+        let mut cgroup = self
+            .cgroup
+            .lock()
+            .map_err(|_| "Failed to lock native helper cgroup".to_string())?;
+        if let Some(cgroup) = cgroup.as_ref() {
+            return cgroup.apply(limits);
+        }
+        if !limits.has_resource_ceiling() {
+            return Ok(());
+        }
+        let created = HelperCgroup::create(name)?;
+        // A ceiling set on a running session also has to catch the provider
+        // processes the helper already spawned, not just the helper itself.
+        if let Err(error) = created
+            .apply(limits)
+            .and_then(|()| self.member_pids())
+            .and_then(|pids| created.adopt(&pids))
+        {
+            created.remove();
+            return Err(error);
+        }
+        *cgroup = Some(created);
+        Ok(())
+    }
+
+    /// Every live process of the helper's process group: the helper and the
+    /// provider processes it spawned.
+    fn member_pids(&self) -> Result<Vec<u32>, String> {
+        let entries =
+            fs::read_dir("/proc").map_err(|error| format!("Failed to list /proc: {}", error))?;
+        Ok(entries
+            .filter_map(Result::ok)
+            .filter_map(|entry| entry.file_name().to_str()?.parse::<u32>().ok())
+            .filter(|pid| {
+                fs::read_to_string(format!("/proc/{}/stat", pid))
+                    .ok()
+                    .and_then(|stat| linux_stat_process_group(&stat))
+                    == Some(self.process_group_id)
+            })
+            .collect())
+    }
+
+    fn release_resource_ceiling(&self) {
+        let cgroup = self
+            .cgroup
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .take();
+        if let Some(cgroup) = cgroup {
+            cgroup.remove();
+        }
+    }
+}
+
+/// The process group field of `/proc/<pid>/stat`. The command name in
+/// parentheses may itself contain spaces and parentheses, so fields are
+/// counted from the last `)`.
+#[cfg(target_os = "linux")]
+fn linux_stat_process_group(stat: &str) -> Option<i32> {
+    let (_, fields) = stat.rsplit_once(')')?;
+    // state, ppid, pgrp
+    fields.split_whitespace().nth(2)?.parse().ok()
+}
+
+#[cfg(not(target_os = "linux"))]
+impl NativeProcessTree {
+    fn apply_resource_ceiling(
+        &self,
+        _name: &str,
+        limits: &NativeSessionLimits,
+    ) -> Result<(), String> {
+        if !limits.has_resource_ceiling() {
+            return Ok(());
+        }
+        Err("Memory and CPU ceilings need cgroup v2 on Linux".to_string())
+    }
+
+    fn release_resource_ceiling(&self) {}
+}
+
 fn spawn_native_helper_process(
     mut command: StdCommand,
 ) -> Result<(Receiver<CommandEvent>, NativeHelperChild), String> {
@@ -1165,6 +1455,7 @@ fn spawn_native_helper_process(
     thread::spawn(move || {
         let wait_result = wait_child.wait();
         let cleanup_result = wait_tree.kill();
+        wait_tree.release_resource_ceiling();
         let _drained = drain_guard
             .write()
             .unwrap_or_else(|poisoned| poisoned.into_inner());
@@ -1280,6 +1571,57 @@ fn native_process_exists(pid: u32) -> bool {
     result == 0 || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
 }
 
+/// Usage the limit watchdog has seen from one helper generation.
+struct NativeLimitTracker {
+    generation: u64,
+    usage: NativeLimitUsage,
+    warned: HashSet<NativeLimitKind>,
+    stopping: bool,
+}
+
+impl NativeLimitTracker {
+    fn new(generation: u64) -> Self {
+        let now = Utc::now();
+        Self {
+            generation,
+            usage: NativeLimitUsage {
+                started_at: now,
+                last_activity_at: now,
+                cost_usd: None,
+                waiting_for_input: false,
+            },
+            warned: HashSet::new(),
+            stopping: false,
+        }
+    }
+}
+
+/// What an event tells the limit watchdog.
+enum NativeLimitSignal {
+    Activity,
+    Cost(f64),
+    Ignored,
+}
+
+fn native_limit_signal(payload: &SessionEventPayload) -> NativeLimitSignal {
+    match payload {
+        SessionEventPayload::SessionUsage { cost_usd, .. } => {
+            cost_usd.map_or(NativeLimitSignal::Ignored, NativeLimitSignal::Cost)
+        }
+        SessionEventPayload::TokenUsage { total_cost_usd, .. } => {
+            total_cost_usd.map_or(NativeLimitSignal::Ignored, NativeLimitSignal::Cost)
+        }
+        SessionEventPayload::StdErrLine { .. }
+        | SessionEventPayload::ContextUsage { .. }
+        | SessionEventPayload::RoutedRequest { .. }
+        | SessionEventPayload::GapNotification { .. } => NativeLimitSignal::Ignored,
+        SessionEventPayload::Lifecycle { stage, .. } if stage.starts_with("limit_") => {
+            NativeLimitSignal::Ignored
+        }
+        _ => NativeLimitSignal::Activity,
+    }
+}
+
 struct NativeSessionHandle {
     generation: u64,
     record: Mutex<NativeSessionRecord>,
@@ -1314,6 +1656,7 @@ impl NativeSessionHandle {
             .map(|tasks| tasks.values().cloned().collect::<Vec<_>>())
             .unwrap_or_default();
         background_tasks.sort_by_key(|task| task.started_at);
+        let worktree = session_worktree_summary(&record);
         NativeSessionSummary {
             runtime_id: record.runtime_id,
             provider: record.provider,
@@ -1336,6 +1679,11 @@ impl NativeSessionHandle {
             background_tasks,
             last_error: record.last_error,
             router: record.router.as_ref().map(SessionRouterState::from),
+            worktree,
+            prompt_queue: record.prompt_queue,
+            limits: record.limits,
+            restart_interrupted_at: record.restart_interrupted_at,
+            imported: record.imported,
         }
     }
 }
@@ -1435,6 +1783,26 @@ pub struct NativeRuntimeManager {
     app_termination_lock: Mutex<()>,
     app_termination_in_progress: AtomicBool,
     terminal_handoff_preparations: Mutex<HashMap<String, String>>,
+    event_subscribers: Mutex<Vec<NativeEventSubscriber>>,
+    sessions_mcp_tokens: Mutex<HashMap<String, String>>,
+    /// Serialises the busy check, enqueueing and dispatch of queued prompts.
+    prompt_queue_lock: Mutex<()>,
+    limit_trackers: Mutex<HashMap<String, NativeLimitTracker>>,
+}
+
+/// Live feed of appended events for one external subscriber. The channel is
+/// bounded by the session ring-buffer capacity; a subscriber that falls that
+/// far behind is flagged as lagged instead of blocking the runtime, and is
+/// expected to resynchronise through `replay_events_limited`.
+pub struct NativeEventSubscription {
+    pub receiver: mpsc::Receiver<SessionEventRecord>,
+    pub lagged: Arc<AtomicBool>,
+}
+
+struct NativeEventSubscriber {
+    runtime_ids: HashSet<String>,
+    sender: mpsc::SyncSender<SessionEventRecord>,
+    lagged: Arc<AtomicBool>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -1565,6 +1933,10 @@ impl NativeRuntimeManager {
             app_termination_lock: Mutex::new(()),
             app_termination_in_progress: AtomicBool::new(false),
             terminal_handoff_preparations: Mutex::new(HashMap::new()),
+            event_subscribers: Mutex::new(Vec::new()),
+            sessions_mcp_tokens: Mutex::new(HashMap::new()),
+            prompt_queue_lock: Mutex::new(()),
+            limit_trackers: Mutex::new(HashMap::new()),
         })
     }
 
@@ -1672,6 +2044,16 @@ impl NativeRuntimeManager {
         let runtime_id = generate_runtime_id();
         inject_ccem_runtime_env(&mut options.helper_env_vars, &runtime_id);
         inject_ccem_runtime_env(&mut options.terminal_env_vars, &runtime_id);
+        let worktree = if options.use_git_worktree {
+            let worktree = git_worktree::create_worktree(
+                &options.working_dir,
+                &session_worktree_branch(&runtime_id),
+            )?;
+            options.working_dir = worktree.session_dir.clone();
+            Some(worktree)
+        } else {
+            None
+        };
         let now = Utc::now();
         let record = NativeSessionRecord {
             runtime_id: runtime_id.clone(),
@@ -1696,6 +2078,12 @@ impl NativeRuntimeManager {
             pending_handoff_allow_background_task_termination: false,
             last_error: None,
             router: options.router_record.clone(),
+            worktree: worktree.clone(),
+            worktree_diff_stat: None,
+            prompt_queue: Vec::new(),
+            limits: options.limits.clone(),
+            restart_interrupted_at: None,
+            imported: None,
         };
 
         let handle = Arc::new(NativeSessionHandle {
@@ -1725,15 +2113,17 @@ impl NativeRuntimeManager {
                 .as_ref()
                 .filter(|router| router.launch_transport == LaunchTransport::Routed),
         ) {
-            manager
-                .register(&runtime_id, handle.generation, router.clone())
-                .map_err(|error| error.to_string())?;
+            if let Err(error) = manager.register(&runtime_id, handle.generation, router.clone()) {
+                remove_abandoned_worktree(worktree.as_ref());
+                return Err(error.to_string());
+            }
         }
 
         if let Err(error) = self.insert_record(record) {
             if let Some(manager) = self.router_manager.get() {
                 manager.unregister_generation(&runtime_id, handle.generation);
             }
+            remove_abandoned_worktree(worktree.as_ref());
             return Err(error);
         }
         let launch_result = (|| {
@@ -1765,100 +2155,597 @@ impl NativeRuntimeManager {
                 let _ = self.kill_child(&runtime_id);
                 let _ = self.remove_handle(&runtime_id);
                 let _ = self.remove_record(&runtime_id);
+                remove_abandoned_worktree(worktree.as_ref());
                 Err(error)
             }
         }
     }
 
-    fn prepare_router_launch(
-        &self,
-        options: &mut NativeSessionOptions,
-        recovering: bool,
-    ) -> Result<(), String> {
-        if options.provider != NativeProvider::Claude {
-            if options.router_launch_draft.is_some() {
-                return Err(
-                    "ROUTER_PROVIDER_UNSUPPORTED: dynamic routing is only available for Claude sessions"
-                        .to_string(),
-                );
+    /// Launch one prompt as sibling sessions, one per target, each in its own
+    /// worktree of `request.working_dir`. Either every member starts or the
+    /// ones already launched are stopped and their worktrees removed.
+    pub fn create_fanout(
+        self: &Arc<Self>,
+        app: AppHandle,
+        request: NativeFanoutRequest,
+    ) -> Result<NativeFanoutGroup, String> {
+        let perm_mode = request
+            .perm_mode
+            .clone()
+            .unwrap_or_else(|| "dev".to_string());
+        // Resolve every environment before touching the repository.
+        let member_options = request
+            .targets
+            .iter()
+            .map(|target| {
+                build_fanout_member_options(
+                    target,
+                    &request.working_dir,
+                    &perm_mode,
+                    &request.prompt,
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let group_id = generate_fanout_group_id();
+        let mut members = Vec::new();
+        let mut failure = None;
+        for (target, options) in request.targets.iter().zip(member_options) {
+            let launched = self
+                .create_session(app.clone(), options)
+                .and_then(|summary| {
+                    let worktree = self
+                        .session_worktree(&summary.runtime_id)?
+                        .ok_or_else(|| "Fan-out session started without a worktree".to_string())?;
+                    Ok((summary, worktree))
+                });
+            match launched {
+                Ok((summary, worktree)) => members.push(NativeFanoutMember {
+                    runtime_id: summary.runtime_id,
+                    provider: target.provider,
+                    env_name: summary.env_name,
+                    worktree,
+                    started_at: summary.created_at,
+                }),
+                Err(error) => {
+                    failure = Some(format!(
+                        "{} ({}): {}",
+                        target.env_name,
+                        target.provider.as_str(),
+                        error
+                    ));
+                    break;
+                }
             }
-            options.router_record = None;
-            return Ok(());
         }
 
-        if let Some(existing) = options.router_record.as_mut() {
-            options.router_launch_draft = None;
-            if existing.launch_transport == LaunchTransport::Direct {
-                let warnings = existing.warnings.clone();
-                prepare_direct_router_launch(options, None)?;
-                if let Some(router) = options.router_record.as_mut() {
-                    router.warnings = warnings;
-                }
-                return Ok(());
-            }
-            let manager = self.router_manager.get().ok_or_else(|| {
-                "ROUTER_UNAVAILABLE: routed session recovery requires the router manager"
-                    .to_string()
-            })?;
-            let status = manager.status();
-            let unavailable_reason = if status.actual_port.is_none() {
-                status
-                    .error
-                    .clone()
-                    .unwrap_or_else(|| "Router listener is unavailable.".to_string())
-            } else if existing.router_auth_capability == RouterAuthCapability::Oauth
-                && !OAUTH_ROUTING_VERIFIED
-            {
-                "OAuth routing is not verified for this runtime.".to_string()
-            } else {
-                "Router launch decision rejected recovery without a readiness reason.".to_string()
-            };
+        let group = NativeFanoutGroup {
+            group_id,
+            prompt: request.prompt,
+            project_dir: request.working_dir,
+            perm_mode,
+            created_at: Utc::now(),
+            members,
+            completed_at: None,
+            adopted_runtime_id: None,
+        };
+        if let Some(error) = failure {
+            self.abandon_fanout_members(&group.members);
+            return Err(error);
+        }
+        if let Err(error) = NativeFanoutGroups::insert(group.clone()) {
+            self.abandon_fanout_members(&group.members);
+            return Err(error);
+        }
+        self.spawn_fanout_watcher(app, group.clone());
+        Ok(group)
+    }
 
-            match router_launch_decision(
-                Some(existing.launch_transport),
-                false,
-                status.actual_port.is_some(),
-                existing.router_auth_capability,
-                OAUTH_ROUTING_VERIFIED,
-            ) {
-                RouterLaunchDecision::RejectUnavailable => {
-                    return Err(if recovering {
-                        format!(
-                            "ROUTER_UNAVAILABLE: routed session recovery requires the router listener ({unavailable_reason})"
-                        )
-                    } else {
-                        format!("ROUTER_UNAVAILABLE: {unavailable_reason}")
-                    });
+    pub fn fanout_comparison(&self, group_id: &str) -> Result<NativeFanoutComparison, String> {
+        let group = NativeFanoutGroups::load().get(group_id)?.clone();
+        let mut members = Vec::with_capacity(group.members.len());
+        for member in &group.members {
+            let summary = self.get_session_summary(&member.runtime_id)?;
+            let terminal = summary
+                .as_ref()
+                .is_some_and(|summary| is_native_terminal_status(&summary.status));
+            let outcome = fanout_member_outcome(&self.event_log, &member.runtime_id)?;
+            members.push(build_member_comparison(
+                member,
+                summary.as_ref(),
+                terminal,
+                outcome,
+            ));
+        }
+        Ok(NativeFanoutComparison {
+            group_id: group.group_id,
+            prompt: group.prompt,
+            project_dir: group.project_dir,
+            created_at: group.created_at,
+            all_finished: members.iter().all(|member| member.finished),
+            adopted_runtime_id: group.adopted_runtime_id,
+            members,
+        })
+    }
+
+    /// Fork tree containing `runtime_id`, rooted at its topmost ancestor.
+    pub fn session_tree(&self, runtime_id: &str) -> Result<NativeSessionTree, String> {
+        let path = self.branch_path(runtime_id)?;
+        let root_runtime_id = path
+            .first()
+            .map(|(root, _)| root.clone())
+            .unwrap_or_else(|| runtime_id.to_string());
+        let mut visited = HashSet::new();
+        let root = self.session_tree_node(&root_runtime_id, None, &mut visited)?;
+        Ok(NativeSessionTree {
+            root_runtime_id,
+            branch_count: visited.len(),
+            root,
+        })
+    }
+
+    /// Files each branch touched after the two diverged. Shared history is
+    /// left out, so a file only shows up if one of the branches changed it
+    /// on its own.
+    pub fn compare_branches(
+        &self,
+        left_runtime_id: &str,
+        right_runtime_id: &str,
+    ) -> Result<NativeBranchComparison, String> {
+        let left_path = self.branch_path(left_runtime_id)?;
+        let right_path = self.branch_path(right_runtime_id)?;
+        let divergence = divergent_segments(&left_path, &right_path).ok_or_else(|| {
+            format!(
+                "Native sessions {} and {} are not branches of the same conversation",
+                left_runtime_id, right_runtime_id
+            )
+        })?;
+        let mut changes = Vec::with_capacity(2);
+        for segments in [&divergence.left, &divergence.right] {
+            let mut files = Vec::new();
+            for segment in segments {
+                let batch = self.event_log.replay(&segment.runtime_id, None, None)?;
+                let batch = segment_events(batch, segment);
+                files.extend(segment_file_changes(&segment.runtime_id, &batch));
+            }
+            changes.push(files);
+        }
+        let right = changes.pop().unwrap_or_default();
+        let left = changes.pop().unwrap_or_default();
+        Ok(NativeBranchComparison {
+            left_runtime_id: left_runtime_id.to_string(),
+            right_runtime_id: right_runtime_id.to_string(),
+            common_runtime_id: divergence.common_runtime_id,
+            diverged_at: divergence.diverged_at,
+            files: compare_file_changes(left, right),
+        })
+    }
+
+    /// Most recent other runtime bound to `provider_session_id`, for fork
+    /// links recorded before the parent runtime id was known.
+    pub fn runtime_for_provider_session(
+        &self,
+        provider_session_id: &str,
+        exclude_runtime_id: &str,
+    ) -> Option<String> {
+        let records = self.records.lock().ok()?;
+        let created_before = records
+            .get(exclude_runtime_id)
+            .map(|record| record.created_at);
+        records
+            .values()
+            .filter(|record| {
+                record.runtime_id != exclude_runtime_id
+                    && record.provider_session_id.as_deref() == Some(provider_session_id)
+                    && created_before.is_none_or(|created_at| record.created_at <= created_at)
+            })
+            .max_by_key(|record| record.created_at)
+            .map(|record| record.runtime_id.clone())
+    }
+
+    /// Runtimes from the tree root down to `runtime_id`, each with the point
+    /// in its parent's conversation it was forked at.
+    fn branch_path(&self, runtime_id: &str) -> Result<Vec<BranchPathEntry>, String> {
+        let mut path = Vec::new();
+        let mut seen = HashSet::new();
+        let mut current = runtime_id.to_string();
+        loop {
+            if !seen.insert(current.clone()) {
+                break;
+            }
+            let parent = find_fork_parent(&current)?.and_then(|link| {
+                let parent_runtime_id = link.parent_runtime_id.clone().or_else(|| {
+                    self.runtime_for_provider_session(&link.parent_session_id, &current)
+                })?;
+                Some((parent_runtime_id, link))
+            });
+            match parent {
+                Some((parent_runtime_id, link)) => {
+                    let forked_at = self.fork_point(&parent_runtime_id, &link);
+                    path.push((current, forked_at));
+                    current = parent_runtime_id;
                 }
-                RouterLaunchDecision::LaunchRouted => {}
-                RouterLaunchDecision::LaunchDirect | RouterLaunchDecision::Bypass => {
-                    unreachable!("persisted routed sessions cannot bypass or launch direct")
+                None => {
+                    path.push((current, None));
+                    break;
                 }
             }
+        }
+        path.reverse();
+        if let Some(root) = path.first_mut() {
+            root.1 = None;
+        }
+        Ok(path)
+    }
 
-            let actual_port = status
-                .actual_port
-                .expect("router readiness checked before routed recovery");
-            let source_env = match existing.router_auth_capability {
-                RouterAuthCapability::Oauth => crate::config::OFFICIAL_ENV_NAME,
-                RouterAuthCapability::Token => existing.default_env.as_str(),
-            };
-            let source =
-                describe_router_environment(source_env).map_err(|error| error.to_string())?;
-            match (existing.router_auth_capability, source.auth_kind) {
-                (RouterAuthCapability::Oauth, RouterEnvironmentAuthKind::RequiresOauth)
-                | (RouterAuthCapability::Token, RouterEnvironmentAuthKind::Token) => {}
-                _ => {
-                    return Err(
-                        "ROUTER_AUTH_CHANGED: helper generation auth source no longer matches the persisted capability"
-                            .to_string(),
-                    )
-                }
-            }
-            validate_session_router_targets(existing, OAUTH_ROUTING_VERIFIED)
-                .map_err(|error| error.to_string())?;
-            let resolved_source = resolve_claude_env(source_env)?;
-            options.helper_env_vars = resolved_source.env_vars;
+    /// When the parent reported the fork message, if it did, otherwise the
+    /// time the fork was recorded.
+    fn fork_point(&self, parent_runtime_id: &str, link: &SessionForkLink) -> Option<DateTime<Utc>> {
+        link.fork_message_id
+            .as_deref()
+            .and_then(|message_id| {
+                self.event_log
+                    .assistant_message_time(parent_runtime_id, message_id)
+                    .ok()
+                    .flatten()
+            })
+            .or_else(|| parse_fork_time(&link.created_at))
+    }
+
+    fn session_tree_node(
+        &self,
+        runtime_id: &str,
+        link: Option<&SessionForkLink>,
+        visited: &mut HashSet<String>,
+    ) -> Result<NativeSessionTreeNode, String> {
+        visited.insert(runtime_id.to_string());
+        let summary = self.get_session_summary(runtime_id)?;
+        let provider_session_id = summary
+            .as_ref()
+            .and_then(|summary| summary.provider_session_id.clone());
+        let mut children = Vec::new();
+        for child_link in list_forks_of(runtime_id, provider_session_id.as_deref())? {
+            if visited.contains(&child_link.ccem_session_id) {
+                continue;
+            }
+            children.push(self.session_tree_node(
+                &child_link.ccem_session_id,
+                Some(&child_link),
+                visited,
+            )?);
+        }
+        Ok(NativeSessionTreeNode {
+            runtime_id: runtime_id.to_string(),
+            provider: summary.as_ref().map(|summary| summary.provider),
+            status: summary.as_ref().map(|summary| summary.status.clone()),
+            is_active: summary.as_ref().is_some_and(|summary| summary.is_active),
+            provider_session_id,
+            fork_message_id: link.and_then(|link| link.fork_message_id.clone()),
+            seed_boundary_message_count: link.and_then(|link| link.seed_boundary_message_count),
+            forked_at: link.and_then(|link| parse_fork_time(&link.created_at)),
+            cost_usd: self.event_log.latest_session_cost(runtime_id)?,
+            last_message: self.event_log.last_assistant_reply(runtime_id)?,
+            children,
+        })
+    }
+
+    /// Write the runtime's full event log, the prompt images it refers to
+    /// and its session metadata to a single archive at `path`.
+    pub fn export_session_archive(
+        &self,
+        runtime_id: &str,
+        path: &Path,
+    ) -> Result<NativeSessionExport, String> {
+        let record = self
+            .records
+            .lock()
+            .map_err(|_| "Failed to lock native runtime records".to_string())?
+            .get(runtime_id)
+            .cloned()
+            .ok_or_else(|| format!("Native runtime {} not found", runtime_id))?;
+        let events = self.event_log.replay(runtime_id, None, None)?.events;
+        let referenced = referenced_images(&events);
+        let images = referenced
+            .iter()
+            .filter_map(|(storage_path, media_type)| {
+                let base64_data = self.prompt_image_store.read_base64(storage_path).ok()?;
+                Some(NativeArchivedImage {
+                    storage_path: storage_path.clone(),
+                    media_type: media_type.clone(),
+                    base64_data,
+                })
+            })
+            .collect::<Vec<_>>();
+        let archive = NativeSessionArchive {
+            format: NATIVE_SESSION_ARCHIVE_FORMAT.to_string(),
+            version: NATIVE_SESSION_ARCHIVE_VERSION,
+            exported_at: Utc::now(),
+            session: NativeArchivedSession {
+                runtime_id: record.runtime_id,
+                provider: record.provider,
+                provider_session_id: record.provider_session_id,
+                project_dir: record.project_dir,
+                env_name: record.env_name,
+                perm_mode: record.perm_mode,
+                effort: record.effort,
+                status: record.status,
+                created_at: record.created_at,
+                updated_at: record.updated_at,
+                last_error: record.last_error,
+            },
+            events,
+            images,
+        };
+        write_archive(path, &archive)?;
+        Ok(NativeSessionExport {
+            path: path.to_string_lossy().to_string(),
+            event_count: archive.events.len(),
+            image_count: archive.images.len(),
+            missing_image_count: referenced.len() - archive.images.len(),
+        })
+    }
+
+    /// Register an exported archive as a read-only replay session under a
+    /// fresh runtime id.
+    pub fn import_session_archive(&self, path: &Path) -> Result<NativeSessionSummary, String> {
+        let archive = read_archive(path)?;
+        let mut image_paths = HashMap::new();
+        for image in &archive.images {
+            let stored = self
+                .prompt_image_store
+                .store_base64_image(&image.media_type, &image.base64_data)?;
+            image_paths.insert(image.storage_path.clone(), stored.storage_path);
+        }
+        let runtime_id = generate_runtime_id();
+        let session = archive.session;
+        let record = NativeSessionRecord {
+            runtime_id: runtime_id.clone(),
+            provider: session.provider,
+            transport: NativeTransport::NativeSdk,
+            provider_session_id: None,
+            seed_boundary_message_count: None,
+            project_dir: session.project_dir,
+            env_name: session.env_name,
+            perm_mode: session.perm_mode,
+            runtime_perm_mode: None,
+            effort: session.effort,
+            pending_env_name: None,
+            pending_effort: None,
+            pending_settings_request_id: None,
+            status: if is_native_terminal_status(&session.status) {
+                session.status
+            } else {
+                "stopped".to_string()
+            },
+            created_at: session.created_at,
+            updated_at: session.updated_at,
+            is_active: false,
+            can_handoff_to_terminal: false,
+            pending_handoff_terminal: None,
+            pending_handoff_allow_background_task_termination: false,
+            last_error: session.last_error,
+            router: None,
+            worktree: None,
+            worktree_diff_stat: None,
+            prompt_queue: Vec::new(),
+            limits: None,
+            restart_interrupted_at: None,
+            imported: Some(NativeSessionImport {
+                source_runtime_id: session.runtime_id,
+                source_provider_session_id: session.provider_session_id,
+                exported_at: archive.exported_at,
+                imported_at: Utc::now(),
+            }),
+        };
+        self.insert_record(record)?;
+        let events = rebind_events(archive.events, &runtime_id, &image_paths);
+        if let Err(error) = self.event_log.import_records(&runtime_id, &events) {
+            let _ = self.remove_record(&runtime_id);
+            return Err(error);
+        }
+        self.get_session_summary(&runtime_id)?
+            .ok_or_else(|| format!("Imported native runtime {} disappeared", runtime_id))
+    }
+
+    pub fn session_worktree(&self, runtime_id: &str) -> Result<Option<GitWorktree>, String> {
+        Ok(self
+            .records
+            .lock()
+            .map_err(|_| "Failed to lock native runtime records".to_string())?
+            .get(runtime_id)
+            .and_then(|record| record.worktree.clone()))
+    }
+
+    fn abandon_fanout_members(self: &Arc<Self>, members: &[NativeFanoutMember]) {
+        for member in members {
+            if let Err(error) =
+                self.apply_worktree_action(&member.runtime_id, NativeWorktreeAction::Discard)
+            {
+                eprintln!(
+                    "Failed to discard fan-out member {}: {}",
+                    member.runtime_id, error
+                );
+            }
+        }
+    }
+
+    /// Follow the group's events until every member has finished its first
+    /// turn (or stopped), then emit `native-fanout-completed` with the
+    /// comparison.
+    fn spawn_fanout_watcher(self: &Arc<Self>, app: AppHandle, group: NativeFanoutGroup) {
+        let manager = Arc::clone(self);
+        thread::spawn(move || {
+            let runtime_ids: Vec<String> = group
+                .members
+                .iter()
+                .map(|member| member.runtime_id.clone())
+                .collect();
+            let subscription = match manager.subscribe_events(&runtime_ids) {
+                Ok(subscription) => subscription,
+                Err(error) => {
+                    eprintln!("Failed to watch fan-out {}: {}", group.group_id, error);
+                    return;
+                }
+            };
+            let mut pending: HashSet<String> = runtime_ids.into_iter().collect();
+            let mut recheck_logs = true;
+            while !pending.is_empty() {
+                if recheck_logs || subscription.lagged.swap(false, Ordering::Relaxed) {
+                    recheck_logs = false;
+                    pending.retain(|runtime_id| {
+                        manager
+                            .event_log
+                            .first_turn_end(runtime_id)
+                            .map(|end| end.is_none())
+                            .unwrap_or(true)
+                    });
+                    continue;
+                }
+                match subscription.receiver.recv_timeout(FANOUT_SETTLE_INTERVAL) {
+                    Ok(record) => {
+                        let finished = match &record.payload {
+                            SessionEventPayload::Lifecycle { stage, .. } => {
+                                stage == "turn_completed"
+                            }
+                            SessionEventPayload::SessionCompleted { .. } => true,
+                            _ => false,
+                        };
+                        if finished {
+                            pending.remove(&record.runtime_id);
+                        }
+                    }
+                    Err(mpsc::RecvTimeoutError::Timeout) => {
+                        pending.retain(|runtime_id| {
+                            match manager.get_session_summary(runtime_id) {
+                                Ok(Some(summary)) => !is_native_terminal_status(&summary.status),
+                                Ok(None) => false,
+                                Err(_) => true,
+                            }
+                        });
+                    }
+                    Err(mpsc::RecvTimeoutError::Disconnected) => break,
+                }
+            }
+            while subscription
+                .receiver
+                .recv_timeout(FANOUT_SETTLE_INTERVAL)
+                .is_ok()
+            {}
+            manager.unsubscribe_events(&subscription);
+            if !pending.is_empty() {
+                return;
+            }
+
+            if let Err(error) = NativeFanoutGroups::modify(&group.group_id, |group| {
+                group.completed_at = Some(Utc::now());
+            }) {
+                eprintln!(
+                    "Failed to mark fan-out {} complete: {}",
+                    group.group_id, error
+                );
+            }
+            match manager.fanout_comparison(&group.group_id) {
+                Ok(comparison) => {
+                    if let Err(error) = app.emit("native-fanout-completed", comparison) {
+                        eprintln!(
+                            "Failed to emit fan-out {} comparison: {}",
+                            group.group_id, error
+                        );
+                    }
+                }
+                Err(error) => eprintln!("Failed to compare fan-out {}: {}", group.group_id, error),
+            }
+        });
+    }
+
+    fn prepare_router_launch(
+        &self,
+        options: &mut NativeSessionOptions,
+        recovering: bool,
+    ) -> Result<(), String> {
+        if options.provider != NativeProvider::Claude {
+            if options.router_launch_draft.is_some() {
+                return Err(
+                    "ROUTER_PROVIDER_UNSUPPORTED: dynamic routing is only available for Claude sessions"
+                        .to_string(),
+                );
+            }
+            options.router_record = None;
+            return Ok(());
+        }
+
+        if let Some(existing) = options.router_record.as_mut() {
+            options.router_launch_draft = None;
+            if existing.launch_transport == LaunchTransport::Direct {
+                let warnings = existing.warnings.clone();
+                prepare_direct_router_launch(options, None)?;
+                if let Some(router) = options.router_record.as_mut() {
+                    router.warnings = warnings;
+                }
+                return Ok(());
+            }
+            let manager = self.router_manager.get().ok_or_else(|| {
+                "ROUTER_UNAVAILABLE: routed session recovery requires the router manager"
+                    .to_string()
+            })?;
+            let status = manager.status();
+            let unavailable_reason = if status.actual_port.is_none() {
+                status
+                    .error
+                    .clone()
+                    .unwrap_or_else(|| "Router listener is unavailable.".to_string())
+            } else if existing.router_auth_capability == RouterAuthCapability::Oauth
+                && !OAUTH_ROUTING_VERIFIED
+            {
+                "OAuth routing is not verified for this runtime.".to_string()
+            } else {
+                "Router launch decision rejected recovery without a readiness reason.".to_string()
+            };
+
+            match router_launch_decision(
+                Some(existing.launch_transport),
+                false,
+                status.actual_port.is_some(),
+                existing.router_auth_capability,
+                OAUTH_ROUTING_VERIFIED,
+            ) {
+                RouterLaunchDecision::RejectUnavailable => {
+                    return Err(if recovering {
+                        format!(
+                            "ROUTER_UNAVAILABLE: routed session recovery requires the router listener ({unavailable_reason})"
+                        )
+                    } else {
+                        format!("ROUTER_UNAVAILABLE: {unavailable_reason}")
+                    });
+                }
+                RouterLaunchDecision::LaunchRouted => {}
+                RouterLaunchDecision::LaunchDirect | RouterLaunchDecision::Bypass => {
+                    unreachable!("persisted routed sessions cannot bypass or launch direct")
+                }
+            }
+
+            let actual_port = status
+                .actual_port
+                .expect("router readiness checked before routed recovery");
+            let source_env = match existing.router_auth_capability {
+                RouterAuthCapability::Oauth => crate::config::OFFICIAL_ENV_NAME,
+                RouterAuthCapability::Token => existing.default_env.as_str(),
+            };
+            let source =
+                describe_router_environment(source_env).map_err(|error| error.to_string())?;
+            match (existing.router_auth_capability, source.auth_kind) {
+                (RouterAuthCapability::Oauth, RouterEnvironmentAuthKind::RequiresOauth)
+                | (RouterAuthCapability::Token, RouterEnvironmentAuthKind::Token) => {}
+                _ => {
+                    return Err(
+                        "ROUTER_AUTH_CHANGED: helper generation auth source no longer matches the persisted capability"
+                            .to_string(),
+                    )
+                }
+            }
+            validate_session_router_targets(existing, OAUTH_ROUTING_VERIFIED)
+                .map_err(|error| error.to_string())?;
+            let resolved_source = resolve_claude_env(source_env)?;
+            options.helper_env_vars = resolved_source.env_vars;
             existing.launch_transport = LaunchTransport::Routed;
             existing.launch_default_env = source.name;
             existing.launch_model_pins = source.pins;
@@ -1983,6 +2870,7 @@ impl NativeRuntimeManager {
                 if let Some(handle) = handles.get(&record.runtime_id) {
                     handle.summary()
                 } else {
+                    let worktree = session_worktree_summary(&record);
                     NativeSessionSummary {
                         runtime_id: record.runtime_id,
                         provider: record.provider,
@@ -2005,6 +2893,11 @@ impl NativeRuntimeManager {
                         background_tasks: Vec::new(),
                         last_error: record.last_error,
                         router: record.router.as_ref().map(SessionRouterState::from),
+                        worktree,
+                        prompt_queue: record.prompt_queue,
+                        limits: record.limits,
+                        restart_interrupted_at: record.restart_interrupted_at,
+                        imported: record.imported,
                     }
                 }
             })
@@ -2079,100 +2972,778 @@ impl NativeRuntimeManager {
                     events: Vec::new(),
                 });
             }
-            return Err(format!("Native runtime {} not found", runtime_id));
-        };
-        handle
-            .events
-            .lock()
-            .map_err(|_| "Failed to lock native session events".to_string())
-            .map(|store| {
-                let mut batch = store.events_since(since_seq);
-                if since_seq.is_none() {
-                    if let Some(limit) = limit.and_then(|value| usize::try_from(value).ok()) {
-                        if limit > 0 && batch.events.len() > limit {
-                            batch.events = batch.events[batch.events.len() - limit..].to_vec();
-                            batch.truncated = true;
+            return Err(format!("Native runtime {} not found", runtime_id));
+        };
+        handle
+            .events
+            .lock()
+            .map_err(|_| "Failed to lock native session events".to_string())
+            .map(|store| {
+                let mut batch = store.events_since(since_seq);
+                if since_seq.is_none() {
+                    if let Some(limit) = limit.and_then(|value| usize::try_from(value).ok()) {
+                        if limit > 0 && batch.events.len() > limit {
+                            batch.events = batch.events[batch.events.len() - limit..].to_vec();
+                            batch.truncated = true;
+                        }
+                    }
+                }
+                batch
+            })
+    }
+
+    /// Send a prompt, or queue it behind the running turn. Queued prompts
+    /// are dispatched one at a time as the session settles.
+    pub fn send_user_message(
+        self: &Arc<Self>,
+        app: &AppHandle,
+        runtime_id: &str,
+        text: &str,
+        display_text: Option<&str>,
+        images: Option<&Vec<PromptImage>>,
+        annotations: Option<&Vec<SessionPromptAnnotation>>,
+    ) -> Result<(), String> {
+        let _termination_guard = self
+            .app_termination_lock
+            .lock()
+            .map_err(|_| "Failed to lock native runtime termination".to_string())?;
+        self.reject_query_mutation_during_transition(runtime_id, "send a prompt")?;
+        let text = text.trim();
+        let has_images = images.as_ref().is_some_and(|imgs| !imgs.is_empty());
+        let annotations = validate_prompt_annotations(annotations)?;
+        if text.is_empty() && !has_images {
+            return Ok(());
+        }
+
+        let _queue_guard = self
+            .prompt_queue_lock
+            .lock()
+            .map_err(|_| "Failed to lock native prompt queue".to_string())?;
+        let (status, queue_len) = self
+            .records
+            .lock()
+            .map_err(|_| "Failed to lock native runtime records".to_string())?
+            .get(runtime_id)
+            .map(|record| (record.status.clone(), record.prompt_queue.len()))
+            .ok_or_else(|| format!("Native runtime {runtime_id} not found"))?;
+        if status == "processing" || queue_len > 0 {
+            if queue_len >= MAX_QUEUED_PROMPTS {
+                return Err(format!(
+                    "Native session {runtime_id} already has {MAX_QUEUED_PROMPTS} queued prompts."
+                ));
+            }
+            self.enqueue_prompt(runtime_id, text, display_text, images, annotations)?;
+            if native_status_dispatches_queued_prompts(&status) {
+                self.dispatch_next_queued_prompt_locked(app, runtime_id)?;
+            }
+            return Ok(());
+        }
+
+        self.deliver_prompt(app, runtime_id, text, images)?;
+        self.append_user_prompt_event(
+            runtime_id,
+            display_text.unwrap_or(text),
+            images,
+            annotations.as_ref(),
+        )
+    }
+
+    /// Hand a prompt to the helper. A settled record is flipped to
+    /// `processing` before the write, so prompts arriving before the helper
+    /// reports its own status are queued instead of racing this one.
+    fn deliver_prompt(
+        self: &Arc<Self>,
+        app: &AppHandle,
+        runtime_id: &str,
+        text: &str,
+        images: Option<&Vec<PromptImage>>,
+    ) -> Result<(), String> {
+        let mut handle = self.ensure_handle(app.clone(), runtime_id)?;
+        let image_count = images.as_ref().map(|imgs| imgs.len()).unwrap_or(0);
+        if !self.is_current_live_handle(runtime_id, &handle)? {
+            handle = self.ensure_handle(app.clone(), runtime_id)?;
+            if !self.is_current_live_handle(runtime_id, &handle)? {
+                return Err("Native runtime helper was replaced while sending prompt".to_string());
+            }
+        }
+        let record = handle
+            .record
+            .lock()
+            .map_err(|_| "Failed to lock native session record".to_string())?
+            .clone();
+        self.append_lifecycle_event(
+            runtime_id,
+            "prompt_send_requested",
+            format!(
+                "runtime_id={} provider={} status={} handle_generation={} chars={} images={}",
+                runtime_id,
+                record.provider.as_str(),
+                record.status,
+                handle.generation,
+                text.chars().count(),
+                image_count
+            ),
+        )?;
+        let mut settled_status = None;
+        self.update_record(runtime_id, |record| {
+            if native_status_dispatches_queued_prompts(&record.status) {
+                settled_status = Some(std::mem::replace(
+                    &mut record.status,
+                    "processing".to_string(),
+                ));
+                record.updated_at = Utc::now();
+            }
+        })?;
+        let images_ref = images
+            .filter(|imgs| !imgs.is_empty())
+            .map(|imgs| imgs.as_slice());
+        if let Err(error) = self.write_to_child_with_reconnect(
+            app,
+            runtime_id,
+            handle,
+            &HelperInputCommand::Prompt {
+                text,
+                images: images_ref,
+            },
+        ) {
+            if let Some(previous) = settled_status {
+                let _ = self.update_record(runtime_id, |record| {
+                    if record.status == "processing" {
+                        record.status = previous;
+                    }
+                });
+            }
+            return Err(error);
+        }
+        self.append_lifecycle_event(
+            runtime_id,
+            "prompt_send_written",
+            format!(
+                "helper accepted prompt command: chars={} images={}",
+                text.chars().count(),
+                image_count
+            ),
+        )
+    }
+
+    /// Caller must hold `prompt_queue_lock`.
+    fn enqueue_prompt(
+        &self,
+        runtime_id: &str,
+        text: &str,
+        display_text: Option<&str>,
+        images: Option<&Vec<PromptImage>>,
+        annotations: Option<Vec<SessionPromptAnnotation>>,
+    ) -> Result<(), String> {
+        let event_images = prompt_images_for_event(images, &self.prompt_image_store)?;
+        let item = NativeQueuedPrompt {
+            item_id: generate_queued_prompt_id(),
+            text: text.to_string(),
+            display_text: display_text
+                .map(str::trim)
+                .filter(|display| !display.is_empty() && *display != text)
+                .map(str::to_string),
+            images: event_images.clone().unwrap_or_default(),
+            annotations: annotations.clone(),
+            queued_at: Utc::now(),
+        };
+        let item_id = item.item_id.clone();
+        self.update_record(runtime_id, |record| {
+            record.prompt_queue.push(item);
+            record.updated_at = Utc::now();
+        })?;
+        self.append_stored_user_prompt_event(
+            runtime_id,
+            display_text.unwrap_or(text),
+            event_images,
+            annotations,
+            Some((&item_id, true)),
+        )
+    }
+
+    /// Dispatch the head of the prompt queue if the session has settled.
+    /// Runs after every helper stdout line, so the common empty-queue case
+    /// only takes the records lock.
+    fn dispatch_queued_prompt_if_settled(self: &Arc<Self>, app: &AppHandle, runtime_id: &str) {
+        let pending = self.records.lock().is_ok_and(|records| {
+            records.get(runtime_id).is_some_and(|record| {
+                !record.prompt_queue.is_empty()
+                    && native_status_dispatches_queued_prompts(&record.status)
+            })
+        });
+        if !pending {
+            return;
+        }
+        let manager = Arc::clone(self);
+        let app = app.clone();
+        let runtime_id = runtime_id.to_string();
+        tauri::async_runtime::spawn_blocking(move || {
+            if let Err(error) = manager.dispatch_next_queued_prompt(&app, &runtime_id) {
+                eprintln!(
+                    "Failed to dispatch queued prompt for {}: {}",
+                    runtime_id, error
+                );
+            }
+        });
+    }
+
+    fn dispatch_next_queued_prompt(
+        self: &Arc<Self>,
+        app: &AppHandle,
+        runtime_id: &str,
+    ) -> Result<(), String> {
+        let _termination_guard = self
+            .app_termination_lock
+            .lock()
+            .map_err(|_| "Failed to lock native runtime termination".to_string())?;
+        if self
+            .reject_query_mutation_during_transition(runtime_id, "send a queued prompt")
+            .is_err()
+        {
+            return Ok(());
+        }
+        let _queue_guard = self
+            .prompt_queue_lock
+            .lock()
+            .map_err(|_| "Failed to lock native prompt queue".to_string())?;
+        self.dispatch_next_queued_prompt_locked(app, runtime_id)
+    }
+
+    /// Caller must hold `prompt_queue_lock`. A prompt the helper refuses
+    /// goes back to the head of the queue.
+    fn dispatch_next_queued_prompt_locked(
+        self: &Arc<Self>,
+        app: &AppHandle,
+        runtime_id: &str,
+    ) -> Result<(), String> {
+        let ready = self
+            .records
+            .lock()
+            .map_err(|_| "Failed to lock native runtime records".to_string())?
+            .get(runtime_id)
+            .is_some_and(|record| {
+                !record.prompt_queue.is_empty()
+                    && native_status_dispatches_queued_prompts(&record.status)
+            });
+        if !ready {
+            return Ok(());
+        }
+        let mut next = None;
+        self.update_record(runtime_id, |record| {
+            if !record.prompt_queue.is_empty() {
+                next = Some(record.prompt_queue.remove(0));
+                record.updated_at = Utc::now();
+            }
+        })?;
+        let Some(item) = next else {
+            return Ok(());
+        };
+
+        let delivered = self
+            .queued_prompt_images(&item)
+            .and_then(|images| self.deliver_prompt(app, runtime_id, &item.text, images.as_ref()));
+        if let Err(error) = delivered {
+            self.update_record(runtime_id, |record| {
+                record.prompt_queue.insert(0, item.clone());
+            })?;
+            self.append_lifecycle_event(
+                runtime_id,
+                "prompt_queue_dispatch_failed",
+                format!("item={} error={}", item.item_id, error),
+            )?;
+            return Err(error);
+        }
+        let images = (!item.images.is_empty()).then_some(item.images);
+        self.append_stored_user_prompt_event(
+            runtime_id,
+            item.display_text.as_deref().unwrap_or(&item.text),
+            images,
+            item.annotations,
+            Some((&item.item_id, false)),
+        )
+    }
+
+    fn queued_prompt_images(
+        &self,
+        item: &NativeQueuedPrompt,
+    ) -> Result<Option<Vec<PromptImage>>, String> {
+        if item.images.is_empty() {
+            return Ok(None);
+        }
+        item.images
+            .iter()
+            .map(|image| -> Result<PromptImage, String> {
+                let storage_path = image
+                    .storage_path
+                    .as_deref()
+                    .ok_or_else(|| "Queued prompt image was not stored".to_string())?;
+                let data_url = self
+                    .prompt_image_store
+                    .read_data_url(storage_path, &image.media_type)?;
+                let base64_data = data_url
+                    .split_once(";base64,")
+                    .map(|(_, data)| data.to_string())
+                    .ok_or_else(|| "Queued prompt image could not be decoded".to_string())?;
+                Ok(PromptImage {
+                    media_type: image.media_type.clone(),
+                    base64_data,
+                    placeholder: image.placeholder.clone(),
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()
+            .map(Some)
+    }
+
+    pub fn list_queued_prompts(&self, runtime_id: &str) -> Result<Vec<NativeQueuedPrompt>, String> {
+        self.records
+            .lock()
+            .map_err(|_| "Failed to lock native runtime records".to_string())?
+            .get(runtime_id)
+            .map(|record| record.prompt_queue.clone())
+            .ok_or_else(|| format!("Native runtime {runtime_id} not found"))
+    }
+
+    /// Reorder the queue. `item_ids` must list exactly the queued items.
+    pub fn reorder_queued_prompts(
+        &self,
+        runtime_id: &str,
+        item_ids: &[String],
+    ) -> Result<Vec<NativeQueuedPrompt>, String> {
+        let _queue_guard = self
+            .prompt_queue_lock
+            .lock()
+            .map_err(|_| "Failed to lock native prompt queue".to_string())?;
+        let mut reordered = Err(format!("Native runtime {runtime_id} not found"));
+        self.update_record(runtime_id, |record| {
+            reordered = reorder_prompt_queue(&mut record.prompt_queue, item_ids)
+                .map(|()| record.prompt_queue.clone());
+            if reordered.is_ok() {
+                record.updated_at = Utc::now();
+            }
+        })?;
+        let queue = reordered?;
+        self.append_lifecycle_event(
+            runtime_id,
+            "prompt_queue_reordered",
+            format!("order={}", item_ids.join(",")),
+        )?;
+        Ok(queue)
+    }
+
+    pub fn cancel_queued_prompt(
+        &self,
+        runtime_id: &str,
+        item_id: &str,
+    ) -> Result<Vec<NativeQueuedPrompt>, String> {
+        let _queue_guard = self
+            .prompt_queue_lock
+            .lock()
+            .map_err(|_| "Failed to lock native prompt queue".to_string())?;
+        let mut removed = false;
+        let mut queue = Vec::new();
+        self.update_record(runtime_id, |record| {
+            let before = record.prompt_queue.len();
+            record.prompt_queue.retain(|item| item.item_id != item_id);
+            removed = record.prompt_queue.len() != before;
+            if removed {
+                record.updated_at = Utc::now();
+            }
+            queue = record.prompt_queue.clone();
+        })?;
+        if !removed {
+            return Err(format!(
+                "Prompt {item_id} is no longer queued for native session {runtime_id}"
+            ));
+        }
+        self.append_lifecycle_event(
+            runtime_id,
+            "prompt_queue_cancelled",
+            format!("item={item_id}"),
+        )?;
+        Ok(queue)
+    }
+
+    /// Replace a session's own limits. A running helper picks up a new
+    /// memory/CPU ceiling right away; warnings already sent are re-armed.
+    pub fn set_session_limits(
+        &self,
+        runtime_id: &str,
+        limits: Option<NativeSessionLimits>,
+    ) -> Result<NativeSessionSummary, String> {
+        if let Some(limits) = limits.as_ref() {
+            limits.validate()?;
+        }
+        self.update_record(runtime_id, |record| {
+            record.limits = limits.clone();
+            record.updated_at = Utc::now();
+        })?;
+        let handle = self
+            .handles
+            .lock()
+            .map_err(|_| "Failed to lock native runtime handles".to_string())?
+            .get(runtime_id)
+            .cloned();
+        if let Some(handle) = handle {
+            if let Ok(mut trackers) = self.limit_trackers.lock() {
+                if let Some(tracker) = trackers.get_mut(runtime_id) {
+                    tracker.warned.clear();
+                }
+            }
+            let effective = self.effective_session_limits(limits.as_ref());
+            let process_tree = handle
+                .child
+                .lock()
+                .map_err(|_| "Failed to lock native sidecar child".to_string())?
+                .as_ref()
+                .map(|child| Arc::clone(&child.process_tree));
+            if let Some(process_tree) = process_tree {
+                let name = format!("ccem-native-{}-{}", runtime_id, handle.generation);
+                if let Err(error) = process_tree.apply_resource_ceiling(&name, &effective) {
+                    self.append_lifecycle_event(runtime_id, "resource_ceiling_unavailable", error)?;
+                }
+            }
+        }
+        self.summary_for(runtime_id)
+    }
+
+    /// Check every live session against its idle, runtime and cost limits
+    /// for as long as the app runs.
+    pub fn start_limit_watchdog(self: &Arc<Self>) {
+        let manager = Arc::clone(self);
+        thread::spawn(move || loop {
+            thread::sleep(LIMIT_WATCHDOG_INTERVAL);
+            manager.enforce_session_limits();
+        });
+    }
+
+    pub fn start_event_log_maintenance(self: &Arc<Self>) {
+        let manager = Arc::clone(self);
+        thread::spawn(move || {
+            thread::sleep(EVENT_LOG_MAINTENANCE_STARTUP_DELAY);
+            loop {
+                if let Err(error) = manager.compact_event_log(false) {
+                    eprintln!("Native event log maintenance failed: {}", error);
+                }
+                thread::sleep(EVENT_LOG_MAINTENANCE_INTERVAL);
+            }
+        });
+    }
+
+    /// Compact quiet sessions, enforce the per-session and total size caps
+    /// on sessions idle past the cap floor and vacuum when the interval has
+    /// passed (or `force_vacuum`). Live runtimes are never touched, and the
+    /// vacuum waits for a pass where no native session is live.
+    pub fn compact_event_log(
+        &self,
+        force_vacuum: bool,
+    ) -> Result<NativeEventCompactionReport, String> {
+        let retention = read_settings()
+            .ok()
+            .and_then(|settings| settings.native_event_log_retention)
+            .unwrap_or_default();
+        let now = Utc::now();
+        let mut report = NativeEventCompactionReport::new(now);
+        let storage = self.event_log.runtime_storage()?;
+        let compact_before = retention.compact_before(now);
+        let cap_before = retention.cap_before(now);
+        let max_session_bytes = retention.max_session_bytes();
+        let idle_since = |runtime: &RuntimeEventStorage, cutoff: DateTime<Utc>| {
+            runtime
+                .newest_occurred_at
+                .is_some_and(|newest| newest < cutoff)
+        };
+
+        for runtime in &storage {
+            let stale = compact_before.is_some_and(|cutoff| idle_since(runtime, cutoff))
+                && runtime.compacted_through_seq != Some(runtime.newest_seq);
+            let oversized = idle_since(runtime, cap_before)
+                && max_session_bytes.is_some_and(|max| runtime.payload_bytes > max);
+            if !stale && !oversized {
+                continue;
+            }
+            self.rewrite_event_history(
+                runtime,
+                |events| {
+                    let events = compact_events(events);
+                    match max_session_bytes {
+                        Some(max) => trim_events_to_size(events, max),
+                        None => events,
+                    }
+                },
+                &mut report,
+            )?;
+        }
+
+        if let Some(max_total_bytes) = retention.max_total_bytes() {
+            let mut storage = self.event_log.runtime_storage()?;
+            let mut total_bytes: u64 = storage.iter().map(|runtime| runtime.payload_bytes).sum();
+            // Least recently active sessions give up their excess first.
+            storage.sort_by_key(|runtime| runtime.newest_occurred_at);
+            for runtime in &storage {
+                if total_bytes <= max_total_bytes || !idle_since(runtime, cap_before) {
+                    break;
+                }
+                let excess = total_bytes - max_total_bytes;
+                let reclaimed = self.rewrite_event_history(
+                    runtime,
+                    |events| {
+                        let events = compact_events(events);
+                        let target = events_size(&events).saturating_sub(excess);
+                        trim_events_to_size(events, target)
+                    },
+                    &mut report,
+                )?;
+                total_bytes = total_bytes.saturating_sub(reclaimed);
+            }
+        }
+        self.event_log.record_compaction_run(now)?;
+
+        let vacuum_due = force_vacuum
+            || retention.vacuum_interval().is_some_and(|interval| {
+                self.event_log
+                    .last_vacuum_at()
+                    .ok()
+                    .flatten()
+                    .is_none_or(|last| now - last >= interval)
+            });
+        if vacuum_due && !self.has_live_sessions()? {
+            self.event_log.vacuum()?;
+            report.vacuumed = true;
+        }
+        Ok(report)
+    }
+
+    /// Apply `compact` to a runtime's stored history, write back what
+    /// changed and mark the runtime compacted. The history is read and
+    /// compacted without locks; only the write takes the reconnect lock, and
+    /// it is skipped if the runtime came back to life or logged anything in
+    /// the meantime. Histories holding events this build cannot decode are
+    /// left alone. Returns the payload bytes reclaimed.
+    fn rewrite_event_history(
+        &self,
+        runtime: &RuntimeEventStorage,
+        compact: impl FnOnce(Vec<SessionEventRecord>) -> Vec<SessionEventRecord>,
+        report: &mut NativeEventCompactionReport,
+    ) -> Result<u64, String> {
+        let runtime_id = runtime.runtime_id.as_str();
+        let is_live = || {
+            self.handles
+                .lock()
+                .map_err(|_| "Failed to lock native runtime handles".to_string())
+                .map(|handles| {
+                    handles
+                        .get(runtime_id)
+                        .is_some_and(|handle| handle.alive.load(Ordering::SeqCst))
+                })
+        };
+        if is_live()? {
+            return Ok(0);
+        }
+        let events = self.event_log.replay(runtime_id, None, None)?.events;
+        if events.len() as u64 != runtime.event_count {
+            return Ok(0);
+        }
+        let Some(newest_seq) = events.last().map(|event| event.seq) else {
+            return Ok(0);
+        };
+        let before_bytes = events_size(&events);
+        let before = events
+            .iter()
+            .map(|event| (event.seq, event.payload.clone()))
+            .collect::<HashMap<_, _>>();
+        let compacted = compact(events);
+        let after_bytes = events_size(&compacted);
+        let kept = compacted
+            .iter()
+            .map(|event| event.seq)
+            .collect::<HashSet<_>>();
+        let removed_seqs = before
+            .keys()
+            .copied()
+            .filter(|seq| !kept.contains(seq))
+            .collect::<Vec<_>>();
+        let updated = compacted
+            .into_iter()
+            .filter(|event| before.get(&event.seq) != Some(&event.payload))
+            .collect::<Vec<_>>();
+
+        let _reconnect_guard = self
+            .reconnect_lock
+            .lock()
+            .map_err(|_| "Failed to lock native runtime reconnect coordinator".to_string())?;
+        if is_live()? || self.event_log.newest_seq(runtime_id)? != Some(newest_seq) {
+            return Ok(0);
+        }
+        self.event_log
+            .rewrite_runtime(runtime_id, &removed_seqs, &updated, newest_seq)?;
+        if removed_seqs.is_empty() && updated.is_empty() {
+            return Ok(0);
+        }
+        let reclaimed = before_bytes.saturating_sub(after_bytes);
+        report.runtimes_compacted += 1;
+        report.events_removed += removed_seqs.len() as u64;
+        report.bytes_reclaimed += reclaimed;
+        Ok(reclaimed)
+    }
+
+    fn has_live_sessions(&self) -> Result<bool, String> {
+        Ok(self
+            .handles
+            .lock()
+            .map_err(|_| "Failed to lock native runtime handles".to_string())?
+            .values()
+            .any(|handle| handle.alive.load(Ordering::SeqCst)))
+    }
+
+    fn effective_session_limits(
+        &self,
+        session: Option<&NativeSessionLimits>,
+    ) -> NativeSessionLimits {
+        read_settings()
+            .ok()
+            .and_then(|settings| settings.native_session_limits)
+            .unwrap_or_default()
+            .overlay(session)
+    }
+
+    fn reset_limit_tracker(&self, runtime_id: &str, generation: u64) {
+        if let Ok(mut trackers) = self.limit_trackers.lock() {
+            trackers.insert(runtime_id.to_string(), NativeLimitTracker::new(generation));
+        }
+    }
+
+    fn record_limit_signal(&self, runtime_id: &str, generation: u64, signal: NativeLimitSignal) {
+        if matches!(signal, NativeLimitSignal::Ignored) {
+            return;
+        }
+        let Ok(mut trackers) = self.limit_trackers.lock() else {
+            return;
+        };
+        let tracker = trackers
+            .entry(runtime_id.to_string())
+            .or_insert_with(|| NativeLimitTracker::new(generation));
+        if tracker.generation != generation {
+            *tracker = NativeLimitTracker::new(generation);
+        }
+        match signal {
+            NativeLimitSignal::Activity => {
+                tracker.usage.last_activity_at = Utc::now();
+                tracker.warned.remove(&NativeLimitKind::IdleTimeout);
+            }
+            NativeLimitSignal::Cost(cost) => {
+                tracker.usage.cost_usd =
+                    Some(tracker.usage.cost_usd.map_or(cost, |seen| seen.max(cost)));
+            }
+            NativeLimitSignal::Ignored => {}
+        }
+    }
+
+    /// Warn about every limit that is getting close, and stop sessions that
+    /// reached one. A limit that is already exceeded on first sight still
+    /// gets its warning one watchdog tick before the stop.
+    fn enforce_session_limits(&self) {
+        let global = read_settings()
+            .ok()
+            .and_then(|settings| settings.native_session_limits)
+            .unwrap_or_default();
+        let handles: Vec<(String, Arc<NativeSessionHandle>)> = match self.handles.lock() {
+            Ok(handles) => handles
+                .iter()
+                .map(|(runtime_id, handle)| (runtime_id.clone(), Arc::clone(handle)))
+                .collect(),
+            Err(_) => return,
+        };
+        let now = Utc::now();
+        for (runtime_id, handle) in handles {
+            let Ok(record) = handle.record.lock().map(|record| record.clone()) else {
+                continue;
+            };
+            if !record.is_active || !handle.alive.load(Ordering::SeqCst) {
+                continue;
+            }
+            let limits = global.overlay(record.limits.as_ref());
+            if limits.is_unbounded() {
+                continue;
+            }
+            let mut warnings = Vec::new();
+            let mut stop = None;
+            {
+                let Ok(mut trackers) = self.limit_trackers.lock() else {
+                    return;
+                };
+                let tracker = trackers
+                    .entry(runtime_id.clone())
+                    .or_insert_with(|| NativeLimitTracker::new(handle.generation));
+                if tracker.generation != handle.generation || tracker.stopping {
+                    continue;
+                }
+                tracker.usage.waiting_for_input =
+                    matches!(record.status.as_str(), "ready" | "idle");
+                for verdict in evaluate_limits(&limits, &tracker.usage, now) {
+                    match verdict {
+                        NativeLimitVerdict::Stop { kind, detail }
+                            if tracker.warned.contains(&kind) =>
+                        {
+                            tracker.stopping = true;
+                            stop = Some((kind, detail));
+                            break;
+                        }
+                        NativeLimitVerdict::Warn { kind, detail }
+                        | NativeLimitVerdict::Stop { kind, detail } => {
+                            if tracker.warned.insert(kind) {
+                                warnings.push((kind, detail));
+                            }
                         }
                     }
                 }
-                batch
-            })
-    }
-
-    pub fn send_user_message(
-        self: &Arc<Self>,
-        app: &AppHandle,
-        runtime_id: &str,
-        text: &str,
-        display_text: Option<&str>,
-        images: Option<&Vec<PromptImage>>,
-        annotations: Option<&Vec<SessionPromptAnnotation>>,
-    ) -> Result<(), String> {
-        let _termination_guard = self
-            .app_termination_lock
-            .lock()
-            .map_err(|_| "Failed to lock native runtime termination".to_string())?;
-        self.reject_query_mutation_during_transition(runtime_id, "send a prompt")?;
-        let text = text.trim();
-        let has_images = images.as_ref().is_some_and(|imgs| !imgs.is_empty());
-        let annotations = validate_prompt_annotations(annotations)?;
-        if text.is_empty() && !has_images {
-            return Ok(());
+            }
+            if let Some((kind, detail)) = stop {
+                self.stop_for_limit(&runtime_id, kind, &detail);
+                continue;
+            }
+            for (kind, detail) in warnings {
+                let _ = self.append_lifecycle_event(
+                    &runtime_id,
+                    "limit_warning",
+                    format!(
+                        "{}: {} The session will be stopped at the limit.",
+                        kind.as_str(),
+                        detail
+                    ),
+                );
+            }
         }
+    }
 
-        let mut handle = self.ensure_handle(app.clone(), runtime_id)?;
-        let image_count = images.as_ref().map(|imgs| imgs.len()).unwrap_or(0);
-        if !self.is_current_live_handle(runtime_id, &handle)? {
-            handle = self.ensure_handle(app.clone(), runtime_id)?;
-            if !self.is_current_live_handle(runtime_id, &handle)? {
-                return Err("Native runtime helper was replaced while sending prompt".to_string());
+    /// Close the helper of a session that reached a limit. Idle sessions
+    /// keep running background tasks; runtime and cost limits end them too.
+    fn stop_for_limit(&self, runtime_id: &str, kind: NativeLimitKind, detail: &str) {
+        let _ = self.append_lifecycle_event(
+            runtime_id,
+            "limit_reached",
+            format!("{}: {}", kind.as_str(), detail),
+        );
+        let result = match self.app_termination_lock.lock() {
+            Ok(_termination_guard) => {
+                if self.app_termination_in_progress.load(Ordering::SeqCst) {
+                    return;
+                }
+                self.shutdown_child(runtime_id, kind != NativeLimitKind::IdleTimeout)
             }
+            Err(_) => Err("Failed to lock native runtime termination".to_string()),
+        };
+        let result = result.and_then(|()| {
+            self.update_record(runtime_id, |record| {
+                if record.is_active {
+                    record.status = "closed_idle".to_string();
+                    record.is_active = false;
+                    record.updated_at = Utc::now();
+                }
+            })
+        });
+        if let Err(error) = result {
+            let _ = self.append_lifecycle_event(runtime_id, "limit_stop_failed", error);
         }
-        let record = handle
-            .record
-            .lock()
-            .map_err(|_| "Failed to lock native session record".to_string())?
-            .clone();
-        self.append_lifecycle_event(
-            runtime_id,
-            "prompt_send_requested",
-            format!(
-                "runtime_id={} provider={} status={} handle_generation={} chars={} images={}",
-                runtime_id,
-                record.provider.as_str(),
-                record.status,
-                handle.generation,
-                text.chars().count(),
-                image_count
-            ),
-        )?;
-        let images_ref = images
-            .filter(|imgs| !imgs.is_empty())
-            .map(|imgs| imgs.as_slice());
-        self.write_to_child_with_reconnect(
-            app,
-            runtime_id,
-            handle,
-            &HelperInputCommand::Prompt {
-                text,
-                images: images_ref,
-            },
-        )?;
-        self.append_lifecycle_event(
-            runtime_id,
-            "prompt_send_written",
-            format!(
-                "helper accepted prompt command: chars={} images={}",
-                text.chars().count(),
-                image_count
-            ),
-        )?;
-        self.append_user_prompt_event(
-            runtime_id,
-            display_text.unwrap_or(text),
-            images,
-            annotations.as_ref(),
-        )
     }
 
     pub fn respond_to_permission(
@@ -2729,6 +4300,87 @@ impl NativeRuntimeManager {
         Ok(())
     }
 
+    /// Apply a settings change expressed as an environment name, as both the
+    /// workspace UI and external control request it: router-managed sessions
+    /// switch their default route, others resolve the environment into
+    /// launch variables.
+    pub fn update_session_settings_by_env_name(
+        self: &Arc<Self>,
+        app: &AppHandle,
+        runtime_id: &str,
+        env_name: Option<&str>,
+        perm_mode: Option<&str>,
+        effort: Option<&str>,
+        force_restart: bool,
+    ) -> Result<(), String> {
+        let current = self
+            .list_sessions()
+            .into_iter()
+            .find(|session| session.runtime_id == runtime_id)
+            .ok_or_else(|| format!("Native runtime {} not found", runtime_id))?;
+        if let (Some(name), Some(router)) = (env_name, current.router.as_ref()) {
+            if !name.trim().is_empty() {
+                let mut allowed_envs = router.allowed_envs.clone();
+                if !allowed_envs.iter().any(|allowed| allowed == name) {
+                    allowed_envs.push(name.to_string());
+                }
+                self.update_session_router(
+                    app,
+                    UpdateSessionRouterRequest {
+                        runtime_id: runtime_id.to_string(),
+                        expected_revision: router.revision,
+                        patch: SessionRouterPatch {
+                            default_env: Some(name.to_string()),
+                            allowed_envs: Some(allowed_envs),
+                            ..SessionRouterPatch::default()
+                        },
+                    },
+                    "environment",
+                )
+                .map_err(|error| error.to_string())?;
+                if perm_mode.is_none() && effort.is_none() {
+                    return Ok(());
+                }
+                return self.update_session_settings(
+                    app,
+                    runtime_id,
+                    None,
+                    perm_mode,
+                    None,
+                    effort,
+                    force_restart,
+                );
+            }
+        }
+        let (resolved_env_name, env_vars) = match env_name {
+            Some(name) if !name.trim().is_empty() => match current.provider {
+                NativeProvider::Claude => {
+                    let resolved = resolve_claude_env(name)?;
+                    (Some(resolved.env_name), Some(resolved.env_vars))
+                }
+                NativeProvider::Codex => {
+                    let resolved = resolve_codex_runtime(name)?;
+                    let resolved_name = if resolved.env_name.is_empty() {
+                        name.to_string()
+                    } else {
+                        resolved.env_name
+                    };
+                    (Some(resolved_name), Some(resolve_codex_proxy_env()))
+                }
+            },
+            _ => (None, None),
+        };
+        self.update_session_settings(
+            app,
+            runtime_id,
+            resolved_env_name.as_deref(),
+            perm_mode,
+            env_vars.as_ref(),
+            effort,
+            force_restart,
+        )
+    }
+
     pub fn update_session_settings(
         self: &Arc<Self>,
         app: &AppHandle,
@@ -3597,6 +5249,64 @@ impl NativeRuntimeManager {
         }
     }
 
+    /// Merge or rebase a worktree session's branch into the main checkout,
+    /// or throw it away. Merge and rebase wait for the turn to finish, commit
+    /// pending changes first and keep the worktree so the session can carry
+    /// on from the new base; discard stops the session and points it back at
+    /// the main checkout.
+    pub fn apply_worktree_action(
+        self: &Arc<Self>,
+        runtime_id: &str,
+        action: NativeWorktreeAction,
+    ) -> Result<NativeSessionSummary, String> {
+        let record = self
+            .records
+            .lock()
+            .map_err(|_| "Failed to lock native runtime records".to_string())?
+            .get(runtime_id)
+            .cloned()
+            .ok_or_else(|| format!("Native runtime {} not found", runtime_id))?;
+        let mut worktree = record
+            .worktree
+            .clone()
+            .ok_or_else(|| format!("Native runtime {} has no worktree", runtime_id))?;
+
+        if action == NativeWorktreeAction::Discard {
+            if record.is_active {
+                self.stop_session(runtime_id)?;
+            }
+            git_worktree::remove_worktree(&worktree, true)?;
+            self.update_record(runtime_id, |record| {
+                record.project_dir = worktree.source_dir();
+                record.worktree = None;
+                record.worktree_diff_stat = None;
+                record.updated_at = Utc::now();
+            })?;
+            return self.summary_for(runtime_id);
+        }
+
+        if !native_status_allows_worktree_actions(&record.status) {
+            return Err(format!(
+                "Native runtime {} is still working; wait for the turn to finish",
+                runtime_id
+            ));
+        }
+        git_worktree::commit_pending_changes(&worktree, &format!("Session {}", runtime_id))?;
+        if action == NativeWorktreeAction::Merge {
+            git_worktree::merge_into_checkout(&worktree)?;
+        } else {
+            git_worktree::rebase_onto_checkout(&worktree)?;
+        }
+        worktree.base_sha = git_worktree::head_sha(&worktree)?;
+        let diff_stat = git_worktree::diff_stat(&worktree).ok();
+        self.update_record(runtime_id, |record| {
+            record.worktree = Some(worktree);
+            record.worktree_diff_stat = diff_stat;
+            record.updated_at = Utc::now();
+        })?;
+        self.summary_for(runtime_id)
+    }
+
     pub fn reconcile_stale_records(&self) -> Result<usize, String> {
         let live_runtime_ids = self
             .handles
@@ -3631,6 +5341,13 @@ impl NativeRuntimeManager {
             if !record.is_active || is_native_terminal_status(&record.status) {
                 continue;
             }
+            if record
+                .provider_session_id
+                .as_deref()
+                .is_some_and(|session_id| !session_id.trim().is_empty())
+            {
+                record.restart_interrupted_at = Some(now);
+            }
 
             if record.status == "idle" {
                 record.is_active = false;
@@ -3647,14 +5364,189 @@ impl NativeRuntimeManager {
                     "Native runtime was interrupted because the desktop app restarted.".to_string(),
                 );
             }
-            changed += 1;
-        }
-
-        if changed > 0 {
-            persist_native_runtime_state_to(&self.state_path, records.values().cloned().collect())?;
+            changed += 1;
+        }
+
+        if changed > 0 {
+            persist_native_runtime_state_to(&self.state_path, records.values().cloned().collect())?;
+        }
+        let stale_worktrees = records
+            .values()
+            .filter(|record| {
+                !record.is_active
+                    && !live_runtime_ids.contains(&record.runtime_id)
+                    && !record.status.starts_with("handoff")
+            })
+            .filter_map(|record| {
+                record
+                    .worktree
+                    .clone()
+                    .map(|worktree| (record.runtime_id.clone(), worktree))
+            })
+            .collect::<Vec<_>>();
+        drop(records);
+
+        Ok(changed + self.collect_stale_worktrees(stale_worktrees)?)
+    }
+
+    /// Sessions the last app run left behind mid-flight, newest first.
+    pub fn list_recovery_candidates(&self) -> Result<Vec<NativeRecoveryCandidate>, String> {
+        let records = self
+            .records
+            .lock()
+            .map_err(|_| "Failed to lock native runtime records".to_string())?
+            .values()
+            .cloned()
+            .collect::<Vec<_>>();
+        let mut candidates = records
+            .into_iter()
+            .filter_map(|record| self.recovery_candidate(record))
+            .collect::<Vec<_>>();
+        candidates.sort_by(|left, right| {
+            right
+                .interrupted_at
+                .cmp(&left.interrupted_at)
+                .then_with(|| left.runtime_id.cmp(&right.runtime_id))
+        });
+        Ok(candidates)
+    }
+
+    /// Reconnect a session interrupted by an app restart. The helper resumes
+    /// the provider session and the persisted timeline carries on; prompts
+    /// that were still queued are sent once the session settles.
+    pub fn resume_recovered_session(
+        self: &Arc<Self>,
+        app: AppHandle,
+        runtime_id: &str,
+    ) -> Result<NativeSessionSummary, String> {
+        let _termination_guard = self
+            .app_termination_lock
+            .lock()
+            .map_err(|_| "Failed to lock native runtime termination".to_string())?;
+        if self.app_termination_in_progress.load(Ordering::SeqCst) {
+            return Err(
+                "CCEM is closing native runtimes; a session cannot be resumed.".to_string(),
+            );
+        }
+        let candidate = self
+            .recovery_candidate(self.current_record(runtime_id)?)
+            .ok_or_else(|| format!("Native runtime {runtime_id} has nothing to recover"))?;
+        // Sessions that were idle at the restart keep their status; mark them
+        // as closed so the reconnect reactivates them.
+        self.update_record(runtime_id, |record| {
+            if record.status == "idle" {
+                record.status = "closed_idle".to_string();
+            }
+        })?;
+        self.ensure_handle(app, runtime_id)?;
+        self.append_lifecycle_event(
+            runtime_id,
+            "runtime_recovered",
+            format!(
+                "Resumed {} session {} interrupted by the desktop app restart at {}.",
+                candidate.provider.as_str(),
+                candidate.provider_session_id,
+                candidate.interrupted_at.to_rfc3339()
+            ),
+        )?;
+        self.summary_for(runtime_id)
+    }
+
+    /// Resume every recovery candidate, for the automatic resume setting.
+    /// Returns how many sessions came back.
+    pub fn resume_recovery_candidates(self: &Arc<Self>, app: AppHandle) -> usize {
+        let candidates = match self.list_recovery_candidates() {
+            Ok(candidates) => candidates,
+            Err(error) => {
+                eprintln!("Failed to list native recovery candidates: {}", error);
+                return 0;
+            }
+        };
+        let mut resumed = 0;
+        for candidate in candidates {
+            match self.resume_recovered_session(app.clone(), &candidate.runtime_id) {
+                Ok(_) => resumed += 1,
+                Err(error) => eprintln!(
+                    "Failed to resume native runtime {}: {}",
+                    candidate.runtime_id, error
+                ),
+            }
+        }
+        resumed
+    }
+
+    pub fn dismiss_recovery_candidate(&self, runtime_id: &str) -> Result<(), String> {
+        self.update_record(runtime_id, |record| {
+            record.restart_interrupted_at = None;
+        })
+    }
+
+    fn recovery_candidate(&self, record: NativeSessionRecord) -> Option<NativeRecoveryCandidate> {
+        let interrupted_at = record.restart_interrupted_at?;
+        if record.is_active || record.status.starts_with("handoff") {
+            return None;
+        }
+        let provider_session_id = record
+            .provider_session_id
+            .as_deref()
+            .map(str::trim)
+            .filter(|session_id| !session_id.is_empty())?
+            .to_string();
+        Some(NativeRecoveryCandidate {
+            last_event_seq: self
+                .event_log
+                .newest_seq(&record.runtime_id)
+                .unwrap_or(None),
+            runtime_id: record.runtime_id,
+            provider: record.provider,
+            provider_session_id,
+            project_dir: record.project_dir,
+            env_name: record.env_name,
+            perm_mode: record.perm_mode,
+            routed: record
+                .router
+                .as_ref()
+                .is_some_and(|router| router.launch_transport == LaunchTransport::Routed),
+            queued_prompts: record.prompt_queue.len(),
+            interrupted_at,
+        })
+    }
+
+    /// Remove worktrees that inactive sessions left without changes, and
+    /// forget ones deleted behind our back. Worktrees holding work stay
+    /// until the user merges or discards them.
+    fn collect_stale_worktrees(
+        &self,
+        stale_worktrees: Vec<(String, GitWorktree)>,
+    ) -> Result<usize, String> {
+        let mut collected = 0;
+        for (runtime_id, worktree) in stale_worktrees {
+            let removal = if Path::new(&worktree.path).exists() {
+                match git_worktree::diff_stat(&worktree) {
+                    Ok(diff_stat) if diff_stat.files_changed == 0 => {
+                        git_worktree::remove_worktree(&worktree, true)
+                    }
+                    Ok(_) => continue,
+                    Err(error) => Err(error),
+                }
+            } else {
+                git_worktree::remove_worktree(&worktree, false)
+            };
+            if let Err(error) = removal {
+                eprintln!(
+                    "Failed to collect worktree {} of {}: {}",
+                    worktree.path, runtime_id, error
+                );
+                continue;
+            }
+            self.update_record(&runtime_id, |record| {
+                record.project_dir = worktree.source_dir();
+                record.worktree = None;
+                record.worktree_diff_stat = None;
+            })?;
+            collected += 1;
         }
-
-        Ok(changed)
+        Ok(collected)
     }
 
     pub fn handoff_to_terminal(
@@ -4416,6 +6308,12 @@ impl NativeRuntimeManager {
             .get(runtime_id)
             .cloned()
             .ok_or_else(|| format!("Native runtime {} not found", runtime_id))?;
+        if record.imported.is_some() {
+            return Err(format!(
+                "Native runtime {} is an imported replay and cannot be resumed",
+                runtime_id
+            ));
+        }
         let rollback_record = rollback_record
             .map(recoverable_record_after_helper_removed)
             .unwrap_or_else(|| record.clone());
@@ -4427,6 +6325,7 @@ impl NativeRuntimeManager {
         }
 
         reactivate_record_for_reconnect(&mut record);
+        record.restart_interrupted_at = None;
 
         let mut options = build_runtime_bootstrap_options(&record).map_err(|error| {
             self.rollback_reconnect_failure(runtime_id, &rollback_record, error)
@@ -4447,13 +6346,26 @@ impl NativeRuntimeManager {
             .unwrap_or(None)
             .map(|seq| seq + 1)
             .unwrap_or(1);
+        // Seed the new generation with the persisted timeline so in-memory
+        // replays and subscribers see the session's history, not just what
+        // happened since it reconnected.
+        let history = self
+            .event_log
+            .replay(
+                runtime_id,
+                None,
+                Some(DEFAULT_SESSION_EVENT_CAPACITY as u64),
+            )
+            .map(|batch| batch.events)
+            .unwrap_or_default();
 
         let handle = Arc::new(NativeSessionHandle {
             generation: self.allocate_handle_generation(),
             record: Mutex::new(record.clone()),
             child: Mutex::new(None),
-            events: Mutex::new(SessionStore::with_start_seq(
+            events: Mutex::new(SessionStore::with_history(
                 runtime_id.to_string(),
+                history,
                 start_seq,
             )),
             background_tasks: Mutex::new(HashMap::new()),
@@ -4569,6 +6481,7 @@ impl NativeRuntimeManager {
             .as_ref()
             .filter(|router| router.launch_transport == LaunchTransport::Routed)
             .map(build_helper_router_init);
+        let helper_sessions_mcp = self.helper_sessions_mcp_init(&app, runtime_id);
         let helper_path = native_helper_script_path(&app)?;
         let command = app
             .shell()
@@ -4578,6 +6491,7 @@ impl NativeRuntimeManager {
             .current_dir(&options.working_dir);
 
         let (mut rx, child) = spawn_native_helper_process(command.into())?;
+        let process_tree = Arc::clone(&child.process_tree);
 
         {
             let mut child_slot = handle
@@ -4586,6 +6500,12 @@ impl NativeRuntimeManager {
                 .map_err(|_| "Failed to lock native sidecar child".to_string())?;
             *child_slot = Some(child);
         }
+        self.reset_limit_tracker(runtime_id, handle.generation);
+        let limits = self.effective_session_limits(options.limits.as_ref());
+        let cgroup_name = format!("ccem-native-{}-{}", runtime_id, handle.generation);
+        if let Err(error) = process_tree.apply_resource_ceiling(&cgroup_name, &limits) {
+            let _ = self.append_lifecycle_event(runtime_id, "resource_ceiling_unavailable", error);
+        }
 
         self.write_to_child(
             &handle,
@@ -4612,6 +6532,7 @@ impl NativeRuntimeManager {
                 effort: options.effort.as_deref(),
                 todo_snapshot_seed: todo_snapshot_seed.as_ref(),
                 router: helper_router_init.as_ref(),
+                sessions_mcp: helper_sessions_mcp.as_ref(),
             },
         )?;
 
@@ -4648,6 +6569,7 @@ impl NativeRuntimeManager {
                                 );
                             }
                         }
+                        manager.dispatch_queued_prompt_if_settled(&app_handle, &runtime);
                     }
                     CommandEvent::Stderr(line) => {
                         for text in drain_helper_output_lines(&mut stderr_buffer, &line) {
@@ -5681,17 +7603,33 @@ impl NativeRuntimeManager {
             return Ok(());
         }
         let event_images = prompt_images_for_event(images, &self.prompt_image_store)?;
+        self.append_stored_user_prompt_event(runtime_id, text, event_images, annotations, None)
+    }
+
+    /// `queue_item` carries the queue item id and whether the prompt is
+    /// still waiting in the queue.
+    fn append_stored_user_prompt_event(
+        &self,
+        runtime_id: &str,
+        text: &str,
+        images: Option<Vec<SessionPromptImage>>,
+        annotations: Option<Vec<SessionPromptAnnotation>>,
+        queue_item: Option<(&str, bool)>,
+    ) -> Result<(), String> {
+        let text = text.trim();
         let canonical_hash =
-            canonical_user_prompt_hash(text, event_images.as_ref(), annotations.as_ref());
+            canonical_user_prompt_hash(text, images.as_ref(), annotations.as_ref());
 
         self.append_event(
             runtime_id,
             SessionEventPayload::UserPrompt {
                 text: text.to_string(),
-                image_count: image_count as u64,
-                images: event_images,
+                image_count: images.as_ref().map(Vec::len).unwrap_or(0) as u64,
+                images,
                 annotations,
                 canonical_hash,
+                queued: queue_item.is_some_and(|(_, queued)| queued),
+                queue_item_id: queue_item.map(|(item_id, _)| item_id.to_string()),
             },
         )
     }
@@ -5738,6 +7676,11 @@ impl NativeRuntimeManager {
 
     fn append_event(&self, runtime_id: &str, payload: SessionEventPayload) -> Result<(), String> {
         let last_error = payload_last_error(&payload);
+        let turn_finished = match &payload {
+            SessionEventPayload::Lifecycle { stage, .. } => stage == "turn_completed",
+            SessionEventPayload::SessionCompleted { .. } => true,
+            _ => false,
+        };
         let settings_change = match &payload {
             SessionEventPayload::RuntimeSettingsChanged {
                 state,
@@ -5766,6 +7709,7 @@ impl NativeRuntimeManager {
         if !apply_background_task_event(handle, &payload)? {
             return Ok(());
         }
+        self.record_limit_signal(runtime_id, handle.generation, native_limit_signal(&payload));
         {
             let mut store = handle
                 .events
@@ -5778,6 +7722,7 @@ impl NativeRuntimeManager {
                     record.runtime_id, record.seq, error
                 );
             }
+            self.publish_event(&record);
         }
         drop(handles);
         if let Some((state, request_id, env_name, effort, pending_env_name, pending_effort)) =
@@ -5804,9 +7749,135 @@ impl NativeRuntimeManager {
         if let Some(message) = last_error {
             self.set_last_error(runtime_id, message)?;
         }
+        if turn_finished {
+            self.refresh_worktree_diff_stat(runtime_id);
+        }
         Ok(())
     }
 
+    /// Re-read the diff-stat of a session running in its own worktree. On
+    /// failure the previous stat is kept.
+    fn refresh_worktree_diff_stat(&self, runtime_id: &str) {
+        let worktree = match self.records.lock() {
+            Ok(records) => records
+                .get(runtime_id)
+                .and_then(|record| record.worktree.clone()),
+            Err(_) => return,
+        };
+        let Some(worktree) = worktree else {
+            return;
+        };
+        let result = git_worktree::diff_stat(&worktree).and_then(|diff_stat| {
+            self.update_record(runtime_id, |record| {
+                record.worktree_diff_stat = Some(diff_stat);
+            })
+        });
+        if let Err(error) = result {
+            eprintln!(
+                "Failed to refresh worktree diff-stat for {}: {}",
+                runtime_id, error
+            );
+        }
+    }
+
+    /// Register a push subscriber for events appended to `runtime_ids`.
+    /// Pair with `unsubscribe_events` once the consumer goes away.
+    pub fn subscribe_events(
+        &self,
+        runtime_ids: &[String],
+    ) -> Result<NativeEventSubscription, String> {
+        let (sender, receiver) = mpsc::sync_channel(DEFAULT_SESSION_EVENT_CAPACITY);
+        let lagged = Arc::new(AtomicBool::new(false));
+        self.event_subscribers
+            .lock()
+            .map_err(|_| "Failed to lock native event subscribers".to_string())?
+            .push(NativeEventSubscriber {
+                runtime_ids: runtime_ids.iter().cloned().collect(),
+                sender,
+                lagged: Arc::clone(&lagged),
+            });
+        Ok(NativeEventSubscription { receiver, lagged })
+    }
+
+    pub fn unsubscribe_events(&self, subscription: &NativeEventSubscription) {
+        if let Ok(mut subscribers) = self.event_subscribers.lock() {
+            subscribers.retain(|subscriber| !Arc::ptr_eq(&subscriber.lagged, &subscription.lagged));
+        }
+    }
+
+    fn publish_event(&self, record: &SessionEventRecord) {
+        let Ok(mut subscribers) = self.event_subscribers.lock() else {
+            return;
+        };
+        subscribers.retain(|subscriber| {
+            if !subscriber.runtime_ids.contains(&record.runtime_id) {
+                return true;
+            }
+            match subscriber.sender.try_send(record.clone()) {
+                Ok(()) => true,
+                Err(mpsc::TrySendError::Full(_)) => {
+                    subscriber.lagged.store(true, Ordering::Relaxed);
+                    true
+                }
+                Err(mpsc::TrySendError::Disconnected(_)) => false,
+            }
+        });
+    }
+
+    /// The runtime a `ccem-sessions` MCP token belongs to, while that runtime
+    /// is still active.
+    pub fn sessions_mcp_caller(&self, token: &str) -> Option<String> {
+        let runtime_id = self.sessions_mcp_tokens.lock().ok()?.get(token).cloned()?;
+        let is_active = self
+            .records
+            .lock()
+            .ok()?
+            .get(&runtime_id)
+            .is_some_and(|record| record.is_active);
+        is_active.then_some(runtime_id)
+    }
+
+    fn issue_sessions_mcp_token(&self, runtime_id: &str) -> Result<String, String> {
+        let token = random_router_secret(24);
+        let mut tokens = self
+            .sessions_mcp_tokens
+            .lock()
+            .map_err(|_| "Failed to lock sessions MCP tokens".to_string())?;
+        tokens.retain(|_, owner| owner != runtime_id);
+        tokens.insert(token.clone(), runtime_id.to_string());
+        Ok(token)
+    }
+
+    /// Only offered when the user has opted in. Skipped (with a log line) when
+    /// external control is not listening, so a session still launches without
+    /// orchestration tools.
+    fn helper_sessions_mcp_init(
+        &self,
+        app: &AppHandle,
+        runtime_id: &str,
+    ) -> Option<HelperSessionsMcpInit> {
+        if !read_settings().is_ok_and(|settings| settings.native_sessions_mcp_enabled) {
+            return None;
+        }
+        let endpoint = app
+            .try_state::<Arc<ExternalControlManager>>()?
+            .current_endpoint()?;
+        let prepared = sessions_mcp::ensure_sessions_mcp_server_script().and_then(|script_path| {
+            Ok(HelperSessionsMcpInit {
+                script_path: script_path.to_string_lossy().to_string(),
+                endpoint,
+                token: self.issue_sessions_mcp_token(runtime_id)?,
+            })
+        });
+        match prepared {
+            Ok(init) => Some(init),
+            Err(error) => {
+                eprintln!("Sessions MCP server unavailable for {runtime_id}: {error}");
+                None
+            }
+        }
+    }
+
     fn append_event_if_current(
         &self,
         runtime_id: &str,
@@ -6147,28 +8218,36 @@ impl NativeRuntimeManager {
             .map_err(|_| "Failed to lock native runtime records".to_string())?
             .get(runtime_id)
             .cloned()
-            .map(|record| NativeSessionSummary {
-                runtime_id: record.runtime_id,
-                provider: record.provider,
-                transport: record.transport,
-                provider_session_id: record.provider_session_id,
-                seed_boundary_message_count: record.seed_boundary_message_count,
-                project_dir: record.project_dir,
-                env_name: record.env_name,
-                perm_mode: record.perm_mode,
-                runtime_perm_mode: record.runtime_perm_mode,
-                effort: record.effort,
-                pending_env_name: record.pending_env_name,
-                pending_effort: record.pending_effort,
-                status: record.status,
-                created_at: record.created_at,
-                updated_at: record.updated_at,
-                is_active: record.is_active,
-                last_event_seq: None,
-                can_handoff_to_terminal: record.can_handoff_to_terminal,
-                background_tasks: Vec::new(),
-                last_error: record.last_error,
-                router: record.router.as_ref().map(SessionRouterState::from),
+            .map(|record| {
+                let worktree = session_worktree_summary(&record);
+                NativeSessionSummary {
+                    runtime_id: record.runtime_id,
+                    provider: record.provider,
+                    transport: record.transport,
+                    provider_session_id: record.provider_session_id,
+                    seed_boundary_message_count: record.seed_boundary_message_count,
+                    project_dir: record.project_dir,
+                    env_name: record.env_name,
+                    perm_mode: record.perm_mode,
+                    runtime_perm_mode: record.runtime_perm_mode,
+                    effort: record.effort,
+                    pending_env_name: record.pending_env_name,
+                    pending_effort: record.pending_effort,
+                    status: record.status,
+                    created_at: record.created_at,
+                    updated_at: record.updated_at,
+                    is_active: record.is_active,
+                    last_event_seq: None,
+                    can_handoff_to_terminal: record.can_handoff_to_terminal,
+                    background_tasks: Vec::new(),
+                    last_error: record.last_error,
+                    router: record.router.as_ref().map(SessionRouterState::from),
+                    worktree,
+                    prompt_queue: record.prompt_queue,
+                    limits: record.limits,
+                    restart_interrupted_at: record.restart_interrupted_at,
+                    imported: record.imported,
+                }
             }))
     }
 
@@ -6431,6 +8510,30 @@ fn generate_runtime_id() -> String {
     format!("native-{}", hex::encode(random))
 }
 
+fn session_worktree_branch(runtime_id: &str) -> String {
+    format!("ccem/{}", runtime_id)
+}
+
+fn remove_abandoned_worktree(worktree: Option<&GitWorktree>) {
+    if let Some(worktree) = worktree {
+        if let Err(error) = git_worktree::remove_worktree(worktree, true) {
+            eprintln!("Failed to remove worktree {}: {}", worktree.path, error);
+        }
+    }
+}
+
+fn generate_queued_prompt_id() -> String {
+    let mut random = [0_u8; 4];
+    rand::rngs::OsRng.fill_bytes(&mut random);
+    format!("queued-{}", hex::encode(random))
+}
+
+fn generate_fanout_group_id() -> String {
+    let mut random = [0_u8; 4];
+    rand::rngs::OsRng.fill_bytes(&mut random);
+    format!("fanout-{}", hex::encode(random))
+}
+
 fn native_runtime_state_file_path() -> PathBuf {
     dirs::home_dir()
         .map(|home| home.join(".ccem/native-runtime-state.json"))
@@ -6536,6 +8639,8 @@ fn build_runtime_bootstrap_options(
         perm_mode: record.perm_mode.clone(),
         runtime_perm_mode: record.runtime_perm_mode.clone(),
         working_dir: record.project_dir.clone(),
+        use_git_worktree: false,
+        limits: record.limits.clone(),
         initial_prompt: None,
         display_prompt: None,
         initial_images: None,
@@ -6555,6 +8660,67 @@ fn build_runtime_bootstrap_options(
     })
 }
 
+/// Options for one fan-out member, launched in its own worktree of
+/// `working_dir`.
+fn build_fanout_member_options(
+    target: &NativeFanoutTarget,
+    working_dir: &str,
+    perm_mode: &str,
+    prompt: &str,
+) -> Result<NativeSessionOptions, String> {
+    let (env_name, helper_env_vars, claude_path, codex_path) = match target.provider {
+        NativeProvider::Claude => {
+            let resolved = resolve_claude_env(&target.env_name)?;
+            (
+                resolved.env_name,
+                resolved.env_vars,
+                resolve_claude_path(),
+                None,
+            )
+        }
+        NativeProvider::Codex => {
+            let resolved = resolve_codex_runtime(&target.env_name)?;
+            let env_name = if resolved.env_name.is_empty() {
+                target.env_name.clone()
+            } else {
+                resolved.env_name
+            };
+            (
+                env_name,
+                resolve_codex_proxy_env(),
+                None,
+                resolve_codex_path(),
+            )
+        }
+    };
+
+    Ok(NativeSessionOptions {
+        provider: target.provider,
+        env_name,
+        perm_mode: perm_mode.to_string(),
+        runtime_perm_mode: None,
+        working_dir: working_dir.to_string(),
+        use_git_worktree: true,
+        limits: None,
+        initial_prompt: Some(prompt.to_string()),
+        display_prompt: None,
+        initial_images: None,
+        initial_annotations: None,
+        provider_session_id: None,
+        seed_boundary_message_count: None,
+        terminal_env_vars: helper_env_vars.clone(),
+        helper_env_vars,
+        claude_path,
+        codex_path,
+        codex_base_url: None,
+        codex_api_key: None,
+        effort: target.effort.clone(),
+        router_launch_draft: None,
+        router_record: None,
+        fork_from_message_id: None,
+    })
+}
+
 fn inject_ccem_runtime_env(env_vars: &mut HashMap<String, String>, runtime_id: &str) {
     env_vars.insert("CCEM_RUNTIME_ID".to_string(), runtime_id.to_string());
     env_vars.insert("CCEM_SESSION_ID".to_string(), runtime_id.to_string());
@@ -6619,6 +8785,8 @@ fn dedupe_nonempty(values: &mut Vec<String>) {
 
 #[cfg(test)]
 mod tests {
+    #[cfg(target_os = "linux")]
+    use super::linux_stat_process_group;
     #[cfg(unix)]
     use super::unix_descendant_process_ids;
     use super::{
@@ -6629,18 +8797,21 @@ mod tests {
         native_status_allows_file_rewind, reactivate_record_for_reconnect,
         read_native_runtime_state_from, recoverable_record_after_helper_removed,
         router_launch_decision, runtime_child_is_owned,
-        session_router_patch_oauth_validation_enabled, stage_runtime_settings_update,
-        take_terminal_launches, validate_router_create_selection,
-        validate_router_launch_draft_profile, HelperInputCommand, NativeProvider,
-        NativeRuntimeManager, NativeSessionHandle, NativeSessionOptions, NativeSessionRecord,
-        NativeTransport, PromptImage, RouterLaunchDecision, RouterLaunchDraft,
+        session_router_patch_oauth_validation_enabled, session_worktree_branch,
+        session_worktree_summary, stage_runtime_settings_update, take_terminal_launches,
+        validate_router_create_selection, validate_router_launch_draft_profile, HelperInputCommand,
+        NativeLimitSignal, NativeProvider, NativeRuntimeManager, NativeSessionHandle,
+        NativeSessionOptions, NativeSessionRecord, NativeTransport, NativeWorktreeAction,
+        PromptImage, RouterLaunchDecision, RouterLaunchDraft,
     };
     use crate::event_bus::{
         NativeBackgroundTask, NativeBackgroundTaskStatus, NativeBackgroundTaskUsage,
-        SessionEventPayload, SessionPromptAnnotation, SessionStore, TodoSnapshotItemV1,
-        TodoSnapshotV1,
+        SessionEventPayload, SessionEventRecord, SessionPromptAnnotation, SessionStore,
+        TodoSnapshotItemV1, TodoSnapshotV1,
     };
+    use crate::git_worktree::{GitWorktree, WorktreeDiffStat};
     use crate::native_event_log::NativeEventLog;
+    use crate::native_limits::NativeLimitKind;
     use crate::prompt_image_store::PromptImageStore;
     use crate::router::{
         LaunchAuthKind, LaunchTransport, RouterAuthCapability, RouterConfig, RouterManager,
@@ -6680,6 +8851,16 @@ mod tests {
         assert_eq!(descendants, vec![101, 102, 103]);
     }
 
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn process_group_is_read_past_parentheses_in_the_command_name() {
+        assert_eq!(
+            linux_stat_process_group("4242 (node (helper)) S 4200 4242 4200 0 -1"),
+            Some(4242)
+        );
+        assert_eq!(linux_stat_process_group("4242 node S"), None);
+    }
+
     fn native_session_handle_with_generation(
         record: NativeSessionRecord,
         generation: u64,
@@ -6745,6 +8926,12 @@ mod tests {
             pending_handoff_allow_background_task_termination: false,
             last_error: None,
             router: None,
+            worktree: None,
+            worktree_diff_stat: None,
+            prompt_queue: Vec::new(),
+            limits: None,
+            restart_interrupted_at: None,
+            imported: None,
         };
         let handle = native_session_handle(record.clone());
         let manager = NativeRuntimeManager {
@@ -6766,6 +8953,10 @@ mod tests {
             app_termination_lock: Mutex::new(()),
             app_termination_in_progress: AtomicBool::new(false),
             terminal_handoff_preparations: Mutex::new(HashMap::new()),
+            event_subscribers: Mutex::new(Vec::new()),
+            sessions_mcp_tokens: Mutex::new(HashMap::new()),
+            prompt_queue_lock: Mutex::new(()),
+            limit_trackers: Mutex::new(HashMap::new()),
         };
         manager
     }
@@ -6798,6 +8989,10 @@ mod tests {
             app_termination_lock: Mutex::new(()),
             app_termination_in_progress: AtomicBool::new(false),
             terminal_handoff_preparations: Mutex::new(HashMap::new()),
+            event_subscribers: Mutex::new(Vec::new()),
+            sessions_mcp_tokens: Mutex::new(HashMap::new()),
+            prompt_queue_lock: Mutex::new(()),
+            limit_trackers: Mutex::new(HashMap::new()),
         }
     }
 
@@ -6883,6 +9078,12 @@ mod tests {
             pending_handoff_allow_background_task_termination: false,
             last_error: None,
             router: None,
+            worktree: None,
+            worktree_diff_stat: None,
+            prompt_queue: Vec::new(),
+            limits: None,
+            restart_interrupted_at: None,
+            imported: None,
         }
     }
 
@@ -7509,6 +9710,8 @@ mod tests {
             perm_mode: perm_mode.to_string(),
             runtime_perm_mode: runtime_perm_mode.map(str::to_string),
             working_dir: "/tmp/project".to_string(),
+            use_git_worktree: false,
+            limits: None,
             initial_prompt: None,
             display_prompt: None,
             initial_images: None,
@@ -7764,6 +9967,7 @@ mod tests {
             effort: None,
             todo_snapshot_seed: None,
             router: None,
+            sessions_mcp: None,
         };
 
         let serialized = serde_json::to_value(&command).expect("serialize fork init command");
@@ -7790,6 +9994,7 @@ mod tests {
             effort: None,
             todo_snapshot_seed: None,
             router: None,
+            sessions_mcp: None,
         };
 
         let plain_serialized = serde_json::to_value(&plain).expect("serialize plain init command");
@@ -7829,6 +10034,7 @@ mod tests {
             effort: None,
             todo_snapshot_seed: None,
             router: None,
+            sessions_mcp: None,
             fork_at_message_id: None,
             fork_session: None,
         };
@@ -7875,6 +10081,7 @@ mod tests {
             effort: None,
             todo_snapshot_seed: Some(&seed),
             router: None,
+            sessions_mcp: None,
             fork_at_message_id: None,
             fork_session: None,
         };
@@ -7907,6 +10114,7 @@ mod tests {
             effort: None,
             todo_snapshot_seed: None,
             router: None,
+            sessions_mcp: None,
             fork_at_message_id: None,
             fork_session: None,
         };
@@ -7965,6 +10173,10 @@ mod tests {
             app_termination_lock: Mutex::new(()),
             app_termination_in_progress: AtomicBool::new(false),
             terminal_handoff_preparations: Mutex::new(HashMap::new()),
+            event_subscribers: Mutex::new(Vec::new()),
+            sessions_mcp_tokens: Mutex::new(HashMap::new()),
+            prompt_queue_lock: Mutex::new(()),
+            limit_trackers: Mutex::new(HashMap::new()),
         };
 
         let handoff = manager
@@ -8053,6 +10265,7 @@ mod tests {
             images,
             annotations,
             canonical_hash,
+            ..
         } = &batch.events[0].payload
         else {
             panic!("expected user prompt event");
@@ -8168,6 +10381,7 @@ mod tests {
             images,
             annotations,
             canonical_hash,
+            ..
         } = &batch.events[0].payload
         else {
             panic!("expected user prompt event");
@@ -8990,6 +11204,317 @@ mod tests {
         assert!(!idle.is_active);
     }
 
+    fn missing_worktree(runtime_id: &str) -> GitWorktree {
+        let path = std::env::temp_dir()
+            .join(format!("ccem-missing-worktree-{runtime_id}"))
+            .to_string_lossy()
+            .to_string();
+        GitWorktree {
+            repo_root: "/tmp/project".to_string(),
+            session_dir: format!("{path}/web"),
+            path,
+            branch: session_worktree_branch(runtime_id),
+            base_sha: "abc123".to_string(),
+        }
+    }
+
+    #[test]
+    fn reconcile_stale_records_forgets_worktrees_deleted_behind_its_back() {
+        let runtime_id = "native-reconcile-worktree";
+        let mut record = native_record(runtime_id, "stopped", false);
+        record.worktree = Some(missing_worktree(runtime_id));
+        record.project_dir = format!("{}/web", record.worktree.as_ref().unwrap().path);
+        let manager = manager_with_records(runtime_id, vec![record]);
+
+        assert_eq!(manager.reconcile_stale_records().unwrap(), 1);
+
+        let summary = manager.summary_for(runtime_id).expect("summary");
+        assert_eq!(summary.project_dir, "/tmp/project/web");
+        assert!(summary.worktree.is_none());
+    }
+
+    #[test]
+    fn reconcile_offers_sessions_with_a_provider_session_for_recovery() {
+        let runtime_id = "native-reconcile-recovery";
+        let mut resumable = native_record(runtime_id, "processing", true);
+        resumable.provider_session_id = Some("claude-session-1".to_string());
+        let fresh = native_record("native-reconcile-recovery-fresh", "processing", true);
+        let mut finished = native_record("native-reconcile-recovery-done", "stopped", false);
+        finished.provider_session_id = Some("claude-session-2".to_string());
+        let manager = manager_with_records(runtime_id, vec![resumable, fresh, finished]);
+
+        manager.reconcile_stale_records().expect("reconcile");
+
+        let candidates = manager.list_recovery_candidates().expect("candidates");
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].runtime_id, runtime_id);
+        assert_eq!(candidates[0].provider_session_id, "claude-session-1");
+        assert!(!candidates[0].routed);
+        assert!(manager
+            .summary_for(runtime_id)
+            .expect("summary")
+            .restart_interrupted_at
+            .is_some());
+
+        manager
+            .dismiss_recovery_candidate(runtime_id)
+            .expect("dismiss");
+        assert!(manager
+            .list_recovery_candidates()
+            .expect("candidates")
+            .is_empty());
+        let _ = fs::remove_file(&manager.state_path);
+    }
+
+    #[test]
+    fn imported_archives_replay_as_read_only_sessions() {
+        let runtime_id = "native-archive-export";
+        let mut source = native_record(runtime_id, "stopped", false);
+        source.provider_session_id = Some("claude-session-archive".to_string());
+        let manager = Arc::new(manager_with_records(runtime_id, vec![source]));
+        for seq in 1..=2 {
+            manager
+                .event_log
+                .append(&SessionEventRecord {
+                    runtime_id: runtime_id.to_string(),
+                    seq,
+                    occurred_at: Utc::now(),
+                    payload: SessionEventPayload::Lifecycle {
+                        stage: "test".to_string(),
+                        detail: format!("event {seq}"),
+                        assistant_message_uuid: None,
+                    },
+                })
+                .expect("append event");
+        }
+        let archive_path =
+            std::env::temp_dir().join(format!("ccem-native-runtime-test-{runtime_id}.gz"));
+
+        let export = manager
+            .export_session_archive(runtime_id, &archive_path)
+            .expect("export");
+        assert_eq!(export.event_count, 2);
+        let imported = manager
+            .import_session_archive(&archive_path)
+            .expect("import");
+
+        assert_ne!(imported.runtime_id, runtime_id);
+        assert!(!imported.is_active);
+        assert_eq!(imported.provider_session_id, None);
+        assert_eq!(
+            imported
+                .imported
+                .as_ref()
+                .map(|import| import.source_runtime_id.as_str()),
+            Some(runtime_id)
+        );
+        let replay = manager
+            .replay_events(&imported.runtime_id, None)
+            .expect("replay import");
+        assert_eq!(replay.events.len(), 2);
+        assert!(replay
+            .events
+            .iter()
+            .all(|event| event.runtime_id == imported.runtime_id));
+        assert!(manager
+            .prepare_reconnect_handle_locked(&imported.runtime_id, false, None)
+            .err()
+            .is_some_and(|error| error.contains("imported replay")));
+
+        let _ = fs::remove_file(&archive_path);
+        let _ = fs::remove_file(&manager.state_path);
+    }
+
+    #[test]
+    fn worktree_summary_offers_actions_once_the_turn_is_over() {
+        let runtime_id = "native-worktree-actions";
+        let mut record = native_record(runtime_id, "processing", true);
+        record.worktree = Some(missing_worktree(runtime_id));
+        record.worktree_diff_stat = Some(WorktreeDiffStat {
+            files_changed: 1,
+            insertions: 3,
+            deletions: 0,
+            files: Vec::new(),
+        });
+
+        let running = session_worktree_summary(&record).expect("worktree summary");
+        assert_eq!(running.branch, "ccem/native-worktree-actions");
+        assert!(running.actions.is_empty());
+
+        record.status = "idle".to_string();
+        let idle = session_worktree_summary(&record).expect("worktree summary");
+        assert_eq!(
+            idle.actions,
+            vec![
+                NativeWorktreeAction::Merge,
+                NativeWorktreeAction::Rebase,
+                NativeWorktreeAction::Discard,
+            ]
+        );
+        assert_eq!(
+            idle.diff_stat.map(|diff_stat| diff_stat.insertions),
+            Some(3)
+        );
+    }
+
+    #[test]
+    fn prompts_sent_mid_turn_are_queued_and_can_be_reordered_or_cancelled() {
+        let runtime_id = "native-prompt-queue";
+        // The event log path is fixed per runtime id; start from an empty log
+        // so replayed prompts from an earlier run don't shadow this one's.
+        let event_log_path =
+            std::env::temp_dir().join(format!("ccem-native-runtime-test-{runtime_id}.sqlite"));
+        for suffix in ["", "-wal", "-shm"] {
+            let mut path = event_log_path.clone().into_os_string();
+            path.push(suffix);
+            let _ = fs::remove_file(path);
+        }
+        let manager = manager_with_handle(runtime_id);
+        for text in ["first", "second", "third"] {
+            manager
+                .enqueue_prompt(runtime_id, text, None, None, None)
+                .expect("enqueue prompt");
+        }
+
+        let queued = manager.list_queued_prompts(runtime_id).expect("queue");
+        let ids = queued
+            .iter()
+            .map(|item| item.item_id.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            queued
+                .iter()
+                .map(|item| item.text.as_str())
+                .collect::<Vec<_>>(),
+            vec!["first", "second", "third"]
+        );
+
+        let batch = manager
+            .replay_events(runtime_id, None)
+            .expect("replay events");
+        let SessionEventPayload::UserPrompt {
+            text,
+            queued,
+            queue_item_id,
+            ..
+        } = &batch.events[0].payload
+        else {
+            panic!("expected user prompt event");
+        };
+        assert_eq!(text, "first");
+        assert!(*queued);
+        assert_eq!(queue_item_id.as_ref(), Some(&ids[0]));
+
+        let partial = manager
+            .reorder_queued_prompts(runtime_id, &ids[..2])
+            .expect_err("partial order is rejected");
+        assert!(partial.contains("changed"));
+        let reordered = manager
+            .reorder_queued_prompts(
+                runtime_id,
+                &[ids[2].clone(), ids[0].clone(), ids[1].clone()],
+            )
+            .expect("reorder queue");
+        assert_eq!(reordered[0].text, "third");
+
+        let remaining = manager
+            .cancel_queued_prompt(runtime_id, &ids[0])
+            .expect("cancel queued prompt");
+        assert_eq!(
+            remaining
+                .iter()
+                .map(|item| item.text.as_str())
+                .collect::<Vec<_>>(),
+            vec!["third", "second"]
+        );
+        assert!(manager.cancel_queued_prompt(runtime_id, &ids[0]).is_err());
+        assert_eq!(
+            manager
+                .summary_for(runtime_id)
+                .expect("summary")
+                .prompt_queue,
+            remaining
+        );
+
+        let _ = fs::remove_file(&manager.state_path);
+    }
+
+    #[test]
+    fn limit_tracker_keeps_the_highest_cost_and_rearms_idle_warnings_on_activity() {
+        let runtime_id = "native-limit-tracker";
+        let manager = manager_with_handle(runtime_id);
+        let generation = manager.handles.lock().unwrap()[runtime_id].generation;
+        manager.reset_limit_tracker(runtime_id, generation);
+        let idle_since = Utc::now() - chrono::Duration::minutes(45);
+        {
+            let mut trackers = manager.limit_trackers.lock().unwrap();
+            let tracker = trackers.get_mut(runtime_id).unwrap();
+            tracker.usage.last_activity_at = idle_since;
+            tracker.warned.insert(NativeLimitKind::IdleTimeout);
+        }
+
+        for payload in [
+            SessionEventPayload::SessionUsage {
+                provider: "claude".to_string(),
+                input_tokens: 10,
+                output_tokens: 5,
+                cache_read_tokens: 0,
+                cache_creation_tokens: 0,
+                cost_usd: Some(0.42),
+                model_usage: Vec::new(),
+                subscription_type: None,
+                rate_limits_available: false,
+                rate_limits: None,
+            },
+            SessionEventPayload::TokenUsage {
+                provider: "claude".to_string(),
+                input_tokens: 10,
+                output_tokens: 5,
+                cache_read_tokens: 0,
+                cache_creation_tokens: 0,
+                total_cost_usd: Some(0.3),
+                scope: None,
+            },
+            SessionEventPayload::StdErrLine {
+                line: "debug noise".to_string(),
+            },
+        ] {
+            manager
+                .append_event(runtime_id, payload)
+                .expect("append event");
+        }
+        manager
+            .append_lifecycle_event(runtime_id, "limit_warning", "idle_timeout")
+            .expect("append warning");
+        {
+            let trackers = manager.limit_trackers.lock().unwrap();
+            let tracker = &trackers[runtime_id];
+            assert_eq!(tracker.usage.cost_usd, Some(0.42));
+            assert_eq!(tracker.usage.last_activity_at, idle_since);
+            assert!(tracker.warned.contains(&NativeLimitKind::IdleTimeout));
+        }
+
+        manager
+            .append_lifecycle_event(runtime_id, "helper_ready", "")
+            .expect("append activity");
+        {
+            let trackers = manager.limit_trackers.lock().unwrap();
+            let tracker = &trackers[runtime_id];
+            assert!(tracker.usage.last_activity_at > idle_since);
+            assert!(tracker.warned.is_empty());
+        }
+
+        manager.record_limit_signal(runtime_id, generation + 1, NativeLimitSignal::Cost(0.1));
+        assert_eq!(
+            manager.limit_trackers.lock().unwrap()[runtime_id]
+                .usage
+                .cost_usd,
+            Some(0.1)
+        );
+
+        let _ = fs::remove_file(&manager.state_path);
+    }
+
     #[test]
     fn summary_includes_persisted_effort() {
         let mut record = native_record("native-effort", "ready", true);
@@ -0,0 +1,358 @@
+// apps/desktop/src-tauri/src/native_session_archive.rs
+//
+// Portable native session archives: one runtime's event log, the prompt
+// images it references and its session metadata, written as gzip-compressed
+// JSON. Importing an archive registers a read-only replay session under a
+// fresh runtime id, so exported sessions can be opened in the workspace for
+// bug reports or sharing without ever reconnecting to a provider.
+
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tauri::State;
+
+use crate::event_bus::{SessionEventPayload, SessionEventRecord};
+use crate::native_runtime::{NativeProvider, NativeRuntimeManager, NativeSessionSummary};
+
+pub const NATIVE_SESSION_ARCHIVE_FORMAT: &str = "ccem-native-session";
+pub const NATIVE_SESSION_ARCHIVE_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct NativeSessionArchive {
+    pub format: String,
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub session: NativeArchivedSession,
+    pub events: Vec<SessionEventRecord>,
+    #[serde(default)]
+    pub images: Vec<NativeArchivedImage>,
+}
+
+/// What the archive keeps of the exported session record.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct NativeArchivedSession {
+    pub runtime_id: String,
+    pub provider: NativeProvider,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider_session_id: Option<String>,
+    pub project_dir: String,
+    pub env_name: String,
+    pub perm_mode: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub effort: Option<String>,
+    pub status: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub struct NativeArchivedImage {
+    /// Storage path the exporting machine's events refer to.
+    pub storage_path: String,
+    pub media_type: String,
+    pub base64_data: String,
+}
+
+/// Marks a session record as an imported replay. Such sessions only serve
+/// their event log; they never reconnect to the provider.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct NativeSessionImport {
+    pub source_runtime_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_provider_session_id: Option<String>,
+    pub exported_at: DateTime<Utc>,
+    pub imported_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NativeSessionExport {
+    pub path: String,
+    pub event_count: usize,
+    pub image_count: usize,
+    /// Images referenced by events whose files were already gone.
+    pub missing_image_count: usize,
+}
+
+/// Distinct `(storage_path, media_type)` pairs of the images prompts refer
+/// to, in the order they first appear.
+pub fn referenced_images(events: &[SessionEventRecord]) -> Vec<(String, String)> {
+    let mut seen = HashSet::new();
+    let mut images = Vec::new();
+    for event in events {
+        let SessionEventPayload::UserPrompt {
+            images: Some(prompt_images),
+            ..
+        } = &event.payload
+        else {
+            continue;
+        };
+        for image in prompt_images {
+            if let Some(storage_path) = image.storage_path.as_deref() {
+                if seen.insert(storage_path.to_string()) {
+                    images.push((storage_path.to_string(), image.media_type.clone()));
+                }
+            }
+        }
+    }
+    images
+}
+
+/// Move archived events onto the runtime they are imported as, pointing
+/// prompt images at where this machine stored them.
+pub fn rebind_events(
+    events: Vec<SessionEventRecord>,
+    runtime_id: &str,
+    image_paths: &HashMap<String, String>,
+) -> Vec<SessionEventRecord> {
+    events
+        .into_iter()
+        .map(|mut event| {
+            event.runtime_id = runtime_id.to_string();
+            if let SessionEventPayload::UserPrompt {
+                images: Some(prompt_images),
+                ..
+            } = &mut event.payload
+            {
+                for image in prompt_images {
+                    if let Some(stored) = image
+                        .storage_path
+                        .as_ref()
+                        .and_then(|path| image_paths.get(path))
+                    {
+                        image.storage_path = Some(stored.clone());
+                    }
+                }
+            }
+            event
+        })
+        .collect()
+}
+
+pub fn write_archive(path: &Path, archive: &NativeSessionArchive) -> Result<(), String> {
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent)
+            .map_err(|error| format!("Failed to create {}: {}", parent.display(), error))?;
+    }
+    let mut temp = path.to_path_buf().into_os_string();
+    temp.push(".tmp");
+    let temp = PathBuf::from(temp);
+    let written = fs::File::create(&temp).and_then(|file| {
+        let mut encoder = GzEncoder::new(BufWriter::new(file), Compression::default());
+        serde_json::to_writer(&mut encoder, archive).map_err(io::Error::from)?;
+        encoder.finish()?.flush()
+    });
+    if let Err(error) = written.and_then(|()| fs::rename(&temp, path)) {
+        let _ = fs::remove_file(&temp);
+        return Err(format!(
+            "Failed to write session archive {}: {}",
+            path.display(),
+            error
+        ));
+    }
+    Ok(())
+}
+
+pub fn read_archive(path: &Path) -> Result<NativeSessionArchive, String> {
+    let file = fs::File::open(path).map_err(|error| {
+        format!(
+            "Failed to open session archive {}: {}",
+            path.display(),
+            error
+        )
+    })?;
+    let archive: NativeSessionArchive =
+        serde_json::from_reader(BufReader::new(GzDecoder::new(file))).map_err(|error| {
+            format!(
+                "{} is not a readable session archive: {}",
+                path.display(),
+                error
+            )
+        })?;
+    validate_archive(&archive)?;
+    Ok(archive)
+}
+
+fn validate_archive(archive: &NativeSessionArchive) -> Result<(), String> {
+    if archive.format != NATIVE_SESSION_ARCHIVE_FORMAT {
+        return Err(format!(
+            "Unsupported session archive format '{}'",
+            archive.format
+        ));
+    }
+    if archive.version == 0 || archive.version > NATIVE_SESSION_ARCHIVE_VERSION {
+        return Err(format!(
+            "Session archive version {} is not supported by this version of the app",
+            archive.version
+        ));
+    }
+    if let Some(event) = archive
+        .events
+        .iter()
+        .find(|event| event.runtime_id != archive.session.runtime_id)
+    {
+        return Err(format!(
+            "Session archive mixes events of {} into {}",
+            event.runtime_id, archive.session.runtime_id
+        ));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn export_native_session(
+    native_state: State<'_, Arc<NativeRuntimeManager>>,
+    runtime_id: String,
+    path: String,
+) -> Result<NativeSessionExport, String> {
+    let native_state = native_state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        native_state.export_session_archive(&runtime_id, Path::new(&path))
+    })
+    .await
+    .map_err(|error| format!("Blocking session export task failed: {error}"))?
+}
+
+/// Registers the archive as a read-only replay session and returns it.
+#[tauri::command]
+pub async fn import_native_session(
+    native_state: State<'_, Arc<NativeRuntimeManager>>,
+    path: String,
+) -> Result<NativeSessionSummary, String> {
+    let native_state = native_state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        native_state.import_session_archive(Path::new(&path))
+    })
+    .await
+    .map_err(|error| format!("Blocking session import task failed: {error}"))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event_bus::SessionPromptImage;
+    use chrono::TimeZone;
+
+    fn prompt(runtime_id: &str, seq: u64, storage_path: Option<&str>) -> SessionEventRecord {
+        SessionEventRecord {
+            runtime_id: runtime_id.to_string(),
+            seq,
+            occurred_at: Utc.with_ymd_and_hms(2026, 3, 1, 12, 0, seq as u32).unwrap(),
+            payload: SessionEventPayload::UserPrompt {
+                text: format!("prompt {seq}"),
+                image_count: u64::from(storage_path.is_some()),
+                images: storage_path.map(|path| {
+                    vec![SessionPromptImage {
+                        media_type: "image/png".to_string(),
+                        base64_data: None,
+                        storage_path: Some(path.to_string()),
+                        sha256: None,
+                        byte_size: Some(5),
+                        placeholder: None,
+                    }]
+                }),
+                annotations: None,
+                canonical_hash: None,
+                queued: false,
+                queue_item_id: None,
+            },
+        }
+    }
+
+    fn archive(events: Vec<SessionEventRecord>) -> NativeSessionArchive {
+        let at = Utc.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap();
+        NativeSessionArchive {
+            format: NATIVE_SESSION_ARCHIVE_FORMAT.to_string(),
+            version: NATIVE_SESSION_ARCHIVE_VERSION,
+            exported_at: at,
+            session: NativeArchivedSession {
+                runtime_id: "native-source".to_string(),
+                provider: NativeProvider::Claude,
+                provider_session_id: Some("claude-session".to_string()),
+                project_dir: "/work/app".to_string(),
+                env_name: "official".to_string(),
+                perm_mode: "dev".to_string(),
+                effort: None,
+                status: "stopped".to_string(),
+                created_at: at,
+                updated_at: at,
+                last_error: None,
+            },
+            events,
+            images: vec![NativeArchivedImage {
+                storage_path: "abc.png".to_string(),
+                media_type: "image/png".to_string(),
+                base64_data: "aGVsbG8=".to_string(),
+            }],
+        }
+    }
+
+    #[test]
+    fn archives_round_trip_and_reject_foreign_events() {
+        let path = std::env::temp_dir().join(format!(
+            "ccem-session-archive-test-{}.json.gz",
+            Utc::now().timestamp_nanos_opt().unwrap_or_default()
+        ));
+        let original = archive(vec![
+            prompt("native-source", 1, Some("abc.png")),
+            prompt("native-source", 2, None),
+        ]);
+
+        write_archive(&path, &original).expect("write archive");
+        assert_eq!(read_archive(&path).expect("read archive"), original);
+
+        write_archive(&path, &archive(vec![prompt("native-other", 1, None)]))
+            .expect("write archive");
+        assert!(read_archive(&path)
+            .unwrap_err()
+            .contains("mixes events of native-other"));
+
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn rebinding_moves_events_and_image_paths_to_the_imported_runtime() {
+        let events = vec![
+            prompt("native-source", 1, Some("abc.png")),
+            prompt("native-source", 2, Some("abc.png")),
+            prompt("native-source", 3, Some("gone.png")),
+        ];
+        assert_eq!(
+            referenced_images(&events),
+            vec![
+                ("abc.png".to_string(), "image/png".to_string()),
+                ("gone.png".to_string(), "image/png".to_string()),
+            ]
+        );
+
+        let image_paths = HashMap::from([("abc.png".to_string(), "def.png".to_string())]);
+        let rebound = rebind_events(events, "native-imported", &image_paths);
+
+        assert!(rebound
+            .iter()
+            .all(|event| event.runtime_id == "native-imported"));
+        assert_eq!(
+            referenced_images(&rebound)
+                .into_iter()
+                .map(|(path, _)| path)
+                .collect::<Vec<_>>(),
+            vec!["def.png".to_string(), "gone.png".to_string()]
+        );
+    }
+}
@@ -0,0 +1,336 @@
+// apps/desktop/src-tauri/src/native_session_tree.rs
+//
+// Conversation branches: native sessions forked from a message of another
+// session form a tree, linked through the `session_forks` provenance table.
+// The tree carries per-branch status, cost and last message; two branches
+// can be compared by the files each one touched after they diverged. File
+// changes are rebuilt from edit tool inputs in the event log, so they work
+// for branches that share a checkout as well as worktree sessions.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tauri::State;
+
+use crate::event_bus::ReplayBatch;
+use crate::native_runtime::{NativeProvider, NativeRuntimeManager};
+use crate::session_digest::{build_session_digest, DigestFileChange};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NativeSessionTreeNode {
+    pub runtime_id: String,
+    /// `None` once the runtime record is gone.
+    pub provider: Option<NativeProvider>,
+    pub status: Option<String>,
+    pub is_active: bool,
+    pub provider_session_id: Option<String>,
+    /// Message of the parent conversation this branch was forked at.
+    pub fork_message_id: Option<String>,
+    pub seed_boundary_message_count: Option<u64>,
+    pub forked_at: Option<DateTime<Utc>>,
+    pub cost_usd: Option<f64>,
+    pub last_message: Option<String>,
+    pub children: Vec<NativeSessionTreeNode>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NativeSessionTree {
+    pub root_runtime_id: String,
+    pub branch_count: usize,
+    pub root: NativeSessionTreeNode,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NativeBranchFileComparison {
+    pub path: String,
+    /// `None` when only the other branch touched the file.
+    pub left: Option<DigestFileChange>,
+    pub right: Option<DigestFileChange>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NativeBranchComparison {
+    pub left_runtime_id: String,
+    pub right_runtime_id: String,
+    /// The branch both sides share history with.
+    pub common_runtime_id: String,
+    pub diverged_at: Option<DateTime<Utc>>,
+    pub files: Vec<NativeBranchFileComparison>,
+}
+
+/// Part of one runtime's event log that belongs to a branch: events after
+/// `since` (the point the other side forked off) up to and including
+/// `until` (the point the next runtime on the path forked off it).
+#[derive(Clone, Debug, PartialEq)]
+pub struct BranchSegment {
+    pub runtime_id: String,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+/// Where two branches part, and the segments holding what each side
+/// changed afterwards.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BranchDivergence {
+    pub common_runtime_id: String,
+    pub diverged_at: Option<DateTime<Utc>>,
+    pub left: Vec<BranchSegment>,
+    pub right: Vec<BranchSegment>,
+}
+
+/// A runtime on the way from the tree root to a branch, with the time it
+/// forked from the previous one.
+pub type BranchPathEntry = (String, Option<DateTime<Utc>>);
+
+/// Paths run from the tree root down to the branch. `None` when the two
+/// branches belong to different trees.
+pub fn divergent_segments(
+    left_path: &[BranchPathEntry],
+    right_path: &[BranchPathEntry],
+) -> Option<BranchDivergence> {
+    let shared = left_path
+        .iter()
+        .zip(right_path)
+        .take_while(|(left, right)| left.0 == right.0)
+        .count();
+    if shared == 0 {
+        return None;
+    }
+    let common = left_path[shared - 1].0.clone();
+    let left_fork = left_path.get(shared).and_then(|(_, forked_at)| *forked_at);
+    let right_fork = right_path.get(shared).and_then(|(_, forked_at)| *forked_at);
+    let diverged_at = match (left_fork, right_fork) {
+        (Some(left), Some(right)) => Some(left.min(right)),
+        (left, right) => left.or(right),
+    };
+    let side = |path: &[BranchPathEntry], own_fork: Option<DateTime<Utc>>| {
+        let mut segments = Vec::new();
+        if diverged_at.is_some() && own_fork != diverged_at {
+            segments.push(BranchSegment {
+                runtime_id: common.clone(),
+                since: diverged_at,
+                until: own_fork,
+            });
+        }
+        for (index, (runtime_id, _)) in path.iter().enumerate().skip(shared) {
+            segments.push(BranchSegment {
+                runtime_id: runtime_id.clone(),
+                since: None,
+                until: path.get(index + 1).and_then(|(_, forked_at)| *forked_at),
+            });
+        }
+        segments
+    };
+    Some(BranchDivergence {
+        left: side(left_path, left_fork),
+        right: side(right_path, right_fork),
+        common_runtime_id: common,
+        diverged_at,
+    })
+}
+
+pub fn segment_events(batch: ReplayBatch, segment: &BranchSegment) -> ReplayBatch {
+    ReplayBatch {
+        events: batch
+            .events
+            .into_iter()
+            .filter(|event| {
+                segment.since.is_none_or(|since| event.occurred_at > since)
+                    && segment.until.is_none_or(|until| event.occurred_at <= until)
+            })
+            .collect(),
+        ..batch
+    }
+}
+
+/// Fold the file changes of a branch's segments into one entry per path.
+pub fn merge_file_changes(changes: Vec<DigestFileChange>) -> BTreeMap<String, DigestFileChange> {
+    let mut merged: BTreeMap<String, DigestFileChange> = BTreeMap::new();
+    for change in changes {
+        match merged.get_mut(&change.path) {
+            Some(existing) => {
+                for tool in change.tools {
+                    if !existing.tools.contains(&tool) {
+                        existing.tools.push(tool);
+                    }
+                }
+                existing.edit_count += change.edit_count;
+                existing.insertions += change.insertions;
+                existing.deletions += change.deletions;
+                existing.diff_truncated |= change.diff_truncated;
+                existing.diff = match (existing.diff.take(), change.diff) {
+                    (Some(left), Some(right)) => Some(format!("{left}\n{right}")),
+                    (left, right) => left.or(right),
+                };
+            }
+            None => {
+                merged.insert(change.path.clone(), change);
+            }
+        }
+    }
+    merged
+}
+
+pub fn compare_file_changes(
+    left: Vec<DigestFileChange>,
+    right: Vec<DigestFileChange>,
+) -> Vec<NativeBranchFileComparison> {
+    let mut left = merge_file_changes(left);
+    let mut right = merge_file_changes(right);
+    let mut paths = left.keys().chain(right.keys()).cloned().collect::<Vec<_>>();
+    paths.sort();
+    paths.dedup();
+    paths
+        .into_iter()
+        .map(|path| NativeBranchFileComparison {
+            left: left.remove(&path),
+            right: right.remove(&path),
+            path,
+        })
+        .collect()
+}
+
+pub fn segment_file_changes(runtime_id: &str, batch: &ReplayBatch) -> Vec<DigestFileChange> {
+    build_session_digest(runtime_id, None, batch).files
+}
+
+/// Whole tree the session belongs to, starting at its topmost ancestor.
+#[tauri::command]
+pub async fn get_session_tree(
+    native_state: State<'_, Arc<NativeRuntimeManager>>,
+    runtime_id: String,
+) -> Result<NativeSessionTree, String> {
+    let native_state = native_state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || native_state.session_tree(&runtime_id))
+        .await
+        .map_err(|error| format!("Blocking session tree task failed: {error}"))?
+}
+
+#[tauri::command]
+pub async fn compare_session_branches(
+    native_state: State<'_, Arc<NativeRuntimeManager>>,
+    left_runtime_id: String,
+    right_runtime_id: String,
+) -> Result<NativeBranchComparison, String> {
+    let native_state = native_state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        native_state.compare_branches(&left_runtime_id, &right_runtime_id)
+    })
+    .await
+    .map_err(|error| format!("Blocking branch comparison task failed: {error}"))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(minute: u32) -> Option<DateTime<Utc>> {
+        Some(Utc.with_ymd_and_hms(2026, 3, 1, 12, minute, 0).unwrap())
+    }
+
+    fn path(entries: &[(&str, Option<DateTime<Utc>>)]) -> Vec<BranchPathEntry> {
+        entries
+            .iter()
+            .map(|(runtime_id, forked_at)| (runtime_id.to_string(), *forked_at))
+            .collect()
+    }
+
+    fn change(path: &str, insertions: usize) -> DigestFileChange {
+        DigestFileChange {
+            path: path.to_string(),
+            tools: vec!["Edit".to_string()],
+            edit_count: 1,
+            insertions,
+            deletions: 0,
+            diff: None,
+            diff_truncated: false,
+        }
+    }
+
+    #[test]
+    fn sibling_branches_include_the_parent_between_their_fork_points() {
+        let left = path(&[("root", None), ("a", at(10))]);
+        let right = path(&[("root", None), ("b", at(20)), ("c", at(30))]);
+
+        let divergence = divergent_segments(&left, &right).expect("shared root");
+
+        assert_eq!(divergence.common_runtime_id, "root");
+        assert_eq!(divergence.diverged_at, at(10));
+        assert_eq!(
+            divergence.left,
+            vec![BranchSegment {
+                runtime_id: "a".to_string(),
+                since: None,
+                until: None,
+            }]
+        );
+        assert_eq!(
+            divergence.right,
+            vec![
+                BranchSegment {
+                    runtime_id: "root".to_string(),
+                    since: at(10),
+                    until: at(20),
+                },
+                BranchSegment {
+                    runtime_id: "b".to_string(),
+                    since: None,
+                    until: at(30),
+                },
+                BranchSegment {
+                    runtime_id: "c".to_string(),
+                    since: None,
+                    until: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn an_ancestor_is_compared_from_the_point_its_descendant_forked() {
+        let left = path(&[("root", None)]);
+        let right = path(&[("root", None), ("a", at(10))]);
+
+        let divergence = divergent_segments(&left, &right).expect("shared root");
+
+        assert_eq!(divergence.common_runtime_id, "root");
+        assert_eq!(divergence.diverged_at, at(10));
+        assert_eq!(
+            divergence.left,
+            vec![BranchSegment {
+                runtime_id: "root".to_string(),
+                since: at(10),
+                until: None,
+            }]
+        );
+        assert_eq!(divergence.right.len(), 1);
+        assert!(divergent_segments(&left, &path(&[("other", None)])).is_none());
+    }
+
+    #[test]
+    fn file_comparison_merges_segments_and_pairs_paths() {
+        let files = compare_file_changes(
+            vec![change("src/lib.rs", 2), change("src/lib.rs", 3)],
+            vec![change("src/lib.rs", 1), change("README.md", 4)],
+        );
+
+        assert_eq!(
+            files
+                .iter()
+                .map(|file| file.path.as_str())
+                .collect::<Vec<_>>(),
+            vec!["README.md", "src/lib.rs"]
+        );
+        assert!(files[0].left.is_none());
+        let lib = &files[1];
+        assert_eq!(lib.left.as_ref().map(|file| file.insertions), Some(5));
+        assert_eq!(lib.left.as_ref().map(|file| file.edit_count), Some(2));
+        assert_eq!(lib.right.as_ref().map(|file| file.insertions), Some(1));
+    }
+}
@@ -515,6 +515,7 @@ mod tests {
             raw_name: "ask_user_question".to_string(),
             input_summary: "question".to_string(),
             needs_response: true,
+            input: None,
             prompt: Some(InteractiveToolPrompt::AskUserQuestion {
                 questions: vec![crate::event_bus::ToolQuestionPrompt {
                     question: "Need a deployment window?".to_string(),
@@ -574,6 +575,7 @@ mod tests {
             raw_name: "exit_plan_mode".to_string(),
             input_summary: "plan ready".to_string(),
             needs_response: true,
+            input: None,
             prompt: Some(InteractiveToolPrompt::PlanExit {
                 allowed_prompts: Vec::new(),
                 plan_summary: None,
@@ -39,6 +39,23 @@ pub struct LocalOpenCodeSession {
     pub model: Option<String>,
 }
 
+/// A storage-DB session row plus its raw `updated_at` column value, which
+/// callers use as an incremental high-water mark.
+#[derive(Debug, Clone)]
+pub struct StoredOpenCodeSession {
+    pub raw_updated_at: i64,
+    pub session: LocalOpenCodeSession,
+}
+
+/// A storage-DB message with its raw parts, read for the usage they carry.
+#[derive(Debug, Clone)]
+pub struct StoredOpenCodeMessage {
+    pub model: Option<String>,
+    /// Milliseconds; when the message finished, else when it last changed.
+    pub timestamp: u64,
+    pub parts: Value,
+}
+
 #[derive(Debug, Clone)]
 pub struct LocalOpenCodeMessage {
     pub id: String,
@@ -224,46 +241,11 @@ pub fn list_local_sessions() -> Result<Vec<LocalOpenCodeSession>, String> {
     let mut sessions_by_id: HashMap<String, LocalOpenCodeSession> = HashMap::new();
 
     for db_path in discover_storage_db_paths() {
-        let Ok(conn) = Connection::open(&db_path) else {
+        let Ok(stored) = query_storage_sessions(&db_path, &metadata, 0) else {
             continue;
         };
-        let inferred_project = storage_project_from_db_path(&db_path);
 
-        let mut stmt = match conn.prepare(
-            "SELECT s.id, s.title, s.updated_at, s.created_at, s.prompt_tokens, \
-                s.completion_tokens, s.cost, \
-                (SELECT m.model FROM messages m \
-                  WHERE m.session_id = s.id AND m.model IS NOT NULL AND m.model != '' \
-                  ORDER BY COALESCE(m.finished_at, m.updated_at, m.created_at) DESC LIMIT 1) AS model \
-             FROM sessions s \
-             WHERE s.parent_session_id IS NULL",
-        ) {
-            Ok(stmt) => stmt,
-            Err(_) => continue,
-        };
-
-        let rows = match stmt.query_map([], |row| {
-            Ok(LocalOpenCodeSession {
-                id: row.get::<_, String>(0)?,
-                title: row.get::<_, String>(1)?,
-                updated_at: normalize_unix_timestamp(row.get::<_, i64>(2)?),
-                created_at: normalize_unix_timestamp(row.get::<_, i64>(3)?),
-                project: inferred_project.clone(),
-                env_name: None,
-                config_source: None,
-                prompt_tokens: row.get::<_, i64>(4).unwrap_or(0).max(0) as u64,
-                completion_tokens: row.get::<_, i64>(5).unwrap_or(0).max(0) as u64,
-                cost: row.get::<_, f64>(6).unwrap_or(0.0),
-                model: normalize_optional_text(row.get::<_, Option<String>>(7).unwrap_or(None)),
-            })
-        }) {
-            Ok(rows) => rows,
-            Err(_) => continue,
-        };
-
-        for row in rows.flatten() {
-            let metadata_entry = metadata.get(&row.id);
-            let merged = apply_metadata_to_local_session(row, metadata_entry);
+        for merged in stored.into_iter().map(|stored| stored.session) {
             match sessions_by_id.get(&merged.id) {
                 Some(existing) if existing.updated_at >= merged.updated_at => {}
                 _ => {
@@ -278,6 +260,117 @@ pub fn list_local_sessions() -> Result<Vec<LocalOpenCodeSession>, String> {
     Ok(sessions)
 }
 
+/// Root sessions in a single storage DB whose raw `updated_at` column is at
+/// or after `updated_at_or_after`. Analytics refreshes each DB incrementally
+/// from the highest raw value it has already seen.
+pub fn list_storage_sessions_since(
+    db_path: &Path,
+    updated_at_or_after: i64,
+) -> Result<Vec<StoredOpenCodeSession>, String> {
+    query_storage_sessions(db_path, &read_session_metadata_map(), updated_at_or_after)
+}
+
+fn query_storage_sessions(
+    db_path: &Path,
+    metadata: &HashMap<String, OpenCodeSessionMetadata>,
+    updated_at_or_after: i64,
+) -> Result<Vec<StoredOpenCodeSession>, String> {
+    let conn = Connection::open(db_path)
+        .map_err(|error| format!("Failed to open OpenCode storage DB: {}", error))?;
+    let inferred_project = storage_project_from_db_path(db_path);
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT s.id, s.title, s.updated_at, s.created_at, s.prompt_tokens, \
+                s.completion_tokens, s.cost, \
+                (SELECT m.model FROM messages m \
+                  WHERE m.session_id = s.id AND m.model IS NOT NULL AND m.model != '' \
+                  ORDER BY COALESCE(m.finished_at, m.updated_at, m.created_at) DESC LIMIT 1) AS model \
+             FROM sessions s \
+             WHERE s.parent_session_id IS NULL AND s.updated_at >= ?1",
+        )
+        .map_err(|error| format!("Failed to prepare OpenCode session query: {}", error))?;
+
+    let rows = stmt
+        .query_map([updated_at_or_after], |row| {
+            let raw_updated_at = row.get::<_, i64>(2)?;
+            Ok(StoredOpenCodeSession {
+                raw_updated_at,
+                session: LocalOpenCodeSession {
+                    id: row.get::<_, String>(0)?,
+                    title: row.get::<_, String>(1)?,
+                    updated_at: normalize_unix_timestamp(raw_updated_at),
+                    created_at: normalize_unix_timestamp(row.get::<_, i64>(3)?),
+                    project: inferred_project.clone(),
+                    env_name: None,
+                    config_source: None,
+                    prompt_tokens: row.get::<_, i64>(4).unwrap_or(0).max(0) as u64,
+                    completion_tokens: row.get::<_, i64>(5).unwrap_or(0).max(0) as u64,
+                    cost: row.get::<_, f64>(6).unwrap_or(0.0),
+                    model: normalize_optional_text(row.get::<_, Option<String>>(7).unwrap_or(None)),
+                },
+            })
+        })
+        .map_err(|error| format!("Failed to read OpenCode sessions: {}", error))?;
+
+    Ok(rows
+        .flatten()
+        .map(|stored| {
+            let metadata_entry = metadata.get(&stored.session.id);
+            StoredOpenCodeSession {
+                raw_updated_at: stored.raw_updated_at,
+                session: apply_metadata_to_local_session(stored.session, metadata_entry),
+            }
+        })
+        .collect())
+}
+
+/// Ids of every root session currently in a storage DB, so callers can drop
+/// what they cached for sessions that were deleted since.
+pub fn list_storage_root_session_ids(db_path: &Path) -> Result<HashSet<String>, String> {
+    let conn = Connection::open(db_path)
+        .map_err(|error| format!("Failed to open OpenCode storage DB: {}", error))?;
+    let mut stmt = conn
+        .prepare("SELECT id FROM sessions WHERE parent_session_id IS NULL")
+        .map_err(|error| format!("Failed to prepare OpenCode session id query: {}", error))?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .map_err(|error| format!("Failed to read OpenCode session ids: {}", error))?;
+    Ok(rows.flatten().collect())
+}
+
+/// Messages of the given sessions in a single storage DB, oldest first per
+/// session.
+pub fn list_storage_messages(
+    db_path: &Path,
+    session_ids: &[String],
+) -> Result<HashMap<String, Vec<StoredOpenCodeMessage>>, String> {
+    let conn = Connection::open(db_path)
+        .map_err(|error| format!("Failed to open OpenCode storage DB: {}", error))?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT model, COALESCE(finished_at, updated_at, created_at), parts \
+             FROM messages WHERE session_id = ?1 ORDER BY created_at ASC",
+        )
+        .map_err(|error| format!("Failed to prepare OpenCode message query: {}", error))?;
+
+    let mut messages = HashMap::new();
+    for session_id in session_ids {
+        let rows = stmt
+            .query_map([session_id], |row| {
+                let raw_parts = row.get::<_, String>(2).unwrap_or_else(|_| "[]".to_string());
+                Ok(StoredOpenCodeMessage {
+                    model: normalize_optional_text(row.get::<_, Option<String>>(0).unwrap_or(None)),
+                    timestamp: normalize_unix_timestamp(row.get::<_, i64>(1).unwrap_or(0)),
+                    parts: serde_json::from_str(&raw_parts).unwrap_or(Value::Null),
+                })
+            })
+            .map_err(|error| format!("Failed to read OpenCode messages: {}", error))?;
+        messages.insert(session_id.clone(), rows.flatten().collect());
+    }
+    Ok(messages)
+}
+
 pub fn load_local_messages(session_id: &str) -> Result<Option<Vec<LocalOpenCodeMessage>>, String> {
     for db_path in discover_storage_db_paths() {
         let Ok(conn) = Connection::open(&db_path) else {
@@ -425,7 +518,7 @@ fn parse_session_snapshots(value: &Value) -> Vec<OpenCodeSessionSnapshot> {
     sessions
 }
 
-fn discover_storage_db_paths() -> Vec<PathBuf> {
+pub fn discover_storage_db_paths() -> Vec<PathBuf> {
     let mut discovered = Vec::new();
     let mut seen = HashSet::new();
 
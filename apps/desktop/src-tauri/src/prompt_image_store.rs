@@ -72,6 +72,11 @@ impl PromptImageStore {
 
     pub fn read_data_url(&self, storage_path: &str, media_type: &str) -> Result<String, String> {
         let media_type = normalize_image_media_type(media_type)?;
+        let encoded = self.read_base64(storage_path)?;
+        Ok(format!("data:{};base64,{}", media_type, encoded))
+    }
+
+    pub fn read_base64(&self, storage_path: &str) -> Result<String, String> {
         let path = self.resolve_storage_path(storage_path)?;
         let bytes = fs::read(&path).map_err(|error| {
             format!(
@@ -80,8 +85,7 @@ impl PromptImageStore {
                 error
             )
         })?;
-        let encoded = STANDARD.encode(bytes);
-        Ok(format!("data:{};base64,{}", media_type, encoded))
+        Ok(STANDARD.encode(bytes))
     }
 
     fn resolve_storage_path(&self, storage_path: &str) -> Result<PathBuf, String> {
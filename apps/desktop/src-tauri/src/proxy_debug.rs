@@ -344,6 +344,18 @@ struct MetricsState {
     route_not_found_requests: u64,
     total_response_ms: u64,
     active_connections: u64,
+    /// Router ledger counts keyed by (target env, sub-route, success).
+    routed_requests: HashMap<(String, bool, bool), u64>,
+}
+
+/// Router request-ledger counter for one target environment, exported on the
+/// external control server's `/metrics` endpoint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoutedRequestCount {
+    pub target_env: String,
+    pub sub_route: bool,
+    pub success: bool,
+    pub count: u64,
 }
 
 struct ProxyRuntime {
@@ -764,6 +776,30 @@ impl ProxyDebugManager {
         }
     }
 
+    pub fn routed_request_counts(&self) -> Vec<RoutedRequestCount> {
+        let metrics = self.metrics.lock().unwrap();
+        let mut counts = metrics
+            .routed_requests
+            .iter()
+            .map(
+                |((target_env, sub_route, success), count)| RoutedRequestCount {
+                    target_env: target_env.clone(),
+                    sub_route: *sub_route,
+                    success: *success,
+                    count: *count,
+                },
+            )
+            .collect::<Vec<_>>();
+        counts.sort_by(|left, right| {
+            (&left.target_env, left.sub_route, left.success).cmp(&(
+                &right.target_env,
+                right.sub_route,
+                right.success,
+            ))
+        });
+        counts
+    }
+
     pub async fn set_enabled(self: &Arc<Self>, enabled: bool) -> Result<ProxyDebugState, String> {
         let _lifecycle = self
             .lifecycle
@@ -1554,6 +1590,14 @@ impl ProxyDebugManager {
             });
             let runtime_id = meta.session_id.clone();
             let target_env = meta.env_name.clone();
+            {
+                let mut metrics = self.metrics.lock().unwrap();
+                let success = meta.status < 400 && !response_incomplete;
+                *metrics
+                    .routed_requests
+                    .entry((target_env.clone(), meta.sub_route, success))
+                    .or_default() += 1;
+            }
             self.emit_routed_usage(
                 &runtime_id,
                 crate::event_bus::SessionEventPayload::RoutedRequest {
@@ -1365,7 +1365,7 @@ fn permission_bridge_root_dir() -> PathBuf {
         .unwrap_or_else(|| PathBuf::from(".ccem/runtime-permission-bridges"))
 }
 
-fn ccem_runtime_tools_dir() -> PathBuf {
+pub(crate) fn ccem_runtime_tools_dir() -> PathBuf {
     if cfg!(test) {
         return std::env::temp_dir().join("ccem-runtime-tools");
     }
@@ -1,8 +1,8 @@
 // apps/desktop/src-tauri/src/session_annotations.rs
 //
 // User-editable workflow stage, expressive sticker, and short label metadata
-// for history sessions. Persisted outside provider history so Claude/Codex files
-// remain immutable.
+// for history sessions, plus generated topic tags and files touched.
+// Persisted outside provider history so Claude/Codex files remain immutable.
 
 use fs2::FileExt;
 use serde::{Deserialize, Serialize};
@@ -36,6 +36,8 @@ const VALID_STICKERS: &[&str] = &[
     "archived",
 ];
 const MAX_LABEL_CHARS: usize = 24;
+pub const VALID_TAGS: &[&str] = &["bugfix", "feature", "refactor", "infra", "docs", "test"];
+const MAX_FILES_TOUCHED: usize = 50;
 
 #[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
 pub struct SessionAnnotation {
@@ -45,9 +47,24 @@ pub struct SessionAnnotation {
     pub sticker: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub label: Option<String>,
+    /// Topic tags from `VALID_TAGS`, produced by the title pipeline.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files_touched: Vec<String>,
     pub updated_at: u64,
 }
 
+impl SessionAnnotation {
+    fn is_empty(&self) -> bool {
+        self.stage.is_none()
+            && self.sticker.is_none()
+            && self.label.is_none()
+            && self.tags.is_empty()
+            && self.files_touched.is_empty()
+    }
+}
+
 #[derive(Serialize, Deserialize, Default, Debug)]
 pub struct SessionAnnotations {
     entries: HashMap<String, SessionAnnotation>,
@@ -139,6 +156,19 @@ impl SessionAnnotations {
         }
     }
 
+    /// Keep known tags only, lowercased and deduplicated in `VALID_TAGS` order.
+    pub fn normalize_tags<S: AsRef<str>>(tags: &[S]) -> Vec<String> {
+        let requested = tags
+            .iter()
+            .map(|tag| tag.as_ref().trim().to_ascii_lowercase())
+            .collect::<Vec<_>>();
+        VALID_TAGS
+            .iter()
+            .filter(|tag| requested.iter().any(|value| value == *tag))
+            .map(|tag| tag.to_string())
+            .collect()
+    }
+
     pub fn validate_sticker(value: Option<&str>) -> Result<(), String> {
         match value {
             Some(sticker) if !VALID_STICKERS.contains(&sticker) => {
@@ -190,19 +220,23 @@ pub async fn set_session_annotation(
         let (mut annotations, _lock) = SessionAnnotations::load_locked()
             .map_err(|error| format!("Failed to acquire annotation lock: {}", error))?;
 
-        if stage.is_none() && sticker.is_none() && label.is_none() {
+        // Generated tags and files touched survive manual edits.
+        let existing = annotations.get(&source, &session_id).cloned();
+        let annotation = SessionAnnotation {
+            stage,
+            sticker,
+            label,
+            tags: existing
+                .as_ref()
+                .map(|item| item.tags.clone())
+                .unwrap_or_default(),
+            files_touched: existing.map(|item| item.files_touched).unwrap_or_default(),
+            updated_at: now_secs(),
+        };
+        if annotation.is_empty() {
             annotations.remove(&source, &session_id);
         } else {
-            annotations.set(
-                &source,
-                &session_id,
-                SessionAnnotation {
-                    stage,
-                    sticker,
-                    label,
-                    updated_at: now_secs(),
-                },
-            );
+            annotations.set(&source, &session_id, annotation);
         }
 
         annotations
@@ -214,6 +248,36 @@ pub async fn set_session_annotation(
     .map_err(|error| format!("Task error: {}", error))?
 }
 
+/// Store generated tags and files touched for a session, keeping its manual
+/// stage, sticker and label.
+pub fn store_session_insights(
+    source: &str,
+    session_id: &str,
+    tags: Vec<String>,
+    files_touched: Vec<String>,
+) -> Result<SessionAnnotation, String> {
+    let (mut annotations, _lock) = SessionAnnotations::load_locked()
+        .map_err(|error| format!("Failed to acquire annotation lock: {}", error))?;
+
+    let mut annotation = annotations
+        .get(source, session_id)
+        .cloned()
+        .unwrap_or_default();
+    annotation.tags = SessionAnnotations::normalize_tags(&tags);
+    annotation.files_touched = files_touched.into_iter().take(MAX_FILES_TOUCHED).collect();
+    annotation.updated_at = now_secs();
+    if annotation.is_empty() {
+        annotations.remove(source, session_id);
+    } else {
+        annotations.set(source, session_id, annotation.clone());
+    }
+
+    annotations
+        .save_locked()
+        .map_err(|error| format!("Failed to save session annotation: {}", error))?;
+    Ok(annotation)
+}
+
 #[tauri::command]
 pub async fn clear_session_annotation(source: String, session_id: String) -> Result<(), String> {
     set_session_annotation(source, session_id, None, None, None).await
@@ -245,6 +309,8 @@ mod tests {
                 stage: Some("validation".to_string()),
                 sticker: Some("focused".to_string()),
                 label: Some("等我跑完整回归".to_string()),
+                tags: vec!["bugfix".to_string()],
+                files_touched: vec!["src/history.rs".to_string()],
                 updated_at: 42,
             },
         );
@@ -258,6 +324,8 @@ mod tests {
         assert_eq!(annotation.stage.as_deref(), Some("validation"));
         assert_eq!(annotation.sticker.as_deref(), Some("focused"));
         assert_eq!(annotation.label.as_deref(), Some("等我跑完整回归"));
+        assert_eq!(annotation.tags, vec!["bugfix".to_string()]);
+        assert_eq!(annotation.files_touched, vec!["src/history.rs".to_string()]);
 
         let _ = fs::remove_file(path);
     }
@@ -269,4 +337,12 @@ mod tests {
         assert!(SessionAnnotations::validate_sticker(Some("celebrating")).is_ok());
         assert!(SessionAnnotations::validate_sticker(Some("bogus")).is_err());
     }
+
+    #[test]
+    fn normalizes_tags_to_known_vocabulary() {
+        assert_eq!(
+            SessionAnnotations::normalize_tags(&[" Docs", "bugfix", "misc", "docs"]),
+            vec!["bugfix".to_string(), "docs".to_string()]
+        );
+    }
 }
@@ -0,0 +1,387 @@
+// apps/desktop/src-tauri/src/session_continuation.rs
+//
+// "Continue in…" for history sessions: condense a Claude/Codex/OpenCode
+// conversation into a handoff prompt that a new native session on another
+// provider or environment can pick up from.
+
+use crate::event_bus::{TodoSnapshotItemV1, TodoSnapshotV1};
+use crate::history::{self, ConversationMessage};
+use crate::native_event_log::NativeEventLog;
+use crate::session_provenance;
+use crate::session_titles::{extract_files_touched, message_text};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+const GOAL_MAX_CHARS: usize = 1_500;
+const LATEST_REQUEST_MAX_CHARS: usize = 800;
+const DECISION_MAX_CHARS: usize = 600;
+const MAX_DECISIONS: usize = 5;
+const MAX_FILES_CHANGED: usize = 40;
+const HANDOFF_PROMPT_MAX_CHARS: usize = 12_000;
+
+// ============================================================================
+// Types — sent to / received from the frontend (camelCase for TypeScript)
+// ============================================================================
+
+/// The history session a new native session continues from.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionContinuationSource {
+    pub source: String,
+    pub session_id: String,
+}
+
+/// Editable handoff shown before launching; `prompt` becomes the new
+/// session's initial prompt.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionContinuationDraft {
+    pub source: String,
+    pub session_id: String,
+    pub goal: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latest_request: Option<String>,
+    pub decisions: Vec<String>,
+    pub files_changed: Vec<String>,
+    pub open_todos: Vec<TodoSnapshotItemV1>,
+    /// Working directory and environment of the original launch, when CCEM
+    /// started it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub working_dir: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub env_name: Option<String>,
+    pub prompt: String,
+}
+
+// ============================================================================
+// Draft building
+// ============================================================================
+
+fn truncate_chars(text: &str, max_chars: usize) -> String {
+    let trimmed = text.trim();
+    match trimmed.char_indices().nth(max_chars) {
+        Some((index, _)) => format!("{}…", trimmed[..index].trim_end()),
+        None => trimmed.to_string(),
+    }
+}
+
+fn is_user_message(message: &ConversationMessage) -> bool {
+    message.msg_type == "user" || message.msg_type == "human"
+}
+
+fn is_assistant_message(message: &ConversationMessage) -> bool {
+    message.msg_type == "assistant" || message.msg_type == "ai"
+}
+
+fn provider_label(source: &str) -> &str {
+    match source {
+        "claude" => "Claude Code",
+        "codex" => "Codex",
+        "opencode" => "OpenCode",
+        other => other,
+    }
+}
+
+/// The latest TodoWrite / update_plan call in the transcript, for sessions
+/// the native event log never saw.
+fn transcript_todos(messages: &[ConversationMessage]) -> Option<Vec<TodoSnapshotItemV1>> {
+    messages
+        .iter()
+        .rev()
+        .filter_map(|message| message.content.as_array())
+        .flat_map(|blocks| blocks.iter().rev())
+        .filter(|block| block.get("type").and_then(Value::as_str) == Some("tool_use"))
+        .find_map(|block| {
+            let input = block.get("input")?;
+            let (items, text_key) = match block.get("name").and_then(Value::as_str)? {
+                "TodoWrite" => (input.get("todos")?.as_array()?, "content"),
+                "update_plan" => (input.get("plan")?.as_array()?, "step"),
+                _ => return None,
+            };
+            Some(
+                items
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(index, item)| {
+                        Some(TodoSnapshotItemV1 {
+                            id: item
+                                .get("id")
+                                .and_then(Value::as_str)
+                                .map(str::to_string)
+                                .unwrap_or_else(|| format!("todo-{}", index + 1)),
+                            text: item.get(text_key)?.as_str()?.to_string(),
+                            status: item
+                                .get("status")
+                                .and_then(Value::as_str)
+                                .unwrap_or("pending")
+                                .to_string(),
+                            active_text: item
+                                .get("activeForm")
+                                .and_then(Value::as_str)
+                                .map(str::to_string),
+                        })
+                    })
+                    .collect(),
+            )
+        })
+}
+
+fn build_continuation_draft(
+    source: &str,
+    session_id: &str,
+    messages: &[ConversationMessage],
+    recorded_todos: Option<TodoSnapshotV1>,
+    launch: Option<&session_provenance::SessionProvenanceRecord>,
+) -> SessionContinuationDraft {
+    let user_requests = messages
+        .iter()
+        .filter(|message| is_user_message(message))
+        .map(message_text)
+        .filter(|text| !text.trim().is_empty())
+        .collect::<Vec<_>>();
+    let goal = user_requests
+        .first()
+        .map(|text| truncate_chars(text, GOAL_MAX_CHARS))
+        .unwrap_or_default();
+    let latest_request = user_requests
+        .last()
+        .filter(|_| user_requests.len() > 1)
+        .map(|text| truncate_chars(text, LATEST_REQUEST_MAX_CHARS));
+
+    // Compaction summaries first (they already condense earlier turns), then
+    // the most recent assistant conclusions.
+    let mut decisions = messages
+        .iter()
+        .filter_map(|message| message.summary.as_deref())
+        .map(|summary| truncate_chars(summary, DECISION_MAX_CHARS))
+        .collect::<Vec<_>>();
+    let mut recent = messages
+        .iter()
+        .rev()
+        .filter(|message| is_assistant_message(message))
+        .map(message_text)
+        .filter(|text| !text.trim().is_empty())
+        .take(MAX_DECISIONS)
+        .map(|text| truncate_chars(&text, DECISION_MAX_CHARS))
+        .collect::<Vec<_>>();
+    recent.reverse();
+    decisions.extend(recent);
+
+    let mut files_changed = extract_files_touched(messages);
+    files_changed.truncate(MAX_FILES_CHANGED);
+
+    let open_todos = recorded_todos
+        .map(|snapshot| snapshot.items)
+        .or_else(|| transcript_todos(messages))
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|item| item.status != "completed")
+        .collect();
+
+    let mut draft = SessionContinuationDraft {
+        source: source.to_string(),
+        session_id: session_id.to_string(),
+        goal,
+        latest_request,
+        decisions,
+        files_changed,
+        open_todos,
+        working_dir: launch.map(|record| record.working_dir.clone()),
+        env_name: launch.map(|record| record.env_name.clone()),
+        prompt: String::new(),
+    };
+    draft.prompt = render_handoff_prompt(&draft);
+    draft
+}
+
+fn render_handoff_prompt(draft: &SessionContinuationDraft) -> String {
+    let mut prompt = format!(
+        "You are continuing a {} session ({}). Its transcript is not available to you; \
+         this handoff summarizes it. Verify the current state of the files before \
+         changing them, then carry on with the open work.\n\n## Goal\n{}\n",
+        provider_label(&draft.source),
+        draft.session_id,
+        if draft.goal.is_empty() {
+            "(not recorded)"
+        } else {
+            draft.goal.as_str()
+        }
+    );
+    if let Some(latest_request) = draft.latest_request.as_deref() {
+        prompt.push_str(&format!("\n## Latest request\n{latest_request}\n"));
+    }
+    if !draft.decisions.is_empty() {
+        prompt.push_str("\n## Decisions and progress\n");
+        for decision in &draft.decisions {
+            prompt.push_str(&format!("- {}\n", decision.replace('\n', "\n  ")));
+        }
+    }
+    if !draft.files_changed.is_empty() {
+        prompt.push_str("\n## Files changed\n");
+        for file in &draft.files_changed {
+            prompt.push_str(&format!("- {file}\n"));
+        }
+    }
+    if !draft.open_todos.is_empty() {
+        prompt.push_str("\n## Open todos\n");
+        for item in &draft.open_todos {
+            prompt.push_str(&format!("- [{}] {}\n", item.status, item.text));
+        }
+    }
+    truncate_chars(&prompt, HANDOFF_PROMPT_MAX_CHARS)
+}
+
+fn prepare_continuation(
+    source: &str,
+    session_id: &str,
+) -> Result<SessionContinuationDraft, String> {
+    let detail = history::load_merged_conversation_detail(session_id, Some(source))?;
+    if detail.messages.is_empty() {
+        return Err(format!("Conversation '{}' not found", session_id));
+    }
+
+    // Sessions CCEM launched natively carry a structured todo snapshot in the
+    // event log, keyed by the CCEM runtime id.
+    let launch = session_provenance::list_records_by_client(source)
+        .unwrap_or_default()
+        .remove(session_id);
+    let recorded_todos = launch.as_ref().and_then(|record| {
+        NativeEventLog::default()
+            .latest_todo_snapshot(&record.ccem_session_id)
+            .unwrap_or_else(|error| {
+                eprintln!("Continuation todo snapshot warning: {error}");
+                None
+            })
+    });
+
+    Ok(build_continuation_draft(
+        source,
+        session_id,
+        &detail.messages,
+        recorded_todos,
+        launch.as_ref(),
+    ))
+}
+
+// ============================================================================
+// Tauri commands
+// ============================================================================
+
+/// Build the handoff draft for continuing a history session elsewhere. The
+/// frontend passes `draft.prompt` (possibly edited) to `create_native_session`
+/// together with `continued_from`, which links the two sessions.
+#[tauri::command]
+pub async fn prepare_session_continuation(
+    source: String,
+    session_id: String,
+) -> Result<SessionContinuationDraft, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        prepare_continuation(source.trim(), session_id.trim())
+    })
+    .await
+    .map_err(|error| format!("Blocking continuation task failed: {error}"))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{build_continuation_draft, transcript_todos};
+    use crate::event_bus::{TodoSnapshotItemV1, TodoSnapshotV1};
+    use crate::history::ConversationMessage;
+    use serde_json::json;
+
+    fn message(msg_type: &str, content: serde_json::Value) -> ConversationMessage {
+        ConversationMessage {
+            msg_type: msg_type.to_string(),
+            uuid: None,
+            content,
+            model: None,
+            summary: None,
+            plan_content: None,
+            timestamp: 0,
+            input_tokens: None,
+            output_tokens: None,
+            cache_creation_tokens: None,
+            cache_read_tokens: None,
+            segment_index: 0,
+            is_compact_boundary: false,
+        }
+    }
+
+    fn transcript() -> Vec<ConversationMessage> {
+        vec![
+            message("user", json!("Move the alias config from webpack to vite")),
+            message(
+                "assistant",
+                json!([
+                    {"type": "text", "text": "Keeping @shared as the only alias."},
+                    {"type": "tool_use", "name": "Edit", "input": {"file_path": "/app/vite.config.ts"}},
+                    {"type": "tool_use", "name": "TodoWrite", "input": {"todos": [
+                        {"content": "Port aliases", "status": "completed"},
+                        {"content": "Remove webpack config", "status": "in_progress", "activeForm": "Removing webpack config"},
+                    ]}},
+                ]),
+            ),
+            message("user", json!("Also drop the old jest moduleNameMapper")),
+        ]
+    }
+
+    #[test]
+    fn handoff_prompt_condenses_goal_decisions_files_and_open_todos() {
+        let draft = build_continuation_draft("claude", "ses-1", &transcript(), None, None);
+
+        assert_eq!(draft.goal, "Move the alias config from webpack to vite");
+        assert_eq!(
+            draft.latest_request.as_deref(),
+            Some("Also drop the old jest moduleNameMapper")
+        );
+        assert_eq!(draft.decisions, vec!["Keeping @shared as the only alias."]);
+        assert_eq!(draft.files_changed, vec!["/app/vite.config.ts"]);
+        assert_eq!(
+            draft
+                .open_todos
+                .iter()
+                .map(|item| item.text.as_str())
+                .collect::<Vec<_>>(),
+            vec!["Remove webpack config"]
+        );
+        assert!(draft
+            .prompt
+            .starts_with("You are continuing a Claude Code session (ses-1)."));
+        assert!(draft
+            .prompt
+            .ends_with("## Open todos\n- [in_progress] Remove webpack config"));
+        assert!(draft
+            .prompt
+            .contains("## Files changed\n- /app/vite.config.ts\n"));
+    }
+
+    #[test]
+    fn recorded_todo_snapshot_wins_over_transcript_todos() {
+        let recorded = TodoSnapshotV1 {
+            version: 1,
+            provider: "claude".to_string(),
+            source: "TodoWrite".to_string(),
+            revision: 3,
+            items: vec![TodoSnapshotItemV1 {
+                id: "todo-1".to_string(),
+                text: "Run the e2e suite".to_string(),
+                status: "pending".to_string(),
+                active_text: None,
+            }],
+        };
+        let draft =
+            build_continuation_draft("claude", "ses-1", &transcript(), Some(recorded), None);
+        assert_eq!(draft.open_todos.len(), 1);
+        assert_eq!(draft.open_todos[0].text, "Run the e2e suite");
+
+        let codex_plan = vec![message(
+            "assistant",
+            json!([{"type": "tool_use", "name": "update_plan", "input": {"plan": [
+                {"step": "Write migration", "status": "in_progress"},
+            ]}}]),
+        )];
+        let todos = transcript_todos(&codex_plan).expect("codex plan todos");
+        assert_eq!(todos[0].id, "todo-1");
+        assert_eq!(todos[0].text, "Write migration");
+    }
+}
@@ -0,0 +1,745 @@
+// apps/desktop/src-tauri/src/session_digest.rs
+//
+// "What changed since I left": a digest of a native runtime's events after a
+// given seq — files edited (with diffs rebuilt from edit tool inputs),
+// commands run, permission decisions and the latest assistant message.
+// The UI takes the structured payload; bot bridges send `render_digest_text`.
+
+use crate::event_bus::{is_capped_tool_input_string, ReplayBatch, SessionEventPayload};
+use crate::native_event_log::NativeEventLog;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Snippets longer than this (in lines) are diffed as a full replacement.
+const MAX_DIFF_LINES: usize = 400;
+const MAX_RESULT_SUMMARY_CHARS: usize = 300;
+const MAX_TEXT_DIGEST_CHARS: usize = 3_500;
+
+// ============================================================================
+// Output types — sent to frontend (camelCase for TypeScript)
+// ============================================================================
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DigestFileChange {
+    pub path: String,
+    /// Tools that touched the file, in first-use order.
+    pub tools: Vec<String>,
+    pub edit_count: usize,
+    pub insertions: usize,
+    pub deletions: usize,
+    /// Snippet diff per edit: each `@@` hunk turns the edit's old text into
+    /// its new text. Hunks carry no line numbers, since tool inputs don't say
+    /// where in the file the snippet sits. `None` when the provider only
+    /// reported the path (Codex file changes).
+    pub diff: Option<String>,
+    /// Some edits had their input cut down before it reached the event log;
+    /// those are left out of `diff` and the line counts.
+    #[serde(default)]
+    pub diff_truncated: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DigestCommand {
+    pub seq: u64,
+    pub command: String,
+    /// `None` while the command is still running.
+    pub success: Option<bool>,
+    pub result_summary: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DigestPermission {
+    pub seq: u64,
+    pub request_id: String,
+    pub tool_name: String,
+    pub input_summary: Option<String>,
+    /// `None` while the request is still waiting for an answer.
+    pub approved: Option<bool>,
+    pub responder: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionDigest {
+    pub runtime_id: String,
+    pub since_seq: Option<u64>,
+    /// Newest seq covered; pass it back as `since_seq` next time.
+    pub through_seq: Option<u64>,
+    pub event_count: usize,
+    /// Events after `since_seq` were already pruned; the digest starts at
+    /// the oldest retained event.
+    pub gap_detected: bool,
+    pub prompts: Vec<String>,
+    pub files: Vec<DigestFileChange>,
+    pub commands: Vec<DigestCommand>,
+    pub permissions: Vec<DigestPermission>,
+    pub final_assistant_message: Option<String>,
+    pub completion_reason: Option<String>,
+}
+
+// ============================================================================
+// Digest builder
+// ============================================================================
+
+/// A file edit seen in `ToolUseStarted`, kept until its completion says
+/// whether it landed.
+struct PendingEdit {
+    tool_name: String,
+    path: String,
+    diff: Option<String>,
+    diff_truncated: bool,
+    insertions: usize,
+    deletions: usize,
+}
+
+pub fn build_session_digest(
+    runtime_id: &str,
+    since_seq: Option<u64>,
+    batch: &ReplayBatch,
+) -> SessionDigest {
+    let mut digest = SessionDigest {
+        runtime_id: runtime_id.to_string(),
+        since_seq,
+        through_seq: batch.events.last().map(|event| event.seq).or(since_seq),
+        event_count: batch.events.len(),
+        gap_detected: batch.gap_detected,
+        prompts: Vec::new(),
+        files: Vec::new(),
+        commands: Vec::new(),
+        permissions: Vec::new(),
+        final_assistant_message: None,
+        completion_reason: None,
+    };
+    let mut pending_edits: HashMap<String, Vec<PendingEdit>> = HashMap::new();
+    let mut command_index: HashMap<String, usize> = HashMap::new();
+    let mut permission_index: HashMap<String, usize> = HashMap::new();
+    let mut current_reply = String::new();
+    let mut last_reply = String::new();
+
+    for event in &batch.events {
+        match &event.payload {
+            // A queued prompt is logged again when it is dispatched.
+            SessionEventPayload::UserPrompt { queued: true, .. } => {}
+            SessionEventPayload::UserPrompt { text, .. } => {
+                finish_reply(&mut current_reply, &mut last_reply);
+                if !text.trim().is_empty() {
+                    digest.prompts.push(text.trim().to_string());
+                }
+            }
+            SessionEventPayload::AssistantChunk { text } => current_reply.push_str(text),
+            SessionEventPayload::ToolUseStarted {
+                tool_use_id,
+                raw_name,
+                input_summary,
+                input,
+                ..
+            } => {
+                finish_reply(&mut current_reply, &mut last_reply);
+                if raw_name == "Bash" || raw_name == "command_execution" {
+                    if command_index.contains_key(tool_use_id) {
+                        continue;
+                    }
+                    command_index.insert(tool_use_id.clone(), digest.commands.len());
+                    digest.commands.push(DigestCommand {
+                        seq: event.seq,
+                        command: input_summary.clone(),
+                        success: None,
+                        result_summary: None,
+                    });
+                } else if let Some(input) = input {
+                    pending_edits
+                        .entry(tool_use_id.clone())
+                        .or_insert_with(|| pending_edits_from_input(raw_name, input));
+                } else if raw_name == "file_change" {
+                    pending_edits
+                        .entry(tool_use_id.clone())
+                        .or_insert_with(|| codex_file_changes(input_summary));
+                }
+            }
+            SessionEventPayload::ToolUseCompleted {
+                tool_use_id,
+                result_summary,
+                success,
+                ..
+            } => {
+                if let Some(edits) = pending_edits.remove(tool_use_id) {
+                    if *success {
+                        for edit in edits {
+                            record_edit(&mut digest.files, edit);
+                        }
+                    }
+                }
+                if let Some(command) = command_index
+                    .get(tool_use_id)
+                    .and_then(|index| digest.commands.get_mut(*index))
+                {
+                    command.success = Some(*success);
+                    command.result_summary = Some(truncate_chars(
+                        result_summary.trim(),
+                        MAX_RESULT_SUMMARY_CHARS,
+                    ))
+                    .filter(|summary| !summary.is_empty());
+                }
+            }
+            SessionEventPayload::PermissionRequired {
+                request_id,
+                tool_name,
+                input_summary,
+                ..
+            } => {
+                if permission_index.contains_key(request_id) {
+                    continue;
+                }
+                permission_index.insert(request_id.clone(), digest.permissions.len());
+                digest.permissions.push(DigestPermission {
+                    seq: event.seq,
+                    request_id: request_id.clone(),
+                    tool_name: tool_name.clone(),
+                    input_summary: input_summary.clone(),
+                    approved: None,
+                    responder: None,
+                });
+            }
+            SessionEventPayload::PermissionResponded {
+                request_id,
+                approved,
+                responder,
+                ..
+            } => {
+                if let Some(permission) = permission_index
+                    .get(request_id)
+                    .and_then(|index| digest.permissions.get_mut(*index))
+                {
+                    permission.approved = Some(*approved);
+                    permission.responder = Some(responder.clone());
+                }
+            }
+            SessionEventPayload::SessionCompleted { reason } => {
+                digest.completion_reason = Some(reason.clone());
+            }
+            _ => {}
+        }
+    }
+
+    // Edits whose completion hasn't arrived yet are reported as made; the
+    // file is already being written.
+    let mut in_flight = pending_edits.into_iter().collect::<Vec<_>>();
+    in_flight.sort_by(|a, b| a.0.cmp(&b.0));
+    for (_, edits) in in_flight {
+        for edit in edits {
+            record_edit(&mut digest.files, edit);
+        }
+    }
+    finish_reply(&mut current_reply, &mut last_reply);
+    digest.final_assistant_message = Some(last_reply.trim().to_string()).filter(|s| !s.is_empty());
+    digest
+}
+
+pub fn load_session_digest(
+    log: &NativeEventLog,
+    runtime_id: &str,
+    since_seq: Option<u64>,
+) -> Result<SessionDigest, String> {
+    let batch = log.replay(runtime_id, since_seq, None)?;
+    Ok(build_session_digest(runtime_id, since_seq, &batch))
+}
+
+fn finish_reply(current: &mut String, last: &mut String) {
+    if !current.trim().is_empty() {
+        *last = std::mem::take(current);
+    } else {
+        current.clear();
+    }
+}
+
+fn record_edit(files: &mut Vec<DigestFileChange>, edit: PendingEdit) {
+    let index = match files.iter().position(|file| file.path == edit.path) {
+        Some(index) => index,
+        None => {
+            files.push(DigestFileChange {
+                path: edit.path.clone(),
+                tools: Vec::new(),
+                edit_count: 0,
+                insertions: 0,
+                deletions: 0,
+                diff: None,
+                diff_truncated: false,
+            });
+            files.len() - 1
+        }
+    };
+    let file = &mut files[index];
+    if !file.tools.contains(&edit.tool_name) {
+        file.tools.push(edit.tool_name);
+    }
+    file.edit_count += 1;
+    file.insertions += edit.insertions;
+    file.deletions += edit.deletions;
+    file.diff_truncated |= edit.diff_truncated;
+    if let Some(diff) = edit.diff {
+        match file.diff.as_mut() {
+            Some(existing) => existing.push_str(&diff),
+            None => file.diff = Some(format!("--- a/{0}\n+++ b/{0}\n{diff}", edit.path)),
+        }
+    }
+}
+
+/// Rebuild the change an `Edit`/`MultiEdit`/`Write`/`NotebookEdit` call makes
+/// from its input. Replacements whose text was capped in the event log are
+/// only flagged, since diffing the cut text would be wrong.
+fn pending_edits_from_input(tool_name: &str, input: &Value) -> Vec<PendingEdit> {
+    let text = |value: &Value, key: &str| {
+        value
+            .get(key)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+    let Some(path) = input
+        .get("file_path")
+        .or_else(|| input.get("notebook_path"))
+        .and_then(Value::as_str)
+        .filter(|path| !path.trim().is_empty())
+    else {
+        return Vec::new();
+    };
+    let replacements = match tool_name {
+        "Edit" => vec![(text(input, "old_string"), text(input, "new_string"))],
+        "MultiEdit" => input
+            .get("edits")
+            .and_then(Value::as_array)
+            .map(|edits| {
+                edits
+                    .iter()
+                    .map(|edit| (text(edit, "old_string"), text(edit, "new_string")))
+                    .collect()
+            })
+            .unwrap_or_default(),
+        "Write" => vec![(String::new(), text(input, "content"))],
+        "NotebookEdit" => vec![(String::new(), text(input, "new_source"))],
+        _ => Vec::new(),
+    };
+
+    let mut diff = String::new();
+    let mut diff_truncated = false;
+    let (mut insertions, mut deletions) = (0, 0);
+    for (old, new) in &replacements {
+        if is_capped_tool_input_string(old) || is_capped_tool_input_string(new) {
+            diff_truncated = true;
+            continue;
+        }
+        let (hunk, added, removed) = snippet_diff(old, new);
+        diff.push_str(&hunk);
+        insertions += added;
+        deletions += removed;
+    }
+    vec![PendingEdit {
+        tool_name: tool_name.to_string(),
+        path: path.to_string(),
+        diff: Some(diff).filter(|diff| !diff.is_empty()),
+        diff_truncated,
+        insertions,
+        deletions,
+    }]
+}
+
+/// Codex reports `{"type":"file_change","changes":[{"path","kind"}]}`
+/// without content, so only the paths make it into the digest.
+fn codex_file_changes(input_summary: &str) -> Vec<PendingEdit> {
+    serde_json::from_str::<Value>(input_summary)
+        .ok()
+        .and_then(|value| value.get("changes").and_then(Value::as_array).cloned())
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|change| {
+            let path = change.get("path").and_then(Value::as_str)?;
+            let kind = change
+                .get("kind")
+                .and_then(Value::as_str)
+                .unwrap_or("update");
+            Some(PendingEdit {
+                tool_name: format!("file_change:{kind}"),
+                path: path.to_string(),
+                diff: None,
+                diff_truncated: false,
+                insertions: 0,
+                deletions: 0,
+            })
+        })
+        .collect()
+}
+
+/// One hunk turning `old` into `new`, with unchanged snippet lines as
+/// context. Returns the hunk and its insertion/deletion counts.
+fn snippet_diff(old: &str, new: &str) -> (String, usize, usize) {
+    let old_lines = old.lines().collect::<Vec<_>>();
+    let new_lines = new.lines().collect::<Vec<_>>();
+    if old_lines == new_lines {
+        return (String::new(), 0, 0);
+    }
+
+    let mut ops = Vec::new();
+    if old_lines.len() > MAX_DIFF_LINES || new_lines.len() > MAX_DIFF_LINES {
+        ops.extend(old_lines.iter().map(|line| ('-', *line)));
+        ops.extend(new_lines.iter().map(|line| ('+', *line)));
+    } else {
+        // Longest common subsequence table, walked forwards.
+        let (n, m) = (old_lines.len(), new_lines.len());
+        let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                lcs[i][j] = if old_lines[i] == new_lines[j] {
+                    lcs[i + 1][j + 1] + 1
+                } else {
+                    lcs[i + 1][j].max(lcs[i][j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < n || j < m {
+            if i < n && j < m && old_lines[i] == new_lines[j] {
+                ops.push((' ', old_lines[i]));
+                i += 1;
+                j += 1;
+            } else if i < n && (j == m || lcs[i + 1][j] >= lcs[i][j + 1]) {
+                ops.push(('-', old_lines[i]));
+                i += 1;
+            } else {
+                ops.push(('+', new_lines[j]));
+                j += 1;
+            }
+        }
+    }
+
+    let insertions = ops.iter().filter(|(op, _)| *op == '+').count();
+    let deletions = ops.iter().filter(|(op, _)| *op == '-').count();
+    let mut hunk = "@@ @@\n".to_string();
+    for (op, line) in ops {
+        hunk.push(op);
+        hunk.push_str(line);
+        hunk.push('\n');
+    }
+    (hunk, insertions, deletions)
+}
+
+fn truncate_chars(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((index, _)) => format!("{}…", &text[..index]),
+        None => text.to_string(),
+    }
+}
+
+/// Plain-text rendering for chat bridges.
+pub fn render_digest_text(digest: &SessionDigest) -> String {
+    let mut lines = vec![format!(
+        "Session {} · {} event(s) since {}",
+        digest.runtime_id,
+        digest.event_count,
+        digest
+            .since_seq
+            .map(|seq| format!("seq {seq}"))
+            .unwrap_or_else(|| "the start".to_string())
+    )];
+    if digest.gap_detected {
+        lines.push("(older events were pruned; showing what is left)".to_string());
+    }
+    if !digest.files.is_empty() {
+        lines.push(format!("Files ({}):", digest.files.len()));
+        for file in &digest.files {
+            lines.push(format!(
+                "• {} +{} -{} ({}){}",
+                file.path,
+                file.insertions,
+                file.deletions,
+                file.tools.join(", "),
+                if file.diff_truncated {
+                    " [partial: large edits not counted]"
+                } else {
+                    ""
+                }
+            ));
+        }
+    }
+    if !digest.commands.is_empty() {
+        lines.push(format!("Commands ({}):", digest.commands.len()));
+        for command in &digest.commands {
+            let status = match command.success {
+                Some(true) => "✓",
+                Some(false) => "✗",
+                None => "…",
+            };
+            lines.push(format!(
+                "{status} {}",
+                truncate_chars(&command.command, 200)
+            ));
+        }
+    }
+    if !digest.permissions.is_empty() {
+        lines.push("Permissions:".to_string());
+        for permission in &digest.permissions {
+            let decision = match permission.approved {
+                Some(true) => "approved",
+                Some(false) => "denied",
+                None => "pending",
+            };
+            lines.push(format!(
+                "• {decision} {}{}",
+                permission.tool_name,
+                permission
+                    .responder
+                    .as_deref()
+                    .map(|responder| format!(" ({responder})"))
+                    .unwrap_or_default()
+            ));
+        }
+    }
+    if let Some(message) = digest.final_assistant_message.as_deref() {
+        lines.push(format!("Last reply:\n{}", truncate_chars(message, 1_500)));
+    }
+    if let Some(reason) = digest.completion_reason.as_deref() {
+        lines.push(format!("Session completed: {reason}"));
+    }
+    if let Some(seq) = digest.through_seq {
+        lines.push(format!("Up to seq {seq}"));
+    }
+    truncate_chars(&lines.join("\n"), MAX_TEXT_DIGEST_CHARS)
+}
+
+// ============================================================================
+// Tauri commands
+// ============================================================================
+
+#[tauri::command]
+pub async fn get_session_digest(
+    runtime_id: String,
+    since_seq: Option<u64>,
+) -> Result<SessionDigest, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        load_session_digest(&NativeEventLog::default(), &runtime_id, since_seq)
+    })
+    .await
+    .map_err(|error| format!("Blocking session digest task failed: {error}"))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{build_session_digest, render_digest_text, snippet_diff};
+    use crate::event_bus::{
+        cap_tool_input_strings, ReplayBatch, SessionEventPayload, SessionEventRecord, ToolCategory,
+        TOOL_INPUT_MAX_STRING_CHARS,
+    };
+    use chrono::Utc;
+
+    fn record(seq: u64, payload: SessionEventPayload) -> SessionEventRecord {
+        SessionEventRecord {
+            runtime_id: "rt-1".to_string(),
+            seq,
+            occurred_at: Utc::now(),
+            payload,
+        }
+    }
+
+    fn started(
+        id: &str,
+        name: &str,
+        summary: &str,
+        input: Option<serde_json::Value>,
+    ) -> SessionEventPayload {
+        SessionEventPayload::ToolUseStarted {
+            tool_use_id: id.to_string(),
+            category: ToolCategory::Unknown {
+                raw_name: name.to_string(),
+            },
+            raw_name: name.to_string(),
+            input_summary: summary.to_string(),
+            needs_response: false,
+            input,
+            prompt: None,
+            todo_snapshot: None,
+        }
+    }
+
+    fn completed(id: &str, name: &str, success: bool) -> SessionEventPayload {
+        SessionEventPayload::ToolUseCompleted {
+            tool_use_id: id.to_string(),
+            raw_name: name.to_string(),
+            result_summary: if success { "ok" } else { "failed" }.to_string(),
+            result_content: None,
+            success,
+            todo_snapshot: None,
+        }
+    }
+
+    #[test]
+    fn snippet_diff_keeps_shared_lines_as_context() {
+        let (hunk, added, removed) = snippet_diff("a\nb\nc", "a\nB\nc\nd");
+        assert_eq!(hunk, "@@ @@\n a\n-b\n+B\n c\n+d\n");
+        assert_eq!((added, removed), (2, 1));
+        assert_eq!(snippet_diff("same", "same").0, "");
+    }
+
+    #[test]
+    fn digest_collects_edits_commands_permissions_and_last_reply() {
+        let events = vec![
+            record(
+                11,
+                SessionEventPayload::UserPrompt {
+                    text: "rename the health route".to_string(),
+                    image_count: 0,
+                    images: None,
+                    annotations: None,
+                    canonical_hash: None,
+                    queued: false,
+                    queue_item_id: None,
+                },
+            ),
+            record(
+                12,
+                SessionEventPayload::AssistantChunk {
+                    text: "Renaming now.".to_string(),
+                },
+            ),
+            record(
+                13,
+                started(
+                    "toolu-edit",
+                    "Edit",
+                    "src/router.ts",
+                    Some(serde_json::json!({
+                        "file_path": "src/router.ts",
+                        "old_string": "get('/health')",
+                        "new_string": "get('/healthz')",
+                    })),
+                ),
+            ),
+            record(
+                14,
+                SessionEventPayload::PermissionRequired {
+                    request_id: "perm-1".to_string(),
+                    tool_use_id: Some("toolu-edit".to_string()),
+                    tool_name: "Edit".to_string(),
+                    input_summary: Some("src/router.ts".to_string()),
+                    background_task_id: None,
+                },
+            ),
+            record(
+                15,
+                SessionEventPayload::PermissionResponded {
+                    request_id: "perm-1".to_string(),
+                    tool_use_id: Some("toolu-edit".to_string()),
+                    approved: true,
+                    responder: "telegram".to_string(),
+                },
+            ),
+            record(16, completed("toolu-edit", "Edit", true)),
+            record(
+                17,
+                started(
+                    "toolu-bad",
+                    "Write",
+                    "src/old.ts",
+                    Some(serde_json::json!({"file_path": "src/old.ts", "content": "x"})),
+                ),
+            ),
+            record(18, completed("toolu-bad", "Write", false)),
+            record(19, started("toolu-bash", "Bash", "pnpm test", None)),
+            record(20, completed("toolu-bash", "Bash", true)),
+            record(
+                21,
+                SessionEventPayload::AssistantChunk {
+                    text: "Done, ".to_string(),
+                },
+            ),
+            record(
+                22,
+                SessionEventPayload::AssistantChunk {
+                    text: "tests pass.".to_string(),
+                },
+            ),
+        ];
+        let batch = ReplayBatch {
+            gap_detected: false,
+            truncated: false,
+            oldest_available_seq: Some(1),
+            newest_available_seq: Some(22),
+            events,
+        };
+
+        let digest = build_session_digest("rt-1", Some(10), &batch);
+        assert_eq!(digest.through_seq, Some(22));
+        assert_eq!(digest.prompts, vec!["rename the health route".to_string()]);
+        assert_eq!(digest.files.len(), 1);
+        assert_eq!(digest.files[0].path, "src/router.ts");
+        assert_eq!(
+            (digest.files[0].insertions, digest.files[0].deletions),
+            (1, 1)
+        );
+        assert!(digest.files[0]
+            .diff
+            .as_deref()
+            .is_some_and(|diff| diff.contains("+get('/healthz')")));
+        assert_eq!(digest.commands.len(), 1);
+        assert_eq!(digest.commands[0].success, Some(true));
+        assert_eq!(digest.permissions[0].approved, Some(true));
+        assert_eq!(
+            digest.final_assistant_message.as_deref(),
+            Some("Done, tests pass.")
+        );
+
+        let text = render_digest_text(&digest);
+        assert!(text.contains("• src/router.ts +1 -1 (Edit)"));
+        assert!(text.contains("✓ pnpm test"));
+        assert!(text.contains("approved Edit (telegram)"));
+    }
+
+    #[test]
+    fn digest_flags_edits_whose_input_was_capped_instead_of_diffing_them() {
+        let mut large_write = serde_json::json!({
+            "file_path": "src/big.ts",
+            "content": "line\n".repeat(TOOL_INPUT_MAX_STRING_CHARS),
+        });
+        cap_tool_input_strings(&mut large_write, TOOL_INPUT_MAX_STRING_CHARS);
+        let events = vec![
+            record(
+                1,
+                started("toolu-big", "Write", "src/big.ts", Some(large_write)),
+            ),
+            record(2, completed("toolu-big", "Write", true)),
+            record(
+                3,
+                started(
+                    "toolu-small",
+                    "Edit",
+                    "src/big.ts",
+                    Some(serde_json::json!({
+                        "file_path": "src/big.ts",
+                        "old_string": "a",
+                        "new_string": "b",
+                    })),
+                ),
+            ),
+            record(4, completed("toolu-small", "Edit", true)),
+        ];
+        let batch = ReplayBatch {
+            gap_detected: false,
+            truncated: false,
+            oldest_available_seq: Some(1),
+            newest_available_seq: Some(4),
+            events,
+        };
+
+        let digest = build_session_digest("rt-1", None, &batch);
+        let file = &digest.files[0];
+        assert_eq!(file.edit_count, 2);
+        assert!(file.diff_truncated);
+        assert_eq!((file.insertions, file.deletions), (1, 1));
+        let diff = file.diff.as_deref().expect("small edit diff");
+        assert!(!diff.contains("more characters]"));
+        assert!(diff.contains("-a\n+b\n"));
+        assert!(render_digest_text(&digest).contains("[partial: large edits not counted]"));
+    }
+}
@@ -1,6 +1,6 @@
 use crate::jsonl_watcher::discover_jsonl_path;
 use chrono::{DateTime, Utc};
-use rusqlite::{params, Connection};
+use rusqlite::{params, Connection, OptionalExtension};
 use std::collections::HashMap;
 use std::fs::{self, File};
 use std::io::{BufRead, BufReader};
@@ -41,6 +41,29 @@ pub struct SessionProvenanceRecord {
     pub updated_at: String,
 }
 
+/// A session started from another session's handoff prompt (for example a
+/// Claude transcript continued in Codex).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionContinuationLink {
+    pub ccem_session_id: String,
+    pub parent_client: String,
+    pub parent_session_id: String,
+    pub created_at: String,
+}
+
+/// A native session forked from another one at a message of its
+/// conversation. `parent_runtime_id` is missing when the parent runtime could
+/// not be identified from its provider session id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionForkLink {
+    pub ccem_session_id: String,
+    pub parent_runtime_id: Option<String>,
+    pub parent_session_id: String,
+    pub fork_message_id: Option<String>,
+    pub seed_boundary_message_count: Option<u64>,
+    pub created_at: String,
+}
+
 pub fn state_db_path() -> PathBuf {
     if let Ok(path) = std::env::var("CCEM_STATE_DB_PATH") {
         let trimmed = path.trim();
@@ -291,6 +314,211 @@ pub fn list_records_by_client(
     Ok(records)
 }
 
+pub fn record_continuation(
+    ccem_session_id: &str,
+    parent_client: &str,
+    parent_session_id: &str,
+) -> Result<(), String> {
+    let ccem_session_id = ccem_session_id.trim();
+    let parent_client = parent_client.trim().to_lowercase();
+    let parent_session_id = parent_session_id.trim();
+    if ccem_session_id.is_empty() || parent_client.is_empty() || parent_session_id.is_empty() {
+        return Err("Missing session ids for continuation link".to_string());
+    }
+
+    let conn = open_db()?;
+    conn.execute(
+        "INSERT OR REPLACE INTO session_continuations (
+            ccem_session_id,
+            parent_client,
+            parent_session_id,
+            created_at
+        ) VALUES (?1, ?2, ?3, ?4)",
+        params![
+            ccem_session_id,
+            parent_client,
+            parent_session_id,
+            Utc::now().to_rfc3339(),
+        ],
+    )
+    .map_err(|error| format!("Failed to record session continuation: {}", error))?;
+
+    Ok(())
+}
+
+pub fn find_continuation_parent(
+    ccem_session_id: &str,
+) -> Result<Option<SessionContinuationLink>, String> {
+    let ccem_session_id = ccem_session_id.trim();
+    if ccem_session_id.is_empty() {
+        return Ok(None);
+    }
+
+    let conn = open_db()?;
+    conn.query_row(
+        "SELECT ccem_session_id, parent_client, parent_session_id, created_at
+         FROM session_continuations
+         WHERE ccem_session_id = ?1",
+        [ccem_session_id],
+        continuation_link_from_row,
+    )
+    .optional()
+    .map_err(|error| format!("Failed to query session continuation: {}", error))
+}
+
+/// Sessions continued from `parent_session_id`, oldest first.
+pub fn list_continuations_of(
+    parent_client: &str,
+    parent_session_id: &str,
+) -> Result<Vec<SessionContinuationLink>, String> {
+    let parent_client = parent_client.trim().to_lowercase();
+    let parent_session_id = parent_session_id.trim();
+    if parent_client.is_empty() || parent_session_id.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let conn = open_db()?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT ccem_session_id, parent_client, parent_session_id, created_at
+             FROM session_continuations
+             WHERE parent_client = ?1
+               AND parent_session_id = ?2
+             ORDER BY created_at ASC, rowid ASC",
+        )
+        .map_err(|error| format!("Failed to prepare session continuation lookup: {}", error))?;
+    let rows = stmt
+        .query_map(
+            params![parent_client, parent_session_id],
+            continuation_link_from_row,
+        )
+        .map_err(|error| format!("Failed to query session continuations: {}", error))?;
+
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|error| format!("Failed to read session continuation row: {}", error))
+}
+
+pub fn record_fork(
+    ccem_session_id: &str,
+    parent_runtime_id: Option<&str>,
+    parent_session_id: &str,
+    fork_message_id: Option<&str>,
+    seed_boundary_message_count: Option<u64>,
+) -> Result<(), String> {
+    let ccem_session_id = ccem_session_id.trim();
+    let parent_session_id = parent_session_id.trim();
+    if ccem_session_id.is_empty() || parent_session_id.is_empty() {
+        return Err("Missing session ids for fork link".to_string());
+    }
+    let parent_runtime_id = normalize_optional_text(parent_runtime_id.map(str::to_string));
+    if parent_runtime_id.as_deref() == Some(ccem_session_id) {
+        return Err(format!(
+            "Session {} cannot fork from itself",
+            ccem_session_id
+        ));
+    }
+
+    let conn = open_db()?;
+    conn.execute(
+        "INSERT OR REPLACE INTO session_forks (
+            ccem_session_id,
+            parent_runtime_id,
+            parent_session_id,
+            fork_message_id,
+            seed_boundary_message_count,
+            created_at
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            ccem_session_id,
+            parent_runtime_id,
+            parent_session_id,
+            normalize_optional_text(fork_message_id.map(str::to_string)),
+            seed_boundary_message_count.map(|count| count as i64),
+            Utc::now().to_rfc3339(),
+        ],
+    )
+    .map_err(|error| format!("Failed to record session fork: {}", error))?;
+
+    Ok(())
+}
+
+pub fn find_fork_parent(ccem_session_id: &str) -> Result<Option<SessionForkLink>, String> {
+    let ccem_session_id = ccem_session_id.trim();
+    if ccem_session_id.is_empty() {
+        return Ok(None);
+    }
+
+    let conn = open_db()?;
+    conn.query_row(
+        "SELECT ccem_session_id, parent_runtime_id, parent_session_id, fork_message_id,
+                seed_boundary_message_count, created_at
+         FROM session_forks
+         WHERE ccem_session_id = ?1",
+        [ccem_session_id],
+        fork_link_from_row,
+    )
+    .optional()
+    .map_err(|error| format!("Failed to query session fork: {}", error))
+}
+
+/// Sessions forked from `parent_runtime_id`, oldest first. Forks whose
+/// parent runtime is unknown match on the parent's provider session id.
+pub fn list_forks_of(
+    parent_runtime_id: &str,
+    parent_session_id: Option<&str>,
+) -> Result<Vec<SessionForkLink>, String> {
+    let parent_runtime_id = parent_runtime_id.trim();
+    if parent_runtime_id.is_empty() {
+        return Ok(Vec::new());
+    }
+    let parent_session_id = normalize_optional_text(parent_session_id.map(str::to_string));
+
+    let conn = open_db()?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT ccem_session_id, parent_runtime_id, parent_session_id, fork_message_id,
+                    seed_boundary_message_count, created_at
+             FROM session_forks
+             WHERE parent_runtime_id = ?1
+                OR (parent_runtime_id IS NULL AND parent_session_id = ?2)
+             ORDER BY created_at ASC, rowid ASC",
+        )
+        .map_err(|error| format!("Failed to prepare session fork lookup: {}", error))?;
+    let rows = stmt
+        .query_map(
+            params![parent_runtime_id, parent_session_id],
+            fork_link_from_row,
+        )
+        .map_err(|error| format!("Failed to query session forks: {}", error))?;
+
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|error| format!("Failed to read session fork row: {}", error))
+}
+
+fn fork_link_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<SessionForkLink> {
+    Ok(SessionForkLink {
+        ccem_session_id: row.get(0)?,
+        parent_runtime_id: row.get(1)?,
+        parent_session_id: row.get(2)?,
+        fork_message_id: row.get(3)?,
+        seed_boundary_message_count: row
+            .get::<_, Option<i64>>(4)?
+            .map(|count| count.max(0) as u64),
+        created_at: row.get(5)?,
+    })
+}
+
+fn continuation_link_from_row(
+    row: &rusqlite::Row<'_>,
+) -> rusqlite::Result<SessionContinuationLink> {
+    Ok(SessionContinuationLink {
+        ccem_session_id: row.get(0)?,
+        parent_client: row.get(1)?,
+        parent_session_id: row.get(2)?,
+        created_at: row.get(3)?,
+    })
+}
+
 pub fn spawn_claude_source_binding(
     ccem_session_id: String,
     project_dir: String,
@@ -384,7 +612,27 @@ fn open_db() -> Result<Connection, String> {
          CREATE INDEX IF NOT EXISTS idx_session_provenance_client_source
              ON session_provenance (client, source_session_id);
          CREATE INDEX IF NOT EXISTS idx_session_provenance_client_updated
-             ON session_provenance (client, updated_at DESC);",
+             ON session_provenance (client, updated_at DESC);
+         CREATE TABLE IF NOT EXISTS session_continuations (
+             ccem_session_id TEXT PRIMARY KEY,
+             parent_client TEXT NOT NULL,
+             parent_session_id TEXT NOT NULL,
+             created_at TEXT NOT NULL
+         );
+         CREATE INDEX IF NOT EXISTS idx_session_continuations_parent
+             ON session_continuations (parent_client, parent_session_id);
+         CREATE TABLE IF NOT EXISTS session_forks (
+             ccem_session_id TEXT PRIMARY KEY,
+             parent_runtime_id TEXT,
+             parent_session_id TEXT NOT NULL,
+             fork_message_id TEXT,
+             seed_boundary_message_count INTEGER,
+             created_at TEXT NOT NULL
+         );
+         CREATE INDEX IF NOT EXISTS idx_session_forks_parent_runtime
+             ON session_forks (parent_runtime_id);
+         CREATE INDEX IF NOT EXISTS idx_session_forks_parent_session
+             ON session_forks (parent_session_id);",
     )
     .map_err(|error| format!("Failed to initialize sqlite provenance schema: {}", error))?;
 
@@ -601,7 +849,9 @@ fn normalize_project_dir(value: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::{
-        bind_source_session_id, find_record_by_ccem_session_id, list_records_by_client, register_launch,
+        bind_source_session_id, find_continuation_parent, find_fork_parent,
+        find_record_by_ccem_session_id, list_continuations_of, list_forks_of,
+        list_records_by_client, record_continuation, record_fork, register_launch,
         spawn_codex_source_binding, state_db_path, SessionProvenanceUpsert,
     };
     use chrono::Utc;
@@ -663,6 +913,89 @@ mod tests {
         let _ = fs::remove_dir_all(root);
     }
 
+    #[test]
+    fn continuation_links_point_new_sessions_at_their_parent() {
+        let _guard = env_guard().lock().expect("lock env guard");
+        let root = temp_root("continuation");
+        std::env::set_var("CCEM_STATE_DB_PATH", root.join("state.sqlite"));
+
+        record_continuation("ccem-codex-1", "Claude", "claude-session-1")
+            .expect("record first continuation");
+        record_continuation("ccem-codex-2", "claude", "claude-session-1")
+            .expect("record second continuation");
+        assert!(record_continuation("ccem-codex-3", "claude", " ").is_err());
+
+        let parent = find_continuation_parent("ccem-codex-1")
+            .expect("lookup parent")
+            .expect("parent present");
+        assert_eq!(parent.parent_client, "claude");
+        assert_eq!(parent.parent_session_id, "claude-session-1");
+        assert!(find_continuation_parent("ccem-other")
+            .expect("lookup missing parent")
+            .is_none());
+        assert_eq!(
+            list_continuations_of("claude", "claude-session-1")
+                .expect("list continuations")
+                .iter()
+                .map(|link| link.ccem_session_id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["ccem-codex-1", "ccem-codex-2"]
+        );
+
+        std::env::remove_var("CCEM_STATE_DB_PATH");
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn fork_links_record_the_parent_runtime_and_fork_point() {
+        let _guard = env_guard().lock().expect("lock env guard");
+        let root = temp_root("forks");
+        std::env::set_var("CCEM_STATE_DB_PATH", root.join("state.sqlite"));
+
+        record_fork(
+            "native-child-1",
+            Some("native-root"),
+            "claude-root",
+            Some("msg-4"),
+            Some(8),
+        )
+        .expect("record first fork");
+        record_fork("native-child-2", None, "claude-root", None, None)
+            .expect("record fork with unknown parent runtime");
+        assert!(record_fork(
+            "native-root",
+            Some("native-root"),
+            "claude-root",
+            None,
+            None
+        )
+        .is_err());
+
+        let parent = find_fork_parent("native-child-1")
+            .expect("lookup fork parent")
+            .expect("fork parent present");
+        assert_eq!(parent.parent_runtime_id.as_deref(), Some("native-root"));
+        assert_eq!(parent.fork_message_id.as_deref(), Some("msg-4"));
+        assert_eq!(parent.seed_boundary_message_count, Some(8));
+        assert_eq!(
+            list_forks_of("native-root", Some("claude-root"))
+                .expect("list forks")
+                .iter()
+                .map(|link| link.ccem_session_id.as_str())
+                .collect::<Vec<_>>(),
+            vec!["native-child-1", "native-child-2"]
+        );
+        assert_eq!(
+            list_forks_of("native-root", None)
+                .expect("list forks by runtime")
+                .len(),
+            1
+        );
+
+        std::env::remove_var("CCEM_STATE_DB_PATH");
+        let _ = fs::remove_dir_all(root);
+    }
+
     #[test]
     fn codex_binding_scans_rollout_files_for_matching_project() {
         let _guard = env_guard().lock().expect("lock env guard");
@@ -1,15 +1,71 @@
 use crate::config;
+use crate::history::{self, ConversationMessage};
 use crate::native_helper_resource::native_helper_script_path;
+use crate::session_annotations::{self, SessionAnnotation, SessionAnnotations};
 use crate::terminal;
 use serde::Serialize;
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use tauri::AppHandle;
 use tauri_plugin_shell::{process::CommandEvent, ShellExt};
 
 const DEFAULT_HAIKU_MODEL: &str = "claude-3-5-haiku-20241022";
 const TITLE_MAX_CHARS: usize = 36;
 const TITLE_INPUT_MAX_CHARS: usize = 2_000;
+const TAGS_LINE_PREFIXES: &[&str] = &["tags:", "tags：", "标签:", "标签："];
+/// Tools whose `file_path`/`notebook_path` input is a file the session wrote.
+pub(crate) const FILE_WRITING_TOOLS: &[&str] = &["Edit", "MultiEdit", "Write", "NotebookEdit"];
+const APPLY_PATCH_FILE_MARKERS: &[&str] = &[
+    "*** Add File: ",
+    "*** Update File: ",
+    "*** Delete File: ",
+    "*** Move to: ",
+];
+/// Keyword fallback when AI enhancement is off or the helper returns no tags.
+/// ASCII keywords match whole words; CJK keywords match substrings.
+const TAG_KEYWORDS: &[(&str, &[&str])] = &[
+    (
+        "bugfix",
+        &[
+            "fix",
+            "bug",
+            "crash",
+            "error",
+            "regression",
+            "broken",
+            "修复",
+            "报错",
+            "崩溃",
+        ],
+    ),
+    (
+        "feature",
+        &[
+            "add",
+            "implement",
+            "support",
+            "feature",
+            "新增",
+            "实现",
+            "支持",
+        ],
+    ),
+    (
+        "refactor",
+        &["refactor", "cleanup", "rename", "simplify", "重构", "整理"],
+    ),
+    (
+        "infra",
+        &[
+            "ci", "docker", "deploy", "pipeline", "workflow", "build", "release", "部署", "构建",
+        ],
+    ),
+    (
+        "docs",
+        &["docs", "readme", "documentation", "changelog", "文档"],
+    ),
+    ("test", &["test", "tests", "spec", "coverage", "测试"]),
+];
 
 #[tauri::command]
 pub async fn generate_workspace_session_title(
@@ -23,6 +79,67 @@ pub async fn generate_workspace_session_title(
     Ok(generated_title.and_then(|title| sanitize_generated_title(&title)))
 }
 
+/// Tag a history session (bugfix, refactor, infra, docs, …) and record the
+/// files it touched. Tags come from the same Haiku title query when AI
+/// enhancement is configured, with a keyword fallback otherwise.
+#[tauri::command]
+pub async fn generate_session_tags(
+    app: AppHandle,
+    source: String,
+    session_id: String,
+) -> Result<SessionAnnotation, String> {
+    let (request_text, files_touched) = {
+        let source = source.clone();
+        let session_id = session_id.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            let detail = history::load_merged_conversation_detail(&session_id, Some(&source))?;
+            Ok::<_, String>((
+                session_request_text(&detail.messages),
+                extract_files_touched(&detail.messages),
+            ))
+        })
+        .await
+        .map_err(|error| format!("Task error: {}", error))??
+    };
+
+    let tag_input = if files_touched.is_empty() {
+        request_text.clone()
+    } else {
+        format!("{}\n\nFiles: {}", request_text, files_touched.join(", "))
+    };
+    let mut tags = match build_title_query_request(&tag_input) {
+        Ok(Some(mut request)) => {
+            request.include_tags = true;
+            match run_title_query_helper(app, request).await {
+                Ok(output) => output
+                    .map(|raw| parse_generated_tags(&raw))
+                    .unwrap_or_default(),
+                Err(error) => {
+                    eprintln!("Session tag query failed, using keyword tags: {}", error);
+                    Vec::new()
+                }
+            }
+        }
+        Ok(None) => Vec::new(),
+        Err(error) => {
+            eprintln!(
+                "Session tag query unavailable, using keyword tags: {}",
+                error
+            );
+            Vec::new()
+        }
+    };
+    if tags.is_empty() {
+        tags = infer_session_tags(&request_text, &files_touched);
+    }
+
+    tauri::async_runtime::spawn_blocking(move || {
+        session_annotations::store_session_insights(&source, &session_id, tags, files_touched)
+    })
+    .await
+    .map_err(|error| format!("Task error: {}", error))?
+}
+
 #[derive(Debug)]
 struct TitleQueryRequest {
     title_input: String,
@@ -30,6 +147,7 @@ struct TitleQueryRequest {
     env_vars: HashMap<String, String>,
     claude_path: Option<String>,
     model: String,
+    include_tags: bool,
 }
 
 #[derive(Debug, Serialize)]
@@ -42,6 +160,8 @@ struct TitleQueryCommand<'a> {
     #[serde(skip_serializing_if = "Option::is_none")]
     claude_path: Option<&'a str>,
     model: &'a str,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    include_tags: bool,
 }
 
 fn build_title_query_request(title_input: &str) -> Result<Option<TitleQueryRequest>, String> {
@@ -91,6 +211,7 @@ fn build_title_query_request(title_input: &str) -> Result<Option<TitleQueryReque
         env_vars,
         claude_path: terminal::resolve_claude_path(),
         model,
+        include_tags: false,
     }))
 }
 
@@ -117,6 +238,7 @@ async fn run_title_query_helper(
         env_vars: &request.env_vars,
         claude_path: request.claude_path.as_deref(),
         model: &request.model,
+        include_tags: request.include_tags,
     };
     let line = serde_json::to_string(&helper_command)
         .map_err(|error| format!("Failed to encode title query command: {}", error))?;
@@ -269,7 +391,7 @@ fn sanitize_generated_title(raw: &str) -> Option<String> {
         .trim_end_matches("```")
         .trim()
         .lines()
-        .find(|line| !line.trim().is_empty())
+        .find(|line| !line.trim().is_empty() && tags_line_value(line).is_none())
         .unwrap_or("")
         .trim()
         .trim_start_matches(['"', '\'', '`', '“', '‘', '[', '【'])
@@ -306,6 +428,160 @@ fn sanitize_generated_title(raw: &str) -> Option<String> {
     }
 }
 
+fn tags_line_value(line: &str) -> Option<&str> {
+    let trimmed = line.trim();
+    TAGS_LINE_PREFIXES.iter().find_map(|prefix| {
+        trimmed
+            .get(..prefix.len())
+            .filter(|head| head.eq_ignore_ascii_case(prefix))
+            .map(|_| &trimmed[prefix.len()..])
+    })
+}
+
+/// Pull the `tags: a, b` line out of the helper's title output.
+fn parse_generated_tags(raw: &str) -> Vec<String> {
+    let Some(value) = raw.lines().find_map(tags_line_value) else {
+        return Vec::new();
+    };
+    let tags = value
+        .split([',', '，', '、', ' '])
+        .map(|tag| tag.trim_matches(|ch: char| !ch.is_ascii_alphanumeric()))
+        .filter(|tag| !tag.is_empty())
+        .collect::<Vec<_>>();
+    SessionAnnotations::normalize_tags(&tags)
+}
+
+fn infer_session_tags(request_text: &str, files_touched: &[String]) -> Vec<String> {
+    let lowered = request_text.to_lowercase();
+    let words = lowered
+        .split(|ch: char| !ch.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<HashSet<_>>();
+    let mut tags = TAG_KEYWORDS
+        .iter()
+        .filter(|(_, keywords)| {
+            keywords.iter().any(|keyword| {
+                if keyword.is_ascii() {
+                    words.contains(keyword)
+                } else {
+                    lowered.contains(keyword)
+                }
+            })
+        })
+        .map(|(tag, _)| *tag)
+        .collect::<Vec<_>>();
+
+    let is_doc_file = |path: &String| {
+        let lowered = path.to_lowercase();
+        [".md", ".mdx", ".rst", ".txt"]
+            .iter()
+            .any(|extension| lowered.ends_with(extension))
+            || lowered.contains("/docs/")
+    };
+    if !files_touched.is_empty() && files_touched.iter().all(is_doc_file) {
+        tags.push("docs");
+    }
+    if files_touched.iter().any(|path| {
+        let lowered = path.to_lowercase();
+        lowered.contains(".github/workflows/")
+            || lowered.ends_with("dockerfile")
+            || lowered.ends_with(".tf")
+    }) {
+        tags.push("infra");
+    }
+    if files_touched.iter().any(|path| {
+        let lowered = path.to_lowercase();
+        lowered.contains("/tests/") || lowered.contains(".test.") || lowered.contains("_test.")
+    }) {
+        tags.push("test");
+    }
+
+    let mut tags = SessionAnnotations::normalize_tags(&tags);
+    tags.truncate(3);
+    tags
+}
+
+pub(crate) fn message_text(message: &ConversationMessage) -> String {
+    match &message.content {
+        Value::String(text) => text.clone(),
+        Value::Array(blocks) => blocks
+            .iter()
+            .filter(|block| block.get("type").and_then(Value::as_str) == Some("text"))
+            .filter_map(|block| block.get("text").and_then(Value::as_str))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+/// The user's requests in the session, capped like a title input.
+fn session_request_text(messages: &[ConversationMessage]) -> String {
+    let text = messages
+        .iter()
+        .filter(|message| message.msg_type == "user" || message.msg_type == "human")
+        .map(message_text)
+        .filter(|text| !text.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join("\n");
+    normalize_title_input(&text)
+}
+
+/// Files written by Claude edit tools or Codex `apply_patch`, in first-touch
+/// order.
+pub(crate) fn extract_files_touched(messages: &[ConversationMessage]) -> Vec<String> {
+    let mut files = Vec::new();
+    let mut seen = HashSet::new();
+    let mut push = |path: &str| {
+        let path = path.trim();
+        if !path.is_empty() && seen.insert(path.to_string()) {
+            files.push(path.to_string());
+        }
+    };
+
+    for message in messages {
+        let Some(blocks) = message.content.as_array() else {
+            continue;
+        };
+        for block in blocks {
+            if block.get("type").and_then(Value::as_str) != Some("tool_use") {
+                continue;
+            }
+            let name = block.get("name").and_then(Value::as_str).unwrap_or("");
+            let input = block.get("input").unwrap_or(&Value::Null);
+            if FILE_WRITING_TOOLS.contains(&name) {
+                if let Some(path) = input
+                    .get("file_path")
+                    .or_else(|| input.get("notebook_path"))
+                    .and_then(Value::as_str)
+                {
+                    push(path);
+                }
+            } else if name == "apply_patch" {
+                let patch = input
+                    .as_str()
+                    .or_else(|| input.get("input").and_then(Value::as_str))
+                    .unwrap_or("");
+                for path in apply_patch_file_paths(patch) {
+                    push(path);
+                }
+            }
+        }
+    }
+    files
+}
+
+/// Paths named by the file headers of an `apply_patch` body.
+pub(crate) fn apply_patch_file_paths(patch: &str) -> Vec<&str> {
+    patch
+        .lines()
+        .filter_map(|line| {
+            APPLY_PATCH_FILE_MARKERS
+                .iter()
+                .find_map(|marker| line.strip_prefix(marker))
+        })
+        .collect()
+}
+
 fn force_haiku_title_model(env_vars: &mut HashMap<String, String>) {
     let model = env_vars
         .get("ANTHROPIC_DEFAULT_HAIKU_MODEL")
@@ -354,6 +630,70 @@ mod tests {
         );
     }
 
+    #[test]
+    fn generated_tags_line_is_parsed_and_skipped_for_title() {
+        let raw = "修复别名解析\nTags: BugFix, infra，misc";
+        assert_eq!(
+            parse_generated_tags(raw),
+            vec!["bugfix".to_string(), "infra".to_string()]
+        );
+        assert_eq!(
+            sanitize_generated_title("tags: docs\n更新部署文档"),
+            Some("更新部署文档".to_string())
+        );
+        assert!(parse_generated_tags("只有标题").is_empty());
+    }
+
+    #[test]
+    fn keyword_tags_and_files_touched_come_from_transcript() {
+        let message = |msg_type: &str, content: Value| ConversationMessage {
+            msg_type: msg_type.to_string(),
+            uuid: None,
+            content,
+            model: None,
+            summary: None,
+            plan_content: None,
+            timestamp: 0,
+            input_tokens: None,
+            output_tokens: None,
+            cache_creation_tokens: None,
+            cache_read_tokens: None,
+            segment_index: 0,
+            is_compact_boundary: false,
+        };
+        let messages = vec![
+            message(
+                "user",
+                Value::String("Fix the CI crash in decide()".to_string()),
+            ),
+            message(
+                "assistant",
+                serde_json::json!([
+                    {"type": "tool_use", "name": "Edit", "input": {"file_path": "/repo/src/lib.rs"}},
+                    {"type": "tool_use", "name": "Read", "input": {"file_path": "/repo/README.md"}},
+                    {
+                        "type": "tool_use",
+                        "name": "apply_patch",
+                        "input": "*** Begin Patch\n*** Update File: .github/workflows/ci.yml\n*** Add File: /repo/src/lib.rs\n*** End Patch",
+                    },
+                ]),
+            ),
+        ];
+
+        let files = extract_files_touched(&messages);
+        assert_eq!(
+            files,
+            vec![
+                "/repo/src/lib.rs".to_string(),
+                ".github/workflows/ci.yml".to_string()
+            ]
+        );
+        assert_eq!(
+            infer_session_tags(&session_request_text(&messages), &files),
+            vec!["bugfix".to_string(), "infra".to_string()]
+        );
+    }
+
     #[test]
     fn title_helper_line_extracts_title_result() {
         let mut status_error = None;
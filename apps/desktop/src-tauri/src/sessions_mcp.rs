@@ -0,0 +1,211 @@
+//! Stdio MCP server that exposes native sessions to the agents running in
+//! them.
+//!
+//! Each helper generation is handed a session-bound token and the loopback
+//! control endpoint; the bundled script maps `create_session`, `send_input`,
+//! `wait_for_idle`, `get_transcript`, `list_sessions` and `stop_session` onto
+//! external control methods. The control server recognises the token as the
+//! calling session and applies the policy below, so a worker can never run
+//! with a looser permission mode than the session that spawned it. Sessions
+//! only get the server once the user enables `nativeSessionsMcpEnabled`.
+
+use crate::runtime::ccem_runtime_tools_dir;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+pub const SESSIONS_MCP_SERVER_NAME: &str = "ccem-sessions";
+const SESSIONS_MCP_SERVER_SOURCE: &str = include_str!("../resources/ccem-sessions-mcp-server.mjs");
+
+/// Control methods reachable with a session token. Permission responses and
+/// router/settings mutations stay with the user.
+pub const SESSION_CALLER_METHODS: &[&str] = &[
+    "ccem.health",
+    "ccem.workspace.listSessions",
+    "ccem.workspace.getSession",
+    "ccem.workspace.getEvents",
+    "ccem.workspace.createSession",
+    "ccem.workspace.sendInput",
+    "ccem.workspace.stopSession",
+];
+
+/// Methods that read or act on an existing session and are limited to
+/// sessions the caller spawned itself. `listSessions` is filtered to the same
+/// set after it runs.
+pub const SESSION_CALLER_CHILD_METHODS: &[&str] = &[
+    "ccem.workspace.getSession",
+    "ccem.workspace.getEvents",
+    "ccem.workspace.sendInput",
+    "ccem.workspace.stopSession",
+];
+
+pub fn ensure_sessions_mcp_server_script() -> Result<PathBuf, String> {
+    let script_dir = ccem_runtime_tools_dir();
+    fs::create_dir_all(&script_dir)
+        .map_err(|error| format!("Failed to create runtime tools dir: {}", error))?;
+
+    let script_path = script_dir.join("ccem-sessions-mcp-server.mjs");
+    let should_write = match fs::read_to_string(&script_path) {
+        Ok(existing) => existing != SESSIONS_MCP_SERVER_SOURCE,
+        Err(error) if error.kind() == ErrorKind::NotFound => true,
+        Err(error) => {
+            return Err(format!(
+                "Failed to read sessions MCP server script: {}",
+                error
+            ))
+        }
+    };
+
+    if should_write {
+        fs::write(&script_path, SESSIONS_MCP_SERVER_SOURCE)
+            .map_err(|error| format!("Failed to write sessions MCP server script: {}", error))?;
+    }
+
+    Ok(script_path)
+}
+
+/// How much a permission mode lets the agent do unattended, from plan-only
+/// (0) to bypassing every prompt (3). Unknown modes have no rank.
+fn permission_rank(mode: &str) -> Option<u8> {
+    match mode.trim() {
+        "readonly" | "audit" | "plan" | "read-only" => Some(0),
+        "safe" | "ci" | "default" | "manual" | "dontAsk" => Some(1),
+        "dev" | "acceptEdits" | "auto" => Some(2),
+        "yolo" | "bypassPermissions" => Some(3),
+        _ => None,
+    }
+}
+
+fn external_mode_for_rank(rank: u8) -> &'static str {
+    match rank {
+        0 => "readonly",
+        1 => "safe",
+        2 => "dev",
+        _ => "yolo",
+    }
+}
+
+/// Resolves the permission mode for a session spawned by `caller_mode`.
+/// Omitted modes inherit the caller's; anything more permissive is rejected.
+/// A caller mode this table does not know is treated as the most restrictive
+/// one, and an unknown requested mode is rejected.
+pub fn delegated_permission_mode(
+    caller_mode: &str,
+    requested: Option<&str>,
+) -> Result<String, String> {
+    let caller_rank = permission_rank(caller_mode).unwrap_or(0);
+    match requested.map(str::trim).filter(|mode| !mode.is_empty()) {
+        None => Ok(external_mode_for_rank(caller_rank).to_string()),
+        Some(mode) if permission_rank(mode).is_some_and(|rank| rank <= caller_rank) => {
+            Ok(mode.to_string())
+        }
+        Some(mode) => Err(format!(
+            "Forbidden: permission mode {} exceeds the calling session's {}",
+            mode, caller_mode
+        )),
+    }
+}
+
+/// Worker directories are given relative to the caller's project, so
+/// `../sibling` lands next to it. Absolute paths and paths that climb out of
+/// the project's parent directory are rejected: an agent must not pick an
+/// arbitrary directory on the machine.
+pub fn delegated_working_dir(
+    caller_project_dir: &str,
+    requested: Option<&str>,
+) -> Result<String, String> {
+    let Some(dir) = requested.map(str::trim).filter(|dir| !dir.is_empty()) else {
+        return Ok(caller_project_dir.to_string());
+    };
+    if Path::new(dir).is_absolute() || dir.starts_with('~') {
+        return Err(format!(
+            "Forbidden: worker directory {} must be relative to the calling session's project",
+            dir
+        ));
+    }
+
+    let project = Path::new(caller_project_dir);
+    let confinement = project.parent().unwrap_or(project);
+    let joined = normalize_joined(project, dir);
+    if !joined.starts_with(confinement) {
+        return Err(format!(
+            "Forbidden: worker directory {} leaves {}",
+            dir,
+            confinement.display()
+        ));
+    }
+    Ok(joined.to_string_lossy().to_string())
+}
+
+fn normalize_joined(base: &Path, relative: &str) -> PathBuf {
+    let mut joined = base.to_path_buf();
+    for component in Path::new(relative).components() {
+        match component {
+            std::path::Component::ParentDir => {
+                joined.pop();
+            }
+            std::path::Component::CurDir => {}
+            other => joined.push(other),
+        }
+    }
+    joined
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delegated_permission_mode_inherits_and_never_escalates() {
+        assert_eq!(
+            delegated_permission_mode("dev", None).expect("inherit"),
+            "dev"
+        );
+        assert_eq!(
+            delegated_permission_mode("plan", None).expect("inherit"),
+            "readonly"
+        );
+        assert_eq!(
+            delegated_permission_mode("dev", Some("safe")).expect("narrow"),
+            "safe"
+        );
+        assert!(delegated_permission_mode("safe", Some("yolo")).is_err());
+        assert!(delegated_permission_mode("readonly", Some("dev")).is_err());
+        assert!(delegated_permission_mode("dev", Some("made-up")).is_err());
+        assert!(delegated_permission_mode("yolo", Some("made-up")).is_err());
+        assert_eq!(
+            delegated_permission_mode("made-up", None).expect("unknown caller"),
+            "readonly"
+        );
+        assert!(delegated_permission_mode("made-up", Some("safe")).is_err());
+    }
+
+    #[test]
+    fn delegated_working_dir_resolves_against_the_caller_project() {
+        assert_eq!(
+            delegated_working_dir("/work/app", None).expect("default"),
+            "/work/app"
+        );
+        assert_eq!(
+            delegated_working_dir("/work/app", Some("../api")).expect("sibling"),
+            "/work/api"
+        );
+        assert_eq!(
+            delegated_working_dir("/work/app", Some("./pkg/core")).expect("nested"),
+            "/work/app/pkg/core"
+        );
+        assert!(delegated_working_dir("/work/app", Some("/tmp/x")).is_err());
+        assert!(delegated_working_dir("/work/app", Some("~/elsewhere")).is_err());
+    }
+
+    #[test]
+    fn delegated_working_dir_rejects_parent_chains_that_leave_the_project_parent() {
+        assert_eq!(
+            delegated_working_dir("/work/app", Some("..")).expect("parent"),
+            "/work"
+        );
+        assert!(delegated_working_dir("/work/app", Some("../../etc")).is_err());
+        assert!(delegated_working_dir("/work/app", Some("../../../..")).is_err());
+        assert!(delegated_working_dir("/work/app", Some("pkg/../../../home")).is_err());
+    }
+}
@@ -0,0 +1,1094 @@
+// apps/desktop/src-tauri/src/storage_manager.rs
+//
+// Disk usage, archiving and retention for the transcript stores CCEM reads
+// (Claude projects, Codex sessions) plus a usage report for the data CCEM
+// writes itself. Archived sessions are gzip bundles under ~/.ccem/archive,
+// listed in the state DB so the history browser can still open them.
+
+use crate::history_index::{collect_jsonl_files, transcript_session_id, HistoryIndex};
+use crate::prompt_image_store::default_prompt_image_store_dir;
+use crate::session_provenance::state_db_path;
+use crate::{config, history};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const SOURCE_CLAUDE: &str = "claude";
+const SOURCE_CODEX: &str = "codex";
+const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+/// Sessions written within this window may still be live, so retention
+/// never selects them.
+const RETENTION_IDLE_GUARD_MS: u64 = DAY_MS;
+/// Lines read from the head of a transcript to find its working directory.
+const PROJECT_PROBE_LINES: usize = 64;
+
+// ============================================================================
+// Output types — sent to frontend (camelCase for TypeScript)
+// ============================================================================
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageUsageEntry {
+    /// `claude`, `codex`, `archive`, `stateDb`, `proxySpool`, `promptImages`
+    /// or `browserArtifacts`.
+    pub store: String,
+    pub path: String,
+    pub bytes: u64,
+    pub file_count: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageProjectUsage {
+    pub source: String,
+    pub project: Option<String>,
+    pub bytes: u64,
+    pub session_count: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageUsageReport {
+    pub stores: Vec<StorageUsageEntry>,
+    /// Transcript bytes per source and project, largest first.
+    pub projects: Vec<StorageProjectUsage>,
+    pub total_bytes: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum RetentionAction {
+    #[default]
+    Archive,
+    Delete,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageRetentionPolicy {
+    /// Transcript sources to apply to; empty means Claude and Codex.
+    #[serde(default)]
+    pub sources: Vec<String>,
+    /// Project directory; also matches sessions in its subdirectories.
+    #[serde(default)]
+    pub project: Option<String>,
+    #[serde(default)]
+    pub max_age_days: Option<u32>,
+    /// Per-source budget; the oldest sessions beyond it are selected.
+    #[serde(default)]
+    pub max_total_bytes: Option<u64>,
+    #[serde(default)]
+    pub action: RetentionAction,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RetentionCandidate {
+    pub source: String,
+    pub session_id: String,
+    pub project: Option<String>,
+    pub bytes: u64,
+    pub last_modified: u64,
+    /// `age` or `size`: which limit selected the session.
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RetentionOutcome {
+    pub action: RetentionAction,
+    pub dry_run: bool,
+    pub candidates: Vec<RetentionCandidate>,
+    pub bytes_selected: u64,
+    /// Bytes actually released; archiving keeps the compressed bundle.
+    pub bytes_freed: u64,
+    pub applied: usize,
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchivedSession {
+    pub source: String,
+    pub session_id: String,
+    pub project: Option<String>,
+    pub original_bytes: u64,
+    pub archived_bytes: u64,
+    pub last_modified: u64,
+    pub archived_at: String,
+}
+
+// ============================================================================
+// Storage manager
+// ============================================================================
+
+/// One transcript session on disk. `files[0]` is the main transcript; the
+/// rest are Claude subagent transcripts.
+#[derive(Debug, Clone, PartialEq)]
+struct StoredSession {
+    source: &'static str,
+    session_id: String,
+    project: Option<String>,
+    root: PathBuf,
+    files: Vec<PathBuf>,
+    bytes: u64,
+    last_modified: u64,
+}
+
+/// Where each store lives; tests point these at temp directories.
+#[derive(Debug, Clone)]
+pub struct StorageLocations {
+    pub claude_projects: Option<PathBuf>,
+    pub codex_sessions: Option<PathBuf>,
+    pub archive_root: PathBuf,
+    pub state_db: PathBuf,
+    /// Stores CCEM writes itself. They are reported only; their owning
+    /// modules prune them (browser artifacts, proxy traffic, event log).
+    pub owned_stores: Vec<(&'static str, PathBuf)>,
+}
+
+impl StorageLocations {
+    pub fn discover() -> Self {
+        let ccem_dir = config::get_ccem_dir();
+        Self {
+            claude_projects: dirs::home_dir().map(|home| home.join(".claude").join("projects")),
+            codex_sessions: history::codex_sessions_root(),
+            archive_root: ccem_dir.join("archive"),
+            state_db: state_db_path(),
+            owned_stores: vec![
+                ("proxySpool", ccem_dir.join("proxy-debug")),
+                ("promptImages", default_prompt_image_store_dir()),
+                ("browserArtifacts", ccem_dir.join("browser")),
+            ],
+        }
+    }
+}
+
+pub struct StorageManager {
+    locations: StorageLocations,
+}
+
+impl Default for StorageManager {
+    fn default() -> Self {
+        Self::new(StorageLocations::discover())
+    }
+}
+
+impl StorageManager {
+    pub fn new(locations: StorageLocations) -> Self {
+        Self { locations }
+    }
+
+    fn open_manifest(&self) -> Result<Connection, String> {
+        let db_path = &self.locations.state_db;
+        if let Some(parent) = db_path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|error| format!("Failed to create sqlite state dir: {}", error))?;
+        }
+        let conn = Connection::open(db_path).map_err(|error| {
+            format!(
+                "Failed to open sqlite state db {}: {}",
+                db_path.display(),
+                error
+            )
+        })?;
+        conn.busy_timeout(Duration::from_secs(3))
+            .map_err(|error| format!("Failed to configure sqlite busy timeout: {}", error))?;
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             PRAGMA synchronous = NORMAL;
+             CREATE TABLE IF NOT EXISTS archived_transcripts (
+                 original_path TEXT PRIMARY KEY,
+                 archive_path TEXT NOT NULL,
+                 source TEXT NOT NULL,
+                 session_id TEXT NOT NULL,
+                 project TEXT,
+                 is_primary INTEGER NOT NULL,
+                 original_bytes INTEGER NOT NULL,
+                 archived_bytes INTEGER NOT NULL,
+                 last_modified_ms INTEGER NOT NULL,
+                 archived_at TEXT NOT NULL
+             );
+             CREATE INDEX IF NOT EXISTS idx_archived_transcripts_session
+                 ON archived_transcripts(source, session_id);",
+        )
+        .map_err(|error| format!("Failed to initialize archive schema: {}", error))?;
+        Ok(conn)
+    }
+
+    /// Bytes and file counts per store, plus transcript bytes per project.
+    pub fn usage(&self) -> Result<StorageUsageReport, String> {
+        let mut stores = Vec::new();
+        let transcript_roots = [
+            (SOURCE_CLAUDE, self.locations.claude_projects.as_ref()),
+            (SOURCE_CODEX, self.locations.codex_sessions.as_ref()),
+        ];
+        for (store, root) in transcript_roots {
+            if let Some(root) = root {
+                stores.push(usage_entry(store, root));
+            }
+        }
+        stores.push(usage_entry("archive", &self.locations.archive_root));
+
+        let mut state_db = usage_entry("stateDb", &self.locations.state_db);
+        for suffix in ["-wal", "-shm"] {
+            let mut sidecar = self.locations.state_db.clone().into_os_string();
+            sidecar.push(suffix);
+            let (bytes, files) = path_usage(Path::new(&sidecar));
+            state_db.bytes += bytes;
+            state_db.file_count += files;
+        }
+        stores.push(state_db);
+        for (store, root) in &self.locations.owned_stores {
+            stores.push(usage_entry(store, root));
+        }
+
+        let mut projects: HashMap<(&str, Option<String>), StorageProjectUsage> = HashMap::new();
+        for session in self.scan_sessions() {
+            let entry = projects
+                .entry((session.source, session.project.clone()))
+                .or_insert_with(|| StorageProjectUsage {
+                    source: session.source.to_string(),
+                    project: session.project.clone(),
+                    bytes: 0,
+                    session_count: 0,
+                });
+            entry.bytes += session.bytes;
+            entry.session_count += 1;
+        }
+        let mut projects = projects.into_values().collect::<Vec<_>>();
+        projects.sort_by(|a, b| {
+            b.bytes
+                .cmp(&a.bytes)
+                .then_with(|| a.project.cmp(&b.project))
+        });
+
+        Ok(StorageUsageReport {
+            total_bytes: stores.iter().map(|store| store.bytes).sum(),
+            stores,
+            projects,
+        })
+    }
+
+    /// Select sessions under `policy` and, unless `dry_run`, archive or
+    /// delete them. One failing session is reported and the rest proceed.
+    pub fn apply_retention(
+        &self,
+        policy: &StorageRetentionPolicy,
+        dry_run: bool,
+    ) -> Result<RetentionOutcome, String> {
+        let selected = select_for_retention(self.scan_sessions(), policy, now_ms())?;
+        let mut outcome = RetentionOutcome {
+            action: policy.action,
+            dry_run,
+            candidates: Vec::with_capacity(selected.len()),
+            bytes_selected: 0,
+            bytes_freed: 0,
+            applied: 0,
+            errors: Vec::new(),
+        };
+        for (session, reason) in selected {
+            outcome.bytes_selected += session.bytes;
+            outcome.candidates.push(RetentionCandidate {
+                source: session.source.to_string(),
+                session_id: session.session_id.clone(),
+                project: session.project.clone(),
+                bytes: session.bytes,
+                last_modified: session.last_modified,
+                reason: reason.to_string(),
+            });
+            if dry_run {
+                continue;
+            }
+            let result = match policy.action {
+                RetentionAction::Archive => self
+                    .archive_session(&session)
+                    .map(|archived| session.bytes.saturating_sub(archived)),
+                RetentionAction::Delete => remove_session_files(&session).map(|()| session.bytes),
+            };
+            match result {
+                Ok(freed) => {
+                    outcome.applied += 1;
+                    outcome.bytes_freed += freed;
+                    // Full-text search must not surface transcripts that
+                    // are no longer on disk.
+                    if let Err(error) = HistoryIndex::new(self.locations.state_db.clone())
+                        .forget_transcripts(&session.files)
+                    {
+                        outcome.errors.push(format!(
+                            "{} session {}: {}",
+                            session.source, session.session_id, error
+                        ));
+                    }
+                }
+                Err(error) => outcome.errors.push(format!(
+                    "{} session {}: {}",
+                    session.source, session.session_id, error
+                )),
+            }
+        }
+        Ok(outcome)
+    }
+
+    pub fn list_archived(&self) -> Result<Vec<ArchivedSession>, String> {
+        let conn = self.open_manifest()?;
+        let mut stmt = conn
+            .prepare(
+                "SELECT source, session_id, MAX(project), SUM(original_bytes),
+                        SUM(archived_bytes), MAX(last_modified_ms), MAX(archived_at)
+                 FROM archived_transcripts
+                 GROUP BY source, session_id
+                 ORDER BY MAX(last_modified_ms) DESC",
+            )
+            .map_err(|error| format!("Failed to prepare archive listing: {}", error))?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok(ArchivedSession {
+                    source: row.get(0)?,
+                    session_id: row.get(1)?,
+                    project: row.get(2)?,
+                    original_bytes: row.get::<_, i64>(3)?.max(0) as u64,
+                    archived_bytes: row.get::<_, i64>(4)?.max(0) as u64,
+                    last_modified: row.get::<_, i64>(5)?.max(0) as u64,
+                    archived_at: row.get(6)?,
+                })
+            })
+            .map_err(|error| format!("Failed to list archived sessions: {}", error))?;
+
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|error| format!("Failed to read archived session row: {}", error))
+    }
+
+    /// Source of an archived session, for history lookups without a hint.
+    pub fn archived_source(&self, session_id: &str) -> Option<&'static str> {
+        let source = self
+            .archived_primary(None, session_id)
+            .ok()
+            .flatten()
+            .map(|(source, _)| source)?;
+        [SOURCE_CLAUDE, SOURCE_CODEX]
+            .into_iter()
+            .find(|known| *known == source)
+    }
+
+    /// Stream the main transcript of an archived session, decompressed.
+    pub fn open_archived_transcript(
+        &self,
+        source: &str,
+        session_id: &str,
+    ) -> Result<Option<Box<dyn BufRead>>, String> {
+        let Some((_, archive_path)) = self.archived_primary(Some(source), session_id)? else {
+            return Ok(None);
+        };
+        let file = fs::File::open(&archive_path).map_err(|error| {
+            format!(
+                "Failed to open archived transcript {}: {}",
+                archive_path.display(),
+                error
+            )
+        })?;
+        Ok(Some(Box::new(BufReader::new(GzDecoder::new(file)))))
+    }
+
+    fn archived_primary(
+        &self,
+        source: Option<&str>,
+        session_id: &str,
+    ) -> Result<Option<(String, PathBuf)>, String> {
+        if !self.locations.state_db.exists() {
+            return Ok(None);
+        }
+        let conn = self.open_manifest()?;
+        conn.query_row(
+            "SELECT source, archive_path FROM archived_transcripts
+             WHERE session_id = ?1 AND (?2 IS NULL OR source = ?2) AND is_primary = 1
+             ORDER BY archived_at DESC
+             LIMIT 1",
+            params![session_id, source],
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    PathBuf::from(row.get::<_, String>(1)?),
+                ))
+            },
+        )
+        .optional()
+        .map_err(|error| format!("Failed to look up archived transcript: {}", error))
+    }
+
+    /// Compress every file of `session` into the archive, record it, then
+    /// remove the originals. Returns the compressed size.
+    fn archive_session(&self, session: &StoredSession) -> Result<u64, String> {
+        let mut written: Vec<(&PathBuf, PathBuf, u64, u64)> = Vec::new();
+        for file in &session.files {
+            let relative = file
+                .strip_prefix(&session.root)
+                .map_err(|_| format!("{} is outside {}", file.display(), session.root.display()))?;
+            let mut target = self
+                .locations
+                .archive_root
+                .join(session.source)
+                .join(relative)
+                .into_os_string();
+            target.push(".gz");
+            let target = PathBuf::from(target);
+            let original_bytes = file_stats(file).0;
+            match compress_file(file, &target) {
+                Ok(archived_bytes) => written.push((file, target, original_bytes, archived_bytes)),
+                Err(error) => {
+                    for (_, target, _, _) in &written {
+                        let _ = fs::remove_file(target);
+                    }
+                    return Err(error);
+                }
+            }
+        }
+
+        let mut conn = self.open_manifest()?;
+        let tx = conn
+            .transaction()
+            .map_err(|error| format!("Failed to begin archive transaction: {}", error))?;
+        let archived_at = chrono::Utc::now().to_rfc3339();
+        for (index, (file, target, original_bytes, archived_bytes)) in written.iter().enumerate() {
+            tx.execute(
+                "INSERT OR REPLACE INTO archived_transcripts
+                     (original_path, archive_path, source, session_id, project, is_primary,
+                      original_bytes, archived_bytes, last_modified_ms, archived_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                params![
+                    file.to_string_lossy(),
+                    target.to_string_lossy(),
+                    session.source,
+                    session.session_id,
+                    session.project,
+                    index == 0,
+                    *original_bytes as i64,
+                    *archived_bytes as i64,
+                    session.last_modified as i64,
+                    archived_at,
+                ],
+            )
+            .map_err(|error| format!("Failed to record archived transcript: {}", error))?;
+        }
+        tx.commit()
+            .map_err(|error| format!("Failed to commit archive transaction: {}", error))?;
+
+        remove_session_files(session)?;
+        Ok(written.iter().map(|(_, _, _, archived)| archived).sum())
+    }
+
+    fn scan_sessions(&self) -> Vec<StoredSession> {
+        let mut sessions = Vec::new();
+        if let Some(root) = self.locations.claude_projects.as_deref() {
+            sessions.extend(scan_claude_sessions(root));
+        }
+        if let Some(root) = self.locations.codex_sessions.as_deref() {
+            for path in collect_jsonl_files(root) {
+                let Some(session_id) = transcript_session_id(SOURCE_CODEX, &path) else {
+                    continue;
+                };
+                sessions.push(stored_session(SOURCE_CODEX, session_id, root, vec![path]));
+            }
+        }
+        sessions
+    }
+}
+
+/// Claude keeps `<project>/<session-id>.jsonl` with subagent transcripts
+/// under `<project>/<session-id>/subagents/`.
+fn scan_claude_sessions(root: &Path) -> Vec<StoredSession> {
+    let mut sessions = Vec::new();
+    let Ok(projects) = fs::read_dir(root) else {
+        return sessions;
+    };
+    for project_dir in projects.flatten().map(|entry| entry.path()) {
+        if !project_dir.is_dir() {
+            continue;
+        }
+        let Ok(entries) = fs::read_dir(&project_dir) else {
+            continue;
+        };
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if path.extension().and_then(|ext| ext.to_str()) != Some("jsonl") {
+                continue;
+            }
+            let Some(session_id) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            let mut files = vec![path.clone()];
+            files.extend(collect_jsonl_files(
+                &project_dir.join(session_id).join("subagents"),
+            ));
+            sessions.push(stored_session(
+                SOURCE_CLAUDE,
+                session_id.to_string(),
+                root,
+                files,
+            ));
+        }
+    }
+    sessions.sort_by(|a, b| a.files[0].cmp(&b.files[0]));
+    sessions
+}
+
+fn stored_session(
+    source: &'static str,
+    session_id: String,
+    root: &Path,
+    files: Vec<PathBuf>,
+) -> StoredSession {
+    let (bytes, last_modified) = files.iter().map(|file| file_stats(file)).fold(
+        (0, 0),
+        |(bytes, modified), (file_bytes, file_modified)| {
+            (bytes + file_bytes, modified.max(file_modified))
+        },
+    );
+    StoredSession {
+        source,
+        session_id,
+        project: probe_project(&files[0]),
+        root: root.to_path_buf(),
+        files,
+        bytes,
+        last_modified,
+    }
+}
+
+/// Pick the sessions a policy applies to, oldest first, with the limit that
+/// selected each.
+fn select_for_retention(
+    mut sessions: Vec<StoredSession>,
+    policy: &StorageRetentionPolicy,
+    now_ms: u64,
+) -> Result<Vec<(StoredSession, &'static str)>, String> {
+    if policy.max_age_days.is_none() && policy.max_total_bytes.is_none() {
+        return Err("Retention policy needs maxAgeDays or maxTotalBytes".to_string());
+    }
+    let mut sources = Vec::new();
+    for source in &policy.sources {
+        match source.trim().to_lowercase().as_str() {
+            SOURCE_CLAUDE => sources.push(SOURCE_CLAUDE),
+            SOURCE_CODEX => sources.push(SOURCE_CODEX),
+            other => return Err(format!("Unsupported retention source: {}", other)),
+        }
+    }
+    let project = policy
+        .project
+        .as_deref()
+        .map(|value| value.trim().trim_end_matches(['/', '\\']))
+        .filter(|value| !value.is_empty());
+    sessions.retain(|session| {
+        (sources.is_empty() || sources.contains(&session.source))
+            && project.is_none_or(|project| {
+                session.project.as_deref().is_some_and(|candidate| {
+                    candidate == project
+                        || candidate
+                            .strip_prefix(project)
+                            .is_some_and(|rest| rest.starts_with('/'))
+                })
+            })
+    });
+    // Newest first, so the size budget is spent on recent sessions.
+    sessions.sort_by_key(|session| std::cmp::Reverse(session.last_modified));
+
+    let age_cutoff = policy
+        .max_age_days
+        .map(|days| now_ms.saturating_sub(u64::from(days) * DAY_MS));
+    let mut used_bytes: HashMap<&str, u64> = HashMap::new();
+    let mut selected = Vec::new();
+    for session in sessions {
+        let used = used_bytes.entry(session.source).or_default();
+        *used += session.bytes;
+        let over_budget = policy.max_total_bytes.is_some_and(|budget| *used > budget);
+        if session.last_modified + RETENTION_IDLE_GUARD_MS > now_ms {
+            continue;
+        }
+        if age_cutoff.is_some_and(|cutoff| session.last_modified <= cutoff) {
+            selected.push((session, "age"));
+        } else if over_budget {
+            selected.push((session, "size"));
+        }
+    }
+    selected.reverse();
+    Ok(selected)
+}
+
+fn remove_session_files(session: &StoredSession) -> Result<(), String> {
+    for file in &session.files {
+        match fs::remove_file(file) {
+            Ok(()) => {}
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {}
+            Err(error) => {
+                return Err(format!("Failed to remove {}: {}", file.display(), error));
+            }
+        }
+    }
+    // Drop the now-empty `<session-id>/subagents/` directories; anything
+    // else Claude keeps there stays put.
+    for file in session.files.iter().skip(1) {
+        for dir in file.ancestors().skip(1).take(2) {
+            let _ = fs::remove_dir(dir);
+        }
+    }
+    Ok(())
+}
+
+fn compress_file(source: &Path, target: &Path) -> Result<u64, String> {
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|error| format!("Failed to create archive dir: {}", error))?;
+    }
+    let mut temp = target.to_path_buf().into_os_string();
+    temp.push(".tmp");
+    let temp = PathBuf::from(temp);
+    if let Err(error) = write_gzip(source, &temp).and_then(|()| fs::rename(&temp, target)) {
+        let _ = fs::remove_file(&temp);
+        return Err(format!("Failed to archive {}: {}", source.display(), error));
+    }
+    Ok(file_stats(target).0)
+}
+
+fn write_gzip(source: &Path, target: &Path) -> io::Result<()> {
+    let mut input = fs::File::open(source)?;
+    let mut encoder = GzEncoder::new(fs::File::create(target)?, Compression::default());
+    io::copy(&mut input, &mut encoder)?;
+    encoder.finish()?.sync_all()
+}
+
+/// Working directory recorded near the top of a transcript (`cwd` on Claude
+/// lines, `payload.cwd` on Codex session metadata).
+fn probe_project(path: &Path) -> Option<String> {
+    let file = fs::File::open(path).ok()?;
+    BufReader::new(file)
+        .lines()
+        .take(PROJECT_PROBE_LINES)
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str::<Value>(&line).ok())
+        .find_map(|value| {
+            value
+                .get("cwd")
+                .or_else(|| value.get("payload").and_then(|payload| payload.get("cwd")))
+                .and_then(Value::as_str)
+                .map(str::trim)
+                .filter(|cwd| !cwd.is_empty())
+                .map(str::to_string)
+        })
+}
+
+fn usage_entry(store: &str, path: &Path) -> StorageUsageEntry {
+    let (bytes, file_count) = path_usage(path);
+    StorageUsageEntry {
+        store: store.to_string(),
+        path: path.to_string_lossy().to_string(),
+        bytes,
+        file_count,
+    }
+}
+
+/// Bytes and file count under `path`, without following symlinks.
+fn path_usage(path: &Path) -> (u64, u64) {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return (0, 0);
+    };
+    if metadata.is_file() {
+        return (metadata.len(), 1);
+    }
+    if !metadata.is_dir() {
+        return (0, 0);
+    }
+    let mut totals = (0, 0);
+    let mut stack = vec![path.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if metadata.is_dir() {
+                stack.push(entry.path());
+            } else if metadata.is_file() {
+                totals.0 += metadata.len();
+                totals.1 += 1;
+            }
+        }
+    }
+    totals
+}
+
+fn file_stats(path: &Path) -> (u64, u64) {
+    let Ok(metadata) = fs::metadata(path) else {
+        return (0, 0);
+    };
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0);
+    (metadata.len(), modified)
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+// ============================================================================
+// Tauri commands
+// ============================================================================
+
+#[tauri::command]
+pub async fn get_storage_usage() -> Result<StorageUsageReport, String> {
+    tauri::async_runtime::spawn_blocking(|| StorageManager::default().usage())
+        .await
+        .map_err(|error| format!("Blocking storage usage task failed: {error}"))?
+}
+
+/// Preview (the default) or apply a retention policy to transcripts.
+#[tauri::command]
+pub async fn apply_storage_retention(
+    policy: StorageRetentionPolicy,
+    dry_run: Option<bool>,
+) -> Result<RetentionOutcome, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        StorageManager::default().apply_retention(&policy, dry_run.unwrap_or(true))
+    })
+    .await
+    .map_err(|error| format!("Blocking storage retention task failed: {error}"))?
+}
+
+#[tauri::command]
+pub async fn list_archived_sessions() -> Result<Vec<ArchivedSession>, String> {
+    tauri::async_runtime::spawn_blocking(|| StorageManager::default().list_archived())
+        .await
+        .map_err(|error| format!("Blocking archive listing task failed: {error}"))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        select_for_retention, RetentionAction, StorageLocations, StorageManager,
+        StorageRetentionPolicy, StoredSession, DAY_MS,
+    };
+    use crate::history_index::{HistoryFullTextFilters, HistoryIndex, TranscriptRoots};
+    use std::fs;
+    use std::io::Read;
+    use std::path::{Path, PathBuf};
+    use std::time::{Duration, SystemTime};
+
+    fn session(id: &str, project: &str, bytes: u64, last_modified: u64) -> StoredSession {
+        StoredSession {
+            source: "claude",
+            session_id: id.to_string(),
+            project: Some(project.to_string()),
+            root: PathBuf::from("/root"),
+            files: vec![PathBuf::from(format!("/root/p/{id}.jsonl"))],
+            bytes,
+            last_modified,
+        }
+    }
+
+    fn write_aged(path: &Path, body: &str, age_days: u64) {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).expect("create transcript dir");
+        }
+        fs::write(path, body).expect("write transcript");
+        let modified = SystemTime::now() - Duration::from_secs(age_days * 24 * 60 * 60);
+        fs::File::options()
+            .write(true)
+            .open(path)
+            .and_then(|file| file.set_modified(modified))
+            .expect("age transcript");
+    }
+
+    fn manager_for(root: &Path, owned_stores: Vec<(&'static str, PathBuf)>) -> StorageManager {
+        StorageManager::new(StorageLocations {
+            claude_projects: Some(root.join("projects")),
+            codex_sessions: None,
+            archive_root: root.join("archive"),
+            state_db: root.join("state.sqlite"),
+            owned_stores,
+        })
+    }
+
+    fn transcript_body(project: &str) -> String {
+        format!("{{\"type\":\"user\",\"cwd\":\"{project}\",\"message\":{{\"content\":\"hi\"}}}}\n")
+    }
+
+    #[test]
+    fn retention_selects_old_and_over_budget_sessions_but_spares_live_ones() {
+        let now = 100 * DAY_MS;
+        let sessions = vec![
+            session("live", "/work/app", 500, now - 1_000),
+            session("recent", "/work/app", 300, now - 2 * DAY_MS),
+            session("older", "/work/app", 300, now - 10 * DAY_MS),
+            session("ancient", "/work/app/api", 100, now - 60 * DAY_MS),
+            session("elsewhere", "/work/application", 100, now - 60 * DAY_MS),
+        ];
+        let policy = StorageRetentionPolicy {
+            project: Some("/work/app/".to_string()),
+            max_age_days: Some(30),
+            max_total_bytes: Some(1_000),
+            ..Default::default()
+        };
+        let selected = select_for_retention(sessions, &policy, now).expect("select");
+        let picked = selected
+            .iter()
+            .map(|(session, reason)| (session.session_id.as_str(), *reason))
+            .collect::<Vec<_>>();
+        assert_eq!(picked, vec![("ancient", "age"), ("older", "size")]);
+
+        assert!(select_for_retention(Vec::new(), &StorageRetentionPolicy::default(), now).is_err());
+        let unknown = StorageRetentionPolicy {
+            sources: vec!["gemini".to_string()],
+            max_age_days: Some(1),
+            ..Default::default()
+        };
+        assert!(select_for_retention(Vec::new(), &unknown, now).is_err());
+    }
+
+    #[test]
+    fn archived_sessions_leave_disk_and_still_open_read_only() {
+        let temp = tempfile::tempdir().expect("storage tempdir");
+        let projects = temp.path().join("projects");
+        let project_dir = projects.join("-work-app");
+        let subagents = project_dir.join("ses-old").join("subagents");
+        fs::create_dir_all(&subagents).expect("create subagents dir");
+        let transcript = project_dir.join("ses-old.jsonl");
+        let body = "{\"type\":\"user\",\"cwd\":\"/work/app\",\"message\":{\"content\":\"hi\"}}\n";
+        fs::write(&transcript, body).expect("write transcript");
+        fs::write(subagents.join("agent-1.jsonl"), "{}\n").expect("write subagent");
+        let old = SystemTime::now() - Duration::from_secs(40 * 24 * 60 * 60);
+        for path in [&transcript, &subagents.join("agent-1.jsonl")] {
+            fs::File::options()
+                .write(true)
+                .open(path)
+                .and_then(|file| file.set_modified(old))
+                .expect("age transcript");
+        }
+
+        let manager = StorageManager::new(StorageLocations {
+            claude_projects: Some(projects.clone()),
+            codex_sessions: None,
+            archive_root: temp.path().join("archive"),
+            state_db: temp.path().join("state.sqlite"),
+            owned_stores: Vec::new(),
+        });
+        let index = HistoryIndex::new(temp.path().join("state.sqlite"));
+        let roots = TranscriptRoots {
+            claude_projects: Some(projects.clone()),
+            codex_sessions: None,
+            include_opencode: false,
+        };
+        index.refresh(&roots).expect("index transcripts");
+        let search = |index: &HistoryIndex| {
+            index
+                .search("hi", &HistoryFullTextFilters::default(), 10)
+                .expect("search")
+                .len()
+        };
+        assert_eq!(search(&index), 1);
+        let usage = manager.usage().expect("usage");
+        assert_eq!(usage.projects.len(), 1);
+        assert_eq!(usage.projects[0].project.as_deref(), Some("/work/app"));
+        assert_eq!(usage.projects[0].bytes, body.len() as u64 + 3);
+
+        let policy = StorageRetentionPolicy {
+            max_age_days: Some(30),
+            action: RetentionAction::Archive,
+            ..Default::default()
+        };
+        let preview = manager.apply_retention(&policy, true).expect("dry run");
+        assert_eq!(preview.candidates.len(), 1);
+        assert_eq!(preview.applied, 0);
+        assert!(transcript.exists());
+
+        let applied = manager.apply_retention(&policy, false).expect("archive");
+        assert_eq!(applied.applied, 1, "errors: {:?}", applied.errors);
+        assert!(!transcript.exists());
+        assert_eq!(search(&index), 0);
+        index.refresh(&roots).expect("refresh after archiving");
+        assert_eq!(search(&index), 0);
+        assert!(!project_dir.join("ses-old").exists());
+
+        let archived = manager.list_archived().expect("list archived");
+        assert_eq!(archived.len(), 1);
+        assert_eq!(archived[0].session_id, "ses-old");
+        assert_eq!(manager.archived_source("ses-old"), Some("claude"));
+
+        let mut restored = String::new();
+        manager
+            .open_archived_transcript("claude", "ses-old")
+            .expect("open archive")
+            .expect("archived transcript")
+            .read_to_string(&mut restored)
+            .expect("decompress");
+        assert_eq!(restored, body);
+    }
+
+    #[test]
+    fn retention_thresholds_are_inclusive_for_age_and_per_source_for_size() {
+        let now = 100 * DAY_MS;
+        let codex = |id: &str, bytes: u64, last_modified: u64| StoredSession {
+            source: "codex",
+            ..session(id, "/work/app", bytes, last_modified)
+        };
+        let sessions = vec![
+            session("at-cutoff", "/work/app", 10, now - 30 * DAY_MS),
+            session("inside-age", "/work/app", 10, now - 30 * DAY_MS + 1),
+            session("guarded", "/work/app", 10, now - DAY_MS + 1),
+            session("just-idle", "/work/app", 10, now - DAY_MS),
+            codex("codex-fits", 80, now - 5 * DAY_MS),
+            codex("codex-over", 30, now - 6 * DAY_MS),
+        ];
+        let policy = StorageRetentionPolicy {
+            max_age_days: Some(30),
+            max_total_bytes: Some(100),
+            ..Default::default()
+        };
+        let selected = select_for_retention(sessions.clone(), &policy, now).expect("select");
+        let picked = selected
+            .iter()
+            .map(|(session, reason)| (session.session_id.as_str(), *reason))
+            .collect::<Vec<_>>();
+        // Claude stays within its own 100-byte budget; only Codex is over.
+        assert_eq!(picked, vec![("at-cutoff", "age"), ("codex-over", "size")]);
+
+        let exact_budget = StorageRetentionPolicy {
+            max_total_bytes: Some(40),
+            sources: vec![" Claude ".to_string()],
+            ..Default::default()
+        };
+        let selected = select_for_retention(sessions, &exact_budget, now).expect("select");
+        assert!(
+            selected.is_empty(),
+            "a source exactly at its budget keeps everything: {:?}",
+            selected
+        );
+    }
+
+    #[test]
+    fn archiving_records_the_bundle_before_a_later_delete_policy_runs() {
+        let temp = tempfile::tempdir().expect("storage tempdir");
+        let project_dir = temp.path().join("projects").join("-work-app");
+        let archived_path = project_dir.join("ses-archived.jsonl");
+        let deleted_path = project_dir.join("ses-deleted.jsonl");
+        let body = transcript_body("/work/app");
+        write_aged(&archived_path, &body, 40);
+        let manager = manager_for(temp.path(), Vec::new());
+
+        let archive = StorageRetentionPolicy {
+            max_age_days: Some(30),
+            action: RetentionAction::Archive,
+            ..Default::default()
+        };
+        let archived = manager.apply_retention(&archive, false).expect("archive");
+        assert_eq!(archived.applied, 1, "errors: {:?}", archived.errors);
+        assert!(!archived_path.exists());
+
+        write_aged(&deleted_path, &body, 40);
+        let delete = StorageRetentionPolicy {
+            max_age_days: Some(30),
+            action: RetentionAction::Delete,
+            ..Default::default()
+        };
+        let deleted = manager.apply_retention(&delete, false).expect("delete");
+        let picked = deleted
+            .candidates
+            .iter()
+            .map(|candidate| candidate.session_id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(picked, vec!["ses-deleted"]);
+        assert_eq!(deleted.applied, 1, "errors: {:?}", deleted.errors);
+        assert_eq!(deleted.bytes_freed, body.len() as u64);
+        assert!(!deleted_path.exists());
+
+        let listed = manager.list_archived().expect("list archived");
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].session_id, "ses-archived");
+        assert_eq!(manager.archived_source("ses-deleted"), None);
+        let mut restored = String::new();
+        manager
+            .open_archived_transcript("claude", "ses-archived")
+            .expect("open archive")
+            .expect("archived transcript")
+            .read_to_string(&mut restored)
+            .expect("decompress");
+        assert_eq!(restored, body);
+    }
+
+    #[test]
+    fn failed_archive_keeps_the_source_transcripts() {
+        let temp = tempfile::tempdir().expect("storage tempdir");
+        let project_dir = temp.path().join("projects").join("-work-app");
+        let transcript = project_dir.join("ses-keep.jsonl");
+        let subagent = project_dir
+            .join("ses-keep")
+            .join("subagents")
+            .join("agent-1.jsonl");
+        write_aged(&transcript, &transcript_body("/work/app"), 40);
+        write_aged(&subagent, "{}\n", 40);
+        // A file where the archive directory should be makes every write fail.
+        fs::write(temp.path().join("archive"), "not a directory").expect("block archive root");
+        let manager = manager_for(temp.path(), Vec::new());
+
+        let policy = StorageRetentionPolicy {
+            max_age_days: Some(30),
+            action: RetentionAction::Archive,
+            ..Default::default()
+        };
+        let outcome = manager.apply_retention(&policy, false).expect("apply");
+
+        assert_eq!(outcome.candidates.len(), 1);
+        assert_eq!(outcome.applied, 0);
+        assert_eq!(outcome.bytes_freed, 0);
+        assert_eq!(outcome.errors.len(), 1);
+        assert!(outcome.errors[0].starts_with("claude session ses-keep:"));
+        assert!(transcript.exists());
+        assert!(subagent.exists());
+        assert!(manager.list_archived().expect("list archived").is_empty());
+    }
+
+    #[test]
+    fn owned_stores_are_reported_but_never_pruned() {
+        let temp = tempfile::tempdir().expect("storage tempdir");
+        let spool = temp.path().join("proxy-debug");
+        let spooled = spool.join("2026-01-01").join("traffic.jsonl");
+        write_aged(&spooled, &transcript_body("/work/app"), 400);
+        let manager = manager_for(temp.path(), vec![("proxySpool", spool.clone())]);
+
+        let usage = manager.usage().expect("usage");
+        let store = usage
+            .stores
+            .iter()
+            .find(|store| store.store == "proxySpool")
+            .expect("owned store in report");
+        assert_eq!(store.file_count, 1);
+        assert_eq!(store.bytes, fs::metadata(&spooled).expect("spool").len());
+        assert!(usage.projects.is_empty());
+
+        for action in [RetentionAction::Archive, RetentionAction::Delete] {
+            let policy = StorageRetentionPolicy {
+                max_age_days: Some(1),
+                max_total_bytes: Some(0),
+                action,
+                ..Default::default()
+            };
+            let outcome = manager.apply_retention(&policy, false).expect("apply");
+            assert!(outcome.candidates.is_empty());
+        }
+        assert!(spooled.exists());
+    }
+}
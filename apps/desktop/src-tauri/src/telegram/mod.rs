@@ -9,12 +9,16 @@ use crate::event_bus::{
     ToolQuestionPrompt,
 };
 use crate::interactive_runtime::{InteractiveRuntimeManager, InteractiveSessionOptions};
+use crate::launch_presets::{LaunchPreset, LaunchPresets};
+use crate::native_event_log::NativeEventLog;
+use crate::native_runtime::NativeProvider;
 use crate::permission_preview::format_permission_preview;
 use crate::runtime::{
     clear_runtime_recovery_candidates_by_claude_session_id, HeadlessRuntimeManager,
     HeadlessSessionOptions, HeadlessSessionSource,
 };
 use crate::session::SessionManager;
+use crate::session_digest;
 use crate::tmux::ClaudeTerminalState;
 use chrono::{DateTime, Utc};
 use reqwest::blocking::Response;
@@ -1717,6 +1721,9 @@ struct NewTopicCommand {
     project_dir: String,
     preferred_env: Option<String>,
     preferred_perm_mode: Option<String>,
+    preset: Option<String>,
+    /// Claude effort level from the launch preset, if any.
+    effort: Option<String>,
     initial_prompt: Option<String>,
 }
 
@@ -1773,7 +1780,8 @@ fn parse_new_topic_command(text: &str) -> Result<NewTopicCommand, String> {
     let args = text.strip_prefix("/new").map(str::trim).unwrap_or_default();
     if args.is_empty() {
         return Err(
-            "Usage: /new <project_dir> [env=<name>|<env>] [perm=<mode>] [prompt...]".to_string(),
+            "Usage: /new <project_dir> [preset=<name>] [env=<name>|<env>] [perm=<mode>] [prompt...]"
+                .to_string(),
         );
     }
 
@@ -1781,9 +1789,15 @@ fn parse_new_topic_command(text: &str) -> Result<NewTopicCommand, String> {
     let project_dir = expand_home_dir(tokens[0]);
     let mut preferred_env = None;
     let mut preferred_perm_mode = None;
+    let mut preset = None;
     let mut prompt_parts = Vec::new();
 
     for (index, token) in tokens.iter().enumerate().skip(1) {
+        if let Some(value) = token.strip_prefix("preset=") {
+            preset = (!value.trim().is_empty()).then(|| value.trim().to_string());
+            continue;
+        }
+
         if let Some(value) = token.strip_prefix("env=") {
             preferred_env = (!value.trim().is_empty()).then(|| value.trim().to_string());
             continue;
@@ -1802,7 +1816,8 @@ fn parse_new_topic_command(text: &str) -> Result<NewTopicCommand, String> {
             break;
         }
 
-        if preferred_env.is_none() && !token.contains('=') {
+        // A preset already names the env, so bare words after it are prompt.
+        if preferred_env.is_none() && preset.is_none() && !token.contains('=') {
             preferred_env = Some((*token).to_string());
             continue;
         }
@@ -1818,10 +1833,123 @@ fn parse_new_topic_command(text: &str) -> Result<NewTopicCommand, String> {
         project_dir,
         preferred_env,
         preferred_perm_mode,
+        preset,
+        effort: None,
         initial_prompt: (!prompt_parts.is_empty()).then(|| prompt_parts.join(" ")),
     })
 }
 
+/// Splits a leading `preset=<name>` token off a `/new <prompt>` argument.
+fn split_new_preset_argument(argument: &str) -> (Option<&str>, &str) {
+    let argument = argument.trim();
+    let (first, rest) = argument
+        .split_once(char::is_whitespace)
+        .unwrap_or((argument, ""));
+    match first.strip_prefix("preset=") {
+        Some(name) if !name.is_empty() => (Some(name), rest.trim()),
+        _ => (None, argument),
+    }
+}
+
+/// Resolves the named preset, or the project's default Claude preset.
+/// Telegram only drives Claude, so a named Codex preset is rejected rather
+/// than ignored.
+fn resolve_telegram_launch_preset(
+    name: Option<&str>,
+    project_dir: Option<&str>,
+) -> Result<Option<LaunchPreset>, String> {
+    let presets = LaunchPresets::load();
+    let Some(preset) = presets.resolve(name, project_dir, Some(NativeProvider::Claude.as_str()))?
+    else {
+        return Ok(None);
+    };
+    if preset.provider != NativeProvider::Claude {
+        return Err(format!(
+            "Preset {} launches {}; Telegram sessions only run Claude.",
+            preset.name,
+            preset.provider.as_str()
+        ));
+    }
+    Ok(Some(preset.clone()))
+}
+
+/// Folds a launch preset into `/new <project_dir>`; `env=` and `perm=`
+/// tokens still win.
+fn apply_launch_preset_to_topic_command(command: &mut NewTopicCommand) -> Result<(), String> {
+    let Some(preset) =
+        resolve_telegram_launch_preset(command.preset.as_deref(), Some(&command.project_dir))?
+    else {
+        return Ok(());
+    };
+    if command.preferred_env.is_none() {
+        command.preferred_env = preset.env_name.clone();
+    }
+    if command.preferred_perm_mode.is_none() {
+        command.preferred_perm_mode = preset.launch_perm_mode().map(str::to_string);
+    }
+    command.effort = preset.effort.clone();
+    command.initial_prompt = preset.compose_prompt(command.initial_prompt.as_deref());
+    Ok(())
+}
+
+/// Overlays a launch preset on the topic binding for `/new <prompt>`. An
+/// explicit `preset=` replaces the topic's env and permission mode; a
+/// project default only fills what the topic leaves unset. The project
+/// directory always stays the topic's.
+fn launch_preset_binding(
+    settings: &TelegramSettings,
+    binding: Option<&TelegramTopicBinding>,
+    preset_name: Option<&str>,
+) -> Result<Option<(TelegramTopicBinding, LaunchPreset)>, String> {
+    let project_dir = binding
+        .map(|binding| binding.project_dir.clone())
+        .or_else(|| settings.default_working_dir.clone())
+        .or_else(config::get_default_working_dir);
+    let Some(preset) = resolve_telegram_launch_preset(preset_name, project_dir.as_deref())? else {
+        return Ok(None);
+    };
+    let explicit = preset_name.is_some();
+    let pick = |current: Option<&String>, from_preset: Option<&str>| {
+        let from_preset = from_preset.map(str::to_string);
+        if explicit {
+            from_preset.or_else(|| current.cloned())
+        } else {
+            current.cloned().or(from_preset)
+        }
+    };
+    let overlay = TelegramTopicBinding {
+        thread_id: binding.map(|binding| binding.thread_id).unwrap_or(0),
+        project_dir: binding
+            .map(|binding| binding.project_dir.clone())
+            .or_else(|| preset.working_dir.clone())
+            .or(project_dir)
+            .or_else(|| dirs::home_dir().map(|path| path.to_string_lossy().to_string()))
+            .unwrap_or_else(|| ".".to_string()),
+        preferred_env: pick(
+            binding.and_then(|binding| binding.preferred_env.as_ref()),
+            preset.env_name.as_deref(),
+        ),
+        preferred_perm_mode: pick(
+            binding.and_then(|binding| binding.preferred_perm_mode.as_ref()),
+            preset.launch_perm_mode(),
+        ),
+        active_runtime_id: binding.and_then(|binding| binding.active_runtime_id.clone()),
+        last_claude_session_id: binding.and_then(|binding| binding.last_claude_session_id.clone()),
+        created_at: binding
+            .map(|binding| binding.created_at)
+            .unwrap_or_else(Utc::now),
+    };
+    Ok(Some((overlay, preset)))
+}
+
+/// Telegram launches the Claude CLI, which reads its effort level from the
+/// same variable the native helper sets.
+fn apply_claude_effort(env_vars: &mut HashMap<String, String>, effort: Option<&str>) {
+    if let Some(effort) = effort.map(str::trim).filter(|effort| !effort.is_empty()) {
+        env_vars.insert("CLAUDE_CODE_EFFORT_LEVEL".to_string(), effort.to_string());
+    }
+}
+
 fn expand_home_dir(path: &str) -> String {
     let trimmed = path.trim();
     if trimmed == "~" {
@@ -1907,6 +2035,7 @@ fn format_topic_command_hint() -> String {
     [
         "Topic commands:",
         "/new <prompt> - start or continue work here",
+        "/new preset=<name> <prompt> - start with a launch preset",
         "/resume [prompt] - resume the last session for this topic",
         "/topic - show the current binding",
         "/topic clear - remove this binding",
@@ -2338,7 +2467,7 @@ fn handle_message(
             token,
             chat_id,
             thread_id,
-            "CCEM Telegram bridge is running.\nCommands:\n/help\n/whoami\n/sessions\n/envs\n/new <project_dir> [env=<name>|<env>] [perm=<mode>] [prompt...]\n/resume [prompt]\n/stop [runtime_id]\n/approve <request_id>\n/deny <request_id>\n/topics\n/topic\n/topic clear\n/bind [<project_dir>] [env=<name>] [perm=<mode>]\n/cron list\n/cron toggle <task>\n/cron run <task>\nPlain text in a bound topic will continue or start that topic's project console.",
+            "CCEM Telegram bridge is running.\nCommands:\n/help\n/whoami\n/sessions\n/envs\n/new <project_dir> [preset=<name>] [env=<name>|<env>] [perm=<mode>] [prompt...]\n/resume [prompt]\n/stop [runtime_id]\n/digest [runtime_id] [since_seq]\n/approve <request_id>\n/deny <request_id>\n/topics\n/topic\n/topic clear\n/bind [<project_dir>] [env=<name>] [perm=<mode>]\n/cron list\n/cron toggle <task>\n/cron run <task>\nPlain text in a bound topic will continue or start that topic's project console.",
         )?;
         return Ok(());
     }
@@ -2608,7 +2737,11 @@ fn handle_message(
             && looks_like_project_path(argument.split_whitespace().next().unwrap_or_default());
 
         if should_create_topic {
-            match parse_new_topic_command(&text) {
+            let parsed = parse_new_topic_command(&text).and_then(|mut command| {
+                apply_launch_preset_to_topic_command(&mut command)?;
+                Ok(command)
+            });
+            match parsed {
                 Ok(command) => {
                     return create_telegram_topic_console(
                         manager,
@@ -2628,6 +2761,27 @@ fn handle_message(
             }
         }
 
+        let (preset_name, argument) = split_new_preset_argument(argument);
+        let preset_launch =
+            match launch_preset_binding(settings, topic_binding.as_ref(), preset_name) {
+                Ok(preset_launch) => preset_launch,
+                Err(error) => {
+                    send_message(token, chat_id, thread_id, &error)?;
+                    return Ok(());
+                }
+            };
+        let prompt = preset_launch
+            .as_ref()
+            .map(|(_, preset)| preset.compose_prompt(Some(argument)).unwrap_or_default())
+            .unwrap_or_else(|| argument.to_string());
+        let preset_effort = preset_launch
+            .as_ref()
+            .and_then(|(_, preset)| preset.effort.as_deref());
+        let launch_binding = preset_launch
+            .as_ref()
+            .map(|(binding, _)| binding)
+            .or(topic_binding.as_ref());
+
         if topic_binding.is_some() {
             return create_interactive_telegram_session(
                 manager,
@@ -2637,9 +2791,10 @@ fn handle_message(
                 settings,
                 chat_id,
                 thread_id,
-                argument,
+                &prompt,
                 None,
-                topic_binding.as_ref(),
+                launch_binding,
+                preset_effort,
             );
         }
 
@@ -2651,17 +2806,18 @@ fn handle_message(
             settings,
             chat_id,
             thread_id,
-            argument,
+            &prompt,
             None,
-            topic_binding.as_ref(),
+            launch_binding,
+            preset_effort,
         );
     }
 
     if text == "/new" {
         let usage = if is_forum_chat && is_general_topic(thread_id) && topic_binding.is_none() {
-            "Usage: /new <project_dir> [env=<name>|<env>] [perm=<mode>] [prompt...]"
+            "Usage: /new <project_dir> [preset=<name>] [env=<name>|<env>] [perm=<mode>] [prompt...]"
         } else {
-            "Usage: /new <prompt>"
+            "Usage: /new [preset=<name>] <prompt>"
         };
         send_message(token, chat_id, thread_id, usage)?;
         return Ok(());
@@ -2697,6 +2853,7 @@ fn handle_message(
             if follow_up.is_empty() { "" } else { follow_up },
             Some(session_id),
             Some(binding),
+            None,
         );
     }
 
@@ -2730,6 +2887,28 @@ fn handle_message(
         return Ok(());
     }
 
+    if let Some(argument) = text.strip_prefix("/digest").map(str::trim) {
+        let mut arguments = argument.split_whitespace();
+        let runtime_id = arguments
+            .next()
+            .map(str::to_string)
+            .or_else(|| active_runtime_id.clone());
+        let since_seq = arguments.next().and_then(|value| value.parse::<u64>().ok());
+        let reply = match runtime_id {
+            Some(runtime_id) => match session_digest::load_session_digest(
+                &NativeEventLog::default(),
+                &runtime_id,
+                since_seq,
+            ) {
+                Ok(digest) => session_digest::render_digest_text(&digest),
+                Err(error) => format!("Failed to build digest for {runtime_id}: {error}"),
+            },
+            None => "Usage: /digest [runtime_id] [since_seq]".to_string(),
+        };
+        send_message(token, chat_id, thread_id, &reply)?;
+        return Ok(());
+    }
+
     if text == "/approve" {
         send_message(token, chat_id, thread_id, "Usage: /approve <request_id>")?;
         return Ok(());
@@ -2829,6 +3008,7 @@ fn handle_message(
             &text,
             binding.last_claude_session_id.clone(),
             Some(binding),
+            None,
         );
     }
 
@@ -2844,6 +3024,7 @@ fn handle_message(
             &text,
             None,
             None,
+            None,
         );
     }
 
@@ -3462,6 +3643,7 @@ fn create_telegram_topic_console(
             command.initial_prompt.as_deref().unwrap_or_default(),
             existing_binding.last_claude_session_id.clone(),
             Some(&existing_binding),
+            command.effort.as_deref(),
         );
     }
 
@@ -3512,6 +3694,7 @@ fn create_telegram_topic_console(
         command.initial_prompt.as_deref().unwrap_or_default(),
         None,
         Some(&binding),
+        command.effort.as_deref(),
     )
 }
 
@@ -3527,6 +3710,7 @@ fn create_telegram_session(
     prompt: &str,
     resume_session_id: Option<String>,
     binding: Option<&TelegramTopicBinding>,
+    effort: Option<&str>,
 ) -> Result<(), String> {
     let thread_id = canonical_thread_id(thread_id);
     let env_name = binding
@@ -3547,7 +3731,8 @@ fn create_telegram_session(
         .or_else(config::get_default_working_dir)
         .or_else(|| dirs::home_dir().map(|path| path.to_string_lossy().to_string()))
         .unwrap_or_else(|| ".".to_string());
-    let resolved = config::resolve_claude_env(&env_name)?;
+    let mut resolved = config::resolve_claude_env(&env_name)?;
+    apply_claude_effort(&mut resolved.env_vars, effort);
 
     let summary = runtime_manager.create_session(
         app.clone(),
@@ -4031,6 +4216,7 @@ fn create_interactive_telegram_session(
     prompt: &str,
     resume_session_id: Option<String>,
     binding: Option<&TelegramTopicBinding>,
+    effort: Option<&str>,
 ) -> Result<(), String> {
     let thread_id = canonical_thread_id(thread_id);
     let env_name = binding
@@ -4048,7 +4234,8 @@ fn create_interactive_telegram_session(
         .or_else(config::get_default_working_dir)
         .or_else(|| dirs::home_dir().map(|path| path.to_string_lossy().to_string()))
         .unwrap_or_else(|| ".".to_string());
-    let resolved = config::resolve_claude_env(&env_name)?;
+    let mut resolved = config::resolve_claude_env(&env_name)?;
+    apply_claude_effort(&mut resolved.env_vars, effort);
     let session_manager = app.state::<Arc<SessionManager>>().inner().clone();
     let runtime_id = generate_telegram_runtime_id();
     let resume_target = resume_session_id.clone();
@@ -5970,13 +6157,13 @@ mod tests {
         ensure_text_entry_option, expand_home_dir, format_active_choice_text,
         format_new_topic_welcome_message, format_permission_command_result,
         format_permission_request_text, format_permission_response_text,
-        format_tool_completed_message,
-        format_topic_binding_success_message, is_chat_about_this_option, is_chat_allowed,
-        is_sender_allowed, is_vscode_text_entry_option, normalize_command_text, parse_bind_command,
-        parse_interactive_tool_cancel_callback_data, parse_interactive_tool_select_callback_data,
-        parse_interactive_tool_submit_callback_data, parse_new_topic_command,
-        parse_permission_callback_data, redact_telegram_token, remove_topic_binding,
-        selection_submit_options, summarize_interactive_prompt_resolution, upsert_topic_binding,
+        format_tool_completed_message, format_topic_binding_success_message,
+        is_chat_about_this_option, is_chat_allowed, is_sender_allowed, is_vscode_text_entry_option,
+        normalize_command_text, parse_bind_command, parse_interactive_tool_cancel_callback_data,
+        parse_interactive_tool_select_callback_data, parse_interactive_tool_submit_callback_data,
+        parse_new_topic_command, parse_permission_callback_data, redact_telegram_token,
+        remove_topic_binding, selection_submit_options, split_new_preset_argument,
+        summarize_interactive_prompt_resolution, upsert_topic_binding,
         ActiveInteractiveChoicePrompt, InteractiveChoiceSubmitMode, PendingInteractiveAction,
         TelegramBotCommandScope, TelegramBridgeManager, TelegramSettings, TelegramTopicBinding,
         TELEGRAM_FULL_GROUP_COMMANDS, TELEGRAM_MINIMAL_GROUP_COMMANDS, TELEGRAM_PRIVATE_COMMANDS,
@@ -6089,6 +6276,28 @@ mod tests {
         );
     }
 
+    #[test]
+    fn new_commands_accept_a_launch_preset() {
+        let parsed = parse_new_topic_command("/new /tmp/web preset=frontend-kimi-dev fix the nav")
+            .expect("parse new topic");
+        assert_eq!(parsed.preset.as_deref(), Some("frontend-kimi-dev"));
+        assert_eq!(parsed.preferred_env, None);
+        assert_eq!(parsed.initial_prompt.as_deref(), Some("fix the nav"));
+
+        assert_eq!(
+            split_new_preset_argument("preset=review-opus-readonly check auth"),
+            (Some("review-opus-readonly"), "check auth")
+        );
+        assert_eq!(
+            split_new_preset_argument("preset=review-opus-readonly"),
+            (Some("review-opus-readonly"), "")
+        );
+        assert_eq!(
+            split_new_preset_argument("explain preset=x"),
+            (None, "explain preset=x")
+        );
+    }
+
     #[test]
     fn canonical_thread_id_omits_general_topic_id() {
         assert_eq!(canonical_thread_id(Some(1)), None);
@@ -1,17 +1,14 @@
 // apps/desktop/src-tauri/src/title_overrides.rs
 //
 // User-editable title overrides for history sessions.
-// Persisted to ~/.ccem/title_overrides.json with atomic writes + file locking.
+// Persisted to ~/.ccem/title_overrides.json through `LockedJsonStore`.
 
-use fs2::FileExt;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::fs::{self, OpenOptions};
-use std::io::ErrorKind;
-use std::path::PathBuf;
+use std::fs;
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use crate::config::get_ccem_dir;
+use crate::locked_json_store::LockedJsonStore;
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct TitleOverride {
@@ -25,56 +22,23 @@ pub struct TitleOverrides {
 }
 
 impl TitleOverrides {
-    fn path() -> PathBuf {
-        get_ccem_dir().join("title_overrides.json")
-    }
-
-    fn lock_path() -> PathBuf {
-        get_ccem_dir().join("title_overrides.json.lock")
+    fn store() -> LockedJsonStore {
+        LockedJsonStore::new("title_overrides.json")
     }
 
     /// Acquire an exclusive lock and load from disk.
     fn load_locked() -> std::io::Result<(Self, fs::File)> {
-        let path = Self::path();
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent)?;
-        }
-
-        let lock_file = OpenOptions::new()
-            .read(true)
-            .write(true)
-            .create(true)
-            .truncate(false)
-            .open(Self::lock_path())?;
-
-        lock_file.lock_exclusive()?;
-
-        let data = match fs::read_to_string(&path) {
-            Ok(content) => serde_json::from_str(&content).unwrap_or_default(),
-            Err(e) if e.kind() == ErrorKind::NotFound => Self::default(),
-            Err(_) => Self::default(),
-        };
-
-        Ok((data, lock_file))
+        Self::store().load_locked()
     }
 
     /// Load without locking (read-only, e.g. for overlay in get_conversation_history).
     pub fn load() -> Self {
-        match fs::read_to_string(Self::path()) {
-            Ok(content) => serde_json::from_str(&content).unwrap_or_default(),
-            Err(e) if e.kind() == ErrorKind::NotFound => Self::default(),
-            Err(_) => Self::default(),
-        }
+        Self::store().load()
     }
 
-    /// Atomic write: write-to-tmp + rename. Caller must hold the lock.
+    /// Caller must hold the lock.
     fn save_locked(&self) -> std::io::Result<()> {
-        let path = Self::path();
-        let json = serde_json::to_string_pretty(self)
-            .map_err(|e| std::io::Error::new(ErrorKind::InvalidData, e))?;
-        let tmp = path.with_extension("tmp");
-        fs::write(&tmp, json)?;
-        fs::rename(tmp, path)
+        Self::store().save_locked(self)
     }
 
     pub fn get(&self, source: &str, id: &str) -> Option<&TitleOverride> {
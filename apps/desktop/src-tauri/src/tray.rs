@@ -10,6 +10,8 @@ use tauri::{
 
 use crate::config;
 use crate::cron::{self, CronTask};
+use crate::external_control::ExternalControlManager;
+use crate::launch_presets::LaunchPresets;
 use crate::session::{Session, SessionManager};
 use crate::terminal;
 
@@ -126,6 +128,31 @@ fn build_perm_menu(app: &AppHandle, current: &str) -> Result<Submenu<tauri::Wry>
     Submenu::with_items(app, &title, true, &item_refs)
 }
 
+/// Build the launch presets submenu dynamically
+fn build_presets_menu(app: &AppHandle) -> Result<Submenu<tauri::Wry>, tauri::Error> {
+    let presets = LaunchPresets::load();
+    let mut items: Vec<MenuItem<tauri::Wry>> = Vec::new();
+
+    for name in presets.names() {
+        let id = format!("preset:{}", name);
+        items.push(MenuItem::with_id(app, &id, name, true, None::<&str>)?);
+    }
+
+    if items.is_empty() {
+        let no_presets =
+            MenuItem::with_id(app, "no_presets", "No launch presets", false, None::<&str>)?;
+        return Submenu::with_items(app, "🧩 预设启动", true, &[&no_presets]);
+    }
+
+    // Convert Vec<MenuItem> to Vec<&dyn IsMenuItem>
+    let item_refs: Vec<&dyn tauri::menu::IsMenuItem<tauri::Wry>> = items
+        .iter()
+        .map(|i| i as &dyn tauri::menu::IsMenuItem<tauri::Wry>)
+        .collect();
+
+    Submenu::with_items(app, "🧩 预设启动", true, &item_refs)
+}
+
 /// Build the active sessions submenu dynamically
 fn build_sessions_menu(app: &AppHandle) -> Result<Submenu<tauri::Wry>, tauri::Error> {
     // Get the SessionManager from app state
@@ -224,6 +251,7 @@ fn build_tray_menu(app: &AppHandle) -> Result<Menu<tauri::Wry>, tauri::Error> {
 
     // Main action items
     let launch_item = MenuItem::with_id(app, "launch", "🚀 启动 Claude Code", true, None::<&str>)?;
+    let presets_submenu = build_presets_menu(app)?;
     let open_window_item =
         MenuItem::with_id(app, "open_window", "🏠 打开主窗口", true, None::<&str>)?;
     let settings_item = MenuItem::with_id(app, "settings", "⚙️ 设置", true, None::<&str>)?;
@@ -242,6 +270,7 @@ fn build_tray_menu(app: &AppHandle) -> Result<Menu<tauri::Wry>, tauri::Error> {
             &stats_item,
             &PredefinedMenuItem::separator(app)?,
             &launch_item,
+            &presets_submenu,
             &open_window_item,
             &settings_item,
             &PredefinedMenuItem::separator(app)?,
@@ -359,6 +388,10 @@ pub fn create_tray(app: &AppHandle) -> Result<TrayIcon, tauri::Error> {
                 let session_id = id.strip_prefix("session:").unwrap();
                 handle_session_focus(app, session_id);
             }
+            id if id.starts_with("preset:") => {
+                let preset = id.strip_prefix("preset:").unwrap();
+                handle_preset_launch(app, preset);
+            }
             id if id.starts_with("env:") => {
                 let env_name = id.strip_prefix("env:").unwrap();
                 handle_env_switch(app, env_name);
@@ -813,6 +846,27 @@ fn handle_perm_switch(app: &AppHandle, perm_mode: &str) {
     }
 }
 
+/// Handle launch preset selection from tray menu
+fn handle_preset_launch(app: &AppHandle, preset: &str) {
+    let Some(manager) = app.try_state::<Arc<ExternalControlManager>>() else {
+        eprintln!("ExternalControlManager not found in app state");
+        return;
+    };
+    match manager.launch_preset(app.clone(), preset) {
+        Ok(()) => {
+            println!("Launched preset from tray: {}", preset);
+            if let Some(window) = app.get_window("main") {
+                let _ = window.show();
+                let _ = window.unminimize();
+                let _ = window.set_focus();
+            }
+        }
+        Err(e) => {
+            eprintln!("Failed to launch preset {}: {}", preset, e);
+        }
+    }
+}
+
 /// Handle session focus from tray menu
 fn handle_session_focus(app: &AppHandle, session_id: &str) {
     // Get the SessionManager from app state
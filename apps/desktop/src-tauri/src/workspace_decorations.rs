@@ -350,7 +350,7 @@ impl AttentionSummary {
                     self.pending_responses.remove(tool_use_id);
                 }
             }
-            SessionEventPayload::UserPrompt { .. } => {
+            SessionEventPayload::UserPrompt { queued: false, .. } => {
                 self.pending_responses.clear();
             }
             SessionEventPayload::SessionCompleted { .. } => {
@@ -688,6 +688,8 @@ mod tests {
                     images: None,
                     annotations: None,
                     canonical_hash: None,
+                    queued: false,
+                    queue_item_id: None,
                 },
             ],
             // Terminal prompt set/clear.
@@ -816,6 +818,7 @@ mod tests {
             raw_name: "ExitPlanMode".to_string(),
             input_summary: String::new(),
             needs_response: true,
+            input: None,
             prompt,
             todo_snapshot: None,
         }
@@ -858,6 +861,7 @@ mod tests {
                 raw_name: "ExitPlanMode".to_string(),
                 input_summary: String::new(),
                 needs_response: true,
+                input: None,
                 prompt: Some(InteractiveToolPrompt::PlanExit {
                     allowed_prompts: vec![],
                     plan_summary: None,
@@ -953,6 +953,8 @@ export function useTauriCommands() {
     routerLaunchDraft?: RouterLaunchDraft | null;
     resumeRouterFromRuntimeId?: string | null;
     codexMigrationProofToken?: string | null;
+    /** History session this launch continues from (handoff prompt); links provenance. */
+    continuedFrom?: { source: string; sessionId: string } | null;
   }): Promise<NativeSessionSummary> => {
     const { currentEnv, permissionMode, selectedWorkingDir } = getSessionDefaults();
     return invoke<NativeSessionSummary>('create_native_session', {
@@ -972,6 +974,7 @@ export function useTauriCommands() {
       routerLaunchDraft: options.routerLaunchDraft ?? null,
       resumeRouterFromRuntimeId: options.resumeRouterFromRuntimeId ?? null,
       codexMigrationProofToken: options.codexMigrationProofToken ?? null,
+      continuedFrom: options.continuedFrom ?? null,
     });
   }, [getSessionDefaults]);
 
@@ -30,6 +30,11 @@ import {
   CLAUDE_SKILL_SETTING_SOURCES,
   ensureClaudeSkillToolAllowed,
 } from './claudeSkills';
+import {
+  buildClaudeSessionsMcpServers,
+  buildCodexSessionsMcpConfig,
+  type SessionsMcpInit,
+} from './sessionsMcp';
 import { buildPromptContentParts, type PromptImage } from './promptContent';
 import { normalizeClaudePermissionMode, normalizeCodexSandboxMode } from './permissionModes';
 import { createLocalImageInputs, cleanupTempFiles } from './imageInputs';
@@ -73,6 +78,7 @@ type InitCommand = {
   disallowed_tools?: string[] | null;
   todo_snapshot_seed?: TodoSnapshotV1 | null;
   router?: ClaudeRouterInit | null;
+  sessions_mcp?: SessionsMcpInit | null;
 };
 
 type PromptCommand = {
@@ -123,6 +129,7 @@ type TitleQueryCommand = {
   claude_path?: string | null;
   model?: string | null;
   effort?: string | null;
+  include_tags?: boolean | null;
 };
 
 type RuntimeSettingsPatch = {
@@ -242,6 +249,8 @@ type ClaudeInteractivePromptResolver = {
 
 const DEFAULT_CLAUDE_IDLE_TTL_MS = 10 * 60 * 1000;
 const CLAUDE_INCOMPLETE_RESPONSE_REASON = 'Claude response ended before a final result. Partial output was preserved; send the next prompt to retry.';
+// Tools whose full input rides along on tool_use_started so edits can be diffed later.
+const FILE_WRITING_TOOL_NAMES = new Set(['Edit', 'MultiEdit', 'Write', 'NotebookEdit']);
 
 let initCommand: InitCommand | null = null;
 let stopped = false;
@@ -942,6 +951,11 @@ function resolveClaudeRuntimeModel(envVars?: Record<string, string>) {
     || nonEmptyEnvValue(envVars, 'ANTHROPIC_SMALL_FAST_MODEL');
 }
 
+const TITLE_REQUIREMENTS =
+  '要求：只输出标题本身；中文 4 到 12 个字或英文 2 到 6 个词；不要引号、标点、编号、解释、Markdown。';
+const TITLE_WITH_TAGS_REQUIREMENTS =
+  '要求：只输出两行。第一行是标题：中文 4 到 12 个字或英文 2 到 6 个词，不要引号、标点、编号、解释、Markdown；第二行输出 tags: 加上从 bugfix, feature, refactor, infra, docs, test 中选出的 1 到 3 个标签，用英文逗号分隔。';
+
 async function runWorkspaceTitleQuery(command: TitleQueryCommand) {
   const titleInput = command.title_input.trim();
   if (!titleInput) {
@@ -960,7 +974,7 @@ async function runWorkspaceTitleQuery(command: TitleQueryCommand) {
     || 'haiku';
   const prompt = [
     '请根据下面这条工作间会话的用户请求生成一个 ProjectTree 短标题。',
-    '要求：只输出标题本身；中文 4 到 12 个字或英文 2 到 6 个词；不要引号、标点、编号、解释、Markdown。',
+    command.include_tags ? TITLE_WITH_TAGS_REQUIREMENTS : TITLE_REQUIREMENTS,
     '',
     '用户请求：',
     titleInput,
@@ -1364,6 +1378,7 @@ function emitClaudeToolUseStarted(payload: {
     raw_name: payload.rawName,
     input_summary: payload.inputSummary,
     needs_response: payload.needsResponse,
+    ...(payload.input && FILE_WRITING_TOOL_NAMES.has(payload.rawName) ? { input: payload.input } : {}),
     ...(payload.prompt ? { prompt: payload.prompt } : {}),
   });
 }
@@ -2207,6 +2222,7 @@ function buildClaudeQueryOptions() {
         () => initCommand?.perm_mode ?? 'safe',
         browserToolBridge.sendBrowserToolRequest,
       ),
+      ...buildClaudeSessionsMcpServers(initCommand.sessions_mcp),
     },
     ...(model ? { model } : {}),
     ...(routerSystemPrompt ? { systemPrompt: routerSystemPrompt } : {}),
@@ -3126,6 +3142,7 @@ async function ensureCodexThread() {
         ...process.env,
         ...initCommand.env_vars,
       },
+      config: buildCodexSessionsMcpConfig(initCommand.sessions_mcp),
     });
   }
 
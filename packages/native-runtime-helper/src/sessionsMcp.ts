@@ -0,0 +1,45 @@
+import type { McpServerConfig } from '@anthropic-ai/claude-agent-sdk';
+
+export const CCEM_SESSIONS_MCP_SERVER_NAME = 'ccem-sessions';
+
+export type SessionsMcpInit = {
+  scriptPath: string;
+  endpoint: string;
+  token: string;
+};
+
+function sessionsMcpLaunch(init: SessionsMcpInit) {
+  return {
+    command: process.execPath,
+    args: [init.scriptPath],
+    env: {
+      CCEM_SESSIONS_MCP_ENDPOINT: init.endpoint,
+      CCEM_SESSIONS_MCP_TOKEN: init.token,
+    },
+  };
+}
+
+export function buildClaudeSessionsMcpServers(
+  init?: SessionsMcpInit | null,
+): Record<string, McpServerConfig> {
+  if (!init) {
+    return {};
+  }
+  return {
+    [CCEM_SESSIONS_MCP_SERVER_NAME]: {
+      type: 'stdio',
+      ...sessionsMcpLaunch(init),
+    },
+  };
+}
+
+export function buildCodexSessionsMcpConfig(init?: SessionsMcpInit | null) {
+  if (!init) {
+    return undefined;
+  }
+  return {
+    mcp_servers: {
+      [CCEM_SESSIONS_MCP_SERVER_NAME]: sessionsMcpLaunch(init),
+    },
+  };
+}
@@ -0,0 +1,60 @@
+import test from 'node:test';
+import assert from 'node:assert/strict';
+import { build } from 'esbuild';
+import fs from 'node:fs/promises';
+import os from 'node:os';
+import path from 'node:path';
+import { fileURLToPath, pathToFileURL } from 'node:url';
+
+const __dirname = path.dirname(fileURLToPath(import.meta.url));
+const packageDir = path.resolve(__dirname, '..');
+
+async function importSessionsMcpModule() {
+  const tempDir = await fs.mkdtemp(path.join(os.tmpdir(), 'ccem-sessions-mcp-test-'));
+  const outfile = path.join(tempDir, 'sessionsMcp.mjs');
+
+  await build({
+    entryPoints: [path.join(packageDir, 'src', 'sessionsMcp.ts')],
+    outfile,
+    bundle: true,
+    platform: 'node',
+    format: 'esm',
+    target: 'node20',
+    logLevel: 'silent',
+  });
+
+  return import(`${pathToFileURL(outfile).href}?t=${Date.now()}`);
+}
+
+const init = {
+  scriptPath: '/home/me/.ccem/runtime-tools/ccem-sessions-mcp-server.mjs',
+  endpoint: 'http://127.0.0.1:4321/rpc',
+  token: 'session-token',
+};
+
+test('registers the sessions MCP server for Claude only when initialized', async () => {
+  const { buildClaudeSessionsMcpServers } = await importSessionsMcpModule();
+
+  assert.deepEqual(buildClaudeSessionsMcpServers(null), {});
+  assert.deepEqual(buildClaudeSessionsMcpServers(init), {
+    'ccem-sessions': {
+      type: 'stdio',
+      command: process.execPath,
+      args: [init.scriptPath],
+      env: {
+        CCEM_SESSIONS_MCP_ENDPOINT: init.endpoint,
+        CCEM_SESSIONS_MCP_TOKEN: init.token,
+      },
+    },
+  });
+});
+
+test('passes the sessions MCP server to Codex as config overrides', async () => {
+  const { buildCodexSessionsMcpConfig } = await importSessionsMcpModule();
+
+  assert.equal(buildCodexSessionsMcpConfig(undefined), undefined);
+  const config = buildCodexSessionsMcpConfig(init);
+  assert.equal(config.mcp_servers['ccem-sessions'].command, process.execPath);
+  assert.deepEqual(config.mcp_servers['ccem-sessions'].args, [init.scriptPath]);
+  assert.equal(config.mcp_servers['ccem-sessions'].env.CCEM_SESSIONS_MCP_TOKEN, 'session-token');
+});